@@ -0,0 +1,27 @@
+#import noisy_bevy::prelude
+
+struct GasCloudMaterial {
+    color: vec4<f32>,
+    density: f32,
+    time: f32,
+    seed: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: GasCloudMaterial;
+
+@fragment
+fn fragment(
+    #import bevy_pbr::mesh_vertex_output
+) -> @location(0) vec4<f32> {
+    let centered = uv * 2.0 - vec2(1.0);
+    let dist = length(centered);
+
+    // Slowly churning noise, fading out towards the edge of the quad
+    let drift = vec2(material.time * 0.15, material.time * -0.1);
+    let noise = fbm_simplex_2d_seeded(centered * 2.0 + drift, 4, 2.0, 0.5, material.seed);
+    let falloff = 1.0 - smoothstep(0.4, 1.0, dist);
+    let alpha = clamp((noise * 0.5 + 0.5) * falloff, 0.0, 1.0) * material.color.a * material.density;
+
+    return vec4(material.color.rgb, alpha);
+}
@@ -0,0 +1,27 @@
+#import noisy_bevy::prelude
+
+struct HeatHazeMaterial {
+    intensity: f32,
+    time: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: HeatHazeMaterial;
+
+@fragment
+fn fragment(
+    #import bevy_sprite::mesh2d_vertex_output
+) -> @location(0) vec4<f32> {
+    let centered = uv * 2.0 - vec2(1.0);
+    // Strongest at the edges of the screen, clear in the middle where the ship is
+    let edge = smoothstep(0.5, 1.3, length(centered));
+
+    // Heat rises, so the ripples drift upwards
+    let rising = vec2(centered.x * 3.0, centered.y * 6.0 + material.time * 1.5);
+    let ripple = simplex_noise_2d(rising) * 0.5 + 0.5;
+    let shimmer = simplex_noise_2d(rising * 2.5 + vec2(material.time, 0.0)) * 0.5 + 0.5;
+
+    let alpha = edge * ripple * shimmer * material.intensity * 0.45;
+    let color = mix(vec3(1.0, 0.35, 0.05), vec3(1.0, 0.8, 0.4), shimmer);
+    return vec4(color, alpha);
+}
@@ -0,0 +1,88 @@
+#import bevy_pbr::mesh_types
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::pbr_types
+
+@group(1) @binding(0)
+var<uniform> mesh: Mesh;
+
+// NOTE: Bindings must come before functions that use them!
+#import bevy_pbr::mesh_functions
+#import bevy_pbr::utils
+#import bevy_pbr::clustered_forward
+#import bevy_pbr::lighting
+#import bevy_pbr::pbr_ambient
+#import bevy_pbr::shadows
+#import bevy_pbr::fog
+#import bevy_pbr::pbr_functions
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+
+    @location(3) i_pos_scale: vec4<f32>,
+    @location(4) i_rotation: vec4<f32>,
+    @location(5) i_color: vec4<f32>,
+    @location(6) i_emissive: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) emissive: vec4<f32>,
+};
+
+struct FragmentInput {
+    @builtin(front_facing) is_front: bool,
+    @builtin(position) frag_coord: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) emissive: vec4<f32>,
+};
+
+fn rotate(q: vec4<f32>, v: vec3<f32>) -> vec3<f32> {
+    let t = 2.0 * cross(q.xyz, v);
+    return v + q.w * t + cross(q.xyz, t);
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let position = rotate(vertex.i_rotation, vertex.position) * vertex.i_pos_scale.w + vertex.i_pos_scale.xyz;
+    var out: VertexOutput;
+    out.world_position = mesh_position_local_to_world(mesh.model, vec4<f32>(position, 1.0));
+    out.clip_position = mesh_position_world_to_clip(out.world_position);
+    out.world_normal = mesh_normal_local_to_world(rotate(vertex.i_rotation, vertex.normal));
+    out.color = vertex.i_color;
+    out.emissive = vertex.i_emissive;
+    return out;
+}
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    // Shaded like a default `StandardMaterial` in the batch's colours, so the ship's lights and
+    // the stage's ambient light reach the rocks and minerals too
+    var pbr_input = pbr_input_new();
+    pbr_input.material.base_color = in.color;
+    pbr_input.material.emissive = in.emissive;
+    pbr_input.material.perceptual_roughness = 0.5;
+    pbr_input.material.metallic = 0.0;
+    pbr_input.frag_coord = in.frag_coord;
+    pbr_input.world_position = in.world_position;
+    pbr_input.world_normal = prepare_world_normal(in.world_normal, false, in.is_front);
+    pbr_input.is_orthographic = view.projection[3].w == 1.0;
+    pbr_input.N = normalize(pbr_input.world_normal);
+    pbr_input.V = calculate_view(in.world_position, pbr_input.is_orthographic);
+    pbr_input.flags = mesh.flags;
+
+    var output_color = pbr(pbr_input);
+    if (fog.mode != FOG_MODE_OFF) {
+        output_color = apply_fog(output_color, in.world_position.xyz, view.world_position.xyz);
+    }
+#ifdef TONEMAP_IN_SHADER
+    output_color = tone_mapping(output_color);
+#endif
+    return output_color;
+}
@@ -0,0 +1,30 @@
+#import noisy_bevy::prelude
+
+struct NebulaMaterial {
+    camera_position: vec3<f32>,
+    parallax_factor: f32,
+    resolution: vec3<f32>,
+    time: f32,
+    color: vec4<f32>,
+    scale: f32,
+    strength: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: NebulaMaterial;
+
+@fragment
+fn fragment(
+    #import bevy_pbr::mesh_vertex_output
+) -> @location(0) vec4<f32> {
+    var st = uv - vec2(0.5) + material.parallax_factor * material.camera_position.xy * vec2(1.0, -1.0) / material.resolution.xy;
+    st.x *= material.resolution.x / material.resolution.y;
+
+    // Two layers of noise, one warping the other, so the clouds look wispy
+    let drift = vec2(material.time * 0.005, 0.0);
+    let warp = fbm_simplex_2d(st * material.scale + drift, 3, 2.0, 0.5);
+    let n = fbm_simplex_2d(st * material.scale + vec2(warp) * 0.5, 5, 2.0, 0.5);
+    let intensity = clamp(n * 0.5 + 0.5, 0.0, 1.0);
+
+    return vec4(material.color.rgb * intensity * intensity * material.strength, 1.0);
+}
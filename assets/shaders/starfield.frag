@@ -8,6 +8,11 @@ struct StarfieldMaterial {
     float parallax_factor;
     vec3 resolution;
     float time;
+    vec4 tint;
+    float density;
+    float twinkle_speed;
+    float warp;
+    vec4 palette[4];
 };
 
 layout(set = 1, binding = 0) uniform StarfieldMaterial material;
@@ -28,39 +33,14 @@ vec2 random2(vec2 c) {
     return r-0.5;
 }
 
-vec3 color_temperature(float k) {
-  k = k / 100.0;
-  vec3 c = vec3(0.0);
-  if (k <= 66.0) {
-    c.r = 255.0;
-    c.g = 99.4708025861 * log(k) - 161.1195681661;
-  } else {
-    c.r = k - 60.0;
-    c.r = 329.698727446 * pow(c.r,-0.1332047592);
-
-    c.g = k - 60.0;
-    c.g = 288.1221695283 * pow(c.g,-0.0755148492);
-  }
-
-  if (k >= 66.0) {
-    c.b = 255.0;
-  } else if (k <= 19.0) {
-    c.b = 0.0;
-  } else {
-    c.b = k - 10.0;
-    c.b = 138.5177312231 * log(c.b) - 305.0447927307;
-  }
-
-  c = clamp(c, vec3(0.0), vec3(255.0)) / 255.0;
-  return c;
-}
-
 float stars(vec2 uv, float scale, float cutoff) {
   uv = uv * scale;
+  cutoff = 1.0 - (1.0 - cutoff) * material.density;
   vec2 id = floor(uv);
   vec2 frac = fract(uv);
 
   float min_dist = 1.0;
+  vec2 nearest = vec2(0.0);
   for (int y = -1; y <= 1; y++) {
     for (int x = -1; x <= 1; x++) {
       vec2 neighbor = vec2(float(x), float(y));
@@ -69,28 +49,41 @@ float stars(vec2 uv, float scale, float cutoff) {
       vec2 diff = neighbor + point - frac;
       float dist = length(diff);
 
-      min_dist = min(min_dist, dist);
+      if (dist < min_dist) {
+        min_dist = dist;
+        nearest = point;
+      }
     }
   }
 
-  return smoothstep(cutoff, 1.0, 1.0 - min_dist);
+  float phase = (nearest.x + nearest.y) * 40.0;
+  float twinkle = material.twinkle_speed > 0.0 ? 0.75 + 0.25 * sin(material.time * material.twinkle_speed + phase) : 1.0;
+
+  return smoothstep(cutoff, 1.0, 1.0 - min_dist) * twinkle;
 }
 
 void main() {
-  vec2 uv = v_Uv - vec2(0.5) + material.parallax_factor * material.camera_position.xy * vec2(1.0, -1.0) / material.resolution.xy;
-  uv.x *= material.resolution.x / material.resolution.y;
+  vec2 aspect = vec2(material.resolution.x / material.resolution.y, 1.0);
+  vec2 centered = (v_Uv - vec2(0.5)) * aspect;
+
+  float radius = length(centered);
+  float ripple = sin(radius * 20.0 - material.time * 3.0);
+  float angle = material.warp * (0.4 / (1.0 + radius * 4.0) + 0.03 * ripple);
+  centered = mat2(cos(angle), sin(angle), -sin(angle), cos(angle)) * centered;
+
+  vec2 uv = centered + material.parallax_factor * material.camera_position.xy * vec2(1.0, -1.0) / material.resolution.xy * aspect;
 
   // float t = material.time * material.camera_position.z * material.resolution.z;
 
   vec3 color = vec3(0.0);
 
-  color += stars(uv, 50.0/1.3, 0.95) * color_temperature(10000.0) * 3.0;
-  color += stars(uv, 25.0/1.3, 0.98) * color_temperature(60000.0) * 25.0;
-  color += stars(uv, 75.0/1.3, 0.9) * color_temperature(6000.0) * 0.3;
-  color += stars(uv, 5, 0.99) * color_temperature(800000.0) * 150.0;
+  color += stars(uv, 50.0/1.3, 0.95) * material.palette[1].rgb * 3.0;
+  color += stars(uv, 25.0/1.3, 0.98) * material.palette[2].rgb * 25.0;
+  color += stars(uv, 75.0/1.3, 0.9) * material.palette[0].rgb * 0.3;
+  color += stars(uv, 5, 0.99) * material.palette[3].rgb * 150.0;
   color += stars(uv, 4.5, 0.995) * vec3(0.9, 0.01, 0.5) * 150.0;
   color += stars(uv, 4.5, 0.995) * vec3(0.9, 0.01, 0.5) * 150.0;
   color += stars(uv, 4.6, 0.995) * vec3(0.7, 0.5, 0.3) * 150.0;
 
-  o_Target = vec4(color, 1.0);
+  o_Target = vec4(color * material.tint.rgb, 1.0);
 }
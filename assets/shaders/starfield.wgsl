@@ -0,0 +1,81 @@
+struct StarfieldMaterial {
+    camera_position: vec3<f32>,
+    parallax_factor: f32,
+    resolution: vec3<f32>,
+    time: f32,
+    tint: vec4<f32>,
+    density: f32,
+    twinkle_speed: f32,
+    warp: f32,
+    palette: array<vec4<f32>, 4>,
+};
+
+@group(1) @binding(0)
+var<uniform> material: StarfieldMaterial;
+
+fn random2(c: vec2<f32>) -> vec2<f32> {
+    var j = 4096.0 * sin(dot(c, vec2(17.0, 59.4)));
+    var r: vec2<f32>;
+    r.y = fract(512.0 * j);
+    j *= 0.125;
+    r.x = fract(512.0 * j);
+    return r - 0.5;
+}
+
+fn stars(uv_in: vec2<f32>, scale: f32, base_cutoff: f32) -> f32 {
+    let uv = uv_in * scale;
+    // Fewer points make it past a higher cutoff, so lower densities mean fewer stars
+    let cutoff = 1.0 - (1.0 - base_cutoff) * material.density;
+    let id = floor(uv);
+    let frac = fract(uv);
+
+    var min_dist = 1.0;
+    var nearest = vec2(0.0);
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let neighbor = vec2(f32(x), f32(y));
+            let point = random2(id + neighbor);
+
+            let diff = neighbor + point - frac;
+            let dist = length(diff);
+            if (dist < min_dist) {
+                min_dist = dist;
+                nearest = point;
+            }
+        }
+    }
+
+    // Every star flickers out of step with its neighbours
+    let phase = (nearest.x + nearest.y) * 40.0;
+    let twinkle = select(1.0, 0.75 + 0.25 * sin(material.time * material.twinkle_speed + phase), material.twinkle_speed > 0.0);
+
+    return smoothstep(cutoff, 1.0, 1.0 - min_dist) * twinkle;
+}
+
+@fragment
+fn fragment(
+    #import bevy_pbr::mesh_vertex_output
+) -> @location(0) vec4<f32> {
+    let aspect = vec2(material.resolution.x / material.resolution.y, 1.0);
+    var centered = (uv - vec2(0.5)) * aspect;
+
+    // Space twists around the ship, more so closer in, with ripples running outwards
+    let radius = length(centered);
+    let ripple = sin(radius * 20.0 - material.time * 3.0);
+    let angle = material.warp * (0.4 / (1.0 + radius * 4.0) + 0.03 * ripple);
+    centered = mat2x2<f32>(cos(angle), sin(angle), -sin(angle), cos(angle)) * centered;
+
+    let st = centered + material.parallax_factor * material.camera_position.xy * vec2(1.0, -1.0) / material.resolution.xy * aspect;
+
+    var color = vec3(0.0);
+
+    color += stars(st, 50.0 / 1.3, 0.95) * material.palette[1].rgb * 3.0;
+    color += stars(st, 25.0 / 1.3, 0.98) * material.palette[2].rgb * 25.0;
+    color += stars(st, 75.0 / 1.3, 0.9) * material.palette[0].rgb * 0.3;
+    color += stars(st, 5.0, 0.99) * material.palette[3].rgb * 150.0;
+    color += stars(st, 4.5, 0.995) * vec3(0.9, 0.01, 0.5) * 150.0;
+    color += stars(st, 4.5, 0.995) * vec3(0.9, 0.01, 0.5) * 150.0;
+    color += stars(st, 4.6, 0.995) * vec3(0.7, 0.5, 0.3) * 150.0;
+
+    return vec4(color * material.tint.rgb, 1.0);
+}
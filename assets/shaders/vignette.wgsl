@@ -0,0 +1,18 @@
+struct VignetteMaterial {
+    color: vec4<f32>,
+    intensity: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: VignetteMaterial;
+
+@fragment
+fn fragment(
+    #import bevy_sprite::mesh2d_vertex_output
+) -> @location(0) vec4<f32> {
+    let centered = uv * 2.0 - vec2(1.0);
+    // Stronger pulses reach further in from the edges
+    let inner = mix(1.2, 0.6, material.intensity);
+    let edge = smoothstep(inner, 1.5, length(centered));
+    return vec4(material.color.rgb, edge * material.intensity);
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+
+use crate::WarlordPlugins;
+
+fn add_windowed_plugins(app: &mut App) {
+    #[cfg(not(debug_assertions))]
+    app.add_plugins(DefaultPlugins);
+    #[cfg(debug_assertions)]
+    app.add_plugins(
+        DefaultPlugins
+            .set(bevy::log::LogPlugin {
+                filter: "error,warlord=debug".into(),
+                level: bevy::log::Level::DEBUG,
+            })
+            .set(AssetPlugin {
+                watch_for_changes: true,
+                ..Default::default()
+            }),
+    );
+}
+
+/// Just enough of the engine for the gameplay plugins to run, without a window, renderer or
+/// audio output. Assets are still registered so the setup systems can create handles, they're
+/// just never drawn or played
+#[cfg(feature = "headless")]
+fn add_headless_plugins(app: &mut App) {
+    use bevy::{
+        audio::AudioSink,
+        diagnostic::DiagnosticsPlugin,
+        input::InputPlugin,
+        render::{mesh::Mesh, render_resource::Shader, texture::Image},
+        sprite::TextureAtlas,
+        text::Font,
+        window::{ExitCondition, WindowPlugin},
+    };
+
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(DiagnosticsPlugin)
+        .add_plugin(InputPlugin)
+        .add_plugin(WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            close_when_requested: false,
+        })
+        .add_plugin(AssetPlugin::default())
+        .add_asset::<Shader>()
+        .add_asset::<Mesh>()
+        .add_asset::<Image>()
+        .add_asset::<TextureAtlas>()
+        .add_asset::<StandardMaterial>()
+        .add_asset::<ColorMaterial>()
+        .add_asset::<Font>()
+        .add_asset::<AudioSource>()
+        .add_asset::<AudioSink>()
+        .init_resource::<Audio>();
+}
+
+/// Builds the whole game. When `headless` is set the app has no window, rendering or audio, so
+/// it can be driven from tests by calling `App::update`
+pub fn build_game_app(headless: bool) -> App {
+    let mut app = App::new();
+    if headless {
+        #[cfg(feature = "headless")]
+        add_headless_plugins(&mut app);
+        #[cfg(not(feature = "headless"))]
+        panic!("Running headless needs the `headless` feature");
+    } else {
+        add_windowed_plugins(&mut app);
+    }
+
+    app.add_plugins(WarlordPlugins::default());
+    app
+}
@@ -0,0 +1,600 @@
+use bevy::{
+    core_pipeline::bloom::BloomSettings,
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        render_resource::{AsBindGroup, ShaderRef},
+        view::RenderLayers,
+    },
+};
+
+use crate::{
+    camera::MainCamera,
+    parallax::{spawn_parallax_layer, ParallaxLayer},
+    physics::PhysicsQuality,
+    starfield_image::BasicStarField,
+    starfield_shader::{
+        StarfieldBundle, StarfieldCamera, StarfieldMaterial, StarfieldMesh, DEFAULT_STAR_PALETTE,
+    },
+    state::{GameState, ProgressStages},
+    util::{parse_config_lines, parse_floats, warn_unknown_config},
+};
+
+/// How the stars behind the game are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StarfieldMode {
+    /// Drawn every frame by the starfield shader, with a nebula
+    Shader,
+    /// Generated once as images and scrolled past. For machines where the shader is too slow
+    Static,
+}
+
+#[derive(Resource, Debug)]
+pub struct GraphicsSettings {
+    pub starfield: StarfieldMode,
+    /// How carefully the physics is stepped, see `PhysicsPresets`
+    pub physics: PhysicsQuality,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            // The web build usually runs on weaker hardware
+            #[cfg(target_arch = "wasm32")]
+            starfield: StarfieldMode::Static,
+            #[cfg(not(target_arch = "wasm32"))]
+            starfield: StarfieldMode::Shader,
+            #[cfg(target_arch = "wasm32")]
+            physics: PhysicsQuality::Low,
+            #[cfg(not(target_arch = "wasm32"))]
+            physics: PhysicsQuality::default(),
+        }
+    }
+}
+
+/// One layer of stars. Layers are drawn back to front in the order they're listed
+#[derive(Debug, Clone, PartialEq)]
+pub struct StarfieldLayerSettings {
+    /// How much the layer moves with the camera. Smaller numbers look further away
+    pub parallax_factor: f32,
+    /// How many stars there are, relative to the shader's usual amount
+    pub density: f32,
+    pub tint: Color,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NebulaSettings {
+    pub parallax_factor: f32,
+    /// How large the clouds of the nebula are. Larger numbers make smaller clouds
+    pub scale: f32,
+    pub color: Color,
+    pub strength: f32,
+}
+
+/// A repeating picture scrolled behind the camera with a `ParallaxLayer`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParallaxImageSettings {
+    /// Path of the image inside `assets`
+    pub path: String,
+    pub parallax_factor: f32,
+    /// How many world units wide and tall each copy of the image is
+    pub tile_size: f32,
+}
+
+/// What the background of the game is made of, read from `assets/background.txt`
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct BackgroundSettings {
+    pub layers: Vec<StarfieldLayerSettings>,
+    pub nebula: Option<NebulaSettings>,
+    /// Drawn on top of the starfield layers, in order
+    pub images: Vec<ParallaxImageSettings>,
+    /// How quickly the stars flicker. 0 means they hold still
+    pub twinkle_speed: f32,
+    /// The colours of the faint, common, bright and giant stars, in linear RGB
+    pub palette: [Vec4; 4],
+}
+
+impl Default for BackgroundSettings {
+    fn default() -> Self {
+        Self::from_text(include_str!("../assets/background.txt"))
+    }
+}
+
+impl BackgroundSettings {
+    /// Each line is a keyword followed by its numbers. Lines that can't be read are skipped
+    pub fn from_text(text: &str) -> Self {
+        let mut settings = Self {
+            layers: Vec::new(),
+            nebula: None,
+            images: Vec::new(),
+            twinkle_speed: 0.0,
+            palette: DEFAULT_STAR_PALETTE,
+        };
+        for (key, value) in parse_config_lines(text) {
+            match key {
+                "layer" => {
+                    let Some([parallax_factor, density, r, g, b]) = parse_floats(value) else {
+                        warn!("Couldn't read starfield layer {value:?}");
+                        continue;
+                    };
+                    settings.layers.push(StarfieldLayerSettings {
+                        parallax_factor,
+                        density,
+                        tint: Color::rgb(r, g, b),
+                    });
+                }
+                "nebula" => {
+                    let Some([parallax_factor, scale, r, g, b, strength]) = parse_floats(value) else {
+                        warn!("Couldn't read nebula {value:?}");
+                        continue;
+                    };
+                    settings.nebula = Some(NebulaSettings {
+                        parallax_factor,
+                        scale,
+                        color: Color::rgb(r, g, b),
+                        strength,
+                    });
+                }
+                "image" => {
+                    let Some((path, numbers)) = value.split_once(' ') else {
+                        warn!("Couldn't read parallax image {value:?}");
+                        continue;
+                    };
+                    let Some([parallax_factor, tile_size]) = parse_floats(numbers) else {
+                        warn!("Couldn't read parallax image {value:?}");
+                        continue;
+                    };
+                    settings.images.push(ParallaxImageSettings {
+                        path: path.to_string(),
+                        parallax_factor,
+                        tile_size,
+                    });
+                }
+                "twinkle" => {
+                    let Some([twinkle_speed]) = parse_floats(value) else {
+                        warn!("Couldn't read twinkle speed {value:?}");
+                        continue;
+                    };
+                    settings.twinkle_speed = twinkle_speed;
+                }
+                "palette" => {
+                    let Some((name, numbers)) = value.split_once(' ') else {
+                        warn!("Couldn't read palette colour {value:?}");
+                        continue;
+                    };
+                    let index = match name {
+                        "faint" => 0,
+                        "common" => 1,
+                        "bright" => 2,
+                        "giant" => 3,
+                        _ => {
+                            warn!("Unknown kind of star {name:?}");
+                            continue;
+                        }
+                    };
+                    let Some([r, g, b]) = parse_floats(numbers) else {
+                        warn!("Couldn't read palette colour {value:?}");
+                        continue;
+                    };
+                    settings.palette[index] = Vec4::new(r, g, b, 1.0);
+                }
+                _ => warn_unknown_config("background setting", key),
+            }
+        }
+        settings
+    }
+}
+
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "9e0b6c41-3f7d-4a25-8c1e-5d2f7a9b0c63"]
+pub struct NebulaMaterial {
+    #[uniform(0)]
+    pub camera_position: Vec3,
+    #[uniform(0)]
+    pub parallax_factor: f32,
+    #[uniform(0)]
+    pub resolution: Vec3,
+    #[uniform(0)]
+    pub time: f32,
+    #[uniform(0)]
+    pub color: Color,
+    #[uniform(0)]
+    pub scale: f32,
+    #[uniform(0)]
+    pub strength: f32,
+}
+
+impl Default for NebulaMaterial {
+    fn default() -> Self {
+        Self {
+            camera_position: Vec3::ZERO,
+            parallax_factor: 0.05,
+            resolution: Vec3::ZERO,
+            time: 0.0,
+            color: Color::rgb(0.5, 0.1, 0.6),
+            scale: 3.0,
+            strength: 0.4,
+        }
+    }
+}
+
+impl From<&NebulaSettings> for NebulaMaterial {
+    fn from(settings: &NebulaSettings) -> Self {
+        Self {
+            parallax_factor: settings.parallax_factor,
+            color: settings.color,
+            scale: settings.scale,
+            strength: settings.strength,
+            ..Default::default()
+        }
+    }
+}
+
+impl Material for NebulaMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/nebula.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        // Drawn on top of the stars, lighting them up rather than covering them
+        AlphaMode::Add
+    }
+}
+
+fn update_nebula_uniforms(
+    main_camera_query: Query<&GlobalTransform, With<MainCamera>>,
+    starfield_camera_query: Query<&Projection, With<StarfieldCamera>>,
+    mut nebulae: ResMut<Assets<NebulaMaterial>>,
+    time: Res<Time>,
+) {
+    let Ok(main_camera) = main_camera_query.get_single() else { return; };
+    let Ok(Projection::Orthographic(proj)) = starfield_camera_query.get_single() else { return; };
+    let size = proj.area.size();
+    for (_, nebula) in nebulae.iter_mut() {
+        nebula.camera_position = main_camera.translation();
+        nebula.resolution = (size, 0.0).into();
+        nebula.time = time.elapsed_seconds_wrapped();
+    }
+}
+
+/// The look a layer was given by the `BackgroundSettings`, before the stage's theme is applied
+#[derive(Component, Debug, Clone)]
+pub struct BackgroundLayer {
+    pub base_tint: Color,
+    /// Star density for starfield layers, strength for the nebula
+    pub base_density: f32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const BACKGROUND_FILE: &str = "assets/background.txt";
+
+/// Picks up edits to the background settings file while the game is running, so the background
+/// can be tweaked without rebuilding. The web build only has the copy it was built with
+#[cfg(not(target_arch = "wasm32"))]
+fn reload_background_settings(
+    mut settings: ResMut<BackgroundSettings>,
+    mut last_modified: Local<Option<std::time::SystemTime>>,
+    mut timer: Local<Option<Timer>>,
+    time: Res<Time>,
+) {
+    let timer = timer.get_or_insert_with(|| Timer::from_seconds(1.0, TimerMode::Repeating));
+    timer.tick(time.delta());
+    if !timer.just_finished() {
+        return;
+    }
+    let Ok(modified) = std::fs::metadata(BACKGROUND_FILE).and_then(|m| m.modified()) else { return; };
+    if *last_modified == Some(modified) {
+        return;
+    }
+    let first_check = last_modified.is_none();
+    *last_modified = Some(modified);
+    let Ok(text) = std::fs::read_to_string(BACKGROUND_FILE) else { return; };
+    let new_settings = BackgroundSettings::from_text(&text);
+    // Only mark the settings as changed when they really are, so the background isn't rebuilt
+    if *settings != new_settings {
+        if !first_check {
+            info!("Reloaded background settings from {BACKGROUND_FILE}");
+        }
+        *settings = new_settings;
+    }
+}
+
+/// Everything needed to build the background
+#[derive(SystemParam)]
+pub struct BackgroundAssets<'w> {
+    starfields: ResMut<'w, Assets<StarfieldMaterial>>,
+    nebulae: ResMut<'w, Assets<NebulaMaterial>>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+    images: ResMut<'w, Assets<Image>>,
+    asset_server: Res<'w, AssetServer>,
+    background_settings: Res<'w, BackgroundSettings>,
+    graphics_settings: Res<'w, GraphicsSettings>,
+}
+
+/// Spawns the layers of the background, but not the camera that draws them
+pub fn spawn_background(commands: &mut Commands, assets: &mut BackgroundAssets) {
+    let mesh = assets.meshes.add(shape::Quad::default().into());
+    let settings = &assets.background_settings;
+
+    match assets.graphics_settings.starfield {
+        StarfieldMode::Shader => {
+            // Every layer after the first is added on top, further forward so they're drawn in order
+            for (i, layer) in settings.layers.iter().enumerate() {
+                let material = assets.starfields.add(StarfieldMaterial {
+                    parallax_factor: layer.parallax_factor,
+                    density: layer.density,
+                    tint: layer.tint,
+                    twinkle_speed: settings.twinkle_speed,
+                    palette: settings.palette,
+                    additive: i > 0,
+                    ..Default::default()
+                });
+                commands.spawn((
+                    StarfieldBundle {
+                        mesh: mesh.clone(),
+                        material,
+                        transform: Transform::from_xyz(0.0, 0.0, -1.0 + i as f32 * 0.01),
+                        ..Default::default()
+                    },
+                    BackgroundLayer {
+                        base_tint: layer.tint,
+                        base_density: layer.density,
+                    },
+                ));
+            }
+
+            if let Some(nebula) = &settings.nebula {
+                commands.spawn((
+                    StarfieldMesh,
+                    MaterialMeshBundle {
+                        mesh,
+                        material: assets.nebulae.add(NebulaMaterial::from(nebula)),
+                        transform: Transform::from_xyz(0.0, 0.0, -0.5),
+                        ..Default::default()
+                    },
+                    RenderLayers::layer(3),
+                    BackgroundLayer {
+                        base_tint: nebula.color,
+                        base_density: nebula.strength,
+                    },
+                ));
+            }
+        }
+        StarfieldMode::Static => {
+            for (i, layer) in settings.layers.iter().enumerate() {
+                let image = BasicStarField {
+                    num_stars: (600.0 * layer.density) as u32,
+                    tint: layer.tint,
+                    seed: i as u64,
+                    ..Default::default()
+                }
+                .generate();
+                spawn_parallax_layer(
+                    commands,
+                    &mut assets.meshes,
+                    &mut assets.materials,
+                    assets.images.add(image),
+                    ParallaxLayer {
+                        factor: layer.parallax_factor,
+                        tile_size: 64.0,
+                    },
+                    -1.0 + i as f32 * 0.01,
+                );
+            }
+        }
+    }
+
+    for (i, image) in settings.images.iter().enumerate() {
+        spawn_parallax_layer(
+            commands,
+            &mut assets.meshes,
+            &mut assets.materials,
+            assets.asset_server.load(image.path.as_str()),
+            ParallaxLayer {
+                factor: image.parallax_factor,
+                tile_size: image.tile_size,
+            },
+            -0.4 + i as f32 * 0.01,
+        );
+    }
+}
+
+/// Swaps the background over when the starfield setting is changed mid-game
+#[allow(clippy::type_complexity)]
+fn rebuild_background(
+    mut commands: Commands,
+    layer_query: Query<Entity, Or<(With<StarfieldMesh>, With<ParallaxLayer>)>>,
+    starfield_camera_query: Query<(), With<StarfieldCamera>>,
+    mut assets: BackgroundAssets,
+) {
+    let changed =
+        assets.graphics_settings.is_changed() || assets.background_settings.is_changed();
+    // Nothing to rebuild outside of a game
+    if !changed || starfield_camera_query.is_empty() {
+        return;
+    }
+    debug!(
+        "Rebuilding background for {:?}",
+        assets.graphics_settings.starfield
+    );
+    for e in &layer_query {
+        commands.entity(e).despawn_recursive();
+    }
+    spawn_background(&mut commands, &mut assets);
+}
+
+/// How the sky looks during a stage. It gets stranger the closer the player gets to Continuum
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackgroundTheme {
+    /// Multiplies the colour of every starfield layer and the nebula
+    pub tint: Color,
+    /// Multiplies the star density of every layer and the strength of the nebula
+    pub density: f32,
+    pub bloom: f32,
+    pub ambient_light: Color,
+}
+
+impl BackgroundTheme {
+    pub fn for_stage(stage: ProgressStages) -> Self {
+        match stage {
+            ProgressStages::None | ProgressStages::Exploration | ProgressStages::GunAndHeat => {
+                Self {
+                    tint: Color::WHITE,
+                    density: 1.0,
+                    bloom: 0.3,
+                    ambient_light: Color::WHITE,
+                }
+            }
+            ProgressStages::CollectExotic => Self {
+                tint: Color::rgb(1.0, 0.9, 1.1),
+                density: 1.1,
+                bloom: 0.35,
+                ambient_light: Color::rgb(1.0, 0.95, 1.0),
+            },
+            ProgressStages::ShieldAndStrange => Self {
+                tint: Color::rgb(0.9, 1.2, 1.3),
+                density: 1.25,
+                bloom: 0.4,
+                ambient_light: Color::rgb(0.9, 1.0, 1.1),
+            },
+            ProgressStages::Continuum | ProgressStages::End => Self {
+                tint: Color::rgb(1.4, 0.8, 1.6),
+                density: 1.5,
+                bloom: 0.5,
+                ambient_light: Color::rgb(1.1, 0.85, 1.2),
+            },
+        }
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp_color = |a: Color, b: Color| -> Color {
+            Vec4::from(a.as_rgba_f32())
+                .lerp(Vec4::from(b.as_rgba_f32()), t)
+                .to_array()
+                .into()
+        };
+        Self {
+            tint: lerp_color(self.tint, other.tint),
+            density: self.density + (other.density - self.density) * t,
+            bloom: self.bloom + (other.bloom - self.bloom) * t,
+            ambient_light: lerp_color(self.ambient_light, other.ambient_light),
+        }
+    }
+}
+
+/// Blends the background from one stage's theme to the next
+#[derive(Resource, Debug)]
+pub struct BackgroundThemeTransition {
+    from: BackgroundTheme,
+    to: BackgroundTheme,
+    timer: Timer,
+}
+
+impl BackgroundThemeTransition {
+    pub fn current(&self) -> BackgroundTheme {
+        self.from.lerp(&self.to, self.timer.percent())
+    }
+}
+
+impl Default for BackgroundThemeTransition {
+    fn default() -> Self {
+        let theme = BackgroundTheme::for_stage(ProgressStages::None);
+        let mut timer = Timer::from_seconds(8.0, TimerMode::Once);
+        timer.tick(timer.duration());
+        Self {
+            from: theme,
+            to: theme,
+            timer,
+        }
+    }
+}
+
+fn start_background_theme_transition(
+    stage: Res<State<ProgressStages>>,
+    mut transition: ResMut<BackgroundThemeTransition>,
+) {
+    if !stage.is_changed() {
+        return;
+    }
+    let to = BackgroundTheme::for_stage(stage.0);
+    if to == transition.to {
+        return;
+    }
+    debug!("Shifting the background to {to:?}");
+    transition.from = transition.current();
+    transition.to = to;
+    transition.timer.reset();
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn apply_background_theme(
+    mut transition: ResMut<BackgroundThemeTransition>,
+    layer_query: Query<(
+        &BackgroundLayer,
+        Option<&Handle<StarfieldMaterial>>,
+        Option<&Handle<NebulaMaterial>>,
+    )>,
+    added_query: Query<(), Added<BackgroundLayer>>,
+    mut bloom_query: Query<&mut BloomSettings, With<StarfieldCamera>>,
+    mut starfields: ResMut<Assets<StarfieldMaterial>>,
+    mut nebulae: ResMut<Assets<NebulaMaterial>>,
+    mut ambient_light: ResMut<AmbientLight>,
+    time: Res<Time>,
+) {
+    // Only touch the materials while something is changing
+    if transition.timer.finished() && added_query.is_empty() {
+        return;
+    }
+    transition.timer.tick(time.delta());
+    let theme = transition.current();
+
+    for (layer, starfield, nebula) in &layer_query {
+        let tint = Color::from(
+            Vec4::from(layer.base_tint.as_rgba_f32()) * Vec4::from(theme.tint.as_rgba_f32()),
+        );
+        let density = layer.base_density * theme.density;
+        if let Some(material) = starfield.and_then(|h| starfields.get_mut(h)) {
+            material.tint = tint;
+            material.density = density;
+        }
+        if let Some(material) = nebula.and_then(|h| nebulae.get_mut(h)) {
+            material.color = tint;
+            material.strength = density;
+        }
+    }
+    for mut bloom in &mut bloom_query {
+        bloom.intensity = theme.bloom;
+    }
+    ambient_light.color = theme.ambient_light;
+}
+
+fn reset_background_theme(
+    mut transition: ResMut<BackgroundThemeTransition>,
+    mut ambient_light: ResMut<AmbientLight>,
+) {
+    *transition = BackgroundThemeTransition::default();
+    ambient_light.color = transition.current().ambient_light;
+}
+
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BackgroundSettings>()
+            .init_resource::<GraphicsSettings>()
+            .add_plugin(MaterialPlugin::<NebulaMaterial>::default())
+            .init_resource::<BackgroundThemeTransition>()
+            .add_system(rebuild_background)
+            .add_system(update_nebula_uniforms.in_set(OnUpdate(GameState::InGame)))
+            .add_systems(
+                (start_background_theme_transition, apply_background_theme)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(reset_background_theme.in_schedule(OnEnter(GameState::MainMenu)));
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_system(reload_background_settings.before(rebuild_background));
+    }
+}
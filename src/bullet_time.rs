@@ -0,0 +1,140 @@
+use bevy::{prelude::*, render::view::RenderLayers};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    game_time::GameTime,
+    input::Action,
+    inventory::{Inventory, Reagent},
+    player::LeadPlayer,
+    screen_effects::{FullscreenOverlay, VignettePulse},
+    state::GameState,
+    ui::UIMarker,
+};
+
+/// How fast the world runs at the slowest point of bullet time
+const TIME_SCALE: f32 = 0.3;
+/// Drained in real time, so slowing down doesn't make it last longer
+const STRANGE_PER_SECOND: f32 = 1.0;
+/// How long it takes to slow down and speed back up
+const EASE_SECONDS: f32 = 0.25;
+/// There's no post processing to actually desaturate the screen, so a grey wash stands in
+const WASH_COLOR: Color = Color::rgba(0.55, 0.6, 0.7, 0.35);
+const BULLET_TIME_VIGNETTE: Color = Color::rgb(0.5, 0.6, 0.8);
+
+/// Slows the world down while the player keeps moving and aiming at full speed. Burns through
+/// Strange matter while it's on
+#[derive(Resource, Debug, Default)]
+pub struct BulletTime {
+    pub active: bool,
+    /// How far into slow motion things are, from 0 to 1
+    amount: f32,
+}
+
+#[derive(Component, Debug, Default)]
+struct BulletTimeWash;
+
+fn reset_bullet_time(mut bullet_time: ResMut<BulletTime>, mut game_time: ResMut<GameTime>) {
+    *bullet_time = BulletTime::default();
+    game_time.set_time_scale(1.0);
+}
+
+fn setup_bullet_time_wash(mut commands: Commands) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::NONE,
+                custom_size: Some(Vec2::ONE),
+                ..Default::default()
+            },
+            // Between the heat haze and the vignette
+            transform: Transform::from_xyz(0.0, 0.0, 0.05),
+            ..Default::default()
+        },
+        BulletTimeWash,
+        FullscreenOverlay,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+fn toggle_bullet_time(
+    player_query: Query<(&ActionState<Action>, &Inventory), LeadPlayer>,
+    mut bullet_time: ResMut<BulletTime>,
+) {
+    let Ok((action_state, inventory)) = player_query.get_single() else { return; };
+    if !action_state.just_pressed(Action::BulletTime) {
+        return;
+    }
+    if bullet_time.active {
+        bullet_time.active = false;
+    } else if inventory.reagent(Reagent::STRANGE).current() > 0.0 {
+        bullet_time.active = true;
+    } else {
+        debug!("No Strange matter for bullet time");
+    }
+}
+
+fn drain_strange_matter(
+    mut player_query: Query<&mut Inventory, LeadPlayer>,
+    mut bullet_time: ResMut<BulletTime>,
+    game_time: Res<GameTime>,
+) {
+    if !bullet_time.active {
+        return;
+    }
+    let Ok(mut inventory) = player_query.get_single_mut() else { return; };
+    let cost = STRANGE_PER_SECOND * game_time.real_delta_seconds();
+    let strange = inventory.reagent_mut(Reagent::STRANGE);
+    if strange.current() <= cost {
+        strange.add(-strange.current());
+        bullet_time.active = false;
+        debug!("Ran out of Strange matter, bullet time is over");
+    } else {
+        strange.add(-cost);
+    }
+}
+
+fn apply_bullet_time(
+    mut bullet_time: ResMut<BulletTime>,
+    mut game_time: ResMut<GameTime>,
+    mut wash_query: Query<&mut Sprite, With<BulletTimeWash>>,
+    mut vignette_writer: EventWriter<VignettePulse>,
+) {
+    let target = if bullet_time.active { 1.0 } else { 0.0 };
+    let step = game_time.real_delta_seconds() / EASE_SECONDS;
+    bullet_time.amount = if bullet_time.amount < target {
+        (bullet_time.amount + step).min(target)
+    } else {
+        (bullet_time.amount - step).max(target)
+    };
+
+    let amount = bullet_time.amount;
+    game_time.set_time_scale(1.0 - (1.0 - TIME_SCALE) * amount);
+    for mut sprite in &mut wash_query {
+        sprite.color = WASH_COLOR.with_a(WASH_COLOR.a() * amount);
+    }
+    if amount > 0.0 {
+        vignette_writer.send(VignettePulse {
+            color: BULLET_TIME_VIGNETTE,
+            strength: 0.4 * amount,
+        });
+    }
+}
+
+pub struct BulletTimePlugin;
+
+impl Plugin for BulletTimePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BulletTime>()
+            .add_systems(
+                (reset_bullet_time, setup_bullet_time_wash)
+                    .in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_system(reset_bullet_time.in_schedule(OnEnter(GameState::Outro)))
+            .add_systems(
+                (toggle_bullet_time, drain_strange_matter, apply_bullet_time)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
@@ -5,8 +5,9 @@ use bevy::{
     prelude::*,
     render::camera::{CameraRenderGraph, ScalingMode},
 };
+use bevy_rapier2d::prelude::Velocity;
 
-use crate::state::GameState;
+use crate::{game_time::GameTime, state::GameState, util::cosmetic::random_in_circle};
 
 #[derive(Component, Debug, Default)]
 pub struct MainCamera;
@@ -26,6 +27,7 @@ pub struct MainCameraBundle {
     pub color_grading: bevy::render::view::ColorGrading,
     pub bloom_settings: BloomSettings,
     pub smooth_follow: SmoothFollow,
+    pub camera_shake: CameraShake,
     pub main_camera: MainCamera,
 }
 
@@ -48,10 +50,8 @@ impl Default for MainCameraBundle {
             transform: Transform::from_xyz(0.0, 0.0, 10.0).looking_to(Vec3::NEG_Z, Vec3::Y),
             global_transform: Default::default(),
             camera_3d: Camera3d {
-                #[cfg(not(target_arch = "wasm32"))]
+                // The starfield camera has already drawn the background
                 clear_color: ClearColorConfig::None,
-                #[cfg(target_arch = "wasm32")]
-                clear_color: ClearColorConfig::Custom(Color::BLACK),
                 ..Default::default()
             },
             tonemapping: Tonemapping::AcesFitted,
@@ -63,6 +63,7 @@ impl Default for MainCameraBundle {
                 ..Default::default()
             },
             smooth_follow: Default::default(),
+            camera_shake: Default::default(),
             main_camera: Default::default(),
         }
     }
@@ -74,6 +75,23 @@ pub struct SmoothFollow {
     pub offset: Vec3,
     pub focus_radius: f32,
     pub focus_centering: f32,
+    /// How many seconds of the target's travel the camera looks ahead by. 0 turns it off
+    pub lead_factor: f32,
+    /// The furthest the camera will look ahead of the target
+    pub max_lead: f32,
+    /// Something else to keep on screen along with the target, like an objective
+    pub secondary_target: Option<Entity>,
+    /// The secondary target is only framed while it's within this distance of the target
+    pub secondary_range: f32,
+    /// The second player in co-op. They're framed along with the target however far apart they
+    /// get, and take the place of the secondary target
+    pub partner: Option<Entity>,
+    /// The zoom when there's nothing else to frame
+    pub base_scale: f32,
+    /// Multiplies `base_scale`, for zooming out without losing track of the usual zoom
+    pub zoom: f32,
+    /// Extra room left around the target and the secondary target when zooming out to fit both
+    pub framing_margin: f32,
 }
 
 impl Default for SmoothFollow {
@@ -83,6 +101,14 @@ impl Default for SmoothFollow {
             offset: Vec3::new(0.0, 0.0, 10.0),
             focus_radius: 1.0,
             focus_centering: 0.5,
+            lead_factor: 0.4,
+            max_lead: 6.0,
+            secondary_target: None,
+            secondary_range: 40.0,
+            partner: None,
+            base_scale: 15.0,
+            zoom: 1.0,
+            framing_margin: 4.0,
         }
     }
 }
@@ -92,10 +118,14 @@ pub struct FocusPoint {
     pub offset: Vec3,
 }
 
+#[allow(clippy::type_complexity)]
 fn follow_target(
     mut query: Query<(&SmoothFollow, &mut Transform)>,
-    target_query: Query<(&Transform, Option<&FocusPoint>), Without<SmoothFollow>>,
-    time: Res<Time>,
+    target_query: Query<
+        (&Transform, Option<&FocusPoint>, Option<&Velocity>),
+        Without<SmoothFollow>,
+    >,
+    game_time: Res<GameTime>,
 ) {
     for (smooth_follow, mut transform) in &mut query {
         // If the camera doesn't have a target, give up
@@ -104,21 +134,30 @@ fn follow_target(
         };
         // if the target the camera is pointing to doesn't exist, give up
         // TODO: maybe clear the camera's focus in this case?
-        let Ok((target_transform, focus_point)) = target_query.get(target_entity) else {
+        let Ok((target_transform, focus_point, velocity)) = target_query.get(target_entity) else {
             continue;
         };
 
-        let focus = if let Some(focus_point) = focus_point {
+        let mut focus = if let Some(focus_point) = focus_point {
             target_transform.translation + focus_point.offset
         } else {
             target_transform.translation
         };
+        // Lead in the direction of travel, so there's more warning of what's coming up at speed
+        if let Some(velocity) = velocity {
+            let lead = (velocity.linvel * smooth_follow.lead_factor)
+                .clamp_length_max(smooth_follow.max_lead);
+            focus += lead.extend(0.0);
+        }
+        if let Some(secondary) = secondary_focus(smooth_follow, target_transform, &target_query) {
+            focus = (focus + secondary) / 2.0;
+        }
         // Now `focus` holds the point we want the camera to follow, however we got it
         if smooth_follow.focus_radius > 0.0 {
             let dist = Vec3::distance(focus + smooth_follow.offset, transform.translation);
             let mut t = 1.0;
             if dist > 0.01 && smooth_follow.focus_centering > 0.0 {
-                t = f32::powf(1.0 - smooth_follow.focus_centering, time.delta_seconds());
+                t = f32::powf(1.0 - smooth_follow.focus_centering, game_time.real_delta_seconds());
             }
             if dist > smooth_follow.focus_radius {
                 transform.translation = Vec3::lerp(
@@ -137,10 +176,105 @@ fn follow_target(
     }
 }
 
+/// Where the partner or the secondary target is, if there's one to frame along with the target
+#[allow(clippy::type_complexity)]
+fn secondary_focus(
+    smooth_follow: &SmoothFollow,
+    target_transform: &Transform,
+    target_query: &Query<
+        (&Transform, Option<&FocusPoint>, Option<&Velocity>),
+        Without<SmoothFollow>,
+    >,
+) -> Option<Vec3> {
+    if let Some(partner) = smooth_follow.partner {
+        if let Ok((partner_transform, _, _)) = target_query.get(partner) {
+            return Some(partner_transform.translation);
+        }
+    }
+    let secondary_entity = smooth_follow.secondary_target?;
+    let (secondary_transform, _, _) = target_query.get(secondary_entity).ok()?;
+    let distance = secondary_transform
+        .translation
+        .truncate()
+        .distance(target_transform.translation.truncate());
+    (distance <= smooth_follow.secondary_range).then_some(secondary_transform.translation)
+}
+
+/// Zooms out to keep both the target and the secondary target on screen, and back in after
+#[allow(clippy::type_complexity)]
+fn frame_secondary_target(
+    mut query: Query<(&SmoothFollow, &Camera, &mut Projection)>,
+    target_query: Query<
+        (&Transform, Option<&FocusPoint>, Option<&Velocity>),
+        Without<SmoothFollow>,
+    >,
+    game_time: Res<GameTime>,
+) {
+    for (smooth_follow, camera, mut projection) in &mut query {
+        let Projection::Orthographic(orthographic) = projection.as_mut() else { continue; };
+        let Some(size) = camera.logical_viewport_size() else { continue; };
+        let aspect_ratio = size.x / size.y;
+
+        let mut scale = smooth_follow.base_scale * smooth_follow.zoom;
+        if let Some(target_entity) = smooth_follow.target {
+            if let Ok((target_transform, _, _)) = target_query.get(target_entity) {
+                if let Some(secondary) =
+                    secondary_focus(smooth_follow, target_transform, &target_query)
+                {
+                    // The scale is half the height of the view, and both are half the gap away
+                    // from the middle
+                    let half_gap = (secondary - target_transform.translation).truncate().abs() / 2.0;
+                    let needed = f32::max(half_gap.y, half_gap.x / aspect_ratio)
+                        + smooth_follow.framing_margin;
+                    scale = scale.max(needed);
+                }
+            }
+        }
+
+        let t = f32::powf(1.0 - smooth_follow.focus_centering, game_time.real_delta_seconds());
+        orthographic.scale = scale + (orthographic.scale - scale) * t;
+    }
+}
+
+#[derive(Component, Debug, Default)]
+pub struct CameraShake {
+    /// The furthest the camera can be knocked from where it would otherwise be. 0 means no shake
+    pub intensity: f32,
+    offset: Vec3,
+}
+
+// The shake offset gets undone before `follow_target` runs, so it never accumulates
+fn remove_camera_shake(mut query: Query<(&mut CameraShake, &mut Transform)>) {
+    for (mut camera_shake, mut transform) in &mut query {
+        transform.translation -= camera_shake.offset;
+        camera_shake.offset = Vec3::ZERO;
+    }
+}
+
+fn apply_camera_shake(mut query: Query<(&mut CameraShake, &mut Transform)>) {
+    for (mut camera_shake, mut transform) in &mut query {
+        if camera_shake.intensity <= 0.0 {
+            continue;
+        }
+        let offset = random_in_circle(camera_shake.intensity);
+        camera_shake.offset = Vec3::new(offset.x, offset.y, 0.0);
+        transform.translation += camera_shake.offset;
+    }
+}
+
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(follow_target.in_set(OnUpdate(GameState::InGame)));
+        app.add_systems(
+            (
+                remove_camera_shake,
+                follow_target,
+                frame_secondary_target,
+                apply_camera_shake,
+            )
+                .chain()
+                .in_set(OnUpdate(GameState::InGame)),
+        );
     }
 }
@@ -0,0 +1,174 @@
+use bevy::{prelude::*, render::view::RenderLayers};
+use leafwing_input_manager::prelude::ToggleActions;
+
+use crate::{
+    camera::{MainCamera, SmoothFollow},
+    game_time::GameTime,
+    input::Action,
+    state::{GameState, ProgressStages},
+    ui::{CustomUICamera, UIMarker},
+};
+
+const SHOT_SECONDS: f32 = 4.0;
+/// The part of the shot spent zooming out. The rest is a slow push back in
+const ZOOM_OUT_FRACTION: f32 = 0.25;
+/// How far the camera zooms out, as a multiple of the usual zoom
+const ZOOM_OUT: f32 = 1.6;
+/// The player gets control back after this much of the shot
+const INPUT_LOCK_FRACTION: f32 = 0.4;
+/// The height of each letterbox bar, as a fraction of the screen
+const LETTERBOX_HEIGHT: f32 = 0.1;
+
+/// Takes over the main camera's zoom for a short scripted move when a new stage starts.
+/// `SmoothFollow` still keeps the player in the middle of the shot
+#[derive(Resource, Debug, Default)]
+pub struct CameraDirector {
+    shot: Option<Timer>,
+}
+
+impl CameraDirector {
+    pub fn in_shot(&self) -> bool {
+        self.shot.is_some()
+    }
+
+    /// How far through the shot we are, from 0 to 1
+    fn progress(&self) -> Option<f32> {
+        self.shot.as_ref().map(|timer| timer.percent())
+    }
+}
+
+#[derive(Component, Debug)]
+enum LetterboxBar {
+    Top,
+    Bottom,
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn reset_camera_director(
+    mut director: ResMut<CameraDirector>,
+    mut toggle_actions: ResMut<ToggleActions<Action>>,
+    mut camera_query: Query<&mut SmoothFollow, With<MainCamera>>,
+) {
+    director.shot = None;
+    toggle_actions.enabled = true;
+    for mut smooth_follow in &mut camera_query {
+        smooth_follow.zoom = 1.0;
+    }
+}
+
+fn setup_letterbox(mut commands: Commands) {
+    for bar in [LetterboxBar::Top, LetterboxBar::Bottom] {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::BLACK,
+                    custom_size: Some(Vec2::ONE),
+                    ..Default::default()
+                },
+                // In front of the rest of the HUD
+                transform: Transform::from_xyz(0.0, 0.0, 20.0),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            bar,
+            UIMarker,
+            RenderLayers::layer(1),
+        ));
+    }
+}
+
+fn start_stage_transition_shot(
+    stage: Res<State<ProgressStages>>,
+    mut director: ResMut<CameraDirector>,
+) {
+    if !stage.is_changed() {
+        return;
+    }
+    // The start of a run already has the intro
+    if matches!(stage.0, ProgressStages::None | ProgressStages::Exploration) {
+        return;
+    }
+    debug!("Starting the camera move for {:?}", stage.0);
+    director.shot = Some(Timer::from_seconds(SHOT_SECONDS, TimerMode::Once));
+}
+
+fn direct_camera(
+    mut director: ResMut<CameraDirector>,
+    mut toggle_actions: ResMut<ToggleActions<Action>>,
+    mut camera_query: Query<&mut SmoothFollow, With<MainCamera>>,
+    game_time: Res<GameTime>,
+) {
+    let Some(timer) = director.shot.as_mut() else { return; };
+    timer.tick(game_time.delta());
+    if timer.finished() {
+        director.shot = None;
+        toggle_actions.enabled = true;
+        for mut smooth_follow in &mut camera_query {
+            smooth_follow.zoom = 1.0;
+        }
+        return;
+    }
+
+    let progress = timer.percent();
+    let zoom_out = smoothstep(0.0, ZOOM_OUT_FRACTION, progress)
+        * (1.0 - smoothstep(ZOOM_OUT_FRACTION, 1.0, progress));
+    for mut smooth_follow in &mut camera_query {
+        smooth_follow.zoom = 1.0 + (ZOOM_OUT - 1.0) * zoom_out;
+    }
+    let locked = progress < INPUT_LOCK_FRACTION;
+    if toggle_actions.enabled == locked {
+        toggle_actions.enabled = !locked;
+    }
+}
+
+fn update_letterbox(
+    mut bar_query: Query<
+        (&LetterboxBar, &mut Transform, &mut Visibility),
+        Without<CustomUICamera>,
+    >,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    director: Res<CameraDirector>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else { return; };
+    let Some(size) = ui_camera.logical_viewport_size() else { return; };
+    let Some(progress) = director.progress() else {
+        for (_, _, mut visibility) in &mut bar_query {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    };
+
+    // Slides in quickly and out a little slower
+    let amount = smoothstep(0.0, 0.1, progress) * (1.0 - smoothstep(0.85, 1.0, progress));
+    let height = size.y * LETTERBOX_HEIGHT * amount;
+    for (bar, mut transform, mut visibility) in &mut bar_query {
+        *visibility = Visibility::Visible;
+        let sign = match bar {
+            LetterboxBar::Top => 1.0,
+            LetterboxBar::Bottom => -1.0,
+        };
+        transform.scale = Vec3::new(size.x, height, 1.0);
+        transform.translation.y = sign * (size.y - height) / 2.0;
+    }
+}
+
+pub struct CameraDirectorPlugin;
+
+impl Plugin for CameraDirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraDirector>()
+            .add_systems(
+                (reset_camera_director, setup_letterbox).in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_system(reset_camera_director.in_schedule(OnEnter(GameState::Outro)))
+            .add_systems(
+                (start_stage_transition_shot, direct_camera, update_letterbox)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
@@ -0,0 +1,637 @@
+use bevy::prelude::*;
+
+use crate::{
+    cli::{stage_from_id, LaunchOptions},
+    game_time::GameTime,
+    heat::Heat,
+    inventory::{Inventory, Reagent, ReagentRegistry},
+    reaction::{Reaction, Reactions},
+    shield::ShieldEmitter,
+    sound::SoundEvent,
+    state::ProgressStages,
+    ui::EnabledControls,
+    util::{load_config_file, parse_config_lines, warn_unknown_config},
+    weapon::{CargoDumper, MainGun},
+};
+
+const CAMPAIGN_FILE: &str = "assets/campaign.txt";
+const NEW_GAME_PLUS_FILE: &str = "assets/campaign_plus.txt";
+
+/// The stages a campaign can be made of. The escape at the end isn't one of them, it always
+/// comes last
+const CAMPAIGN_STAGES: [ProgressStages; 5] = [
+    ProgressStages::Exploration,
+    ProgressStages::GunAndHeat,
+    ProgressStages::CollectExotic,
+    ProgressStages::ShieldAndStrange,
+    ProgressStages::Continuum,
+];
+
+/// Something the ship can do that's switched off at the start of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unlock {
+    Gun,
+    Heat,
+    /// Shows where on the heat bar reactions start
+    HeatThreshold,
+    Shield,
+    Cargo,
+}
+
+impl Unlock {
+    fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "gun" => Some(Self::Gun),
+            "heat" => Some(Self::Heat),
+            "heat-threshold" => Some(Self::HeatThreshold),
+            "shield" => Some(Self::Shield),
+            "cargo" => Some(Self::Cargo),
+            _ => None,
+        }
+    }
+
+    /// How the stage banner announces it
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gun => "MAIN CANNON",
+            Self::Heat => "HEAT MONITOR",
+            Self::HeatThreshold => "REACTION THRESHOLD",
+            Self::Shield => "SHIELD EMITTER",
+            Self::Cargo => "CARGO DUMP",
+        }
+    }
+
+    pub fn apply(
+        &self,
+        heat: &mut Heat,
+        main_gun: &mut MainGun,
+        shield_emitter: &mut ShieldEmitter,
+        cargo_dumper: &mut CargoDumper,
+    ) {
+        match self {
+            Self::Gun => main_gun.enabled = true,
+            Self::Heat => heat.set_enabled(true),
+            Self::HeatThreshold => heat.set_threshold_visible(true),
+            Self::Shield => shield_emitter.enabled = true,
+            Self::Cargo => cargo_dumper.enabled = true,
+        }
+    }
+}
+
+fn control_from_id(id: &str) -> Option<EnabledControls> {
+    match id {
+        "move" => Some(EnabledControls::Move),
+        "look" => Some(EnabledControls::Look),
+        "shoot" => Some(EnabledControls::Shoot),
+        "shield" => Some(EnabledControls::Shield),
+        "dump" => Some(EnabledControls::Dump),
+        _ => None,
+    }
+}
+
+/// Part of what it takes to finish a stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageRule {
+    pub reagent: Reagent,
+    /// How full the reagent's bar has to be
+    pub fraction: f32,
+    /// Whether the bar has to go past `fraction`, rather than just get there
+    pub above: bool,
+    /// Whether `fraction` is marked on the bar during the stage
+    pub marked: bool,
+}
+
+impl StageRule {
+    /// What the player has to do, the way the pause menu puts it
+    pub fn describe(&self, registry: &ReagentRegistry) -> String {
+        let name = &registry.get(self.reagent).name;
+        let percent = self.fraction * 100.0;
+        if self.above {
+            format!("GET {name} ABOVE {percent:.0}%")
+        } else {
+            format!("FILL {name} TO {percent:.0}%")
+        }
+    }
+
+    pub fn met(&self, inventory: &Inventory) -> bool {
+        let fraction = inventory.reagent(self.reagent).fraction();
+        if self.above {
+            fraction > self.fraction
+        } else {
+            fraction >= self.fraction
+        }
+    }
+}
+
+/// One stage of a campaign, as it's written in the campaign file
+#[derive(Debug, Clone)]
+pub struct CampaignStage {
+    pub stage: ProgressStages,
+    /// Id of the text pack hint shown during the stage
+    pub hint: Option<String>,
+    pub unlocks: Vec<Unlock>,
+    pub controls: EnabledControls,
+    pub reactions: Vec<Reaction>,
+    /// The stage is over once every rule is met. A stage without any goes on forever
+    pub rules: Vec<StageRule>,
+    /// Reagents thrown away when the stage is over
+    pub empty: Vec<Reagent>,
+    /// How full each bar is when a run starts from a later chapter
+    pub carry: Vec<(Reagent, f32)>,
+}
+
+impl CampaignStage {
+    fn new(stage: ProgressStages) -> Self {
+        Self {
+            stage,
+            hint: None,
+            unlocks: Vec::new(),
+            controls: EnabledControls::empty(),
+            reactions: Vec::new(),
+            rules: Vec::new(),
+            empty: Vec::new(),
+            carry: Vec::new(),
+        }
+    }
+
+    /// Whether any of the inventories has everything the stage asks for
+    pub fn finished<'a>(&self, mut inventories: impl Iterator<Item = &'a Inventory>) -> bool {
+        !self.rules.is_empty()
+            && inventories.any(|inventory| self.rules.iter().all(|rule| rule.met(inventory)))
+    }
+
+    /// Whether the stage is waiting on the player to collect some of `reagent`, either because
+    /// one of its rules asks for it or because one of its reactions uses it up
+    pub fn needs(&self, reagent: Reagent) -> bool {
+        self.rules.iter().any(|rule| rule.reagent == reagent)
+            || self.reactions.iter().any(|reaction| {
+                reaction.reagent1 == reagent || reaction.reagent2 == Some(reagent)
+            })
+    }
+}
+
+fn parse_rule(marked: bool, value: &str, registry: &ReagentRegistry) -> Option<StageRule> {
+    let parts: Vec<_> = value.split_whitespace().collect();
+    let (reagent, above, fraction) = match parts[..] {
+        [reagent, fraction] if marked => (reagent, false, fraction),
+        [reagent, "above", fraction] if !marked => (reagent, true, fraction),
+        [reagent, "reaches", fraction] if !marked => (reagent, false, fraction),
+        _ => return None,
+    };
+    Some(StageRule {
+        reagent: registry.find(reagent)?,
+        fraction: fraction.parse().ok()?,
+        above,
+        marked,
+    })
+}
+
+/// Settings for the whole campaign rather than one stage, from the lines before the first stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct CampaignSettings {
+    /// Every reagent's bar holds this many times as much, so each goal takes more to reach
+    pub limit_scale: f32,
+    /// How fast the ship cools down, compared to usual
+    pub heat_decay_scale: f32,
+    /// The chance of each rock near one that's shot catching fire. `None` leaves it as usual
+    pub volatile_chance: Option<f32>,
+}
+
+impl Default for CampaignSettings {
+    fn default() -> Self {
+        Self {
+            limit_scale: 1.0,
+            heat_decay_scale: 1.0,
+            volatile_chance: None,
+        }
+    }
+}
+
+/// The order of the stages and what happens in each of them, read from `assets/campaign.txt`.
+/// Other campaigns can be shipped as data and played with `--campaign`
+#[derive(Resource, Debug, Clone)]
+pub struct Campaign {
+    stages: Vec<CampaignStage>,
+    settings: CampaignSettings,
+}
+
+impl Campaign {
+    /// The file given with `--campaign`, or the one next to the game. Falls back to the bundled
+    /// campaign if the file is missing or broken
+    pub fn load(registry: &ReagentRegistry, options: Option<&LaunchOptions>) -> Self {
+        let chosen = options.and_then(|options| options.campaign.as_ref());
+        let path = chosen.map_or_else(|| CAMPAIGN_FILE.into(), |path| path.display().to_string());
+        #[cfg(not(target_arch = "wasm32"))]
+        if chosen.is_some_and(|path| !path.exists()) {
+            error!("Couldn't find {path}");
+        }
+        load_config_file(&path, include_str!("../assets/campaign.txt"), |text| {
+            Self::from_text(text, registry)
+        })
+    }
+
+    /// The harder campaign unlocked by finishing the game, see `NewGamePlus`. Falls back to the
+    /// bundled copy the same way `load` does
+    pub fn load_new_game_plus(registry: &ReagentRegistry) -> Self {
+        load_config_file(
+            NEW_GAME_PLUS_FILE,
+            include_str!("../assets/campaign_plus.txt"),
+            |text| Self::from_text(text, registry),
+        )
+    }
+
+    /// Each line is a keyword followed by its values, and belongs to the stage above it. Lines
+    /// that can't be read are skipped, but the stages themselves have to make sense
+    pub fn from_text(text: &str, registry: &ReagentRegistry) -> Result<Self, String> {
+        let mut stages: Vec<CampaignStage> = Vec::new();
+        let mut settings = CampaignSettings::default();
+        for (key, value) in parse_config_lines(text) {
+            if key == "stage" {
+                let stage = stage_from_id(value.trim())
+                    .filter(|stage| CAMPAIGN_STAGES.contains(stage))
+                    .ok_or_else(|| format!("{value:?} can't be a stage"))?;
+                if stages
+                    .iter()
+                    .any(|campaign_stage| campaign_stage.stage == stage)
+                {
+                    return Err(format!("stage {value:?} is there twice"));
+                }
+                stages.push(CampaignStage::new(stage));
+                continue;
+            }
+            let Some(current) = stages.last_mut() else {
+                let parsed = value.trim().parse::<f32>().ok();
+                match (key, parsed) {
+                    ("limit-scale", Some(scale)) => settings.limit_scale = scale,
+                    ("heat-decay", Some(scale)) => settings.heat_decay_scale = scale,
+                    ("volatile", Some(chance)) => settings.volatile_chance = Some(chance),
+                    ("limit-scale" | "heat-decay" | "volatile", None) => {
+                        warn!("Couldn't read {key} {value:?}")
+                    }
+                    _ => warn!("Campaign setting {key:?} comes before the first stage"),
+                }
+                continue;
+            };
+            match key {
+                "hint" => current.hint = Some(value.trim().to_string()),
+                "unlock" => {
+                    for id in value.split_whitespace() {
+                        match Unlock::from_id(id) {
+                            Some(unlock) => current.unlocks.push(unlock),
+                            None => warn!("Unknown unlock {id:?}"),
+                        }
+                    }
+                }
+                "controls" => {
+                    for id in value.split_whitespace() {
+                        match control_from_id(id) {
+                            Some(control) => current.controls |= control,
+                            None => warn!("Unknown control {id:?}"),
+                        }
+                    }
+                }
+                "reaction" => match registry.parse_reaction(value) {
+                    Some(reaction) => current.reactions.push(reaction),
+                    None => warn!("Couldn't read reaction {value:?}"),
+                },
+                "goal" | "advance" => match parse_rule(key == "goal", value, registry) {
+                    Some(rule) => current.rules.push(rule),
+                    None => warn!("Couldn't read {key} {value:?}"),
+                },
+                "empty" => match registry.find(value.trim()) {
+                    Some(reagent) => current.empty.push(reagent),
+                    None => warn!("Unknown reagent {value:?}"),
+                },
+                "carry" => {
+                    let Some((id, fraction)) = value.split_once(' ') else {
+                        warn!("Couldn't read carry {value:?}");
+                        continue;
+                    };
+                    let (Some(reagent), Ok(fraction)) =
+                        (registry.find(id), fraction.trim().parse())
+                    else {
+                        warn!("Couldn't read carry {value:?}");
+                        continue;
+                    };
+                    current.carry.push((reagent, fraction));
+                }
+                _ => warn_unknown_config("campaign setting", key),
+            }
+        }
+        if stages.is_empty() {
+            return Err("there are no stages".into());
+        }
+        Ok(Self { stages, settings })
+    }
+
+    pub fn settings(&self) -> &CampaignSettings {
+        &self.settings
+    }
+
+    pub fn first_stage(&self) -> ProgressStages {
+        self.stages[0].stage
+    }
+
+    pub fn stage(&self, stage: ProgressStages) -> Option<&CampaignStage> {
+        self.stages
+            .iter()
+            .find(|campaign_stage| campaign_stage.stage == stage)
+    }
+
+    /// Every stage, in the order they're played
+    pub fn stages(&self) -> &[CampaignStage] {
+        &self.stages
+    }
+
+    /// The stage after this one, or the escape after the last
+    pub fn next_stage(&self, stage: ProgressStages) -> ProgressStages {
+        self.stages
+            .iter()
+            .skip_while(|campaign_stage| campaign_stage.stage != stage)
+            .nth(1)
+            .map_or(ProgressStages::End, |campaign_stage| campaign_stage.stage)
+    }
+
+    /// The stages played before this one. All of them when it's the escape
+    pub fn stages_before(
+        &self,
+        stage: ProgressStages,
+    ) -> impl Iterator<Item = &CampaignStage> + '_ {
+        self.stages
+            .iter()
+            .take_while(move |campaign_stage| campaign_stage.stage != stage)
+    }
+}
+
+/// The stage that was last set up, so it can be wound down again. By the time `OnExit` runs the
+/// state has already moved on to the next stage
+#[derive(Resource, Debug, Default)]
+struct CurrentCampaignStage(Option<ProgressStages>);
+
+/// How long the stage hangs on after its threshold is reached, so there's time to notice
+const CELEBRATION_SECONDS: f32 = 1.2;
+
+/// Sent when a player's inventory has everything the current stage asks for. The stage moves
+/// on `CELEBRATION_SECONDS` later
+#[derive(Debug, Clone)]
+pub struct StageThresholdReached {
+    /// The reagents the stage's rules are about
+    pub reagents: Vec<Reagent>,
+    pub position: Vec3,
+}
+
+/// The stage whose threshold was reached, and how long until it's left
+#[derive(Resource, Debug, Default)]
+struct StageCelebration(Option<(ProgressStages, Timer)>);
+
+fn load_campaign(
+    mut commands: Commands,
+    registry: Res<ReagentRegistry>,
+    options: Option<Res<LaunchOptions>>,
+) {
+    commands.insert_resource(Campaign::load(&registry, options.as_deref()));
+}
+
+/// Sizes up the bars and the cooling of each ship as it's spawned
+fn apply_campaign_settings(
+    campaign: Res<Campaign>,
+    mut query: Query<(&mut Inventory, &mut Heat), Added<Inventory>>,
+) {
+    let settings = campaign.settings();
+    for (mut inventory, mut heat) in &mut query {
+        let reagents: Vec<Reagent> = inventory.reagents().map(|(reagent, _)| reagent).collect();
+        for reagent in reagents {
+            inventory
+                .reagent_mut(reagent)
+                .scale_limit(settings.limit_scale);
+        }
+        let decay_rate = heat.decay_rate() * settings.heat_decay_scale;
+        heat.set_decay_rate(decay_rate);
+    }
+}
+
+fn enter_campaign_stage(
+    campaign: Res<Campaign>,
+    stage: Res<State<ProgressStages>>,
+    mut current: ResMut<CurrentCampaignStage>,
+    mut query: Query<(
+        &mut Inventory,
+        &mut Heat,
+        &mut MainGun,
+        &mut ShieldEmitter,
+        &mut CargoDumper,
+    )>,
+    mut enabled_controls: ResMut<EnabledControls>,
+    mut reactions: ResMut<Reactions>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+) {
+    let Some(campaign_stage) = campaign.stage(stage.0) else { return; };
+    current.0 = Some(stage.0);
+    debug!("Entering campaign stage {:?}", stage.0);
+
+    for (mut inventory, mut heat, mut main_gun, mut shield_emitter, mut cargo_dumper) in &mut query
+    {
+        for rule in campaign_stage.rules.iter().filter(|rule| rule.marked) {
+            inventory
+                .reagent_mut(rule.reagent)
+                .set_threshold(Some(rule.fraction));
+        }
+        for unlock in &campaign_stage.unlocks {
+            unlock.apply(
+                &mut heat,
+                &mut main_gun,
+                &mut shield_emitter,
+                &mut cargo_dumper,
+            );
+        }
+    }
+    *enabled_controls |= campaign_stage.controls;
+    reactions
+        .reactions
+        .extend(campaign_stage.reactions.iter().cloned());
+    if stage.0 != campaign.first_stage() {
+        sound_event_writer.send(SoundEvent::NextStage);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_campaign_stage(
+    campaign: Res<Campaign>,
+    stage: Res<State<ProgressStages>>,
+    query: Query<(&Inventory, &Transform)>,
+    mut next_stage: ResMut<NextState<ProgressStages>>,
+    mut celebration: ResMut<StageCelebration>,
+    mut threshold_writer: EventWriter<StageThresholdReached>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    game_time: Res<GameTime>,
+) {
+    // Dropped if the stage was changed some other way in the meantime
+    if let Some((celebrated, timer)) = &mut celebration.0 {
+        if *celebrated == stage.0 {
+            if timer.tick(game_time.delta()).finished() {
+                next_stage.set(campaign.next_stage(stage.0));
+                celebration.0 = None;
+            }
+            return;
+        }
+        celebration.0 = None;
+    }
+
+    let Some(campaign_stage) = campaign.stage(stage.0) else { return; };
+    let Some((_, transform)) = query
+        .iter()
+        .find(|(inventory, _)| campaign_stage.finished(std::iter::once(*inventory)))
+    else {
+        return;
+    };
+    threshold_writer.send(StageThresholdReached {
+        reagents: campaign_stage
+            .rules
+            .iter()
+            .map(|rule| rule.reagent)
+            .collect(),
+        position: transform.translation,
+    });
+    sound_event_writer.send(SoundEvent::ThresholdReached);
+    celebration.0 = Some((
+        stage.0,
+        Timer::from_seconds(CELEBRATION_SECONDS, TimerMode::Once),
+    ));
+}
+
+fn exit_campaign_stage(
+    campaign: Res<Campaign>,
+    mut current: ResMut<CurrentCampaignStage>,
+    mut query: Query<&mut Inventory>,
+) {
+    let Some(stage) = current.0.take() else { return; };
+    let Some(campaign_stage) = campaign.stage(stage) else { return; };
+    for mut inventory in &mut query {
+        for rule in campaign_stage.rules.iter().filter(|rule| rule.marked) {
+            inventory.reagent_mut(rule.reagent).set_threshold(None);
+        }
+        for reagent in &campaign_stage.empty {
+            let entry = inventory.reagent_mut(*reagent);
+            entry.add(-entry.current());
+        }
+    }
+}
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentCampaignStage>()
+            .init_resource::<StageCelebration>()
+            .add_event::<StageThresholdReached>()
+            .add_startup_system(load_campaign)
+            .add_system(update_campaign_stage.run_if(resource_exists::<Campaign>()))
+            .add_system(apply_campaign_settings.run_if(resource_exists::<Campaign>()));
+        for stage in CAMPAIGN_STAGES {
+            app.add_system(enter_campaign_stage.in_schedule(OnEnter(stage)))
+                .add_system(exit_campaign_stage.in_schedule(OnExit(stage)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Campaign, String> {
+        Campaign::from_text(text, &ReagentRegistry::default())
+    }
+
+    #[test]
+    fn the_bundled_campaigns_are_valid() {
+        let campaign = parse(include_str!("../assets/campaign.txt")).unwrap();
+        assert_eq!(campaign.first_stage(), ProgressStages::Exploration);
+        assert_eq!(campaign.settings(), &CampaignSettings::default());
+
+        let new_game_plus = parse(include_str!("../assets/campaign_plus.txt")).unwrap();
+        assert!(new_game_plus.settings().limit_scale > 1.0);
+        assert_eq!(new_game_plus.stages().len(), campaign.stages().len());
+    }
+
+    #[test]
+    fn reads_a_stage() {
+        let campaign = parse(
+            "# A comment
+limit-scale 2
+volatile 0.5
+stage collect-exotic
+hint collect-exotic
+unlock gun heat
+controls move shoot
+goal exotic 0.9
+advance minerals above 0
+empty minerals
+carry exotic 0.5
+",
+        )
+        .unwrap();
+        assert_eq!(campaign.settings().limit_scale, 2.0);
+        assert_eq!(campaign.settings().volatile_chance, Some(0.5));
+        let stage = campaign.stage(ProgressStages::CollectExotic).unwrap();
+        assert_eq!(stage.hint.as_deref(), Some("collect-exotic"));
+        assert_eq!(stage.unlocks, vec![Unlock::Gun, Unlock::Heat]);
+        assert_eq!(
+            stage.controls,
+            EnabledControls::Move | EnabledControls::Shoot
+        );
+        assert_eq!(
+            stage.rules,
+            vec![
+                StageRule {
+                    reagent: Reagent::EXOTIC,
+                    fraction: 0.9,
+                    above: false,
+                    marked: true,
+                },
+                StageRule {
+                    reagent: Reagent::MINERALS,
+                    fraction: 0.0,
+                    above: true,
+                    marked: false,
+                },
+            ]
+        );
+        assert_eq!(stage.empty, vec![Reagent::MINERALS]);
+        assert_eq!(stage.carry, vec![(Reagent::EXOTIC, 0.5)]);
+    }
+
+    #[test]
+    fn stages_follow_the_file() {
+        let campaign = parse("stage continuum\nstage exploration\n").unwrap();
+        assert_eq!(campaign.first_stage(), ProgressStages::Continuum);
+        assert_eq!(
+            campaign.next_stage(ProgressStages::Continuum),
+            ProgressStages::Exploration
+        );
+        assert_eq!(
+            campaign.next_stage(ProgressStages::Exploration),
+            ProgressStages::End
+        );
+    }
+
+    #[test]
+    fn stages_need_what_their_rules_and_reactions_use() {
+        let campaign = parse(include_str!("../assets/campaign.txt")).unwrap();
+        let needs = |stage, reagent| campaign.stage(stage).unwrap().needs(reagent);
+        assert!(needs(ProgressStages::Exploration, Reagent::MINERALS));
+        assert!(!needs(ProgressStages::Exploration, Reagent::EXOTIC));
+        assert!(needs(ProgressStages::GunAndHeat, Reagent::EXOTIC));
+        assert!(needs(ProgressStages::ShieldAndStrange, Reagent::STRANGE));
+        assert!(needs(ProgressStages::Continuum, Reagent::EXOTIC));
+        assert!(needs(ProgressStages::Continuum, Reagent::STRANGE));
+        assert!(!needs(ProgressStages::Continuum, Reagent::MINERALS));
+    }
+
+    #[test]
+    fn rejects_campaigns_that_dont_make_sense() {
+        assert!(parse("").is_err());
+        assert!(parse("stage nowhere\n").is_err());
+        assert!(parse("stage end\n").is_err());
+        assert!(parse("stage exploration\nstage exploration\n").is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{
+    save_data::SaveData,
+    state::{GameState, ProgressStages},
+    stats::RunStats,
+    ui::{CustomUICamera, UIMarker},
+};
+
+const HUD_FONT_SIZE: f32 = 30.0;
+const HUD_PADDING: f32 = 10.0;
+
+/// A fixed scenario from the challenges menu. The seed means the same rocks come at the
+/// player every attempt, so results can be compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Challenge {
+    /// Destroy as many rocks as possible in the time limit
+    RockRush,
+    /// Reach the Exotic threshold as quickly as possible, before the shield is unlocked
+    ExoticWithoutShield,
+}
+
+pub const CHALLENGES: [Challenge; 2] = [Challenge::RockRush, Challenge::ExoticWithoutShield];
+
+impl Challenge {
+    /// Used as the key for the best result in the save file, so it mustn't change
+    pub fn id(&self) -> &'static str {
+        match self {
+            Challenge::RockRush => "rock_rush",
+            Challenge::ExoticWithoutShield => "exotic_without_shield",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Challenge::RockRush => "ROCK RUSH",
+            Challenge::ExoticWithoutShield => "NO SHIELD",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Challenge::RockRush => "Destroy 50 rocks in 90 seconds",
+            Challenge::ExoticWithoutShield => "Reach the Exotic threshold without the shield",
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        match self {
+            Challenge::RockRush => 1312,
+            Challenge::ExoticWithoutShield => 2701,
+        }
+    }
+
+    /// Starts the run from this chapter, with everything the chapters before it would give
+    pub fn start_stage(&self) -> ProgressStages {
+        match self {
+            Challenge::RockRush => ProgressStages::GunAndHeat,
+            Challenge::ExoticWithoutShield => ProgressStages::CollectExotic,
+        }
+    }
+
+    pub fn time_limit(&self) -> f32 {
+        match self {
+            Challenge::RockRush => 90.0,
+            Challenge::ExoticWithoutShield => 300.0,
+        }
+    }
+
+    /// The scores needed for bronze, silver and gold
+    fn thresholds(&self) -> [f32; 3] {
+        match self {
+            Challenge::RockRush => [30.0, 40.0, 50.0],
+            // Seconds, so lower is better
+            Challenge::ExoticWithoutShield => [300.0, 180.0, 120.0],
+        }
+    }
+
+    fn higher_is_better(&self) -> bool {
+        match self {
+            Challenge::RockRush => true,
+            Challenge::ExoticWithoutShield => false,
+        }
+    }
+
+    pub fn medal(&self, score: f32) -> Option<Medal> {
+        let [bronze, silver, gold] = self.thresholds();
+        let reached = |threshold: f32| {
+            if self.higher_is_better() {
+                score >= threshold
+            } else {
+                score <= threshold
+            }
+        };
+        if reached(gold) {
+            Some(Medal::Gold)
+        } else if reached(silver) {
+            Some(Medal::Silver)
+        } else if reached(bronze) {
+            Some(Medal::Bronze)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_better(&self, score: f32, than: Option<f32>) -> bool {
+        match than {
+            None => true,
+            Some(best) if self.higher_is_better() => score > best,
+            Some(best) => score < best,
+        }
+    }
+
+    pub fn format_score(&self, score: f32) -> String {
+        match self {
+            Challenge::RockRush => format!("{} ROCKS", score as u32),
+            Challenge::ExoticWithoutShield => {
+                let seconds = score as u32;
+                format!("{:02}:{:02}", seconds / 60, seconds % 60)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl Medal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Medal::Bronze => "BRONZE",
+            Medal::Silver => "SILVER",
+            Medal::Gold => "GOLD",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Medal::Bronze => Color::rgb(0.8, 0.5, 0.2),
+            Medal::Silver => Color::rgb(0.75, 0.75, 0.8),
+            Medal::Gold => Color::GOLD,
+        }
+    }
+}
+
+/// Set from the challenges menu for the length of the run
+#[derive(Resource, Debug)]
+pub struct ActiveChallenge {
+    pub challenge: Challenge,
+}
+
+/// How the last challenge went, for the results screen
+#[derive(Resource, Debug)]
+pub struct ChallengeResult {
+    pub challenge: Challenge,
+    /// `None` if the challenge was failed
+    pub score: Option<f32>,
+    pub medal: Option<Medal>,
+    pub new_best: bool,
+}
+
+#[derive(Component, Debug, Default)]
+struct ChallengeDisplay;
+
+/// Challenges are straight into the action
+fn skip_intro(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::InGame);
+}
+
+fn setup_challenge_display(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: HUD_FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            ),
+            text_anchor: Anchor::TopLeft,
+            ..Default::default()
+        },
+        ChallengeDisplay,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+/// The score so far, or the final score once the challenge is over
+fn current_score(challenge: Challenge, run_stats: &RunStats) -> Option<f32> {
+    match challenge {
+        Challenge::RockRush => Some(run_stats.rocks_destroyed as f32),
+        Challenge::ExoticWithoutShield => run_stats
+            .stage_starts
+            .iter()
+            .find(|(stage, _)| *stage == ProgressStages::ShieldAndStrange)
+            .map(|(_, time)| time.as_secs_f32()),
+    }
+}
+
+fn track_challenge(
+    mut commands: Commands,
+    active: Res<ActiveChallenge>,
+    run_stats: Res<RunStats>,
+    mut save_data: ResMut<SaveData>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let challenge = active.challenge;
+    let out_of_time = run_stats.time.as_secs_f32() >= challenge.time_limit();
+    let score = current_score(challenge, &run_stats);
+    let finished = match challenge {
+        Challenge::RockRush => out_of_time,
+        Challenge::ExoticWithoutShield => score.is_some() || out_of_time,
+    };
+    if !finished {
+        return;
+    }
+
+    let medal = score.and_then(|score| challenge.medal(score));
+    let best = save_data.challenge_bests.get(challenge.id()).copied();
+    let new_best = match score {
+        Some(score) if challenge.is_better(score, best) => {
+            save_data
+                .challenge_bests
+                .insert(challenge.id().to_string(), score);
+            save_data.save();
+            true
+        }
+        _ => false,
+    };
+    debug!("Finished {challenge:?} with {score:?}, {medal:?}");
+    commands.insert_resource(ChallengeResult {
+        challenge,
+        score,
+        medal,
+        new_best,
+    });
+    next_state.set(GameState::Outro);
+}
+
+#[allow(clippy::type_complexity)]
+fn update_challenge_display(
+    mut display_query: Query<
+        (&mut Text, &mut Transform),
+        (With<ChallengeDisplay>, Without<CustomUICamera>),
+    >,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    active: Res<ActiveChallenge>,
+    run_stats: Res<RunStats>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else { return; };
+    let Some(size) = ui_camera.logical_viewport_size() else { return; };
+    let challenge = active.challenge;
+    let remaining = (challenge.time_limit() - run_stats.time.as_secs_f32()).max(0.0) as u32;
+    let progress = match challenge {
+        Challenge::RockRush => {
+            let rocks = run_stats.rocks_destroyed as f32;
+            format!("  {}", challenge.format_score(rocks))
+        }
+        Challenge::ExoticWithoutShield => String::new(),
+    };
+    for (mut text, mut transform) in &mut display_query {
+        transform.translation.x = -size.x / 2.0 + HUD_PADDING;
+        transform.translation.y = size.y / 2.0 - HUD_PADDING;
+        text.sections[0].value = format!(
+            "{}{progress}  {:02}:{:02}",
+            challenge.title(),
+            remaining / 60,
+            remaining % 60
+        );
+    }
+}
+
+fn end_challenge(mut commands: Commands) {
+    commands.remove_resource::<ActiveChallenge>();
+    commands.remove_resource::<ChallengeResult>();
+}
+
+pub struct ChallengePlugin;
+
+impl Plugin for ChallengePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            skip_intro
+                .run_if(resource_exists::<ActiveChallenge>())
+                .in_set(OnUpdate(GameState::Intro)),
+        )
+        .add_system(
+            setup_challenge_display
+                .run_if(resource_exists::<ActiveChallenge>())
+                .in_schedule(OnExit(GameState::Intro)),
+        )
+        .add_systems(
+            (track_challenge, update_challenge_display)
+                .distributive_run_if(resource_exists::<ActiveChallenge>())
+                .in_set(OnUpdate(GameState::InGame)),
+        )
+        .add_system(end_challenge.in_schedule(OnExit(GameState::Outro)));
+    }
+}
@@ -0,0 +1,244 @@
+use std::{path::PathBuf, str::FromStr};
+
+use bevy::prelude::*;
+
+use crate::{
+    campaign::Campaign,
+    sound::VolumeSettings,
+    state::{GameState, ProgressStages},
+    util::GameRng,
+};
+
+const USAGE: &str = "Usage: warlord [--skip-menu] [--stage <stage>] [--seed <seed>] [--mute]
+               [--record <replay file>] [--replay <replay file>] [--autopilot]
+               [--text-pack <folder>] [--campaign <campaign file>]
+
+Stages: exploration, gun-and-heat, collect-exotic, shield-and-strange, continuum, end";
+
+/// Options for booting straight into the game while developing or testing
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct LaunchOptions {
+    /// Go straight through the main menu and intro into the game
+    pub skip_menu: bool,
+    /// Once in game, advance through the stages until this one is reached
+    pub stage: Option<ProgressStages>,
+    /// Seed for the random number generator, so runs can be reproduced
+    pub seed: Option<u64>,
+    pub mute: bool,
+    /// Record the player's inputs to this file, to be played back later
+    pub record: Option<PathBuf>,
+    /// Play back a recording instead of taking input from the player
+    pub replay: Option<PathBuf>,
+    /// Let the autopilot fly the ship, for demos and smoke tests
+    pub autopilot: bool,
+    /// Folder inside `assets` to read the story and hints from, for translations and mods
+    pub text_pack: Option<String>,
+    /// Play the stages from this file instead of the usual campaign
+    pub campaign: Option<PathBuf>,
+}
+
+impl LaunchOptions {
+    /// Parses the command line arguments, not including the program name
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--skip-menu" => options.skip_menu = true,
+                "--mute" => options.mute = true,
+                "--autopilot" => options.autopilot = true,
+                "--stage" => {
+                    let value = args.next().ok_or("--stage needs a value")?;
+                    options.stage = Some(parse_stage(&value)?);
+                }
+                "--seed" => {
+                    let value = args.next().ok_or("--seed needs a value")?;
+                    let seed = u64::from_str(&value).map_err(|_| format!("Invalid seed {value}"))?;
+                    options.seed = Some(seed);
+                }
+                "--record" => {
+                    let value = args.next().ok_or("--record needs a file")?;
+                    options.record = Some(PathBuf::from(value));
+                }
+                "--replay" => {
+                    let value = args.next().ok_or("--replay needs a file")?;
+                    options.replay = Some(PathBuf::from(value));
+                }
+                "--text-pack" => {
+                    let value = args.next().ok_or("--text-pack needs a folder")?;
+                    options.text_pack = Some(value);
+                }
+                "--campaign" => {
+                    let value = args.next().ok_or("--campaign needs a file")?;
+                    options.campaign = Some(PathBuf::from(value));
+                }
+                "-h" | "--help" => return Err(USAGE.to_owned()),
+                _ => return Err(format!("Unknown argument {arg}\n\n{USAGE}")),
+            }
+        }
+        // There's no point picking a stage if we have to click through the menus to get to it,
+        // and replays start from the beginning of a run
+        if options.stage.is_some() || options.replay.is_some() {
+            options.skip_menu = true;
+        }
+        Ok(options)
+    }
+
+    /// Parses the arguments the game was started with, printing the usage and exiting if
+    /// they're invalid
+    pub fn from_env() -> Self {
+        Self::from_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            std::process::exit(2);
+        })
+    }
+}
+
+/// The name a stage goes by on the command line and in data files
+pub fn stage_from_id(id: &str) -> Option<ProgressStages> {
+    match id {
+        "exploration" => Some(ProgressStages::Exploration),
+        "gun-and-heat" => Some(ProgressStages::GunAndHeat),
+        "collect-exotic" => Some(ProgressStages::CollectExotic),
+        "shield-and-strange" => Some(ProgressStages::ShieldAndStrange),
+        "continuum" => Some(ProgressStages::Continuum),
+        "end" => Some(ProgressStages::End),
+        _ => None,
+    }
+}
+
+fn parse_stage(value: &str) -> Result<ProgressStages, String> {
+    stage_from_id(value).ok_or_else(|| format!("Unknown stage {value}\n\n{USAGE}"))
+}
+
+fn apply_launch_options(
+    mut commands: Commands,
+    options: Res<LaunchOptions>,
+    mut volume: ResMut<VolumeSettings>,
+) {
+    if let Some(seed) = options.seed {
+        info!("Seeding the random number generator with {seed}");
+        commands.insert_resource(GameRng::new(seed));
+    }
+    if options.mute {
+        volume.mute = true;
+    }
+}
+
+fn skip_menus(
+    mut options: ResMut<LaunchOptions>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !options.skip_menu {
+        return;
+    }
+    match current_state.0 {
+        GameState::MainMenu => next_state.set(GameState::Intro),
+        GameState::Intro => {
+            next_state.set(GameState::InGame);
+            // Only skip them once, so finishing a run goes back to the main menu as usual
+            options.skip_menu = false;
+        }
+        _ => (),
+    }
+}
+
+/// Steps through the stages one at a time rather than jumping straight to the target, so each
+/// stage gets to enable its features on the way. The steps are the campaign's, in its order
+fn advance_to_launch_stage(
+    mut options: ResMut<LaunchOptions>,
+    current_stage: Res<State<ProgressStages>>,
+    mut next_stage_state: ResMut<NextState<ProgressStages>>,
+    campaign: Res<Campaign>,
+) {
+    let Some(target) = options.stage else { return; };
+    if target != ProgressStages::End && campaign.stage(target).is_none() {
+        warn!("The campaign doesn't have the stage {target:?}, starting from the beginning");
+        options.stage = None;
+        return;
+    }
+    // The run hasn't started its first stage yet
+    if current_stage.0 == ProgressStages::None {
+        return;
+    }
+    let before_target = campaign
+        .stages_before(target)
+        .any(|campaign_stage| campaign_stage.stage == current_stage.0);
+    if !before_target {
+        options.stage = None;
+        return;
+    }
+    next_stage_state.set(campaign.next_stage(current_stage.0));
+}
+
+pub struct CliPlugin {
+    pub options: LaunchOptions,
+}
+
+impl Plugin for CliPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.options.clone())
+            .add_startup_system(apply_launch_options)
+            .add_system(skip_menus)
+            .add_system(advance_to_launch_stage.in_set(OnUpdate(GameState::InGame)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<LaunchOptions, String> {
+        LaunchOptions::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_is_the_usual_game() {
+        assert_eq!(parse(&[]), Ok(LaunchOptions::default()));
+    }
+
+    #[test]
+    fn reads_every_option() {
+        let options = parse(&[
+            "--mute",
+            "--autopilot",
+            "--seed",
+            "42",
+            "--record",
+            "run.replay",
+            "--text-pack",
+            "text/de",
+            "--campaign",
+            "short.txt",
+        ])
+        .unwrap();
+        assert!(options.mute);
+        assert!(options.autopilot);
+        assert!(!options.skip_menu);
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.record, Some(PathBuf::from("run.replay")));
+        assert_eq!(options.text_pack.as_deref(), Some("text/de"));
+        assert_eq!(options.campaign, Some(PathBuf::from("short.txt")));
+    }
+
+    #[test]
+    fn picking_a_stage_or_a_replay_skips_the_menu() {
+        let options = parse(&["--stage", "continuum"]).unwrap();
+        assert_eq!(options.stage, Some(ProgressStages::Continuum));
+        assert!(options.skip_menu);
+
+        let options = parse(&["--replay", "run.replay"]).unwrap();
+        assert_eq!(options.replay, Some(PathBuf::from("run.replay")));
+        assert!(options.skip_menu);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse(&["--stage", "nowhere"]).is_err());
+        assert!(parse(&["--seed", "lots"]).is_err());
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--fly"]).is_err());
+        assert!(parse(&["--help"]).is_err());
+    }
+}
@@ -1,11 +1,43 @@
+use std::f32::consts::PI;
+
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::{inventory::Reagent, player::Player, rock::Cull, state::GameState};
+use crate::{
+    campaign::{Campaign, CampaignStage},
+    consumable::{ConsumableKind, Consumables},
+    despawn::DespawnQueue,
+    game_time::GameTime,
+    hull::Hull,
+    instancing::{InstanceBatchBundle, InstancedVisual},
+    inventory::{Reagent, ReagentRegistry},
+    lod::LodLevel,
+    physics::COLLECTIBLE_COLLISION_GROUPS,
+    player::Player,
+    rift::RiftCollected,
+    rock::{Cull, Magnetized, RotatingRock},
+    sound::SoundEvent,
+    state::{GameState, ProgressStages},
+    util::cosmetic::random_range,
+};
+
+/// Spends this long of its lifetime blinking before it despawns
+const BLINK_SECONDS: f32 = 4.0;
+const BLINKS_PER_SECOND: f32 = 5.0;
 
 #[derive(Component, Debug)]
 pub enum Collectible {
     CollectibleReagent { reagent: Reagent, amount: f32 },
+    /// Patches up the player's hull instead of going into the inventory
+    Repair { amount: f32 },
+    /// A sealed pod of cargo the player ejected to come back for later. Holds an amount of
+    /// each reagent that was in the hold
+    CargoPod { contents: Vec<(Reagent, f32)> },
+    /// Goes into the player's `Consumables`, to be used later
+    Consumable { kind: ConsumableKind },
+    /// A tear in space that only opens during the continuum stage. Hands over `amount` of
+    /// continuum all at once, at the cost of a `RiftCollected` backlash
+    Rift { amount: f32 },
     Other,
 }
 
@@ -15,9 +47,96 @@ impl Default for Collectible {
     }
 }
 
+/// How long loose collectibles hang around for. Distance culling only helps while the player
+/// keeps moving, so this keeps the number of entities down when they stay in one place
+#[derive(Resource, Debug, Clone)]
+pub struct CollectibleSettings {
+    /// Seconds before a collectible despawns, including the time spent blinking.
+    /// `None` keeps them around until they're culled
+    pub lifetime: Option<f32>,
+}
+
+impl Default for CollectibleSettings {
+    fn default() -> Self {
+        Self {
+            lifetime: Some(30.0),
+        }
+    }
+}
+
+/// Counts down to a collectible despawning. It blinks for the last `BLINK_SECONDS`
+#[derive(Component, Debug)]
+pub struct CollectibleLifetime {
+    timer: Timer,
+}
+
+impl CollectibleLifetime {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+
+    fn blinking(&self) -> bool {
+        self.timer.remaining_secs() < BLINK_SECONDS
+    }
+}
+
+/// Whether the current stage is waiting on the player to collect some of this reagent
+fn stage_needs(stage: &CampaignStage, reagent: Reagent) -> bool {
+    // The shield turns exotic matter into strange matter, so exotic is wanted wherever strange is
+    stage.needs(reagent) || (reagent == Reagent::EXOTIC && stage.needs(Reagent::STRANGE))
+}
+
+/// Drops some of a reagent into the world. Anything that leaves reagents behind sends one of
+/// these, and `spawn_collectibles` works out what it looks like
+#[derive(Debug, Clone)]
+pub struct SpawnCollectibleEvent {
+    pub reagent: Reagent,
+    pub amount: f32,
+    pub position: Vec3,
+    pub velocity: Vec2,
+    /// Loose reagents are usually half size, the minerals out in the rock field are full size
+    pub scale: f32,
+    /// Pulled towards the player for a moment, see `Magnetized`
+    pub magnetized: bool,
+}
+
+impl SpawnCollectibleEvent {
+    pub fn new(reagent: Reagent, amount: f32, position: Vec3, velocity: Vec2) -> Self {
+        Self {
+            reagent,
+            amount,
+            position,
+            velocity,
+            scale: 1.0,
+            magnetized: false,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn magnetized(mut self) -> Self {
+        self.magnetized = true;
+        self
+    }
+}
+
+/// Players pick up whatever they touch here, so anything else claiming collectibles, like the
+/// drone, should run after this
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CollectionSystem;
+
 pub struct CollectionEvent {
     pub reagent: Reagent,
     pub amount: f32,
+    /// Where it was picked up, in world space
+    pub position: Vec3,
+    /// The player who picked it up. `None` goes to the first player
+    pub collector: Option<Entity>,
 }
 
 #[derive(Bundle, Debug)]
@@ -34,6 +153,7 @@ pub struct CollectibleBundle {
 
     pub rigid_body: RigidBody,
     pub collider: Collider,
+    pub collision_groups: CollisionGroups,
     pub sensor: Sensor,
     pub velocity: Velocity,
     pub active_events: ActiveEvents,
@@ -53,6 +173,7 @@ impl Default for CollectibleBundle {
             computed_visibility: Default::default(),
             rigid_body: RigidBody::Dynamic,
             collider: Collider::ball(0.5),
+            collision_groups: COLLECTIBLE_COLLISION_GROUPS,
             sensor: Default::default(),
             velocity: Default::default(),
             active_events: ActiveEvents::COLLISION_EVENTS,
@@ -133,26 +254,93 @@ pub struct Mineral {
     pub value: f32,
 }
 
-#[derive(Resource, Debug, Default)]
-pub struct MineralAppearance {
+/// What a reagent looks like floating in space
+#[derive(Debug, Clone)]
+pub struct ReagentAppearance {
     pub mesh: Handle<Mesh>,
     pub material: Handle<StandardMaterial>,
+    /// From the reagents file. The HUD uses it too, so a reagent is the same colour everywhere
+    pub color: Color,
+    /// Drawn on a child that tumbles, instead of on the collectible itself
+    pub tumbles: bool,
+}
+
+/// How each reagent in the `ReagentRegistry` looks, so a new reagent only needs a line in the
+/// reagents file to show up in the world
+#[derive(Resource, Debug, Default)]
+pub struct ReagentAppearances {
+    appearances: Vec<ReagentAppearance>,
 }
 
-fn setup_mineral_visuals(
+impl ReagentAppearances {
+    pub fn get(&self, reagent: Reagent) -> &ReagentAppearance {
+        &self.appearances[reagent.index()]
+    }
+}
+
+/// Minerals are tetrahedra and exotic matter is a torus. Everything else is a sphere
+fn setup_reagent_appearances(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    registry: Res<ReagentRegistry>,
 ) {
-    let material = materials.add(StandardMaterial {
-        base_color: Color::rgb(0.0, 1.0, 1.0),
-        emissive: Color::rgb(0.0, 0.5, 0.5),
-        ..Default::default()
-    });
+    let tetrahedron = meshes.add(Tetrahedron::default().into());
+    let torus = meshes.add(
+        shape::Torus {
+            radius: 0.5,
+            ring_radius: 0.25,
+            subdivisions_segments: 16,
+            subdivisions_sides: 12,
+        }
+        .into(),
+    );
+    let sphere = meshes.add(
+        shape::UVSphere {
+            radius: 0.25,
+            ..Default::default()
+        }
+        .into(),
+    );
 
-    let mesh = meshes.add(Tetrahedron::default().into());
+    let appearances = registry
+        .reagents()
+        .map(|(reagent, definition)| {
+            let color = definition.color;
+            let (mesh, glow, tumbles) = match reagent {
+                Reagent::MINERALS => (tetrahedron.clone(), 0.5, true),
+                Reagent::EXOTIC => (torus.clone(), 3.0, true),
+                _ => (sphere.clone(), 3.0, false),
+            };
+            let material = materials.add(StandardMaterial {
+                base_color: color,
+                emissive: color * glow,
+                ..Default::default()
+            });
+            ReagentAppearance {
+                mesh,
+                material,
+                color,
+                tumbles,
+            }
+        })
+        .collect();
+
+    // Minerals are drawn in one go by the instancing batch, see `InstancedVisual::Mineral`
+    let color = registry.get(Reagent::MINERALS).color;
+    let billboard = meshes.add(shape::Quad::new(Vec2::splat(0.8)).into());
+    commands.spawn(InstanceBatchBundle::new(
+        InstancedVisual::Mineral,
+        tetrahedron,
+        color,
+        color * 0.5,
+    ));
+    commands.spawn(
+        InstanceBatchBundle::new(InstancedVisual::Mineral, billboard, color, color * 0.5)
+            .with_lod(LodLevel::Low),
+    );
 
-    commands.insert_resource(MineralAppearance { material, mesh });
+    commands.insert_resource(ReagentAppearances { appearances });
 }
 
 #[derive(Component, Debug, Default)]
@@ -160,109 +348,251 @@ pub struct ExoticMatter {
     pub value: f32,
 }
 
+/// Cargo pods slow down the same way, so a stash doesn't drift off forever
+#[allow(clippy::type_complexity)]
+fn exotic_matter_friction(
+    mut query: Query<&mut Velocity, Or<(With<ExoticMatter>, With<CargoPod>)>>,
+    game_time: Res<GameTime>,
+) {
+    for mut velocity in &mut query {
+        velocity.linvel *= f32::powf(0.5, game_time.delta_seconds());
+    }
+}
+
+/// Cargo pods aren't culled, so they stay where they were left until they're picked back up
+#[derive(Component, Debug, Default)]
+pub struct CargoPod;
+
 #[derive(Resource, Debug, Default)]
-pub struct ExoticMatterAppearance {
+pub struct CargoPodAppearance {
     pub mesh: Handle<Mesh>,
     pub material: Handle<StandardMaterial>,
 }
 
-fn setup_exotic_matter_visuals(
+fn setup_cargo_pod_visuals(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     let material = materials.add(StandardMaterial {
-        base_color: Color::rgb(1.0, 0.0, 1.0),
-        emissive: Color::rgb(1.0, 0.0, 1.0) * 3.0,
+        base_color: Color::rgb(0.8, 0.6, 0.2),
+        emissive: Color::rgb(1.0, 0.6, 0.0) * 1.5,
+        metallic: 0.8,
+        perceptual_roughness: 0.4,
         ..Default::default()
     });
 
     let mesh = meshes.add(
-        shape::Torus {
-            radius: 0.5,
-            ring_radius: 0.25,
-            subdivisions_segments: 16,
-            subdivisions_sides: 12,
+        shape::Capsule {
+            radius: 0.4,
+            depth: 0.8,
+            ..Default::default()
         }
         .into(),
     );
 
-    commands.insert_resource(ExoticMatterAppearance { material, mesh });
-}
-
-fn exotic_matter_friction(mut query: Query<&mut Velocity, With<ExoticMatter>>, time: Res<Time>) {
-    for mut velocity in &mut query {
-        velocity.linvel *= f32::powf(0.5, time.delta_seconds());
-    }
+    commands.insert_resource(CargoPodAppearance { material, mesh });
 }
 
 #[derive(Resource, Debug, Default)]
-pub struct StrangeMatterAppearance {
+pub struct RepairKitAppearance {
     pub mesh: Handle<Mesh>,
     pub material: Handle<StandardMaterial>,
 }
 
-fn setup_strange_matter_visuals(
+fn setup_repair_kit_visuals(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     let material = materials.add(StandardMaterial {
-        base_color: Color::rgb(0.0, 1.0, 0.0),
-        emissive: Color::rgb(0.0, 1.0, 0.0) * 3.0,
+        base_color: Color::rgb(1.0, 1.0, 1.0),
+        emissive: Color::rgb(1.0, 0.2, 0.2) * 2.0,
         ..Default::default()
     });
 
-    let mesh = meshes.add(
-        shape::UVSphere {
-            radius: 0.25,
+    let mesh = meshes.add(shape::Cube { size: 0.6 }.into());
+
+    commands.insert_resource(RepairKitAppearance { material, mesh });
+}
+
+/// The one place reagent collectibles are made, looking however `ReagentAppearances` says
+fn spawn_collectibles(
+    mut commands: Commands,
+    mut reader: EventReader<SpawnCollectibleEvent>,
+    reagent_appearances: Res<ReagentAppearances>,
+) {
+    for ev in reader.iter() {
+        let appearance = reagent_appearances.get(ev.reagent);
+        let (mesh, material) = if appearance.tumbles {
+            Default::default()
+        } else {
+            (appearance.mesh.clone(), appearance.material.clone())
+        };
+        let mut entity = commands.spawn(CollectibleBundle {
+            transform: Transform::from_translation(ev.position).with_scale(Vec3::splat(ev.scale)),
+            velocity: Velocity::linear(ev.velocity),
+            mesh,
+            material,
+            collectible: Collectible::CollectibleReagent {
+                reagent: ev.reagent,
+                amount: ev.amount,
+            },
             ..Default::default()
+        });
+        if appearance.tumbles {
+            // Drawn by a child so it can turn in 3d without interference from rapier
+            let angvel = Vec3::new(
+                random_range(-PI, PI),
+                random_range(-PI, PI),
+                random_range(-PI, PI),
+            );
+            entity.with_children(|parent| {
+                if ev.reagent == Reagent::MINERALS {
+                    parent.spawn((
+                        RotatingRock { angvel },
+                        InstancedVisual::Mineral,
+                        SpatialBundle::default(),
+                    ));
+                } else {
+                    parent.spawn((
+                        RotatingRock { angvel },
+                        PbrBundle {
+                            mesh: appearance.mesh.clone(),
+                            material: appearance.material.clone(),
+                            ..Default::default()
+                        },
+                    ));
+                }
+            });
         }
-        .into(),
-    );
-
-    commands.insert_resource(StrangeMatterAppearance { material, mesh });
+        if ev.reagent == Reagent::EXOTIC {
+            entity.insert(ExoticMatter::default());
+        }
+        if ev.magnetized {
+            entity.insert(Magnetized::default());
+        }
+    }
 }
 
 fn handle_collision(
-    mut commands: Commands,
     mut collisions: EventReader<CollisionEvent>,
-    player_query: Query<Entity, With<Player>>,
-    collectible_query: Query<&Collectible, Without<Player>>,
+    mut player_query: Query<(&mut Hull, Option<&mut Consumables>), With<Player>>,
+    collectible_query: Query<(&Collectible, &Transform), Without<Player>>,
     mut writer: EventWriter<CollectionEvent>,
+    mut rift_writer: EventWriter<RiftCollected>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut despawn_queue: ResMut<DespawnQueue>,
 ) {
     for ev in collisions.iter() {
-        match ev {
-            CollisionEvent::Started(e1, e2, _flags) => {
-                if let Ok(_) = player_query.get(*e1) {
-                    if let Ok(collectible) = collectible_query.get(*e2) {
-                        match collectible {
-                            Collectible::CollectibleReagent { reagent, amount } => {
-                                writer.send(CollectionEvent {
-                                    reagent: *reagent,
-                                    amount: *amount,
-                                });
-                            }
-                            _ => warn!("Collected a collectible with no associated Reagent. That's probably not intentional."),
-                        }
-                        commands.entity(*e2).despawn_recursive();
-                    }
-                } else if let Ok(_) = player_query.get(*e2) {
-                    if let Ok(collectible) = collectible_query.get(*e1) {
-                        match collectible {
-                            Collectible::CollectibleReagent { reagent, amount } => {
-                                writer.send(CollectionEvent {
-                                    reagent: *reagent,
-                                    amount: *amount,
-                                });
-                            }
-                            _ => warn!("Collected a collectible with no associated Reagent. That's probably not intentional."),
-                        }
-                        commands.entity(*e1).despawn_recursive();
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        // Collectibles only ever collide with the player and the shield, so if one
+        // side is a collectible we just need to make sure the other is the player
+        let (collectible_entity, other) = if collectible_query.contains(*e1) {
+            (*e1, *e2)
+        } else {
+            (*e2, *e1)
+        };
+        let Ok((mut hull, consumables)) = player_query.get_mut(other) else { continue; };
+        let Ok((collectible, transform)) = collectible_query.get(collectible_entity) else { continue; };
+        // Touching both ships, or the shield took it, in the same frame
+        if despawn_queue.is_queued(collectible_entity) {
+            continue;
+        }
+        match collectible {
+            Collectible::CollectibleReagent { reagent, amount } => {
+                writer.send(CollectionEvent {
+                    reagent: *reagent,
+                    amount: *amount,
+                    position: transform.translation,
+                    collector: Some(other),
+                });
+            }
+            Collectible::Repair { amount } => {
+                hull.repair(*amount);
+                sound_event_writer.send(SoundEvent::Repaired);
+            }
+            Collectible::Consumable { kind } => {
+                // Left floating if there's no room for it
+                let Some(mut consumables) = consumables else { continue; };
+                if !consumables.add(*kind) {
+                    continue;
+                }
+                sound_event_writer.send(SoundEvent::Repaired);
+            }
+            Collectible::CargoPod { contents } => {
+                for (reagent, amount) in contents {
+                    if *amount > 0.0 {
+                        writer.send(CollectionEvent {
+                            reagent: *reagent,
+                            amount: *amount,
+                            position: transform.translation,
+                            collector: Some(other),
+                        });
                     }
                 }
             }
-            _ => {}
+            Collectible::Rift { amount } => {
+                writer.send(CollectionEvent {
+                    reagent: Reagent::CONTINUUM,
+                    amount: *amount,
+                    position: transform.translation,
+                    collector: Some(other),
+                });
+                rift_writer.send(RiftCollected {
+                    player: other,
+                    position: transform.translation,
+                });
+            }
+            _ => warn!("Collected a collectible with no associated Reagent. That's probably not intentional."),
+        }
+        despawn_queue.despawn(collectible_entity);
+    }
+}
+
+fn start_collectible_lifetimes(
+    mut commands: Commands,
+    query: Query<Entity, (Added<Collectible>, Without<CargoPod>)>,
+    settings: Res<CollectibleSettings>,
+) {
+    let Some(lifetime) = settings.lifetime else { return; };
+    for entity in &query {
+        commands
+            .entity(entity)
+            .insert(CollectibleLifetime::new(lifetime));
+    }
+}
+
+fn expire_collectibles(
+    mut query: Query<(Entity, &Collectible, &mut CollectibleLifetime, &mut Visibility)>,
+    stage: Res<State<ProgressStages>>,
+    campaign: Res<Campaign>,
+    game_time: Res<GameTime>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+) {
+    let campaign_stage = campaign.stage(stage.0);
+    for (entity, collectible, mut lifetime, mut visibility) in &mut query {
+        if let Collectible::CollectibleReagent { reagent, .. } = collectible {
+            if campaign_stage.is_some_and(|stage| stage_needs(stage, *reagent)) {
+                // Starts counting again from the beginning once the stage moves on
+                lifetime.timer.reset();
+                *visibility = Visibility::Visible;
+                continue;
+            }
+        }
+
+        lifetime.timer.tick(game_time.delta());
+        if lifetime.timer.finished() {
+            despawn_queue.despawn(entity);
+            continue;
+        }
+        if lifetime.blinking() {
+            let blink = (lifetime.timer.remaining_secs() * BLINKS_PER_SECOND * 2.0) as u32;
+            *visibility = if blink % 2 == 1 {
+                Visibility::Hidden
+            } else {
+                Visibility::Visible
+            };
         }
     }
 }
@@ -271,12 +601,22 @@ pub struct CollectiblePlugin;
 
 impl Plugin for CollectiblePlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_mineral_visuals)
-            .add_startup_system(setup_exotic_matter_visuals)
-            .add_startup_system(setup_strange_matter_visuals)
+        app.add_startup_system(setup_reagent_appearances)
+            .add_startup_system(setup_repair_kit_visuals)
+            .add_startup_system(setup_cargo_pod_visuals)
+            // Runs while paused too, so nothing sent just before pausing is lost
+            .add_system(spawn_collectibles)
             .add_systems(
-                (handle_collision, exotic_matter_friction).in_set(OnUpdate(GameState::InGame)),
+                (
+                    handle_collision.in_set(CollectionSystem),
+                    exotic_matter_friction,
+                    start_collectible_lifetimes,
+                    expire_collectibles,
+                )
+                    .in_set(OnUpdate(GameState::InGame)),
             )
-            .add_event::<CollectionEvent>();
+            .init_resource::<CollectibleSettings>()
+            .add_event::<CollectionEvent>()
+            .add_event::<SpawnCollectibleEvent>();
     }
 }
@@ -0,0 +1,152 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{
+    game_time::GameTime,
+    rock::RockDestroyed,
+    sound::SoundEvent,
+    state::GameState,
+    ui::{CustomUICamera, UIMarker},
+};
+
+/// Destroying another rock within this long keeps the combo going
+const COMBO_WINDOW_SECONDS: f32 = 2.0;
+/// Every this many rocks in a combo is another tier, up to `MAX_TIER`
+const ROCKS_PER_TIER: u32 = 5;
+const MAX_TIER: u32 = 3;
+
+const COMBO_FONT_SIZE: f32 = 40.0;
+const COMBO_PADDING: f32 = 10.0;
+
+/// Rocks destroyed in quick succession
+#[derive(Resource, Debug)]
+pub struct Combo {
+    count: u32,
+    window_timer: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            window_timer: Timer::from_seconds(COMBO_WINDOW_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+impl Combo {
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// 0 until the combo has gone on for `ROCKS_PER_TIER` rocks
+    pub fn tier(&self) -> u32 {
+        (self.count / ROCKS_PER_TIER).min(MAX_TIER)
+    }
+
+    /// How many times the usual number of minerals a destroyed rock drops
+    pub fn mineral_multiplier(&self) -> u32 {
+        1 + self.tier()
+    }
+}
+
+#[derive(Component, Debug, Default)]
+struct ComboDisplay;
+
+fn reset_combo(mut combo: ResMut<Combo>) {
+    *combo = Combo::default();
+}
+
+fn setup_combo_display(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: COMBO_FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            text_anchor: Anchor::TopCenter,
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        ComboDisplay,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+fn track_combo(
+    mut reader: EventReader<RockDestroyed>,
+    mut combo: ResMut<Combo>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    game_time: Res<GameTime>,
+) {
+    combo.window_timer.tick(game_time.delta());
+    if combo.window_timer.finished() && combo.count > 0 {
+        debug!("Combo of {} ended", combo.count);
+        combo.count = 0;
+    }
+
+    // A rock can be hit by more than one thing in the same frame
+    let mut destroyed: Vec<_> = reader.iter().map(|ev| ev.entity).collect();
+    destroyed.sort();
+    destroyed.dedup();
+    if destroyed.is_empty() {
+        return;
+    }
+    combo.count += destroyed.len() as u32;
+    combo.window_timer.reset();
+    if combo.count > 1 {
+        sound_event_writer.send(SoundEvent::Combo { step: combo.count });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_combo_display(
+    mut display_query: Query<
+        (&mut Text, &mut Transform, &mut Visibility),
+        (With<ComboDisplay>, Without<CustomUICamera>),
+    >,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    combo: Res<Combo>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else { return; };
+    let Some(size) = ui_camera.logical_viewport_size() else { return; };
+    for (mut text, mut transform, mut visibility) in &mut display_query {
+        transform.translation.y = size.y / 2.0 - COMBO_PADDING;
+        if combo.count() < 2 {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Visible;
+
+        let section = &mut text.sections[0];
+        section.value = if combo.tier() > 0 {
+            format!("{} COMBO - MINERALS x{}", combo.count(), combo.mineral_multiplier())
+        } else {
+            format!("{} COMBO", combo.count())
+        };
+        // Fades as the window runs out
+        section.style.color = Color::rgba(1.0, 1.0, 1.0, combo.window_timer.percent_left());
+    }
+}
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Combo>()
+            .add_systems(
+                (reset_combo, setup_combo_display).in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_systems(
+                (track_combo, update_combo_display)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
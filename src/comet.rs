@@ -0,0 +1,292 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collectible::SpawnCollectibleEvent,
+    despawn::DespawnQueue,
+    game_time::GameTime,
+    hull::Faction,
+    inventory::Reagent,
+    particles::{Particle, ParticleBundle},
+    physics::COMET_COLLISION_GROUPS,
+    player::{LeadPlayer, Player},
+    replay::ReplaySystem,
+    rock::{Cull, RotatingRock},
+    sound::SoundEvent,
+    state::GameState,
+    status_effect::{ApplyStatus, StatusKind},
+    util::{cosmetic, random_direction, GameRng},
+    weapon::Slug,
+};
+
+#[derive(Component, Debug)]
+pub struct Comet {
+    /// The comet drops a mineral chunk whenever this timer finishes
+    pub drop_timer: Timer,
+    /// The comet leaves a particle in its tail whenever this timer finishes
+    pub tail_timer: Timer,
+    /// How many chunks of minerals the comet bursts into when it's shot
+    pub bonus_chunks: u32,
+}
+
+impl Default for Comet {
+    fn default() -> Self {
+        Self {
+            drop_timer: Timer::from_seconds(0.4, TimerMode::Repeating),
+            tail_timer: Timer::from_seconds(0.02, TimerMode::Repeating),
+            bonus_chunks: 12,
+        }
+    }
+}
+
+pub struct SpawnCometEvent {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+#[derive(Resource, Debug)]
+struct CometAppearance {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    tail_mesh: Handle<Mesh>,
+    tail_material: Handle<StandardMaterial>,
+}
+
+fn setup_comet_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mesh = meshes.add(
+        shape::Icosphere {
+            radius: 0.6,
+            subdivisions: 1,
+        }
+        .try_into()
+        .unwrap(),
+    );
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.8, 1.0, 1.0),
+        emissive: Color::rgb(0.4, 1.0, 1.0) * 4.0,
+        ..Default::default()
+    });
+
+    let tail_mesh = meshes.add(
+        shape::Quad {
+            size: Vec2::splat(0.3),
+            ..Default::default()
+        }
+        .into(),
+    );
+    let tail_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.4, 1.0, 1.0),
+        emissive: Color::rgb(0.0, 0.8, 1.0) * 3.0,
+        ..Default::default()
+    });
+
+    commands.insert_resource(CometAppearance {
+        mesh,
+        material,
+        tail_mesh,
+        tail_material,
+    });
+}
+
+fn spawn_comets(
+    mut commands: Commands,
+    mut reader: EventReader<SpawnCometEvent>,
+    comet_appearance: Res<CometAppearance>,
+) {
+    for SpawnCometEvent { position, velocity } in reader.iter() {
+        debug!("Spawning a comet at {position:?} moving at {velocity:?}");
+        let angvel = Vec3::new(
+            cosmetic::random_range(-PI, PI),
+            cosmetic::random_range(-PI, PI),
+            cosmetic::random_range(-PI, PI),
+        );
+        commands
+            .spawn((
+                Comet::default(),
+                // Comets plough straight through everything, they only care about being shot
+                RigidBody::KinematicVelocityBased,
+                Collider::ball(0.6),
+                COMET_COLLISION_GROUPS,
+                Sensor,
+                ActiveEvents::COLLISION_EVENTS,
+                Velocity::linear(*velocity),
+                Cull::default(),
+                SpatialBundle::from_transform(Transform::from_xyz(position.x, position.y, 3.0)),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    RotatingRock { angvel },
+                    PbrBundle {
+                        mesh: comet_appearance.mesh.clone(),
+                        material: comet_appearance.material.clone(),
+                        ..Default::default()
+                    },
+                ));
+            });
+    }
+}
+
+fn comet_trail(
+    mut commands: Commands,
+    mut query: Query<(&mut Comet, &Transform, &Velocity)>,
+    comet_appearance: Res<CometAppearance>,
+    game_time: Res<GameTime>,
+) {
+    for (mut comet, transform, velocity) in &mut query {
+        comet.tail_timer.tick(game_time.delta());
+        for _ in 0..comet.tail_timer.times_finished_this_tick() {
+            let offset = cosmetic::random_in_circle(0.4);
+            commands.spawn(ParticleBundle {
+                mesh: comet_appearance.tail_mesh.clone(),
+                material: comet_appearance.tail_material.clone(),
+                particle: Particle {
+                    lifetime_timer: Timer::from_seconds(0.6, TimerMode::Once),
+                },
+                velocity: Velocity {
+                    linvel: -velocity.linvel.normalize_or_zero() * 2.0,
+                    angvel: cosmetic::random_range(-PI, PI),
+                },
+                transform: Transform::from_translation(
+                    transform.translation + Vec3::new(offset.x, offset.y, 0.0),
+                )
+                .with_scale(Vec3::splat(cosmetic::random_range(0.5, 1.0))),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+fn drop_comet_chunks(
+    mut query: Query<(&mut Comet, &Transform, &Velocity)>,
+    mut collectible_writer: EventWriter<SpawnCollectibleEvent>,
+    fixed_time: Res<FixedTime>,
+    game_time: Res<GameTime>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("drop_comet_chunks");
+    for (mut comet, transform, velocity) in &mut query {
+        comet
+            .drop_timer
+            .tick(fixed_time.period.mul_f32(game_time.time_scale()));
+        for _ in 0..comet.drop_timer.times_finished_this_tick() {
+            // Dropped chunks lag behind the comet so they're left strewn along its path
+            let chunk_velocity = velocity.linvel * 0.05 + random_direction(rng) * 0.5;
+            collectible_writer.send(
+                SpawnCollectibleEvent::new(
+                    Reagent::MINERALS,
+                    0.25,
+                    transform.translation,
+                    chunk_velocity,
+                )
+                .with_scale(0.35),
+            );
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_comet_collisions(
+    mut reader: EventReader<CollisionEvent>,
+    comet_query: Query<(&Comet, &Transform, &Velocity)>,
+    slug_query: Query<&Faction, With<Slug>>,
+    player_query: Query<&Transform, LeadPlayer>,
+    mut collectible_writer: EventWriter<SpawnCollectibleEvent>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("handle_comet_collisions");
+    // Pirates shoot too, but only the player's slugs break comets open
+    let players_slug = |entity| matches!(slug_query.get(entity), Ok(Faction::Player));
+    for ev in reader.iter() {
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        let comet_entity = if comet_query.get(*e1).is_ok() && players_slug(*e2) {
+            *e1
+        } else if comet_query.get(*e2).is_ok() && players_slug(*e1) {
+            *e2
+        } else {
+            continue;
+        };
+        let Ok((comet, transform, velocity)) = comet_query.get(comet_entity) else { continue; };
+
+        // Two slugs can hit it in the same frame
+        if !despawn_queue.despawn(comet_entity) {
+            continue;
+        }
+        debug!("Comet {comet_entity:?} was shot");
+        for _ in 0..comet.bonus_chunks {
+            let chunk_velocity = velocity.linvel * 0.2 + random_direction(rng) * 3.0;
+            collectible_writer.send(
+                SpawnCollectibleEvent::new(
+                    Reagent::MINERALS,
+                    1.0,
+                    transform.translation,
+                    chunk_velocity,
+                )
+                .with_scale(0.5),
+            );
+        }
+        if let Ok(player_transform) = player_query.get_single() {
+            let diff = transform.translation - player_transform.translation;
+            sound_event_writer.send(SoundEvent::RockDestroyed { relative_pos: diff });
+        }
+    }
+}
+
+/// Flying this close to a comet ices the ship up
+const CHILL_RADIUS: f32 = 4.0;
+const CHILL_SECONDS: f32 = 2.5;
+/// How much of the ship's top speed it loses while iced up
+const CHILL_SLOWDOWN: f32 = 0.4;
+
+#[allow(clippy::type_complexity)]
+fn chill_nearby_players(
+    comet_query: Query<&Transform, With<Comet>>,
+    player_query: Query<(Entity, &Transform), (With<Player>, Without<Comet>)>,
+    mut status_writer: EventWriter<ApplyStatus>,
+) {
+    for comet_transform in &comet_query {
+        let comet_pos = comet_transform.translation.truncate();
+        for (player, transform) in &player_query {
+            if transform.translation.truncate().distance(comet_pos) > CHILL_RADIUS {
+                continue;
+            }
+            status_writer.send(ApplyStatus {
+                entity: player,
+                kind: StatusKind::Slowed,
+                seconds: CHILL_SECONDS,
+                strength: CHILL_SLOWDOWN,
+            });
+        }
+    }
+}
+
+pub struct CometPlugin;
+
+impl Plugin for CometPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnCometEvent>()
+            .add_startup_system(setup_comet_appearance)
+            .add_systems(
+                (
+                    spawn_comets,
+                    comet_trail,
+                    handle_comet_collisions,
+                    chill_nearby_players,
+                )
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(
+                drop_comet_chunks
+                    .after(ReplaySystem)
+                    .run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
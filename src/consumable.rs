@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    heat::Heat,
+    hull::Hull,
+    input::Action,
+    player::Player,
+    sound::SoundEvent,
+    state::GameState,
+    status_effect::{ApplyStatus, StatusKind},
+};
+
+/// Items picked up from broken rocks and carried in small stacks, apart from the reagents.
+/// Each has its own slot on the HUD and its own key to use one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsumableKind {
+    CoolantCell,
+    Accelerant,
+    HullPatch,
+}
+
+impl ConsumableKind {
+    /// In the order of their slots
+    pub const ALL: [ConsumableKind; 3] = [Self::CoolantCell, Self::Accelerant, Self::HullPatch];
+
+    pub fn definition(&self) -> &'static ConsumableDefinition {
+        &CONSUMABLES[*self as usize]
+    }
+
+    /// What's pressed to use one
+    pub fn action(&self) -> Action {
+        match self {
+            Self::CoolantCell => Action::UseCoolant,
+            Self::Accelerant => Action::UseAccelerant,
+            Self::HullPatch => Action::UseHullPatch,
+        }
+    }
+}
+
+/// The ship a consumable is being used on
+pub struct ConsumableTarget<'a, 'w> {
+    pub ship: Entity,
+    pub heat: &'a mut Heat,
+    pub hull: &'a mut Hull,
+    pub statuses: &'a mut EventWriter<'w, ApplyStatus>,
+}
+
+/// What a consumable is called on the HUD, and what using one does
+pub struct ConsumableDefinition {
+    pub name: &'static str,
+    pub color: Color,
+    /// The most of them a ship can carry. Any more are left floating
+    pub max_stack: u32,
+    pub apply: fn(&mut ConsumableTarget<'_, '_>),
+}
+
+/// Indexed by `ConsumableKind`
+const CONSUMABLES: [ConsumableDefinition; 3] = [
+    ConsumableDefinition {
+        name: "COOLANT",
+        color: Color::rgb(0.3, 0.7, 1.0),
+        max_stack: 3,
+        apply: vent_coolant,
+    },
+    ConsumableDefinition {
+        name: "ACCELERANT",
+        color: Color::rgb(0.9, 0.9, 0.2),
+        max_stack: 3,
+        apply: accelerate_reactions,
+    },
+    ConsumableDefinition {
+        name: "HULL PATCH",
+        color: Color::rgb(1.0, 0.3, 0.3),
+        max_stack: 2,
+        apply: patch_hull,
+    },
+];
+
+/// How much heat a coolant cell takes away
+const COOLANT_HEAT: f32 = 40.0;
+const ACCELERANT_SECONDS: f32 = 8.0;
+/// How much faster reactions run while accelerated, on top of their usual speed
+const ACCELERANT_STRENGTH: f32 = 1.0;
+const HULL_PATCH_REPAIR: f32 = 25.0;
+
+fn vent_coolant(target: &mut ConsumableTarget) {
+    target.heat.cool(COOLANT_HEAT);
+}
+
+fn accelerate_reactions(target: &mut ConsumableTarget) {
+    target.statuses.send(ApplyStatus {
+        entity: target.ship,
+        kind: StatusKind::Accelerated,
+        seconds: ACCELERANT_SECONDS,
+        strength: ACCELERANT_STRENGTH,
+    });
+}
+
+fn patch_hull(target: &mut ConsumableTarget) {
+    target.hull.repair(HULL_PATCH_REPAIR);
+}
+
+/// How many of each consumable a ship is carrying
+#[derive(Component, Debug, Default)]
+pub struct Consumables {
+    counts: [u32; 3],
+}
+
+impl Consumables {
+    pub fn count(&self, kind: ConsumableKind) -> u32 {
+        self.counts[kind as usize]
+    }
+
+    /// Adds one to the stack, unless it's full. Whether there was room for it
+    pub fn add(&mut self, kind: ConsumableKind) -> bool {
+        let count = &mut self.counts[kind as usize];
+        if *count >= kind.definition().max_stack {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    fn take(&mut self, kind: ConsumableKind) -> bool {
+        let count = &mut self.counts[kind as usize];
+        if *count == 0 {
+            return false;
+        }
+        *count -= 1;
+        true
+    }
+}
+
+/// One mesh for all of them, coloured by kind
+#[derive(Resource, Debug, Default)]
+pub struct ConsumableAppearance {
+    pub mesh: Handle<Mesh>,
+    /// Indexed by `ConsumableKind`
+    pub materials: Vec<Handle<StandardMaterial>>,
+}
+
+impl ConsumableAppearance {
+    pub fn material(&self, kind: ConsumableKind) -> Handle<StandardMaterial> {
+        self.materials[kind as usize].clone()
+    }
+}
+
+fn setup_consumable_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let materials = ConsumableKind::ALL
+        .iter()
+        .map(|kind| {
+            materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                emissive: kind.definition().color * 2.0,
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    let mesh = meshes.add(
+        shape::Cylinder {
+            radius: 0.25,
+            height: 0.7,
+            ..Default::default()
+        }
+        .into(),
+    );
+
+    commands.insert_resource(ConsumableAppearance { mesh, materials });
+}
+
+#[allow(clippy::type_complexity)]
+fn use_consumables(
+    mut query: Query<
+        (
+            Entity,
+            &ActionState<Action>,
+            &mut Consumables,
+            &mut Heat,
+            &mut Hull,
+        ),
+        With<Player>,
+    >,
+    mut status_writer: EventWriter<ApplyStatus>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+) {
+    for (ship, action_state, mut consumables, mut heat, mut hull) in &mut query {
+        for kind in ConsumableKind::ALL {
+            if !action_state.just_pressed(kind.action()) || !consumables.take(kind) {
+                continue;
+            }
+            debug!("Used {:?}", kind);
+            (kind.definition().apply)(&mut ConsumableTarget {
+                ship,
+                heat: &mut heat,
+                hull: &mut hull,
+                statuses: &mut status_writer,
+            });
+            sound_event_writer.send(SoundEvent::UsedConsumable);
+        }
+    }
+}
+
+pub struct ConsumablePlugin;
+
+impl Plugin for ConsumablePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_consumable_visuals)
+            .add_system(use_consumables.in_set(OnUpdate(GameState::InGame)));
+    }
+}
@@ -0,0 +1,191 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    game_time::GameTime,
+    inventory::{Inventory, Reagent, ReagentRegistry},
+    player::LeadPlayer,
+    starfield_shader::StarfieldMaterial,
+    state::GameState,
+    util::cosmetic::random_range,
+};
+
+/// How many wisps circle the ship once the continuum reaches its threshold
+const MAX_WISPS: usize = 12;
+const MIN_WISP_RADIUS: f32 = 2.0;
+const MAX_WISP_RADIUS: f32 = 4.5;
+/// How far the starfield twists at the threshold, see `StarfieldMaterial::warp`
+const FULL_WARP: f32 = 1.5;
+/// The warp throbs by this much on top of `FULL_WARP` once the threshold is reached
+const CLIMAX_THROB: f32 = 0.5;
+/// How quickly the warp catches up with the continuum, per second
+const WARP_EASING: f32 = 1.5;
+
+/// How full the continuum is, from 0 to 1 at the current stage's threshold, or at a full bar
+/// when there's no threshold
+#[derive(Resource, Debug, Default)]
+pub struct ContinuumFill(pub f32);
+
+/// The twist the starfield is at right now, easing towards what the continuum calls for
+#[derive(Resource, Debug, Default)]
+struct StarfieldWarp(f32);
+
+fn update_continuum_fill(
+    inventory_query: Query<&Inventory, LeadPlayer>,
+    mut fill: ResMut<ContinuumFill>,
+) {
+    let Ok(inventory) = inventory_query.get_single() else { return; };
+    let entry = inventory.reagent(Reagent::CONTINUUM);
+    let threshold = entry.threshold().unwrap_or(1.0).max(0.01);
+    fill.0 = (entry.fraction() / threshold).clamp(0.0, 1.0);
+}
+
+/// A shimmer of bent space circling the ship, more of them the more continuum it holds
+#[derive(Component, Debug)]
+struct ContinuumWisp {
+    angle: f32,
+    radius: f32,
+    /// Radians per second, some go the other way round
+    speed: f32,
+    /// So they don't all shimmer together
+    phase: f32,
+}
+
+#[derive(Resource, Debug, Default)]
+struct WispAppearance {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+fn setup_wisp_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    registry: Res<ReagentRegistry>,
+) {
+    let color = registry.get(Reagent::CONTINUUM).color;
+    let material = materials.add(StandardMaterial {
+        base_color: color.with_a(0.4),
+        emissive: color * 2.0,
+        alpha_mode: AlphaMode::Add,
+        unlit: true,
+        ..Default::default()
+    });
+    let mesh = meshes.add(
+        shape::UVSphere {
+            radius: 0.2,
+            ..Default::default()
+        }
+        .into(),
+    );
+    commands.insert_resource(WispAppearance { mesh, material });
+}
+
+/// Adds or takes away wisps until there are as many as the continuum calls for
+fn spawn_continuum_wisps(
+    mut commands: Commands,
+    wisp_query: Query<Entity, With<ContinuumWisp>>,
+    wisp_appearance: Res<WispAppearance>,
+    fill: Res<ContinuumFill>,
+) {
+    let wanted = (fill.0 * MAX_WISPS as f32).round() as usize;
+    let current = wisp_query.iter().count();
+    for entity in wisp_query.iter().skip(wanted) {
+        commands.entity(entity).despawn_recursive();
+    }
+    for _ in current.min(wanted)..wanted {
+        commands.spawn((
+            PbrBundle {
+                mesh: wisp_appearance.mesh.clone(),
+                material: wisp_appearance.material.clone(),
+                // Out of sight until it's moved next to the ship
+                transform: Transform::from_scale(Vec3::ZERO),
+                ..Default::default()
+            },
+            ContinuumWisp {
+                angle: random_range(0.0, TAU),
+                radius: random_range(MIN_WISP_RADIUS, MAX_WISP_RADIUS),
+                speed: random_range(0.5, 1.5) * random_range(-1.0_f32, 1.0).signum(),
+                phase: random_range(0.0, TAU),
+            },
+        ));
+    }
+}
+
+/// The wisps circle faster and shimmer harder as the continuum fills
+fn orbit_continuum_wisps(
+    mut wisp_query: Query<(&mut ContinuumWisp, &mut Transform)>,
+    player_query: Query<&Transform, (LeadPlayer, Without<ContinuumWisp>)>,
+    fill: Res<ContinuumFill>,
+    game_time: Res<GameTime>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let elapsed = time.elapsed_seconds();
+    for (mut wisp, mut transform) in &mut wisp_query {
+        wisp.angle += wisp.speed * (1.0 + fill.0) * game_time.delta_seconds();
+        let shimmer = (elapsed * 6.0 + wisp.phase).sin();
+        let radius = wisp.radius + 0.3 * (elapsed * 1.3 + wisp.phase).sin();
+        let offset = Vec2::from_angle(wisp.angle) * radius;
+        transform.translation = player_transform.translation + offset.extend(0.5);
+        transform.scale = Vec3::splat((1.0 + 0.5 * shimmer) * (0.5 + fill.0));
+    }
+}
+
+/// Twists the starfield in step with the continuum, throbbing once it's reached the threshold
+fn warp_starfield(
+    mut starfields: ResMut<Assets<StarfieldMaterial>>,
+    fill: Res<ContinuumFill>,
+    mut warp: ResMut<StarfieldWarp>,
+    game_time: Res<GameTime>,
+    time: Res<Time>,
+) {
+    let mut target = fill.0 * fill.0 * FULL_WARP;
+    if fill.0 >= 1.0 {
+        target += CLIMAX_THROB * (time.elapsed_seconds() * 2.0).sin().abs();
+    }
+    let t = (WARP_EASING * game_time.delta_seconds()).min(1.0);
+    warp.0 += (target - warp.0) * t;
+    for (_, starfield) in starfields.iter_mut() {
+        starfield.warp = warp.0;
+    }
+}
+
+fn cleanup_continuum(
+    mut commands: Commands,
+    query: Query<Entity, With<ContinuumWisp>>,
+    mut starfields: ResMut<Assets<StarfieldMaterial>>,
+    mut fill: ResMut<ContinuumFill>,
+    mut warp: ResMut<StarfieldWarp>,
+) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+    for (_, starfield) in starfields.iter_mut() {
+        starfield.warp = 0.0;
+    }
+    fill.0 = 0.0;
+    warp.0 = 0.0;
+}
+
+pub struct ContinuumPlugin;
+
+impl Plugin for ContinuumPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ContinuumFill>()
+            .init_resource::<StarfieldWarp>()
+            .add_startup_system(setup_wisp_appearance)
+            .add_systems(
+                (
+                    update_continuum_fill,
+                    spawn_continuum_wisps,
+                    orbit_continuum_wisps,
+                    warp_starfield,
+                )
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(cleanup_continuum.in_schedule(OnEnter(GameState::Outro)));
+    }
+}
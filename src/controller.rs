@@ -0,0 +1,227 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    cli::LaunchOptions,
+    collectible::Collectible,
+    heat::Heat,
+    input::Action,
+    inventory::Inventory,
+    player::{LeadPlayer, Player},
+    replay::ReplaySystem,
+    rock::Rock,
+    state::GameState,
+};
+
+/// The autopilot only shoots at rocks this close, anything further is likely to miss
+const AUTOPILOT_RANGE: f32 = 20.0;
+/// The autopilot lets go of the trigger above this much heat, so it doesn't cook itself
+const AUTOPILOT_MAX_HEAT: f32 = 0.8;
+
+/// What a script can see of the world on each tick
+pub struct ControllerView<'a> {
+    /// Fixed ticks since the script took over
+    pub tick: u64,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub heat: &'a Heat,
+    pub inventory: &'a Inventory,
+    pub rocks: &'a [Vec2],
+    pub collectibles: &'a [Vec2],
+}
+
+/// Flies a ship in place of the keyboard and mouse, for attract mode, smoke tests and ghosts.
+/// Actions pressed here are read the same way as ones from the input map
+pub trait ControllerScript: Send + Sync + 'static {
+    /// Called at the start of every fixed tick. `facing` is the angle the ship aims at, which
+    /// otherwise comes from the mouse
+    fn update(
+        &mut self,
+        view: &ControllerView,
+        action_state: &mut ActionState<Action>,
+        facing: &mut f32,
+    );
+}
+
+/// Who's flying a ship. Ships without one are flown by their input map as usual
+#[derive(Component)]
+pub enum PlayerController {
+    Input,
+    Script {
+        script: Box<dyn ControllerScript>,
+        tick: u64,
+    },
+}
+
+impl PlayerController {
+    pub fn script(script: impl ControllerScript) -> Self {
+        Self::Script {
+            script: Box::new(script),
+            tick: 0,
+        }
+    }
+
+    pub fn is_scripted(&self) -> bool {
+        matches!(self, Self::Script { .. })
+    }
+}
+
+/// The input map taken off a scripted ship, to be put back if it's handed back to the input
+#[derive(Component)]
+struct DetachedInputMap(InputMap<Action>);
+
+/// A simple bot that turns to the nearest rock and shoots it, and flies to the nearest
+/// collectible. Good enough to show the game off and to get through the first few stages
+#[derive(Debug, Default)]
+pub struct Autopilot;
+
+impl ControllerScript for Autopilot {
+    fn update(
+        &mut self,
+        view: &ControllerView,
+        action_state: &mut ActionState<Action>,
+        facing: &mut f32,
+    ) {
+        let nearest = |points: &[Vec2]| {
+            points.iter().copied().min_by(|a, b| {
+                a.distance_squared(view.position)
+                    .total_cmp(&b.distance_squared(view.position))
+            })
+        };
+
+        let mut fire = false;
+        if let Some(rock) = nearest(view.rocks) {
+            let dir = rock - view.position;
+            *facing = f32::atan2(dir.y, dir.x);
+            fire = dir.length() < AUTOPILOT_RANGE && view.heat.fraction() < AUTOPILOT_MAX_HEAT;
+        }
+        if fire {
+            action_state.press(Action::FireMainGun);
+        } else {
+            action_state.release(Action::FireMainGun);
+        }
+
+        let dir = nearest(view.collectibles)
+            .map(|collectible| (collectible - view.position).normalize_or_zero())
+            .unwrap_or(Vec2::ZERO);
+        let directions = [
+            (Action::MoveRight, dir.x > 0.3),
+            (Action::MoveLeft, dir.x < -0.3),
+            (Action::MoveUp, dir.y > 0.3),
+            (Action::MoveDown, dir.y < -0.3),
+        ];
+        for (action, pressed) in directions {
+            if pressed {
+                action_state.press(action);
+            } else {
+                action_state.release(action);
+            }
+        }
+    }
+}
+
+/// Without an input map the keyboard and mouse can't fight the script over the actions. The map
+/// is kept to one side rather than thrown away
+fn detach_scripted_input(
+    mut commands: Commands,
+    query: Query<(Entity, &PlayerController, &InputMap<Action>)>,
+) {
+    for (entity, controller, input_map) in &query {
+        if controller.is_scripted() {
+            commands
+                .entity(entity)
+                .remove::<InputMap<Action>>()
+                .insert(DetachedInputMap(input_map.clone()));
+        }
+    }
+}
+
+fn reattach_input(
+    mut commands: Commands,
+    mut query: Query<(Entity, &PlayerController, &DetachedInputMap, &mut ActionState<Action>)>,
+) {
+    for (entity, controller, detached, mut action_state) in &mut query {
+        if !controller.is_scripted() {
+            // Don't leave anything held down from the script
+            action_state.release_all();
+            commands
+                .entity(entity)
+                .remove::<DetachedInputMap>()
+                .insert(detached.0.clone());
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn run_scripts(
+    mut player_query: Query<(
+        &mut PlayerController,
+        &mut Player,
+        &mut ActionState<Action>,
+        &Transform,
+        &Velocity,
+        &Heat,
+        &Inventory,
+    )>,
+    rock_query: Query<&Transform, With<Rock>>,
+    collectible_query: Query<&Transform, With<Collectible>>,
+) {
+    let rocks: Vec<_> = rock_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .collect();
+    let collectibles: Vec<_> = collectible_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .collect();
+
+    for (mut controller, mut player, mut action_state, transform, velocity, heat, inventory) in
+        &mut player_query
+    {
+        let PlayerController::Script { script, tick } = controller.as_mut() else { continue; };
+        let view = ControllerView {
+            tick: *tick,
+            position: transform.translation.truncate(),
+            velocity: velocity.linvel,
+            heat,
+            inventory,
+            rocks: &rocks,
+            collectibles: &collectibles,
+        };
+        script.update(&view, &mut action_state, &mut player.facing);
+        *tick += 1;
+    }
+}
+
+/// `--autopilot` hands the first player over to the `Autopilot` as soon as they spawn
+fn attach_autopilot(
+    mut commands: Commands,
+    options: Option<Res<LaunchOptions>>,
+    query: Query<Entity, (Added<Player>, LeadPlayer)>,
+) {
+    if !options.is_some_and(|options| options.autopilot) {
+        return;
+    }
+    for entity in &query {
+        info!("Handing the player over to the autopilot");
+        commands
+            .entity(entity)
+            .insert(PlayerController::script(Autopilot));
+    }
+}
+
+pub struct ControllerPlugin;
+
+impl Plugin for ControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems((detach_scripted_input, reattach_input, attach_autopilot))
+            .add_system(
+                run_scripts
+                    // Same as a replay, so movement sees the actions on the same tick
+                    .before(ReplaySystem)
+                    .run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
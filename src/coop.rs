@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::{
+    camera::{MainCamera, SmoothFollow},
+    input::second_player_input_map,
+    inventory::{Inventory, ReagentRegistry},
+    player::{LeadPlayer, PlayerBundle, SecondPlayer},
+    state::GameState,
+};
+
+/// How far to the right of the first player the second one starts
+const SPAWN_OFFSET: f32 = 3.0;
+
+/// Set from the settings menu. Whether there's a second player is only looked at when a run
+/// starts, the cargo can be switched between shared and split at any time
+#[derive(Resource, Debug)]
+pub struct CoopSettings {
+    /// Whether a second player joins on the first gamepad
+    pub enabled: bool,
+    /// Whether both players fill the same cargo hold, or each has their own
+    pub shared_inventory: bool,
+}
+
+impl Default for CoopSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shared_inventory: true,
+        }
+    }
+}
+
+fn coop_enabled(coop_settings: Res<CoopSettings>) -> bool {
+    coop_settings.enabled
+}
+
+fn spawn_second_player(
+    mut commands: Commands,
+    mut main_camera_query: Query<&mut SmoothFollow, With<MainCamera>>,
+    registry: Res<ReagentRegistry>,
+) {
+    let second_player = commands
+        .spawn((
+            PlayerBundle {
+                transform: Transform::from_xyz(SPAWN_OFFSET, 0.0, 1.0),
+                input_map: second_player_input_map(),
+                inventory: Inventory::new(&registry),
+                ..Default::default()
+            },
+            SecondPlayer,
+        ))
+        .id();
+    debug!("Spawned the second player");
+
+    let Ok(mut smooth_follow) = main_camera_query.get_single_mut() else { return; };
+    smooth_follow.partner = Some(second_player);
+}
+
+/// With shared cargo, whatever the second player picked up or used since last time goes into the
+/// first player's inventory, which is then copied back so they both see the same amounts
+fn share_inventory(
+    coop_settings: Res<CoopSettings>,
+    mut lead_query: Query<&mut Inventory, LeadPlayer>,
+    mut second_query: Query<(Entity, &mut Inventory), With<SecondPlayer>>,
+    mut last_synced: Local<Option<(Entity, Vec<f32>)>>,
+) {
+    if !coop_settings.shared_inventory {
+        // Anything that happens while it's split stays split
+        *last_synced = None;
+        return;
+    }
+    let Ok(mut lead) = lead_query.get_single_mut() else { return; };
+    let Ok((entity, mut second)) = second_query.get_single_mut() else { return; };
+
+    let reagents: Vec<_> = lead.reagents().map(|(reagent, _)| reagent).collect();
+    let mut synced = Vec::with_capacity(reagents.len());
+    for reagent in reagents {
+        // Nothing to carry over the first time around, or when it's a new second player
+        if let Some((last_entity, last)) = &*last_synced {
+            if *last_entity == entity {
+                let delta = second.reagent(reagent).current() - last[reagent.index()];
+                lead.reagent_mut(reagent).add(delta);
+            }
+        }
+        let shared = lead.reagent(reagent).current();
+        let entry = second.reagent_mut(reagent);
+        entry.add(shared - entry.current());
+        synced.push(shared);
+    }
+    *last_synced = Some((entity, synced));
+}
+
+pub struct CoopPlugin;
+
+impl Plugin for CoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CoopSettings>()
+            .add_system(
+                spawn_second_player
+                    .run_if(coop_enabled)
+                    .in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_system(share_inventory.in_set(OnUpdate(GameState::InGame)));
+    }
+}
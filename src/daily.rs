@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{
+    menu::MenuEvent,
+    save_data::SaveData,
+    state::{GameState, ProgressStages},
+    stats::RunStats,
+    ui::{CustomUICamera, UIMarker},
+    util::GameRng,
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const TIMER_FONT_SIZE: f32 = 30.0;
+const TIMER_PADDING: f32 = 10.0;
+
+/// Days since the unix epoch, in UTC so everyone gets the same day at the same time
+#[cfg(not(target_arch = "wasm32"))]
+fn today() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or_default()
+}
+
+/// `SystemTime` isn't available on the web
+#[cfg(target_arch = "wasm32")]
+fn today() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64 / SECONDS_PER_DAY
+}
+
+/// Spreads consecutive days out over the whole range, so neighbouring days don't get similar
+/// rocks. This is splitmix64's finalizer
+fn daily_seed(day: u64) -> u64 {
+    let mut z = day.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The day as YYYY-MM-DD, from days since the epoch. See
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn format_date(day: u64) -> String {
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day_of_month:02}")
+}
+
+/// Set from the main menu. The run is the usual one, but everyone playing on the same day gets
+/// the same seed
+#[derive(Resource, Debug, Clone)]
+pub struct DailyRun {
+    pub date: String,
+    pub seed: u64,
+}
+
+impl DailyRun {
+    pub fn today() -> Self {
+        let day = today();
+        Self {
+            date: format_date(day),
+            seed: daily_seed(day),
+        }
+    }
+}
+
+/// A finished daily run
+#[derive(Debug, Clone)]
+pub struct DailyResult {
+    pub date: String,
+    pub seed: u64,
+    pub time: Duration,
+}
+
+/// Somewhere to send daily results besides the save file. Nothing is sent anywhere unless a
+/// `DailyLeaderboard` is added to the app
+pub trait LeaderboardBackend: Send + Sync + 'static {
+    fn submit(&self, result: &DailyResult);
+}
+
+#[derive(Resource)]
+pub struct DailyLeaderboard(pub Box<dyn LeaderboardBackend>);
+
+#[derive(Component, Debug, Default)]
+struct DailyTimer;
+
+fn start_daily_run(
+    mut commands: Commands,
+    mut reader: EventReader<MenuEvent>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_game_stage: ResMut<NextState<ProgressStages>>,
+) {
+    for ev in reader.iter() {
+        if let MenuEvent::DailyRun = ev {
+            if current_state.0 == GameState::MainMenu {
+                let daily_run = DailyRun::today();
+                debug!("Starting the daily run for {}", daily_run.date);
+                commands.insert_resource(daily_run);
+                next_state.set(GameState::Intro);
+                next_game_stage.set(ProgressStages::default());
+            }
+        }
+    }
+}
+
+/// Like a replay, the seed goes in before anything random happens in the run
+fn seed_daily_run(mut commands: Commands, daily_run: Res<DailyRun>) {
+    commands.insert_resource(GameRng::new(daily_run.seed));
+}
+
+fn setup_daily_timer(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: TIMER_FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            ),
+            text_anchor: Anchor::TopLeft,
+            ..Default::default()
+        },
+        DailyTimer,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+fn update_daily_timer(
+    mut timer_query: Query<
+        (&mut Text, &mut Transform),
+        (With<DailyTimer>, Without<CustomUICamera>),
+    >,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    daily_run: Res<DailyRun>,
+    run_stats: Res<RunStats>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else { return; };
+    let Some(size) = ui_camera.logical_viewport_size() else { return; };
+    let elapsed = run_stats.time.as_secs_f32();
+    for (mut text, mut transform) in &mut timer_query {
+        transform.translation.x = -size.x / 2.0 + TIMER_PADDING;
+        transform.translation.y = size.y / 2.0 - TIMER_PADDING;
+        text.sections[0].value = format!(
+            "DAILY {}  {:02}:{:05.2}",
+            daily_run.date,
+            (elapsed / 60.0) as u32,
+            elapsed % 60.0
+        );
+    }
+}
+
+/// The outro is only reached by escaping, so this is a finished run
+fn record_daily_result(
+    daily_run: Res<DailyRun>,
+    run_stats: Res<RunStats>,
+    mut save_data: ResMut<SaveData>,
+    leaderboard: Option<Res<DailyLeaderboard>>,
+) {
+    let result = DailyResult {
+        date: daily_run.date.clone(),
+        seed: daily_run.seed,
+        time: run_stats.time,
+    };
+    info!(
+        "Finished the daily run for {} in {:.2}s",
+        result.date,
+        result.time.as_secs_f32()
+    );
+    let seconds = result.time.as_secs_f32();
+    let best = save_data.daily_bests.get(&result.date).copied();
+    if !best.is_some_and(|best| best <= seconds) {
+        save_data.daily_bests.insert(result.date.clone(), seconds);
+        save_data.save();
+    }
+    if let Some(leaderboard) = leaderboard {
+        leaderboard.0.submit(&result);
+    }
+}
+
+fn end_daily_run(mut commands: Commands) {
+    commands.remove_resource::<DailyRun>();
+}
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(start_daily_run.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(
+                seed_daily_run
+                    .run_if(resource_exists::<DailyRun>())
+                    .in_schedule(OnEnter(GameState::Intro)),
+            )
+            .add_system(
+                setup_daily_timer
+                    .run_if(resource_exists::<DailyRun>())
+                    .in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_system(
+                update_daily_timer
+                    .run_if(resource_exists::<DailyRun>())
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(
+                record_daily_result
+                    .run_if(resource_exists::<DailyRun>())
+                    .in_schedule(OnEnter(GameState::Outro)),
+            )
+            // Kept until after the end screen, which shows the date
+            .add_system(end_daily_run.in_schedule(OnExit(GameState::EndScreen)));
+    }
+}
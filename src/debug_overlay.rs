@@ -0,0 +1,147 @@
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    render::view::RenderLayers,
+    sprite::Anchor,
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    heat::Heat,
+    input::MenuAction,
+    particles::Particle,
+    player::LeadPlayer,
+    rock::{Rock, RockLimit},
+    state::{GameState, ProgressStages},
+    ui::CustomUICamera,
+    weapon::Slug,
+};
+
+const OVERLAY_FONT_SIZE: f32 = 20.0;
+const OVERLAY_PADDING: f32 = 10.0;
+
+#[derive(Component, Debug, Default)]
+pub struct DebugOverlay;
+
+fn setup_debug_overlay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: OVERLAY_FONT_SIZE,
+                    color: Color::YELLOW_GREEN,
+                },
+            ),
+            text_anchor: Anchor::BottomLeft,
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        DebugOverlay,
+        RenderLayers::layer(1),
+    ));
+}
+
+fn toggle_debug_overlay(
+    action_query: Query<&ActionState<MenuAction>>,
+    mut overlay_query: Query<&mut Visibility, With<DebugOverlay>>,
+) {
+    if !action_query
+        .iter()
+        .any(|action_state| action_state.just_pressed(MenuAction::DebugOverlay))
+    {
+        return;
+    }
+    for mut visibility in &mut overlay_query {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn reposition_debug_overlay(
+    mut overlay_query: Query<&mut Transform, (With<DebugOverlay>, Without<CustomUICamera>)>,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else { return; };
+    let Some(size) = ui_camera.logical_viewport_size() else { return; };
+    for mut transform in &mut overlay_query {
+        transform.translation.x = -size.x / 2.0 + OVERLAY_PADDING;
+        transform.translation.y = -size.y / 2.0 + OVERLAY_PADDING;
+        transform.translation.z = 10.0;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_debug_overlay(
+    mut overlay_query: Query<(&mut Text, &Visibility), With<DebugOverlay>>,
+    diagnostics: Res<Diagnostics>,
+    rock_limit: Option<Res<RockLimit>>,
+    rock_query: Query<(), With<Rock>>,
+    particle_query: Query<(), With<Particle>>,
+    slug_query: Query<(), With<Slug>>,
+    player_query: Query<(&Transform, &Heat), LeadPlayer>,
+    game_state: Res<State<GameState>>,
+    progress_stage: Res<State<ProgressStages>>,
+) {
+    let Ok((mut text, visibility)) = overlay_query.get_single_mut() else { return; };
+    if visibility == Visibility::Hidden {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|d| d.smoothed())
+        .unwrap_or(0.0);
+    let frame_time = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|d| d.smoothed())
+        .unwrap_or(0.0);
+
+    let mut lines = vec![
+        format!("FPS: {fps:.0} ({frame_time:.2} ms)"),
+        match rock_limit {
+            // The count is kept separately from the rocks, so the real number is shown as a check
+            Some(rock_limit) => format!(
+                "Rocks: {} / {} ({} alive)",
+                rock_limit.current(),
+                rock_limit.limit(),
+                rock_query.iter().count()
+            ),
+            None => "Rocks: -".to_owned(),
+        },
+        format!("Particles: {}", particle_query.iter().count()),
+        format!("Slugs: {}", slug_query.iter().count()),
+        format!("State: {:?} / {:?}", game_state.0, progress_stage.0),
+    ];
+    if let Ok((transform, heat)) = player_query.get_single() {
+        lines.push(format!("Heat: {:.1} / {:.1}", heat.current(), heat.limit()));
+        lines.push(format!(
+            "Position: {:.1}, {:.1}",
+            transform.translation.x, transform.translation.y
+        ));
+    }
+
+    text.sections[0].value = lines.join("\n");
+}
+
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugin(FrameTimeDiagnosticsPlugin);
+        }
+        app.add_startup_system(setup_debug_overlay).add_systems(
+            (
+                toggle_debug_overlay,
+                reposition_debug_overlay,
+                update_debug_overlay,
+            )
+                .chain(),
+        );
+    }
+}
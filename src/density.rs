@@ -0,0 +1,205 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{camera::MainCamera, rock::Rock, state::GameState};
+
+/// Rocks are counted up in squares of space this big
+pub const DENSITY_CELL_SIZE: f32 = 10.0;
+/// Counting every rock every frame isn't needed for something this coarse
+const REBUILD_SECONDS: f32 = 0.5;
+/// Past this orthographic scale the rocks are too small to make out, so the ones further out
+/// are swapped for the overlay
+const DENSITY_ZOOM: f32 = 40.0;
+/// Rocks this close to the camera are still drawn when zoomed out
+const DETAIL_RADIUS: f32 = 40.0;
+/// The overlay has this many shades, from sparse to packed
+const DENSITY_LEVELS: usize = 6;
+const SPARSE_COLOR: Color = Color::rgba(0.2, 0.3, 1.0, 0.15);
+const DENSE_COLOR: Color = Color::rgba(1.0, 0.5, 0.1, 0.5);
+
+/// How many rocks there are in each square of space, a coarse index of where the rocks are
+#[derive(Resource, Debug, Default)]
+pub struct RockDensity {
+    cells: HashMap<IVec2, u32>,
+    busiest: u32,
+}
+
+impl RockDensity {
+    /// The centre of each square with rocks in it, and how full it is from 0 to 1 compared to
+    /// the busiest one
+    pub fn cells(&self) -> impl Iterator<Item = (Vec2, f32)> + '_ {
+        self.cells.iter().map(|(cell, count)| {
+            let center = (cell.as_vec2() + Vec2::splat(0.5)) * DENSITY_CELL_SIZE;
+            (center, *count as f32 / self.busiest.max(1) as f32)
+        })
+    }
+}
+
+/// The overlay's shade for a square that's `fraction` as full as the busiest one
+pub fn density_color(fraction: f32) -> Color {
+    let [r1, g1, b1, a1] = SPARSE_COLOR.as_rgba_f32();
+    let [r2, g2, b2, a2] = DENSE_COLOR.as_rgba_f32();
+    let t = fraction.clamp(0.0, 1.0);
+    Color::rgba(
+        r1 + (r2 - r1) * t,
+        g1 + (g2 - g1) * t,
+        b1 + (b2 - b1) * t,
+        a1 + (a2 - a1) * t,
+    )
+}
+
+fn rebuild_rock_density(
+    rock_query: Query<&Transform, With<Rock>>,
+    mut density: ResMut<RockDensity>,
+    mut timer: Local<Option<Timer>>,
+    time: Res<Time>,
+) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(REBUILD_SECONDS, TimerMode::Repeating));
+    timer.tick(time.delta());
+    if !timer.just_finished() {
+        return;
+    }
+    density.cells.clear();
+    for transform in &rock_query {
+        let cell = (transform.translation.truncate() / DENSITY_CELL_SIZE)
+            .floor()
+            .as_ivec2();
+        *density.cells.entry(cell).or_default() += 1;
+    }
+    density.busiest = density.cells.values().copied().max().unwrap_or_default();
+}
+
+/// One square of the overlay. There's a pool of them, taken out as the rocks spread
+#[derive(Component, Debug)]
+struct DensityTile;
+
+#[derive(Resource, Debug, Default)]
+struct DensityAppearance {
+    mesh: Handle<Mesh>,
+    /// One for each shade, sparsest first
+    materials: Vec<Handle<StandardMaterial>>,
+}
+
+fn setup_density_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mesh = meshes.add(shape::Quad::new(Vec2::splat(DENSITY_CELL_SIZE)).into());
+    let materials = (0..DENSITY_LEVELS)
+        .map(|level| {
+            materials.add(StandardMaterial {
+                base_color: density_color(level as f32 / (DENSITY_LEVELS - 1) as f32),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..Default::default()
+            })
+        })
+        .collect();
+    commands.insert_resource(DensityAppearance { mesh, materials });
+}
+
+/// Whether the camera's zoomed out far enough for the overlay. The camera director's wide
+/// shots and photo mode are the only things that get this far out
+fn zoomed_out(camera_query: &Query<(&Projection, &GlobalTransform), With<MainCamera>>) -> bool {
+    camera_query
+        .get_single()
+        .is_ok_and(|(projection, _)| match projection {
+            Projection::Orthographic(orthographic) => orthographic.scale > DENSITY_ZOOM,
+            _ => false,
+        })
+}
+
+fn update_density_overlay(
+    mut commands: Commands,
+    camera_query: Query<(&Projection, &GlobalTransform), With<MainCamera>>,
+    mut tile_query: Query<
+        (
+            &mut Transform,
+            &mut Handle<StandardMaterial>,
+            &mut Visibility,
+        ),
+        With<DensityTile>,
+    >,
+    density: Res<RockDensity>,
+    appearance: Res<DensityAppearance>,
+) {
+    let mut cells = density.cells();
+    if zoomed_out(&camera_query) {
+        for (mut transform, mut material, mut visibility) in &mut tile_query {
+            let Some((center, fraction)) = cells.next() else {
+                *visibility = Visibility::Hidden;
+                continue;
+            };
+            let level = (fraction * (DENSITY_LEVELS - 1) as f32).round() as usize;
+            // Behind the rocks, in front of the background
+            transform.translation = center.extend(-1.0);
+            *material = appearance.materials[level].clone();
+            *visibility = Visibility::Visible;
+        }
+        // More squares than tiles, the rest are made next frame
+        for _ in cells {
+            commands.spawn((
+                PbrBundle {
+                    mesh: appearance.mesh.clone(),
+                    material: appearance.materials[0].clone(),
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+                DensityTile,
+            ));
+        }
+    } else {
+        for (_, _, mut visibility) in &mut tile_query {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+/// Hides the rocks the overlay stands in for, and brings them back when zooming in again
+fn hide_distant_rocks(
+    camera_query: Query<(&Projection, &GlobalTransform), With<MainCamera>>,
+    mut rock_query: Query<(&GlobalTransform, &mut Visibility), With<Rock>>,
+) {
+    let zoomed_out = zoomed_out(&camera_query);
+    let Ok((_, camera_transform)) = camera_query.get_single() else { return; };
+    let camera_pos = camera_transform.translation().truncate();
+    for (transform, mut visibility) in &mut rock_query {
+        let distance = transform.translation().truncate().distance(camera_pos);
+        let wanted = if zoomed_out && distance > DETAIL_RADIUS {
+            Visibility::Hidden
+        } else {
+            Visibility::Visible
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+fn cleanup_density_overlay(mut commands: Commands, query: Query<Entity, With<DensityTile>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn game_on_screen(state: Res<State<GameState>>) -> bool {
+    matches!(state.0, GameState::InGame | GameState::Paused)
+}
+
+pub struct DensityPlugin;
+
+impl Plugin for DensityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RockDensity>()
+            .add_startup_system(setup_density_appearance)
+            .add_system(rebuild_rock_density.in_set(OnUpdate(GameState::InGame)))
+            // Photo mode zooms out while the game is paused
+            .add_systems(
+                (update_density_overlay, hide_distant_rocks)
+                    .after(rebuild_rock_density)
+                    .distributive_run_if(game_on_screen),
+            )
+            .add_system(cleanup_density_overlay.in_schedule(OnEnter(GameState::Outro)));
+    }
+}
@@ -0,0 +1,43 @@
+use bevy::{prelude::*, utils::HashSet};
+
+/// Entities waiting to be despawned, along with their children. Gameplay systems queue them
+/// up here instead of despawning them straight away, and they're all despawned together at the
+/// end of the frame. A rock hit by a slug and the shield in the same frame is only taken out,
+/// and only counted, once
+#[derive(Resource, Debug, Default)]
+pub struct DespawnQueue {
+    pending: HashSet<Entity>,
+}
+
+impl DespawnQueue {
+    /// Queues `entity` up to be despawned. Whether it wasn't already queued, so whatever
+    /// happens when something goes only happens once
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        self.pending.insert(entity)
+    }
+
+    /// Whether `entity` is on its way out this frame, and shouldn't be touched any more
+    pub fn is_queued(&self, entity: Entity) -> bool {
+        self.pending.contains(&entity)
+    }
+}
+
+/// Runs whatever state the game is in, so nothing queued just before pausing or leaving the run
+/// is left behind
+fn apply_despawns(mut commands: Commands, mut queue: ResMut<DespawnQueue>) {
+    for entity in queue.pending.drain() {
+        // Something outside the queue may have got to it first
+        if let Some(entity_commands) = commands.get_entity(entity) {
+            entity_commands.despawn_recursive();
+        }
+    }
+}
+
+pub struct DespawnPlugin;
+
+impl Plugin for DespawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DespawnQueue>()
+            .add_system(apply_despawns.in_base_set(CoreSet::Last));
+    }
+}
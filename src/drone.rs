@@ -0,0 +1,333 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collectible::{Collectible, CollectionEvent, CollectionSystem},
+    despawn::DespawnQueue,
+    game_time::GameTime,
+    inventory::Reagent,
+    particles::{Particle, ParticleBundle},
+    player::LeadPlayer,
+    practice::Practice,
+    state::{GameState, ProgressStages},
+    util::cosmetic::{random_in_circle, random_range},
+};
+
+/// The drone is unlocked once the player has collected enough minerals to move on from exploring
+const UNLOCK_STAGE: ProgressStages = ProgressStages::GunAndHeat;
+
+/// A helper drone that hangs around the player and brings back any loose minerals nearby
+#[derive(Component, Debug)]
+pub struct Drone {
+    pub state: DroneState,
+    /// Where the drone is along its orbit, in radians
+    pub orbit_angle: f32,
+    /// How often the drone looks around for something to fetch
+    pub scan_timer: Timer,
+    /// The drone leaves a particle in its trail whenever this timer finishes
+    pub trail_timer: Timer,
+}
+
+impl Default for Drone {
+    fn default() -> Self {
+        Self {
+            state: DroneState::Orbiting,
+            orbit_angle: 0.0,
+            scan_timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+            trail_timer: Timer::from_seconds(0.05, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DroneState {
+    Orbiting,
+    Fetching { target: Entity },
+    Returning { reagent: Reagent, amount: f32 },
+}
+
+const ORBIT_RADIUS: f32 = 3.0;
+const ORBIT_SPEED: f32 = 1.5;
+/// Only collectibles this close to the player are worth fetching
+const FETCH_RANGE: f32 = 15.0;
+const FLY_SPEED: f32 = 14.0;
+const GRAB_DISTANCE: f32 = 0.6;
+const DEPOSIT_DISTANCE: f32 = 1.0;
+
+#[derive(Resource, Debug)]
+struct DroneAppearance {
+    body_mesh: Handle<Mesh>,
+    body_material: Handle<StandardMaterial>,
+    ring_mesh: Handle<Mesh>,
+    ring_material: Handle<StandardMaterial>,
+    trail_mesh: Handle<Mesh>,
+    trail_material: Handle<StandardMaterial>,
+}
+
+fn setup_drone_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let body_mesh = meshes.add(
+        shape::UVSphere {
+            radius: 0.25,
+            ..Default::default()
+        }
+        .into(),
+    );
+    let body_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.7, 0.7, 0.75),
+        metallic: 0.8,
+        perceptual_roughness: 0.4,
+        ..Default::default()
+    });
+    let ring_mesh = meshes.add(
+        shape::Torus {
+            radius: 0.4,
+            ring_radius: 0.06,
+            subdivisions_segments: 16,
+            subdivisions_sides: 8,
+        }
+        .into(),
+    );
+    let ring_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(1.0, 0.8, 0.2),
+        emissive: Color::rgb(1.0, 0.6, 0.0) * 3.0,
+        ..Default::default()
+    });
+    let trail_mesh = meshes.add(
+        shape::Quad {
+            size: Vec2::splat(0.12),
+            ..Default::default()
+        }
+        .into(),
+    );
+    let trail_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(1.0, 0.8, 0.2),
+        emissive: Color::rgb(1.0, 0.5, 0.0) * 2.0,
+        ..Default::default()
+    });
+
+    commands.insert_resource(DroneAppearance {
+        body_mesh,
+        body_material,
+        ring_mesh,
+        ring_material,
+        trail_mesh,
+        trail_material,
+    });
+}
+
+fn spawn_drone_when_unlocked(
+    mut commands: Commands,
+    drone_query: Query<(), With<Drone>>,
+    player_query: Query<&Transform, LeadPlayer>,
+    progress: Res<State<ProgressStages>>,
+    practice: Option<Res<Practice>>,
+    drone_appearance: Res<DroneAppearance>,
+) {
+    let unlocked = progress.0 >= UNLOCK_STAGE || practice.is_some();
+    if !unlocked || !drone_query.is_empty() {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else { return; };
+
+    debug!("Drone unlocked");
+    let position = player_transform.translation + Vec3::new(ORBIT_RADIUS, 0.0, 0.0);
+    commands
+        .spawn((
+            Drone::default(),
+            SpatialBundle::from_transform(Transform::from_translation(position)),
+        ))
+        .with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: drone_appearance.body_mesh.clone(),
+                material: drone_appearance.body_material.clone(),
+                ..Default::default()
+            });
+            parent.spawn(PbrBundle {
+                mesh: drone_appearance.ring_mesh.clone(),
+                material: drone_appearance.ring_material.clone(),
+                // Tilt the ring so it reads from the top down camera
+                transform: Transform::from_rotation(Quat::from_rotation_x(PI / 3.0)),
+                ..Default::default()
+            });
+        });
+}
+
+/// Picks the closest loose mineral near the player that no other drone is already after
+fn find_fetch_target(
+    player_position: Vec2,
+    collectible_query: &Query<(Entity, &Collectible, &Transform), Without<Drone>>,
+    claimed: &[Entity],
+) -> Option<Entity> {
+    collectible_query
+        .iter()
+        .filter(|(entity, collectible, _)| {
+            matches!(
+                collectible,
+                Collectible::CollectibleReagent {
+                    reagent: Reagent::MINERALS,
+                    ..
+                }
+            ) && !claimed.contains(entity)
+        })
+        .map(|(entity, _, transform)| {
+            (entity, transform.translation.truncate().distance(player_position))
+        })
+        .filter(|(_, distance)| *distance < FETCH_RANGE)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+/// Moves `transform` towards `target` at `FLY_SPEED`, returning how far away it still is
+fn fly_towards(transform: &mut Transform, target: Vec2, delta_seconds: f32) -> f32 {
+    let position = transform.translation.truncate();
+    let offset = target - position;
+    let step = FLY_SPEED * delta_seconds;
+    if offset.length() <= step {
+        transform.translation.x = target.x;
+        transform.translation.y = target.y;
+        return 0.0;
+    }
+    let new_position = position + offset.normalize() * step;
+    transform.translation.x = new_position.x;
+    transform.translation.y = new_position.y;
+    offset.length() - step
+}
+
+fn update_drones(
+    mut drone_query: Query<(&mut Drone, &mut Transform), Without<Collectible>>,
+    player_query: Query<&Transform, (LeadPlayer, Without<Drone>)>,
+    collectible_query: Query<(Entity, &Collectible, &Transform), Without<Drone>>,
+    mut writer: EventWriter<CollectionEvent>,
+    game_time: Res<GameTime>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let player_position = player_transform.translation.truncate();
+    let delta_seconds = game_time.delta_seconds();
+
+    let mut claimed: Vec<Entity> = drone_query
+        .iter()
+        .filter_map(|(drone, _)| match drone.state {
+            DroneState::Fetching { target } => Some(target),
+            _ => None,
+        })
+        .collect();
+
+    for (mut drone, mut transform) in &mut drone_query {
+        drone.orbit_angle = (drone.orbit_angle + ORBIT_SPEED * delta_seconds) % TAU;
+        drone.scan_timer.tick(game_time.delta());
+
+        match drone.state {
+            DroneState::Orbiting => {
+                let orbit_offset = Vec2::from_angle(drone.orbit_angle) * ORBIT_RADIUS;
+                fly_towards(&mut transform, player_position + orbit_offset, delta_seconds);
+                if !drone.scan_timer.just_finished() {
+                    continue;
+                }
+                let target = find_fetch_target(player_position, &collectible_query, &claimed);
+                if let Some(target) = target {
+                    debug!("Drone fetching {target:?}");
+                    claimed.push(target);
+                    drone.state = DroneState::Fetching { target };
+                }
+            }
+            DroneState::Fetching { target } => {
+                // Someone else got to it first
+                let Ok((_, collectible, target_transform)) = collectible_query.get(target) else {
+                    drone.state = DroneState::Orbiting;
+                    continue;
+                };
+                let target_position = target_transform.translation.truncate();
+                if target_position.distance(player_position) > FETCH_RANGE * 1.5 {
+                    drone.state = DroneState::Orbiting;
+                    continue;
+                }
+                if fly_towards(&mut transform, target_position, delta_seconds) > GRAB_DISTANCE {
+                    continue;
+                }
+                let Collectible::CollectibleReagent { reagent, amount } = *collectible else {
+                    drone.state = DroneState::Orbiting;
+                    continue;
+                };
+                // The player or the shield took it this frame
+                if !despawn_queue.despawn(target) {
+                    drone.state = DroneState::Orbiting;
+                    continue;
+                }
+                drone.state = DroneState::Returning { reagent, amount };
+            }
+            DroneState::Returning { reagent, amount } => {
+                if fly_towards(&mut transform, player_position, delta_seconds) > DEPOSIT_DISTANCE {
+                    continue;
+                }
+                // Deposits go through the same path as the player picking things up themselves
+                writer.send(CollectionEvent {
+                    reagent,
+                    amount,
+                    position: transform.translation,
+                    collector: None,
+                });
+                drone.state = DroneState::Orbiting;
+            }
+        }
+    }
+}
+
+fn spin_drones(mut query: Query<(&Drone, &mut Transform)>) {
+    for (drone, mut transform) in &mut query {
+        transform.rotation = Quat::from_rotation_z(drone.orbit_angle * 4.0);
+    }
+}
+
+fn drone_trail(
+    mut commands: Commands,
+    mut query: Query<(&mut Drone, &Transform)>,
+    drone_appearance: Res<DroneAppearance>,
+    game_time: Res<GameTime>,
+) {
+    for (mut drone, transform) in &mut query {
+        drone.trail_timer.tick(game_time.delta());
+        for _ in 0..drone.trail_timer.times_finished_this_tick() {
+            let offset = random_in_circle(0.15);
+            commands.spawn(ParticleBundle {
+                mesh: drone_appearance.trail_mesh.clone(),
+                material: drone_appearance.trail_material.clone(),
+                particle: Particle {
+                    lifetime_timer: Timer::from_seconds(0.4, TimerMode::Once),
+                },
+                velocity: Velocity {
+                    linvel: Vec2::ZERO,
+                    angvel: random_range(-PI, PI),
+                },
+                transform: Transform::from_translation(
+                    transform.translation + Vec3::new(offset.x, offset.y, -0.1),
+                )
+                .with_scale(Vec3::splat(random_range(0.6, 1.0))),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+pub struct DronePlugin;
+
+impl Plugin for DronePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_drone_appearance).add_systems(
+            (
+                spawn_drone_when_unlocked,
+                update_drones.after(CollectionSystem),
+                spin_drones,
+                drone_trail,
+            )
+                .chain()
+                .in_set(OnUpdate(GameState::InGame)),
+        );
+    }
+}
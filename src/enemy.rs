@@ -0,0 +1,344 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    camera::MainCamera,
+    collectible::SpawnCollectibleEvent,
+    game_time::GameTime,
+    hull::{Faction, Hull},
+    inventory::Reagent,
+    physics::PIRATE_COLLISION_GROUPS,
+    player::LeadPlayer,
+    replay::ReplaySystem,
+    rock::Cull,
+    sound::SoundEvent,
+    state::{GameState, ProgressStages},
+    util::{random_direction, random_range, GameRng},
+    weapon::{Slug, SlugBundle, SlugPool},
+};
+
+#[derive(Component, Debug)]
+pub struct PirateShip {
+    pub facing: f32,
+    pub max_speed: f32,
+    pub acceleration: f32,
+    /// Pirates try to circle the player at about this distance
+    pub preferred_distance: f32,
+    /// Either 1.0 or -1.0, which way around the player the pirate is circling
+    pub strafe_direction: f32,
+    /// The pirate changes which way it's circling whenever this timer finishes
+    pub strafe_timer: Timer,
+    pub fire_timer: Timer,
+    pub fire_range: f32,
+    pub projectile_speed: f32,
+    pub damage: f32,
+}
+
+impl Default for PirateShip {
+    fn default() -> Self {
+        Self {
+            facing: 0.0,
+            max_speed: 10.0,
+            acceleration: 20.0,
+            preferred_distance: 12.0,
+            strafe_direction: 1.0,
+            strafe_timer: Timer::from_seconds(3.0, TimerMode::Repeating),
+            fire_timer: Timer::from_seconds(1.5, TimerMode::Repeating),
+            fire_range: 20.0,
+            projectile_speed: 25.0,
+            damage: 10.0,
+        }
+    }
+}
+
+#[derive(Component, Debug, Default)]
+struct PirateModel;
+
+#[derive(Component, Debug)]
+pub struct PirateSpawner {
+    pub max_pirates: usize,
+    /// Pirates spawn within this range of the main camera
+    pub min_spawn_distance: f32,
+    pub max_spawn_distance: f32,
+    /// The PirateSpawner tries to spawn a pirate whenever this timer finishes
+    pub spawn_timer: Timer,
+}
+
+impl Default for PirateSpawner {
+    fn default() -> Self {
+        Self {
+            max_pirates: 3,
+            min_spawn_distance: 35.0,
+            max_spawn_distance: 45.0,
+            spawn_timer: Timer::from_seconds(15.0, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+struct PirateAppearance {
+    body_mesh: Handle<Mesh>,
+    body_material: Handle<StandardMaterial>,
+    slug_mesh: Handle<Mesh>,
+    slug_material: Handle<StandardMaterial>,
+}
+
+fn setup_pirate_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let body_mesh = meshes.add(shape::Box::new(1.6, 0.8, 0.5).into());
+    let body_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.4, 0.05, 0.05),
+        emissive: Color::rgb(0.5, 0.0, 0.0),
+        metallic: 0.8,
+        perceptual_roughness: 0.3,
+        ..Default::default()
+    });
+
+    let slug_mesh = meshes.add(
+        shape::Capsule {
+            depth: 0.5,
+            radius: 0.1,
+            ..Default::default()
+        }
+        .into(),
+    );
+    let slug_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.3, 0.5, 1.0) * 5.0,
+        emissive: Color::rgb(3.0, 3.0, 5.0),
+        ..Default::default()
+    });
+
+    commands.insert_resource(PirateAppearance {
+        body_mesh,
+        body_material,
+        slug_mesh,
+        slug_material,
+    });
+}
+
+fn setup_pirate_spawner(mut commands: Commands) {
+    commands.spawn(PirateSpawner::default());
+}
+
+fn spawn_pirates(
+    mut commands: Commands,
+    mut spawner_query: Query<&mut PirateSpawner>,
+    pirate_query: Query<(), With<PirateShip>>,
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+    pirate_appearance: Res<PirateAppearance>,
+    fixed_time: Res<FixedTime>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("spawn_pirates");
+    let Ok(main_camera) = camera_query.get_single() else { return; };
+    let mut num_pirates = pirate_query.iter().count();
+    for mut spawner in &mut spawner_query {
+        spawner.spawn_timer.tick(fixed_time.period);
+        for _ in 0..spawner.spawn_timer.times_finished_this_tick() {
+            if num_pirates >= spawner.max_pirates {
+                continue;
+            }
+            num_pirates += 1;
+
+            let dist = random_range(rng, spawner.min_spawn_distance, spawner.max_spawn_distance);
+            let pos = random_direction(rng) * dist + main_camera.translation().truncate();
+            debug!("Spawning a pirate at {pos:?}");
+            commands
+                .spawn((
+                    PirateShip {
+                        strafe_direction: if random_range(rng, 0.0, 1.0) < 0.5 {
+                            1.0
+                        } else {
+                            -1.0
+                        },
+                        ..Default::default()
+                    },
+                    Hull::new(30.0),
+                    Faction::Pirate,
+                    RigidBody::Dynamic,
+                    TransformInterpolation::default(),
+                    Collider::ball(0.8),
+                    PIRATE_COLLISION_GROUPS,
+                    LockedAxes::ROTATION_LOCKED,
+                    Velocity::default(),
+                    ExternalImpulse::default(),
+                    ActiveEvents::COLLISION_EVENTS,
+                    Cull::default(),
+                    SpatialBundle::from_transform(Transform::from_xyz(pos.x, pos.y, 1.0)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        PbrBundle {
+                            mesh: pirate_appearance.body_mesh.clone(),
+                            material: pirate_appearance.body_material.clone(),
+                            ..Default::default()
+                        },
+                        PirateModel,
+                    ));
+                });
+        }
+    }
+}
+
+fn pirate_movement(
+    mut pirate_query: Query<(&mut PirateShip, &Transform, &Velocity, &mut ExternalImpulse)>,
+    player_query: Query<&Transform, (LeadPlayer, Without<PirateShip>)>,
+    game_time: Res<GameTime>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    for (mut pirate, transform, velocity, mut ext_impulse) in &mut pirate_query {
+        pirate
+            .strafe_timer
+            .tick(game_time.delta());
+        if pirate.strafe_timer.just_finished() {
+            pirate.strafe_direction = -pirate.strafe_direction;
+        }
+
+        let to_player = (player_transform.translation - transform.translation).truncate();
+        let dist = to_player.length();
+        let towards = to_player.normalize_or_zero();
+        pirate.facing = f32::atan2(towards.y, towards.x);
+
+        // Close the distance to the preferred range, while circling around the player
+        let radial = towards * ((dist - pirate.preferred_distance) / pirate.preferred_distance);
+        let tangential = towards.perp() * pirate.strafe_direction;
+        let desired_velocity = (radial + tangential).normalize_or_zero() * pirate.max_speed;
+
+        let accel_needed = desired_velocity - velocity.linvel;
+        ext_impulse.impulse +=
+            accel_needed.normalize_or_zero() * pirate.acceleration * game_time.delta_seconds();
+    }
+}
+
+fn rotate_pirate_models(
+    mut model_query: Query<(&Parent, &mut Transform), With<PirateModel>>,
+    pirate_query: Query<&PirateShip>,
+) {
+    for (parent, mut transform) in &mut model_query {
+        let Ok(pirate) = pirate_query.get(parent.get()) else { continue; };
+        transform.rotation = Quat::from_rotation_z(pirate.facing);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn pirate_fire(
+    mut commands: Commands,
+    mut pirate_query: Query<(&mut PirateShip, &Transform, &Velocity)>,
+    player_query: Query<&Transform, (LeadPlayer, Without<PirateShip>)>,
+    pirate_appearance: Res<PirateAppearance>,
+    mut slug_pool: ResMut<SlugPool>,
+    fixed_time: Res<FixedTime>,
+    game_time: Res<GameTime>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("pirate_fire");
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    for (mut pirate, transform, velocity) in &mut pirate_query {
+        pirate
+            .fire_timer
+            .tick(fixed_time.period.mul_f32(game_time.time_scale()));
+        if !pirate.fire_timer.just_finished() {
+            continue;
+        }
+        let to_player = (player_transform.translation - transform.translation).truncate();
+        if to_player.length() > pirate.fire_range {
+            continue;
+        }
+
+        // Pirates aren't very good shots
+        let facing = pirate.facing + random_range(rng, -0.15, 0.15);
+        let facing_dir = Vec2::from_angle(facing);
+        let pos = transform.translation.truncate() + facing_dir * 1.2;
+        let rot = Quat::from_rotation_z(PI / 2.0 + facing);
+        let slug_velocity = facing_dir * pirate.projectile_speed + velocity.linvel;
+        let time_to_live = pirate.fire_range * 1.5 / pirate.projectile_speed;
+
+        slug_pool.fire(
+            &mut commands,
+            SlugBundle {
+                slug: Slug {
+                    timer: Timer::from_seconds(time_to_live, TimerMode::Once),
+                    damage: pirate.damage,
+                    ricochets_left: 0,
+                    pierces_left: 0,
+                },
+                faction: Faction::Pirate,
+                velocity: Velocity::linear(slug_velocity),
+                transform: Transform::from_xyz(pos.x, pos.y, transform.translation.z)
+                    .with_rotation(rot),
+                mesh: pirate_appearance.slug_mesh.clone(),
+                material: pirate_appearance.slug_material.clone(),
+            },
+            Color::rgb(0.3, 0.5, 1.0),
+        );
+
+        let from_player = -to_player;
+        sound_event_writer.send(SoundEvent::CannonFire {
+            direction: f32::atan2(from_player.y, from_player.x),
+        });
+    }
+}
+
+fn destroy_pirates(
+    mut commands: Commands,
+    pirate_query: Query<(Entity, &Hull, &Transform, &Velocity), With<PirateShip>>,
+    player_query: Query<&Transform, (LeadPlayer, Without<PirateShip>)>,
+    mut collectible_writer: EventWriter<SpawnCollectibleEvent>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("destroy_pirates");
+    for (e, hull, transform, velocity) in &pirate_query {
+        if !hull.destroyed() {
+            continue;
+        }
+        debug!("Pirate {e:?} destroyed");
+        commands.entity(e).despawn_recursive();
+
+        if let Ok(player_transform) = player_query.get_single() {
+            let diff = transform.translation - player_transform.translation;
+            sound_event_writer.send(SoundEvent::RockDestroyed { relative_pos: diff });
+        }
+
+        // Pirates carry a little mined and exotic matter
+        let drops = [
+            (Reagent::MINERALS, 3),
+            (Reagent::EXOTIC, 2),
+        ];
+        for (reagent, count) in drops {
+            for _ in 0..count {
+                let linvel = velocity.linvel + random_direction(rng) * 2.0;
+                collectible_writer.send(
+                    SpawnCollectibleEvent::new(reagent, 1.0, transform.translation, linvel)
+                        .with_scale(0.5),
+                );
+            }
+        }
+    }
+}
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_pirate_appearance)
+            .add_system(
+                setup_pirate_spawner.in_schedule(OnEnter(ProgressStages::ShieldAndStrange)),
+            )
+            .add_systems(
+                (spawn_pirates, pirate_fire, destroy_pirates)
+                    .after(ReplaySystem)
+                    .distributive_run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (pirate_movement, rotate_pirate_models).in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::state::GameState;
+
+/// A clock that only runs while the game is actually being played, so gameplay
+/// timers stay frozen in the menus and while paused.
+///
+/// It also runs slower while `time_scale` is below 1. Anything that should stay responsive
+/// during slow motion, like the camera and aiming, uses `real_delta` instead
+#[derive(Resource, Debug)]
+pub struct GameTime {
+    delta: Duration,
+    real_delta: Duration,
+    elapsed: Duration,
+    time_scale: f32,
+}
+
+impl Default for GameTime {
+    fn default() -> Self {
+        Self {
+            delta: Duration::ZERO,
+            real_delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            time_scale: 1.0,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl GameTime {
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+    /// How long the frame took, ignoring the time scale. Still 0 while the game is paused
+    pub fn real_delta(&self) -> Duration {
+        self.real_delta
+    }
+    pub fn real_delta_seconds(&self) -> f32 {
+        self.real_delta.as_secs_f32()
+    }
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+    /// Also slows the physics down, see `physics.rs`
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+    }
+}
+
+fn advance_game_time(mut game_time: ResMut<GameTime>, time: Res<Time>) {
+    game_time.real_delta = time.delta();
+    let delta = time.delta().mul_f32(game_time.time_scale);
+    game_time.delta = delta;
+    game_time.elapsed += delta;
+}
+
+fn freeze_game_time(mut game_time: ResMut<GameTime>) {
+    game_time.delta = Duration::ZERO;
+    game_time.real_delta = Duration::ZERO;
+}
+
+fn reset_game_time(mut game_time: ResMut<GameTime>) {
+    *game_time = GameTime::default();
+}
+
+pub struct GameTimePlugin;
+
+impl Plugin for GameTimePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameTime>()
+            .add_system(
+                advance_game_time
+                    .in_base_set(CoreSet::PreUpdate)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_system(freeze_game_time.in_schedule(OnExit(GameState::InGame)))
+            .add_system(reset_game_time.in_schedule(OnExit(GameState::Intro)));
+    }
+}
@@ -0,0 +1,232 @@
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::render_resource::{AsBindGroup, ShaderRef},
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    game_time::GameTime,
+    heat::Heat,
+    hull::{Hull, HullDamageEvent},
+    player::{LeadPlayer, Player},
+    rock::Cull,
+    shield::Shield,
+    state::GameState,
+    util::{cosmetic, random_range, GameRng},
+    weapon::Slug,
+};
+
+/// A drifting cloud of corrosive gas. It heats up the ship while it's inside, or eats away at
+/// the hull once the heat sink is offline. Shooting or shielding through it thins it out
+#[derive(Component, Debug)]
+pub struct GasCloud {
+    pub radius: f32,
+    /// How thick the cloud is, from 0 to 1. The cloud is gone once this reaches 0
+    pub density: f32,
+    pub heat_per_second: f32,
+    pub damage_per_second: f32,
+    /// How much density each slug passing through takes away per second
+    pub slug_dispersal: f32,
+    /// How much density the shield takes away per second while it overlaps the cloud
+    pub shield_dispersal: f32,
+}
+
+impl Default for GasCloud {
+    fn default() -> Self {
+        Self {
+            radius: 6.0,
+            density: 1.0,
+            heat_per_second: 12.0,
+            damage_per_second: 5.0,
+            slug_dispersal: 4.0,
+            shield_dispersal: 0.8,
+        }
+    }
+}
+
+pub struct SpawnGasCloudEvent {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "4b1d3a0e-7c52-4f0b-9d6e-2a8f1c9b5e37"]
+pub struct GasCloudMaterial {
+    #[uniform(0)]
+    pub color: Color,
+    #[uniform(0)]
+    pub density: f32,
+    #[uniform(0)]
+    pub time: f32,
+    /// Offsets the noise so no two clouds look the same
+    #[uniform(0)]
+    pub seed: f32,
+}
+
+impl Default for GasCloudMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::rgba(0.4, 0.9, 0.2, 0.6),
+            density: 1.0,
+            time: 0.0,
+            seed: 0.0,
+        }
+    }
+}
+
+impl Material for GasCloudMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/gas_cloud.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+#[derive(Resource, Debug)]
+struct GasCloudAppearance {
+    mesh: Handle<Mesh>,
+}
+
+fn setup_gas_cloud_appearance(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    // A unit quad, scaled up to the size of each cloud
+    let mesh = meshes.add(
+        shape::Quad {
+            size: Vec2::splat(2.0),
+            ..Default::default()
+        }
+        .into(),
+    );
+    commands.insert_resource(GasCloudAppearance { mesh });
+}
+
+fn spawn_gas_clouds(
+    mut commands: Commands,
+    mut reader: EventReader<SpawnGasCloudEvent>,
+    mut materials: ResMut<Assets<GasCloudMaterial>>,
+    gas_cloud_appearance: Res<GasCloudAppearance>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("spawn_gas_clouds");
+    for SpawnGasCloudEvent { position, velocity } in reader.iter() {
+        debug!("Spawning a gas cloud at {position:?} drifting at {velocity:?}");
+        let gas_cloud = GasCloud {
+            radius: random_range(rng, 4.0, 8.0),
+            ..Default::default()
+        };
+        // Each cloud gets its own material, since its density changes independently
+        let material = materials.add(GasCloudMaterial {
+            seed: cosmetic::random_range(0.0, 1000.0),
+            ..Default::default()
+        });
+        commands.spawn((
+            RigidBody::KinematicVelocityBased,
+            Velocity::linear(*velocity),
+            Cull::default(),
+            MaterialMeshBundle {
+                mesh: gas_cloud_appearance.mesh.clone(),
+                material,
+                // Drawn above the rocks so the ship can hide inside it
+                transform: Transform::from_xyz(position.x, position.y, 4.0)
+                    .with_scale(Vec3::splat(gas_cloud.radius)),
+                ..Default::default()
+            },
+            gas_cloud,
+        ));
+    }
+}
+
+fn corrode_player(
+    cloud_query: Query<(&GasCloud, &Transform), Without<Player>>,
+    mut player_query: Query<(Entity, &Transform, &mut Heat, &mut Hull), LeadPlayer>,
+    mut hull_damage_writer: EventWriter<HullDamageEvent>,
+    fixed_time: Res<FixedTime>,
+) {
+    let dt = fixed_time.period.as_secs_f32();
+    let Ok((player_entity, player_transform, mut heat, mut hull)) = player_query.get_single_mut() else { return; };
+    let player_pos = player_transform.translation.truncate();
+
+    for (cloud, transform) in &cloud_query {
+        if transform.translation.truncate().distance(player_pos) > cloud.radius {
+            continue;
+        }
+        if heat.enabled() {
+            heat.add(cloud.heat_per_second * cloud.density * dt);
+        } else {
+            let amount = cloud.damage_per_second * cloud.density * dt;
+            hull.damage(amount);
+            hull_damage_writer.send(HullDamageEvent {
+                entity: player_entity,
+                amount,
+            });
+        }
+    }
+}
+
+fn disperse_gas_clouds(
+    mut commands: Commands,
+    mut cloud_query: Query<(Entity, &mut GasCloud, &Transform)>,
+    slug_query: Query<&GlobalTransform, With<Slug>>,
+    shield_query: Query<(&GlobalTransform, &Collider), With<Shield>>,
+    game_time: Res<GameTime>,
+) {
+    let dt = game_time.delta_seconds();
+    for (entity, mut cloud, transform) in &mut cloud_query {
+        let center = transform.translation.truncate();
+        for slug_transform in &slug_query {
+            if slug_transform.translation().truncate().distance(center) < cloud.radius {
+                cloud.density -= cloud.slug_dispersal * dt;
+            }
+        }
+        for (shield_transform, collider) in &shield_query {
+            let shield_radius = collider.as_ball().map(|b| b.radius()).unwrap_or(0.0);
+            if shield_transform.translation().truncate().distance(center)
+                < cloud.radius + shield_radius
+            {
+                cloud.density -= cloud.shield_dispersal * dt;
+            }
+        }
+        if cloud.density <= 0.0 {
+            debug!("Gas cloud {entity:?} dispersed");
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn update_gas_cloud_materials(
+    query: Query<(&GasCloud, &Handle<GasCloudMaterial>)>,
+    mut materials: ResMut<Assets<GasCloudMaterial>>,
+    game_time: Res<GameTime>,
+) {
+    for (cloud, handle) in &query {
+        let Some(material) = materials.get_mut(handle) else { continue; };
+        material.density = cloud.density.max(0.0);
+        material.time = game_time.elapsed_seconds();
+    }
+}
+
+pub struct GasCloudPlugin;
+
+impl Plugin for GasCloudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnGasCloudEvent>()
+            .add_plugin(MaterialPlugin::<GasCloudMaterial>::default())
+            .add_startup_system(setup_gas_cloud_appearance)
+            .add_system(
+                corrode_player
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                (
+                    spawn_gas_clouds,
+                    disperse_gas_clouds,
+                    update_gas_cloud_materials,
+                )
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
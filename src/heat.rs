@@ -1,8 +1,67 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
 
-use crate::state::GameState;
+use crate::{
+    game_time::GameTime,
+    state::GameState,
+    util::{load_config_file, parse_config_lines, parse_floats, warn_unknown_config},
+};
+
+const HEAT_FILE: &str = "assets/heat.txt";
+
+/// How the ships cool down, read from `assets/heat.txt`
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct HeatSettings {
+    /// Extra cooling per second for each unit of speed the ship is moving at
+    pub ram_air_per_speed: f32,
+    /// The most extra cooling flying fast can give
+    pub ram_air_max: f32,
+}
+
+impl Default for HeatSettings {
+    fn default() -> Self {
+        Self::from_text(include_str!("../assets/heat.txt"))
+    }
+}
+
+impl HeatSettings {
+    /// The heat file next to the game, falling back to the bundled copy if it's missing. The
+    /// web build only has the copy it was built with
+    pub fn load() -> Self {
+        load_config_file(HEAT_FILE, include_str!("../assets/heat.txt"), |text| {
+            Ok(Self::from_text(text))
+        })
+    }
+
+    /// Each line is a keyword followed by its numbers. Lines that can't be read are skipped
+    pub fn from_text(text: &str) -> Self {
+        let mut settings = Self {
+            ram_air_per_speed: 0.0,
+            ram_air_max: 0.0,
+        };
+        for (key, value) in parse_config_lines(text) {
+            match key {
+                "ram-air" => {
+                    let Some([per_speed, max]) = parse_floats(value) else {
+                        warn!("Couldn't read ram-air cooling {value:?}");
+                        continue;
+                    };
+                    settings.ram_air_per_speed = per_speed;
+                    settings.ram_air_max = max;
+                }
+                _ => warn_unknown_config("heat setting", key),
+            }
+        }
+        settings
+    }
+
+    /// The extra cooling for a ship moving at `speed`
+    pub fn ram_air(&self, speed: f32) -> f32 {
+        (speed * self.ram_air_per_speed).clamp(0.0, self.ram_air_max)
+    }
+}
 
 #[derive(Component, Debug)]
 #[allow(dead_code)]
@@ -13,6 +72,8 @@ pub struct Heat {
     reaction_threshold: f32,
     threshold_visible: bool,
     decay_rate: f32,
+    /// Extra cooling from flying fast, as of the last tick
+    ram_air: f32,
     decay_timer: Timer,
 }
 
@@ -45,23 +106,49 @@ impl Heat {
     pub fn can_react(&self) -> bool {
         self.fraction() > self.reaction_threshold
     }
+    /// Takes heat away without holding off the usual cooling the way `add` does
+    pub fn cool(&mut self, heat: f32) {
+        self.current = (self.current - heat).clamp(0.0, self.limit);
+    }
     pub fn add(&mut self, heat: f32) {
         self.current = (self.current + heat).clamp(0.0, self.limit);
         self.decay_timer.reset();
     }
-    pub fn tick(&mut self, dt: f32) {
+    /// How much heat goes every second before the extra cooling from flying fast
+    pub fn decay_rate(&self) -> f32 {
+        self.decay_rate
+    }
+    pub fn set_decay_rate(&mut self, decay_rate: f32) {
+        self.decay_rate = decay_rate;
+    }
+    /// How much heat goes every second once it's had the chance to start cooling down
+    pub fn effective_decay_rate(&self) -> f32 {
+        self.decay_rate + self.ram_air
+    }
+    /// `ram_air` is the extra cooling from how fast the ship's going
+    pub fn tick(&mut self, dt: f32, ram_air: f32) {
+        self.ram_air = ram_air;
         let leftover = dt - self.decay_timer.remaining_secs();
         self.decay_timer.tick(Duration::from_secs_f32(dt));
         if leftover > 0.0 && self.decay_timer.finished() {
-            self.current -= self.decay_rate * leftover;
+            self.current -= self.effective_decay_rate() * leftover;
             self.current = self.current.clamp(0.0, self.limit);
         }
     }
 }
 
-fn tick_heat(mut query: Query<&mut Heat>, time: Res<Time>) {
-    for mut heat in &mut query {
-        heat.tick(time.delta_seconds());
+fn tick_heat(
+    mut query: Query<(&mut Heat, Option<&Velocity>)>,
+    fixed_time: Res<FixedTime>,
+    game_time: Res<GameTime>,
+    heat_settings: Res<HeatSettings>,
+) {
+    for (mut heat, velocity) in &mut query {
+        let speed = velocity.map_or(0.0, |velocity| velocity.linvel.length());
+        heat.tick(
+            fixed_time.period.as_secs_f32() * game_time.time_scale(),
+            heat_settings.ram_air(speed),
+        );
     }
 }
 
@@ -74,6 +161,7 @@ impl Default for Heat {
             reaction_threshold: 0.75,
             threshold_visible: true,
             decay_rate: 25.0,
+            ram_air: 0.0,
             decay_timer: Timer::from_seconds(1.5, TimerMode::Once),
         }
     }
@@ -83,6 +171,10 @@ pub struct HeatPlugin;
 
 impl Plugin for HeatPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(tick_heat.in_set(OnUpdate(GameState::InGame)));
+        app.insert_resource(HeatSettings::load()).add_system(
+            tick_heat
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .run_if(in_state(GameState::InGame)),
+        );
     }
 }
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collectible::SpawnCollectibleEvent,
+    inventory::{Inventory, Reagent},
+    player::Player,
+    replay::ReplaySystem,
+    sound::SoundEvent,
+    state::GameState,
+    util::{random_direction, GameRng},
+    weapon::{Slug, SlugPool},
+};
+
+/// Which side an entity is on. Slugs only damage hulls belonging to a different faction
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Faction {
+    Player,
+    Pirate,
+}
+
+#[derive(Component, Debug)]
+pub struct Hull {
+    current: f32,
+    limit: f32,
+}
+
+#[allow(dead_code)]
+impl Hull {
+    pub fn new(limit: f32) -> Self {
+        Self {
+            current: limit,
+            limit,
+        }
+    }
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+    pub fn limit(&self) -> f32 {
+        self.limit
+    }
+    pub fn fraction(&self) -> f32 {
+        self.current / self.limit
+    }
+    pub fn destroyed(&self) -> bool {
+        self.current <= 0.0
+    }
+    pub fn damage(&mut self, amount: f32) {
+        self.current = (self.current - amount).clamp(0.0, self.limit);
+    }
+    pub fn repair(&mut self, amount: f32) {
+        self.current = (self.current + amount).clamp(0.0, self.limit);
+    }
+}
+
+impl Default for Hull {
+    fn default() -> Self {
+        Self::new(100.0)
+    }
+}
+
+#[derive(Debug)]
+pub struct HullDamageEvent {
+    pub entity: Entity,
+    pub amount: f32,
+}
+
+fn handle_slug_hits(
+    mut commands: Commands,
+    mut reader: EventReader<CollisionEvent>,
+    slug_query: Query<(&Slug, &Faction)>,
+    mut hull_query: Query<(&mut Hull, &Faction), Without<Slug>>,
+    mut slug_pool: ResMut<SlugPool>,
+    mut writer: EventWriter<HullDamageEvent>,
+) {
+    for ev in reader.iter() {
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        for (slug_entity, target_entity) in [(*e1, *e2), (*e2, *e1)] {
+            let Ok((slug, slug_faction)) = slug_query.get(slug_entity) else { continue; };
+            let Ok((mut hull, target_faction)) = hull_query.get_mut(target_entity) else { continue; };
+            if slug_faction == target_faction {
+                continue;
+            }
+
+            hull.damage(slug.damage);
+            writer.send(HullDamageEvent {
+                entity: target_entity,
+                amount: slug.damage,
+            });
+            slug_pool.release(&mut commands, slug_entity);
+        }
+    }
+}
+
+fn play_hull_damage_sound(
+    mut reader: EventReader<HullDamageEvent>,
+    player_query: Query<Entity, With<Player>>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+) {
+    for ev in reader.iter() {
+        if player_query.get(ev.entity).is_ok() {
+            debug!("Player took {} hull damage", ev.amount);
+            sound_event_writer.send(SoundEvent::RockCollision);
+        }
+    }
+}
+
+/// When the player's hull gives out, the cargo hold breaks open and all the minerals spill out.
+/// The replication facilities patch the hull back up right away.
+fn spill_cargo_on_hull_breach(
+    mut query: Query<(&mut Hull, &mut Inventory, &Transform, &Velocity), With<Player>>,
+    mut collectible_writer: EventWriter<SpawnCollectibleEvent>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("spill_cargo_on_hull_breach");
+    for (mut hull, mut inventory, transform, velocity) in &mut query {
+        if !hull.destroyed() {
+            continue;
+        }
+        debug!("Player hull breached, spilling cargo");
+        let limit = hull.limit();
+        hull.repair(limit);
+
+        let amount = inventory.reagent(Reagent::MINERALS).current();
+        inventory.reagent_mut(Reagent::MINERALS).add(-amount);
+        let num_chunks = (amount * 2.0) as u32;
+        for _ in 0..num_chunks {
+            let linvel = velocity.linvel + random_direction(rng) * 3.0;
+            collectible_writer.send(
+                SpawnCollectibleEvent::new(Reagent::MINERALS, 0.5, transform.translation, linvel)
+                    .with_scale(0.5),
+            );
+        }
+        sound_event_writer.send(SoundEvent::RockDestroyed {
+            relative_pos: Vec3::ZERO,
+        });
+    }
+}
+
+pub struct HullPlugin;
+
+impl Plugin for HullPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HullDamageEvent>()
+            .add_systems(
+                (handle_slug_hits, play_hull_damage_sound)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(
+                spill_cargo_on_hull_breach
+                    .after(ReplaySystem)
+                    .run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
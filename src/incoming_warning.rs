@@ -0,0 +1,193 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::{
+    prelude::*,
+    render::view::RenderLayers,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+use bevy_rapier2d::prelude::Velocity;
+
+use crate::{
+    camera::MainCamera,
+    game_time::GameTime,
+    player::LeadPlayer,
+    rock::Rock,
+    sound::SoundEvent,
+    state::GameState,
+    ui::{CustomUICamera, UIMarker},
+};
+
+/// Rocks that would hit within this many seconds get a warning
+const TIME_TO_IMPACT: f32 = 1.5;
+/// Counts as a hit if the rock passes this close to the player
+const HIT_RADIUS: f32 = 2.0;
+/// Slower than this and there's plenty of time to react anyway
+const MIN_CLOSING_SPEED: f32 = 8.0;
+/// Enough for a cluster coming from a few directions at once
+const MAX_CHEVRONS: usize = 6;
+const CHEVRON_SIZE: f32 = 18.0;
+/// How far in from the edge of the screen the chevrons sit
+const EDGE_PADDING: f32 = 30.0;
+const FLASHES_PER_SECOND: f32 = 4.0;
+/// Stops the alert from going off over and over while rocks are streaming past
+const ALERT_COOLDOWN_SECONDS: f32 = 2.0;
+
+/// An arrow at the edge of the screen pointing at a rock that's about to hit
+#[derive(Component, Debug, Default)]
+struct IncomingChevron;
+
+#[derive(Resource, Debug)]
+struct IncomingWarningState {
+    /// Screen-space directions to rocks on a collision course, this frame
+    directions: Vec<Vec2>,
+    alert_cooldown: Timer,
+}
+
+impl Default for IncomingWarningState {
+    fn default() -> Self {
+        let mut alert_cooldown = Timer::from_seconds(ALERT_COOLDOWN_SECONDS, TimerMode::Once);
+        // Ready to go off straight away
+        alert_cooldown.tick(alert_cooldown.duration());
+        Self {
+            directions: Vec::with_capacity(MAX_CHEVRONS),
+            alert_cooldown,
+        }
+    }
+}
+
+fn setup_incoming_chevrons(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mesh = Mesh2dHandle(meshes.add(shape::RegularPolygon::new(CHEVRON_SIZE, 3).into()));
+    for _ in 0..MAX_CHEVRONS {
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: mesh.clone(),
+                // Each one fades on its own, so they don't share a material
+                material: materials.add(ColorMaterial::from(Color::ORANGE_RED)),
+                transform: Transform::from_xyz(0.0, 0.0, 5.0),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            IncomingChevron,
+            UIMarker,
+            RenderLayers::layer(1),
+        ));
+    }
+}
+
+fn reset_incoming_warnings(mut state: ResMut<IncomingWarningState>) {
+    *state = IncomingWarningState::default();
+}
+
+/// Whether a rock at `offset` from the player, moving at `velocity` relative to it, will pass
+/// within `HIT_RADIUS` in the next `TIME_TO_IMPACT` seconds
+fn on_collision_course(offset: Vec2, velocity: Vec2) -> bool {
+    let closing_speed = -offset.dot(velocity) / offset.length().max(0.001);
+    if closing_speed < MIN_CLOSING_SPEED {
+        return false;
+    }
+    let time_to_closest = -offset.dot(velocity) / velocity.length_squared();
+    if time_to_closest > TIME_TO_IMPACT {
+        return false;
+    }
+    (offset + velocity * time_to_closest).length() < HIT_RADIUS
+}
+
+fn detect_incoming_rocks(
+    rock_query: Query<(&Transform, &Velocity), With<Rock>>,
+    player_query: Query<(&Transform, &Velocity), (LeadPlayer, Without<Rock>)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut state: ResMut<IncomingWarningState>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    game_time: Res<GameTime>,
+) {
+    state.alert_cooldown.tick(game_time.delta());
+    let was_warning = !state.directions.is_empty();
+    state.directions.clear();
+
+    let Ok((player_transform, player_velocity)) = player_query.get_single() else { return; };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return; };
+    let Some(size) = camera.logical_viewport_size() else { return; };
+    let player_pos = player_transform.translation.truncate();
+
+    for (transform, velocity) in &rock_query {
+        let offset = transform.translation.truncate() - player_pos;
+        if !on_collision_course(offset, velocity.linvel - player_velocity.linvel) {
+            continue;
+        }
+        // Rocks that are already on screen can be seen coming
+        if let Some(pos) = camera.world_to_viewport(camera_transform, transform.translation) {
+            if pos.x >= 0.0 && pos.y >= 0.0 && pos.x <= size.x && pos.y <= size.y {
+                continue;
+            }
+        }
+        state.directions.push(offset.normalize());
+        if state.directions.len() == MAX_CHEVRONS {
+            break;
+        }
+    }
+
+    if !was_warning && !state.directions.is_empty() && state.alert_cooldown.finished() {
+        sound_event_writer.send(SoundEvent::IncomingRock);
+        state.alert_cooldown.reset();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_incoming_chevrons(
+    mut chevron_query: Query<
+        (&mut Transform, &mut Visibility, &Handle<ColorMaterial>),
+        (With<IncomingChevron>, Without<CustomUICamera>),
+    >,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    state: Res<IncomingWarningState>,
+    time: Res<Time>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else { return; };
+    let Some(size) = ui_camera.logical_viewport_size() else { return; };
+    let half_size = size / 2.0 - EDGE_PADDING;
+    let flash = 0.5 + 0.5 * (time.elapsed_seconds() * FLASHES_PER_SECOND * TAU).sin();
+
+    let mut directions = state.directions.iter();
+    for (mut transform, mut visibility, handle) in &mut chevron_query {
+        let Some(direction) = directions.next() else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Visible;
+        // Push out along the direction until it meets the edge of the screen
+        let to_edge = f32::min(
+            half_size.x / direction.x.abs().max(0.001),
+            half_size.y / direction.y.abs().max(0.001),
+        );
+        let position = *direction * to_edge;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        // The triangle points up to begin with
+        transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x) - FRAC_PI_2);
+        if let Some(material) = materials.get_mut(handle) {
+            material.color.set_a(0.3 + 0.7 * flash);
+        }
+    }
+}
+
+pub struct IncomingWarningPlugin;
+
+impl Plugin for IncomingWarningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IncomingWarningState>()
+            .add_systems(
+                (reset_incoming_warnings, setup_incoming_chevrons)
+                    .in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_systems(
+                (detect_incoming_rocks, update_incoming_chevrons)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
@@ -1,5 +1,9 @@
 use bevy::prelude::*;
-use leafwing_input_manager::{prelude::*, user_input::InputKind};
+use leafwing_input_manager::{
+    axislike::DualAxis, plugin::InputManagerSystem, prelude::*, user_input::InputKind,
+};
+
+use crate::{player::Player, ui::EnabledControls};
 
 #[derive(Actionlike, Debug, PartialEq, Clone, Copy, Hash)]
 pub enum Action {
@@ -10,6 +14,24 @@ pub enum Action {
     FireMainGun,
     Shield,
     DumpCargo,
+    DeployTurret,
+    LayMine,
+    SwitchFireMode,
+    Ultimate,
+    SwitchShieldShape,
+    EjectCargoPod,
+    BulletTime,
+    /// Stick movement, on top of the four directions
+    Move,
+    /// Stick aiming, for players without a mouse
+    Aim,
+    Screenshot,
+    ToggleHud,
+    UseCoolant,
+    UseAccelerant,
+    UseHullPatch,
+    /// Drops a marker where the cursor is, or takes one down
+    PingWaypoint,
 }
 
 pub fn default_input_map() -> InputMap<Action> {
@@ -22,17 +44,165 @@ pub fn default_input_map() -> InputMap<Action> {
         (InputKind::Mouse(MouseButton::Right), Action::Shield),
         (InputKind::Keyboard(KeyCode::Space), Action::Shield),
         (InputKind::Keyboard(KeyCode::F), Action::DumpCargo),
+        (InputKind::Keyboard(KeyCode::T), Action::DeployTurret),
+        (InputKind::Keyboard(KeyCode::G), Action::LayMine),
+        (InputKind::Keyboard(KeyCode::Q), Action::SwitchFireMode),
+        (InputKind::Keyboard(KeyCode::R), Action::Ultimate),
+        (InputKind::Keyboard(KeyCode::E), Action::SwitchShieldShape),
+        (InputKind::Keyboard(KeyCode::V), Action::EjectCargoPod),
+        (InputKind::Keyboard(KeyCode::C), Action::BulletTime),
+        (InputKind::Keyboard(KeyCode::F12), Action::Screenshot),
+        (InputKind::Keyboard(KeyCode::F10), Action::ToggleHud),
+        (InputKind::Keyboard(KeyCode::Key1), Action::UseCoolant),
+        (InputKind::Keyboard(KeyCode::Key2), Action::UseAccelerant),
+        (InputKind::Keyboard(KeyCode::Key3), Action::UseHullPatch),
+        (InputKind::Mouse(MouseButton::Middle), Action::PingWaypoint),
+        (InputKind::Keyboard(KeyCode::Z), Action::PingWaypoint),
         // TODO: add gamepad inputs
     ])
 }
 
+/// The second player in co-op plays on the first gamepad. Only the ship's own controls are bound,
+/// the shield and everything shared with the first player stay with them
+pub fn second_player_input_map() -> InputMap<Action> {
+    let mut input_map = InputMap::new([
+        (InputKind::DualAxis(DualAxis::left_stick()), Action::Move),
+        (InputKind::DualAxis(DualAxis::right_stick()), Action::Aim),
+        (InputKind::GamepadButton(GamepadButtonType::RightTrigger2), Action::FireMainGun),
+        (InputKind::GamepadButton(GamepadButtonType::North), Action::SwitchFireMode),
+        (InputKind::GamepadButton(GamepadButtonType::East), Action::DumpCargo),
+        (InputKind::GamepadButton(GamepadButtonType::West), Action::EjectCargoPod),
+        (InputKind::GamepadButton(GamepadButtonType::DPadLeft), Action::UseCoolant),
+        (InputKind::GamepadButton(GamepadButtonType::DPadUp), Action::UseAccelerant),
+        (InputKind::GamepadButton(GamepadButtonType::DPadRight), Action::UseHullPatch),
+    ]);
+    input_map.set_gamepad(Gamepad::new(0));
+    input_map
+}
+
 #[derive(Actionlike, Debug, PartialEq, Clone, Copy, Hash)]
 pub enum MenuAction {
     Menu,
+    DebugOverlay,
+    ToggleGhost,
+    Map,
 }
 
 pub fn default_menu_input_map() -> InputMap<MenuAction> {
-    InputMap::new([(InputKind::Keyboard(KeyCode::Escape), MenuAction::Menu)])
+    InputMap::new([
+        (InputKind::Keyboard(KeyCode::Escape), MenuAction::Menu),
+        (InputKind::Keyboard(KeyCode::F3), MenuAction::DebugOverlay),
+        (InputKind::Keyboard(KeyCode::H), MenuAction::ToggleGhost),
+        (InputKind::Keyboard(KeyCode::M), MenuAction::Map),
+    ])
+}
+
+#[derive(Actionlike, Debug, PartialEq, Clone, Copy, Hash)]
+pub enum PhotoModeAction {
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    RollLeft,
+    RollRight,
+    Screenshot,
+}
+
+pub fn default_photo_mode_input_map() -> InputMap<PhotoModeAction> {
+    InputMap::new([
+        (InputKind::Keyboard(KeyCode::W), PhotoModeAction::PanUp),
+        (InputKind::Keyboard(KeyCode::S), PhotoModeAction::PanDown),
+        (InputKind::Keyboard(KeyCode::A), PhotoModeAction::PanLeft),
+        (InputKind::Keyboard(KeyCode::D), PhotoModeAction::PanRight),
+        (InputKind::Keyboard(KeyCode::Z), PhotoModeAction::ZoomIn),
+        (InputKind::Keyboard(KeyCode::X), PhotoModeAction::ZoomOut),
+        (InputKind::Keyboard(KeyCode::Q), PhotoModeAction::RollLeft),
+        (InputKind::Keyboard(KeyCode::E), PhotoModeAction::RollRight),
+        (InputKind::Keyboard(KeyCode::Return), PhotoModeAction::Screenshot),
+    ])
+}
+
+/// Whichever kind of device the player last pressed a button on
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ActiveInputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad,
+}
+
+fn track_active_input_device(
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut active_device: ResMut<ActiveInputDevice>,
+) {
+    let device = if gamepad_buttons.get_just_pressed().next().is_some() {
+        ActiveInputDevice::Gamepad
+    } else if keys.get_just_pressed().next().is_some()
+        || mouse_buttons.get_just_pressed().next().is_some()
+    {
+        ActiveInputDevice::KeyboardMouse
+    } else {
+        return;
+    };
+    // Only touch the resource when it actually changes, so change detection stays useful
+    if *active_device != device {
+        *active_device = device;
+    }
+}
+
+/// Which of the `EnabledControls` an action belongs to. Anything else is always available
+fn required_control(action: Action) -> Option<EnabledControls> {
+    match action {
+        Action::MoveUp | Action::MoveDown | Action::MoveLeft | Action::MoveRight | Action::Move => {
+            Some(EnabledControls::Move)
+        }
+        Action::Aim => Some(EnabledControls::Look),
+        Action::FireMainGun | Action::SwitchFireMode => Some(EnabledControls::Shoot),
+        Action::Shield | Action::SwitchShieldShape => Some(EnabledControls::Shield),
+        Action::DumpCargo | Action::EjectCargoPod => Some(EnabledControls::Dump),
+        _ => None,
+    }
+}
+
+/// Sent when a player presses something the campaign hasn't unlocked yet
+#[derive(Debug, Clone, Copy)]
+pub struct LockedActionPressed {
+    pub control: EnabledControls,
+}
+
+/// Keeps the actions for controls that aren't enabled yet released, so nothing reading them
+/// has to check. They stay consumed until they're let go of, so holding a key through the
+/// unlock doesn't fire it
+fn gate_locked_actions(
+    mut query: Query<&mut ActionState<Action>, With<Player>>,
+    enabled_controls: Option<Res<EnabledControls>>,
+    mut writer: EventWriter<LockedActionPressed>,
+) {
+    let Some(enabled_controls) = enabled_controls else { return; };
+    for mut action_state in &mut query {
+        let mut pressed = EnabledControls::empty();
+        for action in Action::variants() {
+            let Some(control) = required_control(action) else { continue; };
+            if !enabled_controls.contains(control) {
+                if action_state.just_pressed(action) && !pressed.contains(control) {
+                    pressed |= control;
+                    writer.send(LockedActionPressed { control });
+                }
+                action_state.consume(action);
+            }
+            // Consuming only releases the action, the input map still sets how far it's pushed,
+            // and that's what moving and firing go by. Turning the actions off for a camera move
+            // releases them without touching that either
+            let data = action_state.action_data_mut(action);
+            if data.consumed || data.state.released() {
+                data.value = 0.0;
+                data.axis_pair = None;
+            }
+        }
+    }
 }
 
 pub struct InputPlugin;
@@ -40,6 +210,15 @@ pub struct InputPlugin;
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(InputManagerPlugin::<Action>::default())
-            .add_plugin(InputManagerPlugin::<MenuAction>::default());
+            .add_plugin(InputManagerPlugin::<MenuAction>::default())
+            .add_plugin(InputManagerPlugin::<PhotoModeAction>::default())
+            .init_resource::<ActiveInputDevice>()
+            .add_event::<LockedActionPressed>()
+            .add_system(track_active_input_device)
+            .add_system(
+                gate_locked_actions
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputManagerSystem::ReleaseOnDisable),
+            );
     }
 }
@@ -0,0 +1,339 @@
+use bevy::{
+    core_pipeline::core_3d::Opaque3d,
+    ecs::system::{lifetimeless::*, SystemParamItem},
+    pbr::{MeshPipeline, MeshPipelineKey, MeshUniform, SetMeshBindGroup, SetMeshViewBindGroup},
+    prelude::*,
+    render::{
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        mesh::{GpuBufferInfo, MeshVertexBufferLayout},
+        render_asset::RenderAssets,
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItem, RenderCommand, RenderCommandResult,
+            RenderPhase, SetItemPipeline, TrackedRenderPass,
+        },
+        render_resource::*,
+        renderer::{RenderDevice, RenderQueue},
+        view::{ExtractedView, NoFrustumCulling, VisibilitySystems, VisibleEntities},
+        RenderApp, RenderSet,
+    },
+    transform::TransformSystem,
+    utils::HashMap,
+};
+use bytemuck::{Pod, Zeroable};
+
+use crate::lod::LodLevel;
+
+/// Entities with this are drawn as part of the matching `InstanceBatch`, all in a single draw call,
+/// instead of having their own mesh and material
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstancedVisual {
+    Rock,
+    Mineral,
+}
+
+#[derive(Component, Debug)]
+pub struct InstanceBatch {
+    pub kind: InstancedVisual,
+    /// Only visuals at this level of detail are drawn by this batch. Low detail batches are
+    /// drawn as camera facing billboards
+    pub lod: LodLevel,
+    pub color: Color,
+    pub emissive: Color,
+}
+
+// The fields are only ever read by the shader
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct InstanceData {
+    position: Vec3,
+    scale: f32,
+    rotation: Vec4,
+    color: [f32; 4],
+    emissive: [f32; 4],
+}
+
+// SAFETY: `InstanceData` is `repr(C)` and made up entirely of `f32`s, so it has no padding
+unsafe impl Zeroable for InstanceData {}
+unsafe impl Pod for InstanceData {}
+
+#[derive(Component, Debug, Default, Clone, Deref)]
+pub struct InstanceMaterialData(Vec<InstanceData>);
+
+impl ExtractComponent for InstanceMaterialData {
+    type Query = &'static InstanceMaterialData;
+    type Filter = ();
+    type Out = Self;
+
+    fn extract_component(item: &InstanceMaterialData) -> Option<Self> {
+        Some(item.clone())
+    }
+}
+
+#[derive(Bundle)]
+pub struct InstanceBatchBundle {
+    pub batch: InstanceBatch,
+    pub instances: InstanceMaterialData,
+    pub mesh: Handle<Mesh>,
+    pub spatial: SpatialBundle,
+    // Frustum culling would use the batch entity's own Aabb, which doesn't
+    // account for where the instances actually are
+    pub no_frustum_culling: NoFrustumCulling,
+}
+
+impl InstanceBatchBundle {
+    pub fn new(kind: InstancedVisual, mesh: Handle<Mesh>, color: Color, emissive: Color) -> Self {
+        Self {
+            batch: InstanceBatch {
+                kind,
+                lod: LodLevel::High,
+                color,
+                emissive,
+            },
+            instances: InstanceMaterialData::default(),
+            mesh,
+            spatial: SpatialBundle::INHERITED_IDENTITY,
+            no_frustum_culling: NoFrustumCulling,
+        }
+    }
+
+    pub fn with_lod(mut self, lod: LodLevel) -> Self {
+        self.batch.lod = lod;
+        self
+    }
+}
+
+fn update_instance_batches(
+    mut batch_query: Query<(&InstanceBatch, &mut InstanceMaterialData)>,
+    visual_query: Query<(
+        &InstancedVisual,
+        &GlobalTransform,
+        &ComputedVisibility,
+        Option<&LodLevel>,
+    )>,
+) {
+    for (batch, mut instances) in &mut batch_query {
+        instances.0.clear();
+        let color = batch.color.as_linear_rgba_f32();
+        let emissive = batch.emissive.as_linear_rgba_f32();
+        for (kind, transform, visibility, lod) in &visual_query {
+            if *kind != batch.kind || !visibility.is_visible_in_hierarchy() {
+                continue;
+            }
+            if lod.copied().unwrap_or_default() != batch.lod {
+                continue;
+            }
+            let (scale, mut rotation, position) = transform.to_scale_rotation_translation();
+            if batch.lod == LodLevel::Low {
+                rotation = Quat::IDENTITY;
+            }
+            instances.0.push(InstanceData {
+                position,
+                scale: scale.x,
+                rotation: Vec4::from(rotation),
+                color,
+                emissive,
+            });
+        }
+    }
+}
+
+#[derive(Component)]
+struct InstanceBuffer {
+    buffer: Buffer,
+    length: usize,
+}
+
+/// Each batch's buffer is kept from frame to frame and only replaced when the instances outgrow
+/// it, so most frames just write the new instances over the old ones
+#[derive(Resource, Default)]
+struct InstanceBuffers(HashMap<Entity, Buffer>);
+
+fn prepare_instance_buffers(
+    mut commands: Commands,
+    query: Query<(Entity, &InstanceMaterialData)>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut buffers: ResMut<InstanceBuffers>,
+) {
+    buffers.0.retain(|entity, _| query.contains(*entity));
+    for (entity, instance_data) in &query {
+        if instance_data.is_empty() {
+            continue;
+        }
+        let contents: &[u8] = bytemuck::cast_slice(instance_data.as_slice());
+        let fits = buffers
+            .0
+            .get(&entity)
+            .is_some_and(|buffer| buffer.size() >= contents.len() as u64);
+        if !fits {
+            // With room to spare, so a filling field of rocks doesn't need a new one every frame
+            let buffer = render_device.create_buffer(&BufferDescriptor {
+                label: Some("instance data buffer"),
+                size: contents.len() as u64 * 2,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            buffers.0.insert(entity, buffer);
+        }
+        let buffer = &buffers.0[&entity];
+        render_queue.write_buffer(buffer, 0, contents);
+        commands.entity(entity).insert(InstanceBuffer {
+            buffer: buffer.clone(),
+            length: instance_data.len(),
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn queue_instanced(
+    opaque_3d_draw_functions: Res<DrawFunctions<Opaque3d>>,
+    instanced_pipeline: Res<InstancedPipeline>,
+    msaa: Res<Msaa>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<InstancedPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<Mesh>>,
+    batch_query: Query<(&MeshUniform, &Handle<Mesh>, &InstanceMaterialData)>,
+    mut views: Query<(&ExtractedView, &VisibleEntities, &mut RenderPhase<Opaque3d>)>,
+) {
+    let draw_instanced = opaque_3d_draw_functions.read().id::<DrawInstanced>();
+    let msaa_key = MeshPipelineKey::from_msaa_samples(msaa.samples());
+
+    for (view, visible_entities, mut opaque_phase) in &mut views {
+        let view_key = msaa_key | MeshPipelineKey::from_hdr(view.hdr);
+        let rangefinder = view.rangefinder3d();
+        // Going through the visible entities means batches respect `RenderLayers`
+        for entity in &visible_entities.entities {
+            let Ok((mesh_uniform, mesh_handle, instances)) = batch_query.get(*entity) else { continue; };
+            if instances.is_empty() {
+                continue;
+            }
+            let Some(mesh) = meshes.get(mesh_handle) else { continue; };
+            let key = view_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology);
+            let Ok(pipeline) =
+                pipelines.specialize(&pipeline_cache, &instanced_pipeline, key, &mesh.layout) else { continue; };
+            opaque_phase.add(Opaque3d {
+                entity: *entity,
+                pipeline,
+                draw_function: draw_instanced,
+                distance: rangefinder.distance(&mesh_uniform.transform),
+            });
+        }
+    }
+}
+
+#[derive(Resource)]
+struct InstancedPipeline {
+    shader: Handle<Shader>,
+    mesh_pipeline: MeshPipeline,
+}
+
+impl FromWorld for InstancedPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let shader = asset_server.load("shaders/instancing.wgsl");
+
+        let mesh_pipeline = world.resource::<MeshPipeline>();
+
+        InstancedPipeline {
+            shader,
+            mesh_pipeline: mesh_pipeline.clone(),
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for InstancedPipeline {
+    type Key = MeshPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
+        descriptor.vertex.shader = self.shader.clone();
+        descriptor.vertex.buffers.push(VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceData>() as u64,
+            step_mode: VertexStepMode::Instance,
+            // Locations 0-2 are taken up by the mesh's position, normal and uv
+            attributes: (0..4)
+                .map(|i| VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: i * VertexFormat::Float32x4.size(),
+                    shader_location: 3 + i as u32,
+                })
+                .collect(),
+        });
+        descriptor.fragment.as_mut().unwrap().shader = self.shader.clone();
+        Ok(descriptor)
+    }
+}
+
+type DrawInstanced = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    DrawMeshInstanced,
+);
+
+struct DrawMeshInstanced;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
+    type Param = SRes<RenderAssets<Mesh>>;
+    type ViewWorldQuery = ();
+    type ItemWorldQuery = (Read<Handle<Mesh>>, Read<InstanceBuffer>);
+
+    #[inline]
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        (mesh_handle, instance_buffer): (&'w Handle<Mesh>, &'w InstanceBuffer),
+        meshes: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_handle) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, instance_buffer.buffer.slice(..));
+
+        match &gpu_mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*count, 0, 0..instance_buffer.length as u32);
+            }
+            GpuBufferInfo::NonIndexed { vertex_count } => {
+                pass.draw(0..*vertex_count, 0..instance_buffer.length as u32);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
+
+pub struct InstancingPlugin;
+
+impl Plugin for InstancingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ExtractComponentPlugin::<InstanceMaterialData>::default())
+            .add_system(
+                update_instance_batches
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(TransformSystem::TransformPropagate)
+                    .after(VisibilitySystems::CheckVisibility),
+            );
+        // There's nothing to draw when running headless
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else { return; };
+        render_app
+            .add_render_command::<Opaque3d, DrawInstanced>()
+            .init_resource::<InstancedPipeline>()
+            .init_resource::<SpecializedMeshPipelines<InstancedPipeline>>()
+            .init_resource::<InstanceBuffers>()
+            .add_system(queue_instanced.in_set(RenderSet::Queue))
+            .add_system(prepare_instance_buffers.in_set(RenderSet::Prepare));
+    }
+}
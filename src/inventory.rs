@@ -1,35 +1,201 @@
 use bevy::prelude::*;
 
-use crate::{collectible::CollectionEvent, sound::SoundEvent, state::GameState};
+use crate::{
+    collectible::CollectionEvent,
+    player::LeadPlayer,
+    reaction::Reaction,
+    sound::SoundEvent,
+    state::GameState,
+    util::{load_config_file, parse_config_lines, parse_floats, warn_unknown_config},
+};
 
-// KEEP THIS UPDATED:
-pub const REAGENT_TYPES: usize = 4;
+const REAGENTS_FILE: &str = "assets/reagents.txt";
 
-#[derive(Debug, Clone, Copy)]
-pub enum Reagent {
-    Minerals = 0,
-    Exotic = 1,
-    Strange = 2,
-    Continuum = 3,
+/// The reagents the stages are built around. The reagents file has to start with these
+const BUILT_IN_REAGENTS: [&str; 4] = ["minerals", "exotic", "strange", "continuum"];
+
+/// A kind of material, by where it is in the `ReagentRegistry`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reagent(usize);
+
+impl Reagent {
+    pub const MINERALS: Reagent = Reagent(0);
+    pub const EXOTIC: Reagent = Reagent(1);
+    pub const STRANGE: Reagent = Reagent(2);
+    pub const CONTINUUM: Reagent = Reagent(3);
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// One kind of material, as it's written in the reagents file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReagentDefinition {
+    pub id: String,
+    pub name: String,
+    pub color: Color,
+    pub limit: f32,
+    /// Whether the bar is on screen from the start, rather than from the first pickup
+    pub visible: bool,
+}
+
+/// Every kind of material and the reactions between them, read from `assets/reagents.txt`
+#[derive(Resource, Debug, Clone)]
+pub struct ReagentRegistry {
+    reagents: Vec<ReagentDefinition>,
+    /// The reactions every run starts with
+    pub reactions: Vec<Reaction>,
 }
 
-impl TryFrom<usize> for Reagent {
-    type Error = ();
-    fn try_from(value: usize) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(Self::Minerals),
-            1 => Ok(Self::Exotic),
-            2 => Ok(Self::Strange),
-            3 => Ok(Self::Continuum),
-            _ => Err(()),
+impl Default for ReagentRegistry {
+    fn default() -> Self {
+        Self::from_text(include_str!("../assets/reagents.txt"))
+            .expect("The bundled reagents file should be valid")
+    }
+}
+
+impl ReagentRegistry {
+    /// The reagents file next to the game, falling back to the bundled copy if it's missing or
+    /// broken. The web build only has the copy it was built with
+    pub fn load() -> Self {
+        load_config_file(
+            REAGENTS_FILE,
+            include_str!("../assets/reagents.txt"),
+            Self::from_text,
+        )
+    }
+
+    /// Each line is a keyword followed by its values. Lines that can't be read are skipped, but
+    /// the built in reagents have to be there
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut reagents: Vec<ReagentDefinition> = Vec::new();
+        let mut visible = Vec::new();
+        let mut reaction_lines = Vec::new();
+        for (key, value) in parse_config_lines(text) {
+            match key {
+                "reagent" => {
+                    let Some((id, rest)) = value.split_once(' ') else {
+                        warn!("Couldn't read reagent {value:?}");
+                        continue;
+                    };
+                    let Some([limit, r, g, b]) = parse_floats(rest) else {
+                        warn!("Couldn't read reagent {value:?}");
+                        continue;
+                    };
+                    let name = rest
+                        .split_whitespace()
+                        .skip(4)
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    if name.is_empty() {
+                        warn!("Reagent {id:?} has no name");
+                        continue;
+                    }
+                    if reagents.iter().any(|reagent| reagent.id == id) {
+                        warn!("Reagent {id:?} is defined twice");
+                        continue;
+                    }
+                    reagents.push(ReagentDefinition {
+                        id: id.to_string(),
+                        name,
+                        color: Color::rgb(r, g, b),
+                        limit,
+                        visible: false,
+                    });
+                }
+                "visible" => visible.push(value.trim()),
+                "reaction" => reaction_lines.push(value),
+                _ => warn_unknown_config("reagent setting", key),
+            }
         }
+
+        for (i, id) in BUILT_IN_REAGENTS.iter().enumerate() {
+            if reagents.get(i).map(|reagent| reagent.id.as_str()) != Some(id) {
+                return Err(format!("reagent {} should be {id:?}", i + 1));
+            }
+        }
+
+        let mut registry = Self {
+            reagents,
+            reactions: Vec::new(),
+        };
+        for id in visible {
+            match registry.find(id) {
+                Some(reagent) => registry.reagents[reagent.0].visible = true,
+                None => warn!("Unknown reagent {id:?}"),
+            }
+        }
+        for value in reaction_lines {
+            match registry.parse_reaction(value) {
+                Some(reaction) => registry.reactions.push(reaction),
+                None => warn!("Couldn't read reaction {value:?}"),
+            }
+        }
+        Ok(registry)
+    }
+
+    /// A reaction written as `<id> <second id or -> <result id or -> <heat|always> <rate>`
+    pub fn parse_reaction(&self, value: &str) -> Option<Reaction> {
+        let optional = |id: &str| match id {
+            "-" => Some(None),
+            id => self.find(id).map(Some),
+        };
+        let parts: Vec<_> = value.split_whitespace().collect();
+        let [reagent1, reagent2, result, heat, rate] = parts[..] else { return None; };
+        Some(Reaction {
+            reagent1: self.find(reagent1)?,
+            reagent2: optional(reagent2)?,
+            needs_heat: match heat {
+                "heat" => true,
+                "always" => false,
+                _ => return None,
+            },
+            rate: rate.parse().ok()?,
+            result: optional(result)?,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.reagents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reagents.is_empty()
+    }
+
+    pub fn get(&self, reagent: Reagent) -> &ReagentDefinition {
+        &self.reagents[reagent.0]
+    }
+
+    /// Looks a reagent up by the id it has in the reagents file
+    pub fn find(&self, id: &str) -> Option<Reagent> {
+        self.reagents
+            .iter()
+            .position(|reagent| reagent.id == id)
+            .map(Reagent)
+    }
+
+    pub fn reagents(&self) -> impl Iterator<Item = (Reagent, &'_ ReagentDefinition)> {
+        self.reagents
+            .iter()
+            .enumerate()
+            .map(|(i, reagent)| (Reagent(i), reagent))
     }
 }
 
+/// What made a reagent's amount change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReagentSource {
+    Collected,
+    Reaction,
+}
+
 #[derive(Debug)]
 pub struct ReagentEvent {
     pub reagent: Reagent,
     pub delta: f32,
+    pub source: ReagentSource,
 }
 
 #[derive(Component, Debug)]
@@ -51,6 +217,12 @@ impl InventoryEntry {
         self.limit
     }
 
+    /// Makes the bar hold `factor` times as much, keeping it just as full
+    pub fn scale_limit(&mut self, factor: f32) {
+        self.limit *= factor;
+        self.current *= factor;
+    }
+
     pub fn threshold(&self) -> Option<f32> {
         self.threshold
     }
@@ -83,85 +255,78 @@ impl InventoryEntry {
 
 #[derive(Component, Debug)]
 pub struct Inventory {
-    reagents: [InventoryEntry; REAGENT_TYPES],
+    reagents: Vec<InventoryEntry>,
 }
 
 impl Inventory {
+    /// An empty hold with room for every reagent in the registry
+    pub fn new(registry: &ReagentRegistry) -> Self {
+        Inventory {
+            reagents: registry
+                .reagents()
+                .map(|(_, reagent)| InventoryEntry {
+                    current: 0.0,
+                    threshold: None,
+                    limit: reagent.limit,
+                    visible: reagent.visible,
+                    color: reagent.color,
+                    name: reagent.name.clone(),
+                })
+                .collect(),
+        }
+    }
+
     pub fn reagent(&self, reagent: Reagent) -> &InventoryEntry {
-        &self.reagents[reagent as usize]
+        &self.reagents[reagent.0]
     }
     pub fn reagent_mut(&mut self, reagent: Reagent) -> &mut InventoryEntry {
-        &mut self.reagents[reagent as usize]
+        &mut self.reagents[reagent.0]
     }
     pub fn reagents(&self) -> impl Iterator<Item = (Reagent, &'_ InventoryEntry)> {
-        self.reagents.iter().enumerate().map(|(i, e)| (TryInto::<Reagent>::try_into(i).expect("There should be the same number of entries in `reagents` as there are in the Reagent enum."), e))
+        self.reagents
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (Reagent(i), e))
     }
 }
 
+/// Only the bundled reagents. Ships spawned in a run should use `Inventory::new` with the
+/// `ReagentRegistry`, which has any added by mods
 impl Default for Inventory {
     fn default() -> Self {
-        Inventory {
-            reagents: [
-                InventoryEntry {
-                    current: 0.0,
-                    threshold: None,
-                    limit: 10.0,
-                    visible: true,
-                    color: Color::CYAN,
-                    name: "MINERALS".into(),
-                },
-                InventoryEntry {
-                    current: 0.0,
-                    threshold: None,
-                    limit: 25.0,
-                    visible: false,
-                    color: Color::rgb(1.0, 0.0, 1.0),
-                    name: "EXOTIC MATTER".into(),
-                },
-                InventoryEntry {
-                    current: 0.0,
-                    threshold: None,
-                    limit: 50.0,
-                    visible: false,
-                    color: Color::rgb(0.0, 1.0, 0.0),
-                    name: "STRANGE MATTER".into(),
-                },
-                InventoryEntry {
-                    current: 0.0,
-                    threshold: None,
-                    limit: 100.0,
-                    visible: false,
-                    color: Color::rgb(1.0, 0.9, 0.1),
-                    name: "CONTINUUM".into(),
-                },
-            ],
-        }
+        Self::new(&ReagentRegistry::default())
     }
 }
 
 fn handle_collection_event(
     mut reader: EventReader<CollectionEvent>,
     mut inventory_query: Query<&mut Inventory>,
+    lead_query: Query<Entity, LeadPlayer>,
     mut reagent_event_writer: EventWriter<ReagentEvent>,
     mut sound_event_writer: EventWriter<SoundEvent>,
 ) {
     for ev in reader.iter() {
-        for mut inv in &mut inventory_query {
-            debug!("Adding {:?} to reagent {:?}", ev.amount, ev.reagent);
-            inv.reagent_mut(ev.reagent).add(ev.amount);
-            reagent_event_writer.send(ReagentEvent {
-                reagent: ev.reagent,
-                delta: ev.amount,
-            });
-            sound_event_writer.send(SoundEvent::Collected);
-        }
+        // Pickups that aren't anyone's in particular, like the drone's, go to the first player
+        let Some(collector) = ev.collector.or(lead_query.get_single().ok()) else { continue; };
+        let Ok(mut inv) = inventory_query.get_mut(collector) else { continue; };
+        debug!("Adding {:?} to reagent {:?}", ev.amount, ev.reagent);
+        inv.reagent_mut(ev.reagent).add(ev.amount);
+        reagent_event_writer.send(ReagentEvent {
+            reagent: ev.reagent,
+            delta: ev.amount,
+            source: ReagentSource::Collected,
+        });
+        sound_event_writer.send(SoundEvent::Collected);
     }
 }
 
+/// Every player's bars show up together, whoever picked it up
 fn set_visibility(mut reader: EventReader<ReagentEvent>, mut query: Query<&mut Inventory>) {
-    let Ok(mut inventory) = query.get_single_mut() else { return; };
     for ev in reader.iter() {
-        if ev.delta > 0.0 {
+        if ev.delta <= 0.0 {
+            continue;
+        }
+        for mut inventory in &mut query {
             inventory.reagent_mut(ev.reagent).visible = true;
         }
     }
@@ -171,8 +336,10 @@ pub struct InventoryPlugin;
 
 impl Plugin for InventoryPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<ReagentEvent>().add_systems(
-            (handle_collection_event, set_visibility).in_set(OnUpdate(GameState::InGame)),
-        );
+        app.insert_resource(ReagentRegistry::load())
+            .add_event::<ReagentEvent>()
+            .add_systems(
+                (handle_collection_event, set_visibility).in_set(OnUpdate(GameState::InGame)),
+            );
     }
 }
@@ -0,0 +1,258 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+pub mod app;
+pub mod background;
+pub mod bullet_time;
+pub mod camera;
+pub mod campaign;
+pub mod camera_director;
+pub mod challenge;
+pub mod cli;
+pub mod collectible;
+pub mod combo;
+pub mod consumable;
+pub mod continuum;
+pub mod controller;
+pub mod coop;
+pub mod daily;
+pub mod comet;
+pub mod debug_overlay;
+pub mod density;
+pub mod despawn;
+pub mod drone;
+pub mod enemy;
+pub mod gas_cloud;
+pub mod game_time;
+pub mod heat;
+pub mod hull;
+pub mod incoming_warning;
+pub mod input;
+pub mod instancing;
+pub mod inventory;
+pub mod loading;
+pub mod map;
+pub mod lod;
+pub mod menu;
+pub mod mine;
+pub mod new_game_plus;
+pub mod parallax;
+pub mod particles;
+pub mod photo_mode;
+pub mod ping;
+pub mod pickup_text;
+pub mod practice;
+pub mod physics;
+pub mod player;
+pub mod reaction;
+pub mod reagent_ticker;
+pub mod replay;
+pub mod rift;
+pub mod rock;
+pub mod save_data;
+pub mod screen_effects;
+pub mod screenshot;
+pub mod setup_cleanup;
+pub mod shield;
+pub mod sound;
+pub mod starfield_image;
+pub mod starfield_shader;
+pub mod state;
+pub mod stats;
+pub mod status_effect;
+pub mod swarm;
+pub mod text_pack;
+pub mod ui;
+pub mod turret;
+pub mod ultimate;
+pub mod tutorial_ghost;
+pub mod tutorial_prompts;
+pub mod util;
+pub mod weapon;
+pub mod wormhole;
+
+pub use app::build_game_app;
+pub use background::BackgroundPlugin;
+pub use bullet_time::BulletTimePlugin;
+pub use camera::CameraPlugin;
+pub use campaign::CampaignPlugin;
+pub use camera_director::CameraDirectorPlugin;
+pub use challenge::ChallengePlugin;
+pub use cli::{CliPlugin, LaunchOptions};
+pub use collectible::CollectiblePlugin;
+pub use combo::ComboPlugin;
+pub use consumable::ConsumablePlugin;
+pub use continuum::ContinuumPlugin;
+pub use controller::ControllerPlugin;
+pub use coop::CoopPlugin;
+pub use daily::DailyPlugin;
+pub use comet::CometPlugin;
+pub use debug_overlay::DebugOverlayPlugin;
+pub use density::DensityPlugin;
+pub use despawn::DespawnPlugin;
+pub use drone::DronePlugin;
+pub use enemy::EnemyPlugin;
+pub use gas_cloud::GasCloudPlugin;
+pub use game_time::GameTimePlugin;
+pub use heat::HeatPlugin;
+pub use hull::HullPlugin;
+pub use incoming_warning::IncomingWarningPlugin;
+pub use input::InputPlugin;
+pub use instancing::InstancingPlugin;
+pub use inventory::InventoryPlugin;
+pub use loading::LoadingPlugin;
+pub use map::MapPlugin;
+pub use lod::LodPlugin;
+pub use menu::MenuPlugin;
+pub use mine::MinePlugin;
+pub use new_game_plus::NewGamePlusPlugin;
+pub use parallax::ParallaxPlugin;
+pub use particles::ParticlePlugin;
+pub use photo_mode::PhotoModePlugin;
+pub use ping::PingPlugin;
+pub use pickup_text::PickupTextPlugin;
+pub use practice::PracticePlugin;
+pub use physics::{PhysicsPlugin, PhysicsSettings};
+pub use player::PlayerPlugin;
+pub use reaction::ReactionPlugin;
+pub use reagent_ticker::ReagentTickerPlugin;
+pub use replay::ReplayPlugin;
+pub use rift::RiftPlugin;
+pub use rock::RockPlugin;
+pub use save_data::SaveDataPlugin;
+pub use screen_effects::ScreenEffectsPlugin;
+pub use screenshot::ScreenshotPlugin;
+pub use setup_cleanup::SetupCleanupPlugin;
+pub use shield::ShieldPlugin;
+pub use sound::SoundPlugin;
+pub use starfield_shader::StarfieldShaderPlugin;
+pub use state::StatePlugin;
+pub use stats::StatsPlugin;
+pub use status_effect::StatusEffectPlugin;
+pub use swarm::SwarmPlugin;
+pub use text_pack::TextPackPlugin;
+pub use turret::TurretPlugin;
+pub use ultimate::UltimatePlugin;
+pub use tutorial_ghost::TutorialGhostPlugin;
+pub use tutorial_prompts::TutorialPromptPlugin;
+pub use ui::UIPlugin;
+pub use weapon::WeaponPlugin;
+pub use wormhole::WormholePlugin;
+
+/// All of the game's plugins. Add this on top of bevy's own plugins, or use `build_game_app`
+/// to get both at once. Individual plugins can be left out with `.build().disable::<T>()`
+#[derive(Debug)]
+pub struct WarlordPlugins {
+    physics_debug: bool,
+    physics_settings: Option<PhysicsSettings>,
+    debug_overlay: bool,
+}
+
+impl Default for WarlordPlugins {
+    fn default() -> Self {
+        Self {
+            physics_debug: false,
+            physics_settings: None,
+            debug_overlay: true,
+        }
+    }
+}
+
+impl WarlordPlugins {
+    /// Draw the physics colliders
+    pub fn with_physics_debug(mut self, enabled: bool) -> Self {
+        self.physics_debug = enabled;
+        self
+    }
+
+    /// Step the physics with these, rather than the quality picked in the graphics settings
+    pub fn with_physics_settings(mut self, settings: PhysicsSettings) -> Self {
+        self.physics_settings = Some(settings);
+        self
+    }
+
+    /// Whether the F3 debug overlay is available
+    pub fn with_debug_overlay(mut self, enabled: bool) -> Self {
+        self.debug_overlay = enabled;
+        self
+    }
+}
+
+impl PluginGroup for WarlordPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        let group = PluginGroupBuilder::start::<Self>()
+            .add(StatePlugin)
+            .add(CampaignPlugin)
+            .add(LoadingPlugin)
+            .add(TextPackPlugin)
+            .add(SaveDataPlugin)
+            .add(GameTimePlugin)
+            .add(SetupCleanupPlugin)
+            .add(DespawnPlugin)
+            .add(PhysicsPlugin {
+                debug: self.physics_debug,
+                settings: self.physics_settings,
+            })
+            .add(StarfieldShaderPlugin)
+            .add(BackgroundPlugin)
+            .add(ParallaxPlugin)
+            .add(PlayerPlugin)
+            .add(CameraPlugin)
+            .add(CameraDirectorPlugin)
+            .add(InstancingPlugin)
+            .add(LodPlugin)
+            .add(RockPlugin)
+            .add(ComboPlugin)
+            .add(WeaponPlugin)
+            .add(TurretPlugin)
+            .add(MinePlugin)
+            .add(SwarmPlugin)
+            .add(UltimatePlugin)
+            .add(BulletTimePlugin)
+            .add(HeatPlugin)
+            .add(HullPlugin)
+            .add(StatusEffectPlugin)
+            .add(EnemyPlugin)
+            .add(UIPlugin)
+            .add(ScreenEffectsPlugin)
+            .add(IncomingWarningPlugin)
+            .add(TutorialPromptPlugin)
+            .add(TutorialGhostPlugin)
+            .add(CollectiblePlugin)
+            .add(ConsumablePlugin)
+            .add(DensityPlugin)
+            .add(CometPlugin)
+            .add(RiftPlugin)
+            .add(GasCloudPlugin)
+            .add(WormholePlugin)
+            .add(DronePlugin)
+            .add(InventoryPlugin)
+            .add(PickupTextPlugin)
+            .add(ReagentTickerPlugin)
+            .add(ReactionPlugin)
+            .add(ContinuumPlugin)
+            .add(InputPlugin)
+            // The menu hides itself while photo mode is on, so the state has to exist first
+            .add(PhotoModePlugin)
+            .add(MenuPlugin)
+            .add(MapPlugin)
+            .add(PingPlugin)
+            .add(PracticePlugin)
+            .add(ChallengePlugin)
+            .add(DailyPlugin)
+            .add(NewGamePlusPlugin)
+            .add(CoopPlugin)
+            .add(ControllerPlugin)
+            .add(ParticlePlugin)
+            .add(ShieldPlugin)
+            .add(SoundPlugin)
+            .add(ScreenshotPlugin)
+            .add(ReplayPlugin)
+            .add(StatsPlugin)
+            .add(DebugOverlayPlugin);
+        if self.debug_overlay {
+            group
+        } else {
+            group.disable::<DebugOverlayPlugin>()
+        }
+    }
+}
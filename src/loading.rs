@@ -0,0 +1,164 @@
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{state::GameState, text_pack::TextPack};
+
+const FONT: &str = "font/BebasNeueRegular.otf";
+
+/// Everything that would otherwise pop in the first time it's used
+const PRELOADED_ASSETS: &[&str] = &[
+    FONT,
+    "sound/bgm.mp3",
+    "sound/buttonclick.mp3",
+    "sound/cannon.mp3",
+    "sound/collect.mp3",
+    "sound/hitrock.mp3",
+    "sound/nextstage.mp3",
+    "sound/rock.mp3",
+    "sound/transmute.mp3",
+    "shaders/gas_cloud.wgsl",
+    "shaders/heat_haze.wgsl",
+    "shaders/instancing.wgsl",
+    "shaders/nebula.wgsl",
+    "shaders/starfield.wgsl",
+    "shaders/vignette.wgsl",
+];
+
+const BAR_WIDTH: f32 = 300.0;
+const BAR_HEIGHT: f32 = 12.0;
+
+/// Holds on to the preloaded assets so they aren't unloaded before anything else asks for them
+#[derive(Resource, Debug, Default)]
+pub struct PreloadedAssets {
+    handles: Vec<HandleUntyped>,
+}
+
+#[derive(Component, Debug)]
+struct LoadingScreenRoot;
+
+#[derive(Component, Debug)]
+struct LoadingBarFill;
+
+fn start_loading(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = PRELOADED_ASSETS
+        .iter()
+        .map(|path| asset_server.load_untyped(*path))
+        .collect();
+    commands.insert_resource(PreloadedAssets { handles });
+}
+
+fn setup_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load(FONT);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::all(Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(12.0)),
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            LoadingScreenRoot,
+        ))
+        .with_children(|parent| {
+            // Shows up once the font itself has loaded, the bar is there before that
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    "LOADING",
+                    TextStyle {
+                        font,
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(BAR_WIDTH), Val::Px(BAR_HEIGHT)),
+                        ..default()
+                    },
+                    background_color: Color::DARK_GRAY.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: Color::ORANGE_RED.into(),
+                            ..default()
+                        },
+                        LoadingBarFill,
+                    ));
+                });
+        });
+}
+
+fn update_loading(
+    asset_server: Res<AssetServer>,
+    preloaded: Res<PreloadedAssets>,
+    text_pack: Res<TextPack>,
+    mut fill_query: Query<&mut Style, With<LoadingBarFill>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let handles = preloaded.handles.iter().map(|handle| handle.id());
+    // The story is needed as soon as a run starts, so the menu waits for the text pack too
+    let load_state = match (
+        asset_server.get_group_load_state(handles),
+        text_pack.load_state(&asset_server),
+    ) {
+        (LoadState::Failed, _) | (_, LoadState::Failed) => LoadState::Failed,
+        (LoadState::Loaded, LoadState::Loaded) => LoadState::Loaded,
+        _ => LoadState::Loading,
+    };
+    match load_state {
+        LoadState::Loaded => {
+            debug!("Finished loading {} assets", preloaded.handles.len());
+            next_state.set(GameState::MainMenu);
+            return;
+        }
+        LoadState::Failed => {
+            // Whatever failed will just be missing, same as if it had never been preloaded
+            warn!("Some assets failed to load, carrying on without them");
+            next_state.set(GameState::MainMenu);
+            return;
+        }
+        _ => (),
+    }
+
+    let loaded = preloaded
+        .handles
+        .iter()
+        .filter(|handle| asset_server.get_load_state(handle.id()) == LoadState::Loaded)
+        .count();
+    let progress = loaded as f32 / preloaded.handles.len().max(1) as f32;
+    for mut style in &mut fill_query {
+        style.size.width = Val::Percent(100.0 * progress);
+    }
+}
+
+fn cleanup_loading_screen(mut commands: Commands, query: Query<Entity, With<LoadingScreenRoot>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (start_loading, setup_loading_screen).in_schedule(OnEnter(GameState::Loading)),
+        )
+        .add_system(update_loading.in_set(OnUpdate(GameState::Loading)))
+        .add_system(cleanup_loading_screen.in_schedule(OnExit(GameState::Loading)));
+    }
+}
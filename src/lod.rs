@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+
+use crate::{camera::MainCamera, instancing::InstancedVisual, state::GameState};
+
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LodLevel {
+    #[default]
+    High,
+    Low,
+}
+
+#[derive(Resource, Debug)]
+pub struct LodSettings {
+    /// Once the camera is zoomed out past this orthographic scale, everything uses low detail
+    pub low_detail_zoom: f32,
+    /// Instanced visuals further than this from the camera use low detail
+    pub low_detail_distance: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            low_detail_zoom: 25.0,
+            low_detail_distance: 35.0,
+        }
+    }
+}
+
+/// The level of detail the camera's current zoom calls for
+#[derive(Resource, Debug, Default)]
+pub struct CameraLod(pub LodLevel);
+
+fn update_camera_lod(
+    camera_query: Query<&Projection, With<MainCamera>>,
+    settings: Res<LodSettings>,
+    mut camera_lod: ResMut<CameraLod>,
+) {
+    let Ok(Projection::Orthographic(projection)) = camera_query.get_single() else { return; };
+    let level = if projection.scale > settings.low_detail_zoom {
+        LodLevel::Low
+    } else {
+        LodLevel::High
+    };
+    // Only write when it actually changes so other systems can rely on change detection
+    if camera_lod.0 != level {
+        camera_lod.0 = level;
+    }
+}
+
+fn update_visual_lods(
+    mut commands: Commands,
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+    mut query: Query<(Entity, &GlobalTransform, Option<&mut LodLevel>), With<InstancedVisual>>,
+    settings: Res<LodSettings>,
+    camera_lod: Res<CameraLod>,
+) {
+    let Ok(camera_transform) = camera_query.get_single() else { return; };
+    let camera_pos = camera_transform.translation().truncate();
+    for (e, transform, lod_level) in &mut query {
+        let distance = transform.translation().truncate().distance(camera_pos);
+        let level = if camera_lod.0 == LodLevel::Low || distance > settings.low_detail_distance {
+            LodLevel::Low
+        } else {
+            LodLevel::High
+        };
+        match lod_level {
+            Some(mut lod_level) => {
+                if *lod_level != level {
+                    *lod_level = level;
+                }
+            }
+            None => {
+                commands.entity(e).insert(level);
+            }
+        }
+    }
+}
+
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LodSettings>()
+            .init_resource::<CameraLod>()
+            .add_systems(
+                (update_camera_lod, update_visual_lods)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
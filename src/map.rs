@@ -0,0 +1,471 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    prelude::*,
+    render::view::RenderLayers,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    ui::{FocusPolicy, RelativeCursorPosition},
+    utils::{HashMap, HashSet},
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    camera::MainCamera,
+    collectible::CargoPod,
+    density::{density_color, RockDensity, DENSITY_CELL_SIZE},
+    input::MenuAction,
+    player::LeadPlayer,
+    state::GameState,
+    ui::{CustomUICamera, UIMarker},
+    wormhole::Wormhole,
+};
+
+/// The map splits space up into squares this big, and fills in the ones that have been visited
+const CELL_SIZE: f32 = 40.0;
+/// The path is marked with a dot every time the player gets this far from the last one
+const PATH_SPACING: f32 = 10.0;
+/// Long runs leave a lot of path behind, only this many dots of it are drawn
+const MAX_PATH_DOTS: usize = 300;
+/// Things closer to the player than this get marked on the map
+const DISCOVER_RADIUS: f32 = 30.0;
+/// The waypoint is taken off once the player gets this close to it
+const WAYPOINT_REACHED_RADIUS: f32 = 5.0;
+/// On screen, in pixels
+const MAP_SIZE: f32 = 600.0;
+const DOT_SIZE: f32 = 4.0;
+const MARKER_SIZE: f32 = 10.0;
+const FONT_SIZE: f32 = 24.0;
+const CHEVRON_SIZE: f32 = 14.0;
+/// How far in from the edge of the screen the waypoint chevron sits
+const EDGE_PADDING: f32 = 50.0;
+
+const CELL_COLOR: Color = Color::rgba(0.3, 0.4, 0.6, 0.5);
+const PATH_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.4);
+const PLAYER_COLOR: Color = Color::WHITE;
+const WAYPOINT_COLOR: Color = Color::YELLOW;
+
+/// Opened with M while playing, which pauses the game. Closing it carries on
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum MapState {
+    #[default]
+    Closed,
+    Open,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PointOfInterest {
+    Wormhole,
+    CargoPod,
+}
+
+impl PointOfInterest {
+    fn color(&self) -> Color {
+        match self {
+            Self::Wormhole => Color::rgb(0.7, 0.3, 1.0),
+            Self::CargoPod => Color::rgb(1.0, 0.6, 0.0),
+        }
+    }
+}
+
+/// Everywhere the lead player has been this run
+#[derive(Resource, Debug, Default)]
+struct ExploredSpace {
+    cells: HashSet<IVec2>,
+    path: Vec<Vec2>,
+    /// Wormholes are removed once they're far enough away, so they're remembered here
+    wormholes: HashMap<Entity, Vec2>,
+}
+
+/// How far the lead player has flown this run
+#[derive(Resource, Debug, Default)]
+pub struct Odometer {
+    pub trip: f32,
+    last_position: Option<Vec2>,
+}
+
+/// Jumps further than this in a frame are wormholes, which don't count towards the trip
+const MAX_STEP: f32 = 20.0;
+
+/// A spot picked on the map. The compass chevron at the edge of the screen points at it
+#[derive(Resource, Debug, Default)]
+pub struct Waypoint(pub Option<Vec2>);
+
+/// What part of space the map is showing, so clicks can be turned back into world positions
+#[derive(Resource, Debug, Default)]
+struct MapView {
+    center: Vec2,
+    /// The width and height of the square of space on the map
+    extent: f32,
+}
+
+impl MapView {
+    /// From 0 to 1 across the map, left to right and top to bottom
+    fn to_map(&self, position: Vec2) -> Vec2 {
+        let offset = (position - self.center) / self.extent;
+        Vec2::new(0.5 + offset.x, 0.5 - offset.y)
+    }
+
+    fn to_world(&self, normalized: Vec2) -> Vec2 {
+        self.center + Vec2::new(normalized.x - 0.5, 0.5 - normalized.y) * self.extent
+    }
+}
+
+#[derive(Component, Debug)]
+struct MapRoot;
+
+/// The square the map is drawn in. Everything on it is rebuilt whenever it's opened
+#[derive(Component, Debug)]
+struct MapArea;
+
+/// Points from the edge of the screen towards the waypoint
+#[derive(Component, Debug)]
+struct WaypointChevron;
+
+fn reset_map(mut commands: Commands) {
+    commands.insert_resource(ExploredSpace::default());
+    commands.insert_resource(Waypoint::default());
+    commands.insert_resource(Odometer::default());
+}
+
+fn record_exploration(
+    player_query: Query<&Transform, LeadPlayer>,
+    wormhole_query: Query<(Entity, &Transform), With<Wormhole>>,
+    mut explored: ResMut<ExploredSpace>,
+    mut odometer: ResMut<Odometer>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let position = player_transform.translation.truncate();
+    if let Some(last_position) = odometer.last_position {
+        let step = last_position.distance(position);
+        if step < MAX_STEP {
+            odometer.trip += step;
+        }
+    }
+    odometer.last_position = Some(position);
+    explored
+        .cells
+        .insert((position / CELL_SIZE).floor().as_ivec2());
+    let moved_on = !explored
+        .path
+        .last()
+        .is_some_and(|last| last.distance(position) <= PATH_SPACING);
+    if moved_on {
+        explored.path.push(position);
+    }
+    for (entity, transform) in &wormhole_query {
+        let wormhole_position = transform.translation.truncate();
+        if wormhole_position.distance(position) < DISCOVER_RADIUS {
+            explored.wormholes.insert(entity, wormhole_position);
+        }
+    }
+}
+
+fn toggle_map(
+    action_query: Query<&ActionState<MenuAction>>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    current_map_state: Res<State<MapState>>,
+    mut next_map_state: ResMut<NextState<MapState>>,
+) {
+    if !action_query
+        .iter()
+        .any(|action_state| action_state.just_pressed(MenuAction::Map))
+    {
+        return;
+    }
+    match (&current_state.0, &current_map_state.0) {
+        (GameState::InGame, _) => {
+            next_state.set(GameState::Paused);
+            next_map_state.set(MapState::Open);
+        }
+        (GameState::Paused, MapState::Open) => next_state.set(GameState::InGame),
+        (GameState::Paused, MapState::Closed) => next_map_state.set(MapState::Open),
+        _ => {}
+    }
+}
+
+fn close_map(mut next_map_state: ResMut<NextState<MapState>>) {
+    next_map_state.set(MapState::Closed);
+}
+
+fn setup_map(mut commands: Commands, assets_server: Res<AssetServer>) {
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::all(Val::Percent(100.0)),
+                    position_type: PositionType::Absolute,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.05, 0.95).into(),
+                visibility: Visibility::Hidden,
+                // Above the pause menu and everything that opens from it
+                z_index: ZIndex::Global(3),
+                focus_policy: FocusPolicy::Block,
+                ..default()
+            },
+            MapRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::all(Val::Px(MAP_SIZE)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(1.0, 1.0, 1.0, 0.05).into(),
+                    ..default()
+                },
+                Interaction::default(),
+                RelativeCursorPosition::default(),
+                MapArea,
+            ));
+            parent.spawn(TextBundle::from_section(
+                "CLICK TO SET A WAYPOINT, RIGHT CLICK TO CLEAR IT. M TO CLOSE",
+                TextStyle {
+                    font,
+                    font_size: FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+fn cleanup_map(mut commands: Commands, query: Query<Entity, With<MapRoot>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn show_map(mut query: Query<&mut Visibility, With<MapRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn hide_map(mut query: Query<&mut Visibility, With<MapRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+/// A square on the map, centred on `position`
+fn spawn_map_dot(
+    parent: &mut ChildBuilder,
+    view: &MapView,
+    position: Vec2,
+    size: f32,
+    color: Color,
+) {
+    let normalized = view.to_map(position);
+    parent.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(normalized.x * MAP_SIZE - size / 2.0),
+                top: Val::Px(normalized.y * MAP_SIZE - size / 2.0),
+                ..default()
+            },
+            size: Size::all(Val::Px(size)),
+            ..default()
+        },
+        background_color: color.into(),
+        // Clicks go through to the map underneath
+        focus_policy: FocusPolicy::Pass,
+        ..default()
+    });
+}
+
+/// Fits everything that's been explored into the map, then draws it all from scratch
+#[allow(clippy::too_many_arguments)]
+fn draw_map(
+    mut commands: Commands,
+    area_query: Query<Entity, With<MapArea>>,
+    player_query: Query<&Transform, LeadPlayer>,
+    pod_query: Query<&Transform, With<CargoPod>>,
+    explored: Res<ExploredSpace>,
+    density: Res<RockDensity>,
+    waypoint: Res<Waypoint>,
+    mut view: ResMut<MapView>,
+) {
+    let Ok(area) = area_query.get_single() else { return; };
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let player_position = player_transform.translation.truncate();
+
+    let mut min = player_position;
+    let mut max = player_position;
+    for cell in &explored.cells {
+        min = min.min(cell.as_vec2() * CELL_SIZE);
+        max = max.max((cell.as_vec2() + Vec2::ONE) * CELL_SIZE);
+    }
+    if let Some(position) = waypoint.0 {
+        min = min.min(position);
+        max = max.max(position);
+    }
+    view.center = (min + max) / 2.0;
+    // A little room around the edges
+    view.extent = (max - min).max_element().max(CELL_SIZE) * 1.1;
+
+    let cell_size = CELL_SIZE / view.extent * MAP_SIZE;
+    commands.entity(area).despawn_descendants();
+    commands.entity(area).with_children(|parent| {
+        for cell in &explored.cells {
+            let center = (cell.as_vec2() + Vec2::splat(0.5)) * CELL_SIZE;
+            spawn_map_dot(parent, &view, center, cell_size, CELL_COLOR);
+        }
+        // Where the rocks are thickest, to pick a direction to mine in
+        let density_size = DENSITY_CELL_SIZE / view.extent * MAP_SIZE;
+        for (center, fraction) in density.cells() {
+            // Rocks out past anywhere that's been explored would hang off the edge of the map
+            let normalized = view.to_map(center);
+            if normalized.min_element() < 0.0 || normalized.max_element() > 1.0 {
+                continue;
+            }
+            spawn_map_dot(parent, &view, center, density_size, density_color(fraction));
+        }
+        let step = (explored.path.len() / MAX_PATH_DOTS).max(1);
+        for position in explored.path.iter().step_by(step) {
+            spawn_map_dot(parent, &view, *position, DOT_SIZE, PATH_COLOR);
+        }
+        for position in explored.wormholes.values() {
+            let color = PointOfInterest::Wormhole.color();
+            spawn_map_dot(parent, &view, *position, MARKER_SIZE, color);
+        }
+        for transform in &pod_query {
+            let color = PointOfInterest::CargoPod.color();
+            let position = transform.translation.truncate();
+            spawn_map_dot(parent, &view, position, MARKER_SIZE, color);
+        }
+        if let Some(position) = waypoint.0 {
+            spawn_map_dot(parent, &view, position, MARKER_SIZE, WAYPOINT_COLOR);
+        }
+        spawn_map_dot(parent, &view, player_position, MARKER_SIZE, PLAYER_COLOR);
+    });
+}
+
+fn place_waypoint(
+    area_query: Query<&RelativeCursorPosition, With<MapArea>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    view: Res<MapView>,
+    mut waypoint: ResMut<Waypoint>,
+) {
+    if mouse_buttons.just_pressed(MouseButton::Right) && waypoint.0.is_some() {
+        waypoint.0 = None;
+        return;
+    }
+    if !mouse_buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Ok(cursor) = area_query.get_single() else { return; };
+    let Some(normalized) = cursor.normalized else { return; };
+    if !cursor.mouse_over() {
+        return;
+    }
+    waypoint.0 = Some(view.to_world(normalized));
+}
+
+fn setup_waypoint_chevron(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(shape::RegularPolygon::new(CHEVRON_SIZE, 3).into())),
+            material: materials.add(ColorMaterial::from(WAYPOINT_COLOR)),
+            transform: Transform::from_xyz(0.0, 0.0, 5.0),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        WaypointChevron,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+/// Sits at the edge of the screen in the waypoint's direction, or over it once it's on screen
+#[allow(clippy::type_complexity)]
+fn update_waypoint_chevron(
+    mut chevron_query: Query<
+        (&mut Transform, &mut Visibility),
+        (With<WaypointChevron>, Without<CustomUICamera>),
+    >,
+    player_query: Query<&Transform, (LeadPlayer, Without<WaypointChevron>)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    mut waypoint: ResMut<Waypoint>,
+) {
+    let Ok((mut transform, mut visibility)) = chevron_query.get_single_mut() else { return; };
+    *visibility = Visibility::Hidden;
+    let Some(target) = waypoint.0 else { return; };
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let offset = target - player_transform.translation.truncate();
+    if offset.length() < WAYPOINT_REACHED_RADIUS {
+        waypoint.0 = None;
+        return;
+    }
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return; };
+    let Some(size) = ui_camera
+        .get_single()
+        .ok()
+        .and_then(|camera| camera.logical_viewport_size())
+    else {
+        return;
+    };
+    let half_size = size / 2.0 - EDGE_PADDING;
+    let direction = offset.normalize();
+    let mut position = direction
+        * f32::min(
+            half_size.x / direction.x.abs().max(0.001),
+            half_size.y / direction.y.abs().max(0.001),
+        );
+    // Once it's on screen, hang over the waypoint itself instead
+    if let Some(on_screen) = camera.world_to_viewport(camera_transform, target.extend(0.0)) {
+        let centred = on_screen - size / 2.0;
+        if centred.x.abs() < half_size.x && centred.y.abs() < half_size.y {
+            position = centred;
+        }
+    }
+    *visibility = Visibility::Visible;
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+    // The triangle points up to begin with
+    transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x) - FRAC_PI_2);
+}
+
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<MapState>()
+            .init_resource::<ExploredSpace>()
+            .init_resource::<Waypoint>()
+            .init_resource::<MapView>()
+            .init_resource::<Odometer>()
+            .add_systems(
+                (reset_map, setup_map, setup_waypoint_chevron)
+                    .in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_system(cleanup_map.in_schedule(OnEnter(GameState::Outro)))
+            .add_system(toggle_map)
+            .add_systems(
+                (record_exploration, update_waypoint_chevron).in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_systems((draw_map, show_map).in_schedule(OnEnter(MapState::Open)))
+            .add_system(hide_map.in_schedule(OnExit(MapState::Open)))
+            .add_system(close_map.in_schedule(OnExit(GameState::Paused)))
+            .add_systems(
+                (
+                    place_waypoint,
+                    draw_map.run_if(resource_changed::<Waypoint>()),
+                )
+                    .chain()
+                    .in_set(OnUpdate(MapState::Open)),
+            );
+    }
+}
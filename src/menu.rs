@@ -1,11 +1,33 @@
-use bevy::{app::AppExit, prelude::*, ui::FocusPolicy};
-use leafwing_input_manager::{prelude::ActionState, InputManagerBundle};
+use std::time::Duration;
+
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*, ui::FocusPolicy};
+use leafwing_input_manager::{
+    prelude::{ActionState, InputMap},
+    InputManagerBundle,
+};
 
 use crate::{
-    input::default_menu_input_map,
+    background::{GraphicsSettings, StarfieldMode},
+    campaign::Campaign,
+    challenge::{ActiveChallenge, Challenge, ChallengeResult, CHALLENGES},
+    coop::CoopSettings,
+    daily::DailyRun,
+    input::{default_input_map, default_menu_input_map, Action, ActiveInputDevice},
+    inventory::{Inventory, Reagent, ReagentRegistry},
+    photo_mode::PhotoModeState,
+    physics::PhysicsQuality,
+    player::LeadPlayer,
+    practice::Practice,
+    save_data::{SaveData, ShipPart},
+    setup_cleanup::ChapterStart,
     sound::{SoundEvent, VolumeSettings},
     state::{GameState, ProgressStages},
-    util::markup_to_text_sections,
+    stats::RunStats,
+    text_pack::{PackFile, PackText, TextAsset, TextPack},
+    tutorial_prompts::enabled_control_bindings,
+    ui::{EnabledControls, HintLog, HudSettings},
+    util::{markup_to_text_sections, GameRng},
+    weapon::WeaponSettings,
 };
 
 #[derive(Component, Debug)]
@@ -34,8 +56,32 @@ pub enum MenuEvent {
     Continue,
     Resume,
     Settings,
+    Stats,
+    CloseStats,
+    HintLog,
+    CloseHintLog,
+    EditHud,
+    CloseHudEdit,
+    MoveReagentBarUp(Reagent),
+    PinReagentBar(Reagent),
+    ToggleReagentBar(Reagent),
+    PhotoMode,
+    Chapters,
+    StartChapter(ProgressStages),
+    CloseChapters,
     Exit,
     Restart,
+    Practice,
+    ResetPractice,
+    LeavePractice,
+    Challenges,
+    StartChallenge(Challenge),
+    CloseChallenges,
+    DailyRun,
+    NewGamePlus,
+    Customize,
+    CycleShipColor(ShipPart),
+    CloseCustomize,
 }
 
 #[derive(Component, Debug)]
@@ -62,7 +108,14 @@ impl Default for SettingsButton {
 pub enum SettingsMenuEvent {
     SoundEffectVolume { delta: f32 },
     MusicVolume { delta: f32 },
+    HudOpacity { delta: f32 },
     ToggleMute,
+    ToggleStarfield,
+    CyclePhysicsQuality,
+    ToggleCoop,
+    ToggleSharedInventory,
+    ToggleAimLine,
+    ToggleCounterThrust,
     CloseSettings,
 }
 
@@ -170,7 +223,15 @@ fn add_settings_button(
 #[derive(Component)]
 struct PauseMenuRoot;
 
-fn setup_pause_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
+/// The column down the right of the pause menu with the objective, controls and reagents
+#[derive(Component)]
+struct PauseSummaryDisplay;
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    practice: Option<Res<Practice>>,
+) {
     commands
         .spawn((
             NodeBundle {
@@ -194,11 +255,43 @@ fn setup_pause_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
                 ..Default::default()
             };
             add_menu_button(parent, &assets_server, "RESUME", resume_button);
+            if practice.is_some() {
+                let reset_button = MenuButton {
+                    event: Some(MenuEvent::ResetPractice),
+                    ..Default::default()
+                };
+                add_menu_button(parent, &assets_server, "RESET TARGETS", reset_button);
+                let leave_button = MenuButton {
+                    event: Some(MenuEvent::LeavePractice),
+                    ..Default::default()
+                };
+                add_menu_button(parent, &assets_server, "LEAVE PRACTICE", leave_button);
+            }
             let settings_button = MenuButton {
                 event: Some(MenuEvent::Settings),
                 ..Default::default()
             };
             add_menu_button(parent, &assets_server, "SETTINGS", settings_button);
+            let stats_button = MenuButton {
+                event: Some(MenuEvent::Stats),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "STATS", stats_button);
+            let log_button = MenuButton {
+                event: Some(MenuEvent::HintLog),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "LOG", log_button);
+            let hud_button = MenuButton {
+                event: Some(MenuEvent::EditHud),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "HUD", hud_button);
+            let photo_mode_button = MenuButton {
+                event: Some(MenuEvent::PhotoMode),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "PHOTO MODE", photo_mode_button);
             #[cfg(not(target_arch = "wasm32"))]
             {
                 let exit_button = MenuButton {
@@ -209,9 +302,101 @@ fn setup_pause_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
                 };
                 add_menu_button(parent, &assets_server, "QUIT", exit_button);
             }
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            right: Val::Px(40.0),
+                            top: Val::Percent(10.0),
+                            ..default()
+                        },
+                        padding: UiRect::all(Val::Px(15.0)),
+                        max_size: Size::width(Val::Percent(30.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.2, 0.2, 0.2, 0.8).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((TextBundle::default(), PauseSummaryDisplay));
+                });
         });
 }
 
+/// Filled in when the game is paused. Nothing in it can change until it's unpaused again
+#[allow(clippy::too_many_arguments)]
+fn update_pause_summary(
+    mut query: Query<&mut Text, With<PauseSummaryDisplay>>,
+    stage: Res<State<ProgressStages>>,
+    campaign: Res<Campaign>,
+    registry: Res<ReagentRegistry>,
+    enabled_controls: Res<EnabledControls>,
+    player_query: Query<(&Inventory, Option<&InputMap<Action>>), LeadPlayer>,
+    active_device: Res<ActiveInputDevice>,
+    assets_server: Res<AssetServer>,
+) {
+    const FONT_SIZE: f32 = 25.0;
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+    let heading = |value: &str| {
+        TextSection::new(
+            format!("{value}\n"),
+            TextStyle {
+                font: font.clone(),
+                font_size: FONT_SIZE * 1.2,
+                color: Color::ORANGE_RED,
+            },
+        )
+    };
+    let line = |value: String, color: Color| {
+        TextSection::new(
+            format!("{value}\n"),
+            TextStyle {
+                font: font.clone(),
+                font_size: FONT_SIZE,
+                color,
+            },
+        )
+    };
+
+    let mut sections = vec![heading("OBJECTIVE")];
+    let rules = campaign
+        .stage(stage.0)
+        .map(|campaign_stage| campaign_stage.rules.as_slice())
+        .unwrap_or_default();
+    if stage.0 == ProgressStages::End {
+        sections.push(line("OUTRUN THE SHOCKWAVE".to_string(), TEXT_COLOR));
+    } else if rules.is_empty() {
+        sections.push(line("NONE".to_string(), TEXT_COLOR));
+    }
+    for rule in rules {
+        sections.push(line(rule.describe(&registry), TEXT_COLOR));
+    }
+
+    // Replays take the input map off the player, so fall back to the defaults
+    let default_map = default_input_map();
+    let player = player_query.get_single().ok();
+    let input_map = player
+        .and_then(|(_, input_map)| input_map)
+        .unwrap_or(&default_map);
+    sections.push(heading("\nCONTROLS"));
+    for (label, keys) in enabled_control_bindings(*enabled_controls, input_map, *active_device) {
+        sections.push(line(format!("{keys}  {label}"), TEXT_COLOR));
+    }
+
+    if let Some((inventory, _)) = player {
+        sections.push(heading("\nREAGENTS"));
+        for (_, entry) in inventory.reagents() {
+            let amount = format!("{:.1} / {:.0}", entry.current(), entry.limit());
+            sections.push(line(format!("{} {amount}", entry.name()), entry.color()));
+        }
+    }
+
+    for mut text in &mut query {
+        text.sections = sections.clone();
+    }
+}
+
 fn cleanup_pause_menu(mut commands: Commands, query: Query<Entity, With<PauseMenuRoot>>) {
     for e in &query {
         debug!("Cleaned up after pause menu");
@@ -227,12 +412,87 @@ struct SoundEffectsVolumeDisplay;
 #[derive(Component)]
 struct MusicVolumeDisplay;
 #[derive(Component)]
+struct HudOpacityDisplay;
+#[derive(Component)]
+struct AimLineDisplay;
+#[derive(Component)]
+struct CounterThrustDisplay;
+#[derive(Component)]
 struct MuteDisplay;
+#[derive(Component)]
+struct StarfieldModeDisplay;
+#[derive(Component)]
+struct PhysicsQualityDisplay;
+#[derive(Component)]
+struct CoopDisplay;
+#[derive(Component)]
+struct SharedInventoryDisplay;
+
+fn starfield_mode_label(mode: StarfieldMode) -> &'static str {
+    match mode {
+        StarfieldMode::Shader => "STARS: SHADER",
+        StarfieldMode::Static => "STARS: STATIC",
+    }
+}
+
+fn physics_quality_label(quality: PhysicsQuality) -> &'static str {
+    match quality {
+        PhysicsQuality::Low => "PHYSICS: LOW",
+        PhysicsQuality::Medium => "PHYSICS: MEDIUM",
+        PhysicsQuality::High => "PHYSICS: HIGH",
+    }
+}
+
+fn aim_line_label(enabled: bool) -> &'static str {
+    if enabled {
+        "AIM LINE: ON"
+    } else {
+        "AIM LINE: OFF"
+    }
+}
+
+fn counter_thrust_label(enabled: bool) -> &'static str {
+    if enabled {
+        "RECOIL: CANCELLED"
+    } else {
+        "RECOIL: FULL"
+    }
+}
+
+fn coop_label(enabled: bool) -> &'static str {
+    if enabled {
+        "CO-OP: ON"
+    } else {
+        "CO-OP: OFF"
+    }
+}
+
+fn shared_inventory_label(shared: bool) -> &'static str {
+    if shared {
+        "CARGO: SHARED"
+    } else {
+        "CARGO: SPLIT"
+    }
+}
 
-fn setup_settings_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
+fn setup_settings_menu(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    graphics_settings: Res<GraphicsSettings>,
+    coop_settings: Res<CoopSettings>,
+    hud_settings: Res<HudSettings>,
+    weapon_settings: Res<WeaponSettings>,
+) {
     const VOLUME_DELTA: f32 = 0.05;
+    const OPACITY_DELTA: f32 = 0.1;
     let font = assets_server.load("font/BebasNeueRegular.otf");
     let mut mute: Entity = Entity::PLACEHOLDER;
+    let mut starfield_mode: Entity = Entity::PLACEHOLDER;
+    let mut physics_quality: Entity = Entity::PLACEHOLDER;
+    let mut aim_line: Entity = Entity::PLACEHOLDER;
+    let mut counter_thrust: Entity = Entity::PLACEHOLDER;
+    let mut coop: Entity = Entity::PLACEHOLDER;
+    let mut shared_inventory: Entity = Entity::PLACEHOLDER;
 
     let rect = UiRect::all(Val::Percent(30.0));
     commands
@@ -370,6 +630,63 @@ fn setup_settings_menu(mut commands: Commands, assets_server: Res<AssetServer>)
                     };
                     add_settings_button(parent, &assets_server, "+", plus_button);
                 });
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::width(Val::Percent(100.0)),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle {
+                        text: Text::from_section(
+                            "HUD",
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: FONT_HEIGHT,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        style: Style {
+                            size: Size::width(Val::Px(100.0)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    });
+                    let minus_button = SettingsButton {
+                        event: Some(SettingsMenuEvent::HudOpacity {
+                            delta: -OPACITY_DELTA,
+                        }),
+                        ..Default::default()
+                    };
+                    add_settings_button(parent, &assets_server, "-", minus_button);
+                    parent.spawn((
+                        TextBundle {
+                            text: Text::from_section(
+                                format!("{:.0}", hud_settings.opacity * 100.0),
+                                TextStyle {
+                                    font: font.clone(),
+                                    font_size: FONT_HEIGHT,
+                                    color: Color::ORANGE_RED,
+                                },
+                            ),
+                            ..Default::default()
+                        },
+                        HudOpacityDisplay,
+                    ));
+                    let plus_button = SettingsButton {
+                        event: Some(SettingsMenuEvent::HudOpacity {
+                            delta: OPACITY_DELTA,
+                        }),
+                        ..Default::default()
+                    };
+                    add_settings_button(parent, &assets_server, "+", plus_button);
+                });
             parent
                 .spawn(NodeBundle {
                     style: Style {
@@ -388,6 +705,95 @@ fn setup_settings_menu(mut commands: Commands, assets_server: Res<AssetServer>)
                         ..Default::default()
                     };
                     mute = add_settings_button(parent, &assets_server, "MUTE", mute_button);
+                    let starfield_button = SettingsButton {
+                        event: Some(SettingsMenuEvent::ToggleStarfield),
+                        ..Default::default()
+                    };
+                    starfield_mode = add_settings_button(
+                        parent,
+                        &assets_server,
+                        starfield_mode_label(graphics_settings.starfield),
+                        starfield_button,
+                    );
+                    let physics_button = SettingsButton {
+                        event: Some(SettingsMenuEvent::CyclePhysicsQuality),
+                        ..Default::default()
+                    };
+                    physics_quality = add_settings_button(
+                        parent,
+                        &assets_server,
+                        physics_quality_label(graphics_settings.physics),
+                        physics_button,
+                    );
+                });
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::width(Val::Percent(100.0)),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let aim_line_button = SettingsButton {
+                        event: Some(SettingsMenuEvent::ToggleAimLine),
+                        ..Default::default()
+                    };
+                    aim_line = add_settings_button(
+                        parent,
+                        &assets_server,
+                        aim_line_label(hud_settings.aim_line),
+                        aim_line_button,
+                    );
+                    let counter_thrust_button = SettingsButton {
+                        event: Some(SettingsMenuEvent::ToggleCounterThrust),
+                        ..Default::default()
+                    };
+                    counter_thrust = add_settings_button(
+                        parent,
+                        &assets_server,
+                        counter_thrust_label(weapon_settings.counter_thrust),
+                        counter_thrust_button,
+                    );
+                });
+            // Co-op takes effect from the next run
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::width(Val::Percent(100.0)),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let coop_button = SettingsButton {
+                        event: Some(SettingsMenuEvent::ToggleCoop),
+                        ..Default::default()
+                    };
+                    coop = add_settings_button(
+                        parent,
+                        &assets_server,
+                        coop_label(coop_settings.enabled),
+                        coop_button,
+                    );
+                    let shared_inventory_button = SettingsButton {
+                        event: Some(SettingsMenuEvent::ToggleSharedInventory),
+                        ..Default::default()
+                    };
+                    shared_inventory = add_settings_button(
+                        parent,
+                        &assets_server,
+                        shared_inventory_label(coop_settings.shared_inventory),
+                        shared_inventory_button,
+                    );
                 });
 
             let back_button = SettingsButton {
@@ -397,6 +803,12 @@ fn setup_settings_menu(mut commands: Commands, assets_server: Res<AssetServer>)
             add_settings_button(parent, &assets_server, "BACK", back_button);
         });
     commands.entity(mute).insert(MuteDisplay);
+    commands.entity(starfield_mode).insert(StarfieldModeDisplay);
+    commands.entity(physics_quality).insert(PhysicsQualityDisplay);
+    commands.entity(aim_line).insert(AimLineDisplay);
+    commands.entity(counter_thrust).insert(CounterThrustDisplay);
+    commands.entity(coop).insert(CoopDisplay);
+    commands.entity(shared_inventory).insert(SharedInventoryDisplay);
 }
 
 fn cleanup_settings_menu(mut commands: Commands, query: Query<Entity, With<SettingsMenuRoot>>) {
@@ -473,101 +885,672 @@ fn update_settings_menu_displays(
     }
 }
 
+fn update_graphics_settings_display(
+    mut query: Query<&mut Text, With<StarfieldModeDisplay>>,
+    mut physics_query: Query<
+        &mut Text,
+        (With<PhysicsQualityDisplay>, Without<StarfieldModeDisplay>),
+    >,
+    graphics_settings: Res<GraphicsSettings>,
+) {
+    if !graphics_settings.is_changed() {
+        return;
+    }
+    for mut text in &mut query {
+        text.sections[0].value = starfield_mode_label(graphics_settings.starfield).to_string();
+    }
+    for mut text in &mut physics_query {
+        text.sections[0].value = physics_quality_label(graphics_settings.physics).to_string();
+    }
+}
+
+fn update_hud_settings_display(
+    mut query: Query<&mut Text, With<HudOpacityDisplay>>,
+    mut aim_line_query: Query<&mut Text, (With<AimLineDisplay>, Without<HudOpacityDisplay>)>,
+    hud_settings: Res<HudSettings>,
+) {
+    if !hud_settings.is_changed() {
+        return;
+    }
+    for mut text in &mut query {
+        text.sections[0].value = format!("{:.0}", hud_settings.opacity * 100.0);
+    }
+    for mut text in &mut aim_line_query {
+        text.sections[0].value = aim_line_label(hud_settings.aim_line).to_string();
+    }
+}
+
+fn update_weapon_settings_display(
+    mut query: Query<&mut Text, With<CounterThrustDisplay>>,
+    weapon_settings: Res<WeaponSettings>,
+) {
+    if !weapon_settings.is_changed() {
+        return;
+    }
+    for mut text in &mut query {
+        text.sections[0].value = counter_thrust_label(weapon_settings.counter_thrust).to_string();
+    }
+}
+
+fn update_coop_settings_display(
+    mut coop_query: Query<&mut Text, With<CoopDisplay>>,
+    mut shared_inventory_query: Query<
+        &mut Text,
+        (With<SharedInventoryDisplay>, Without<CoopDisplay>),
+    >,
+    coop_settings: Res<CoopSettings>,
+) {
+    if !coop_settings.is_changed() {
+        return;
+    }
+    for mut text in &mut coop_query {
+        text.sections[0].value = coop_label(coop_settings.enabled).to_string();
+    }
+    for mut text in &mut shared_inventory_query {
+        text.sections[0].value = shared_inventory_label(coop_settings.shared_inventory).to_string();
+    }
+}
+
 #[derive(Component)]
-struct MainMenuRoot;
+struct StatsMenuRoot;
 
-fn setup_main_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
+#[derive(Component)]
+struct StatsDisplay;
+
+fn setup_stats_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
     let font = assets_server.load("font/BebasNeueRegular.otf");
-    debug!("Setting up the main menu");
     commands
         .spawn((
             NodeBundle {
                 style: Style {
-                    size: Size::width(Val::Percent(100.0)),
-                    align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
                     flex_direction: FlexDirection::Column,
                     gap: Size::all(Val::Px(4.0)),
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::all(Val::Px(15.0)),
+                    position: UiRect::all(Val::Percent(20.0)),
                     ..default()
                 },
-                z_index: ZIndex::Global(0),
-                background_color: Color::BLACK.into(),
+                background_color: Color::rgb(0.4, 0.4, 0.4).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(2),
+                focus_policy: FocusPolicy::Block,
                 ..default()
             },
-            MainMenuRoot,
+            StatsMenuRoot,
         ))
         .with_children(|parent| {
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    "WARLORD",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 120.0,
-                        color: Color::ORANGE_RED,
-                    },
-                ),
-                ..Default::default()
-            });
-            let start_button = MenuButton {
-                event: Some(MenuEvent::Start),
-                ..Default::default()
-            };
-            add_menu_button(parent, &assets_server, "START", start_button);
-            let settings_button = MenuButton {
-                event: Some(MenuEvent::Settings),
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: FONT_HEIGHT * 0.6,
+                            color: TEXT_COLOR,
+                        },
+                    ),
+                    ..default()
+                },
+                StatsDisplay,
+            ));
+            let back_button = MenuButton {
+                event: Some(MenuEvent::CloseStats),
                 ..Default::default()
             };
-            add_menu_button(parent, &assets_server, "SETTINGS", settings_button);
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                let exit_button = MenuButton {
-                    event: Some(MenuEvent::Exit),
-                    hover_color: Color::RED,
-                    pressed_color: Color::BLACK,
-                    ..Default::default()
-                };
-                add_menu_button(parent, &assets_server, "QUIT", exit_button);
-            }
+            add_menu_button(parent, &assets_server, "BACK", back_button);
         });
 }
 
-fn cleanup_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenuRoot>>) {
+fn cleanup_stats_menu(mut commands: Commands, query: Query<Entity, With<StatsMenuRoot>>) {
     for e in &query {
-        debug!("Cleaned up after main menu");
+        debug!("Cleaned up after stats menu");
         commands.entity(e).despawn_recursive();
     }
 }
 
-#[derive(Component)]
-struct IntroMenuRoot;
+fn update_stats_menu_display(
+    mut query: Query<&mut Text, With<StatsDisplay>>,
+    run_stats: Res<RunStats>,
+    registry: Res<ReagentRegistry>,
+) {
+    let mut value = format!(
+        "TIME {}\nROCKS DESTROYED {}\n",
+        format_run_time(run_stats.time),
+        run_stats.rocks_destroyed
+    );
+    for (reagent, definition) in registry.reagents() {
+        let amount = run_stats.gathered(reagent);
+        value += &format!("{} GATHERED {amount:.1}\n", definition.name);
+    }
+    value += &format!("SHOTS FIRED {}\n", run_stats.shots_fired);
+    match run_stats.accuracy() {
+        Some(accuracy) => value += &format!("ACCURACY {:.0}%", accuracy * 100.0),
+        None => value += "ACCURACY -",
+    }
+    for mut text in &mut query {
+        text.sections[0].value = value.clone();
+    }
+}
 
-fn setup_intro_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
-    let font = assets_server.load("font/BebasNeueRegular.otf");
+#[derive(Component)]
+struct HintLogMenuRoot;
 
-    let story = include_str!("story.txt");
-    let text = markup_to_text_sections(story, font.clone(), 30.0, Color::ORANGE_RED, TEXT_COLOR);
+#[derive(Component)]
+struct HintLogDisplay;
 
+fn setup_hint_log_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
     commands
         .spawn((
             NodeBundle {
                 style: Style {
-                    size: Size::width(Val::Percent(100.0)),
-                    align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
                     flex_direction: FlexDirection::Column,
-                    gap: Size::all(Val::Px(15.0)),
+                    gap: Size::all(Val::Px(4.0)),
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::all(Val::Px(15.0)),
+                    position: UiRect::all(Val::Percent(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.4, 0.4, 0.4).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(2),
+                focus_policy: FocusPolicy::Block,
+                ..default()
+            },
+            HintLogMenuRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle {
+                    style: Style {
+                        max_size: Size::width(Val::Percent(100.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                HintLogDisplay,
+            ));
+            let back_button = MenuButton {
+                event: Some(MenuEvent::CloseHintLog),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "BACK", back_button);
+        });
+}
+
+fn cleanup_hint_log_menu(mut commands: Commands, query: Query<Entity, With<HintLogMenuRoot>>) {
+    for e in &query {
+        debug!("Cleaned up after hint log menu");
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+/// Lists every hint seen this run, most recent last, under the name of its chapter
+fn update_hint_log_display(
+    mut query: Query<&mut Text, With<HintLogDisplay>>,
+    hint_log: Res<HintLog>,
+    assets_server: Res<AssetServer>,
+    campaign: Res<Campaign>,
+    text_pack: Res<TextPack>,
+    texts: Res<Assets<TextAsset>>,
+) {
+    const FONT_SIZE: f32 = 25.0;
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+
+    let mut sections = Vec::new();
+    for stage in &hint_log.seen {
+        let Some(campaign_stage) = campaign.stage(*stage) else { continue; };
+        let Some(hint) = campaign_stage.hint.clone() else { continue; };
+        let hint = text_pack.text(&PackFile::Hint(hint), &texts);
+        let Some((label, _)) = CHAPTERS.iter().find(|(_, s)| s == stage) else { continue; };
+        sections.push(TextSection::new(
+            format!("{label}\n"),
+            TextStyle {
+                font: font.clone(),
+                font_size: FONT_SIZE * 1.2,
+                color: Color::ORANGE_RED,
+            },
+        ));
+        sections.extend(markup_to_text_sections(
+            hint,
+            font.clone(),
+            FONT_SIZE,
+            Color::ORANGE_RED,
+            TEXT_COLOR,
+        ));
+        sections.push(TextSection::new(
+            "\n\n",
+            TextStyle {
+                font: font.clone(),
+                font_size: FONT_SIZE,
+                color: TEXT_COLOR,
+            },
+        ));
+    }
+    for mut text in &mut query {
+        text.sections = sections.clone();
+    }
+}
+
+#[derive(Component)]
+struct MainMenuRoot;
+
+fn setup_main_menu(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    save_data: Res<SaveData>,
+) {
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+    debug!("Setting up the main menu");
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::width(Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(4.0)),
                     ..default()
                 },
+                z_index: ZIndex::Global(0),
                 background_color: Color::BLACK.into(),
                 ..default()
             },
-            IntroMenuRoot,
+            MainMenuRoot,
         ))
         .with_children(|parent| {
             parent.spawn(TextBundle {
-                text: Text::from_sections(text),
+                text: Text::from_section(
+                    "WARLORD",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 120.0,
+                        color: Color::ORANGE_RED,
+                    },
+                ),
                 ..Default::default()
             });
+            let start_button = MenuButton {
+                event: Some(MenuEvent::Start),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "START", start_button);
+            let practice_button = MenuButton {
+                event: Some(MenuEvent::Practice),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "PRACTICE", practice_button);
+            let daily_button = MenuButton {
+                event: Some(MenuEvent::DailyRun),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "DAILY RUN", daily_button);
+            let challenges_button = MenuButton {
+                event: Some(MenuEvent::Challenges),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "CHALLENGES", challenges_button);
+            // Chapters and New Game+ are unlocked by finishing the game once
+            if save_data.has_completed_run() {
+                let new_game_plus_button = MenuButton {
+                    event: Some(MenuEvent::NewGamePlus),
+                    ..Default::default()
+                };
+                add_menu_button(parent, &assets_server, "NEW GAME+", new_game_plus_button);
+                let chapters_button = MenuButton {
+                    event: Some(MenuEvent::Chapters),
+                    ..Default::default()
+                };
+                add_menu_button(parent, &assets_server, "CHAPTERS", chapters_button);
+            }
+            let customize_button = MenuButton {
+                event: Some(MenuEvent::Customize),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "SHIP", customize_button);
+            let settings_button = MenuButton {
+                event: Some(MenuEvent::Settings),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "SETTINGS", settings_button);
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let exit_button = MenuButton {
+                    event: Some(MenuEvent::Exit),
+                    hover_color: Color::RED,
+                    pressed_color: Color::BLACK,
+                    ..Default::default()
+                };
+                add_menu_button(parent, &assets_server, "QUIT", exit_button);
+            }
+        });
+}
+
+fn cleanup_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenuRoot>>) {
+    for e in &query {
+        debug!("Cleaned up after main menu");
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+struct ChaptersMenuRoot;
+
+const CHAPTERS: [(&str, ProgressStages); 6] = [
+    ("EXPLORATION", ProgressStages::Exploration),
+    ("GUN AND HEAT", ProgressStages::GunAndHeat),
+    ("EXOTIC MATTER", ProgressStages::CollectExotic),
+    ("SHIELD", ProgressStages::ShieldAndStrange),
+    ("CONTINUUM", ProgressStages::Continuum),
+    ("ESCAPE", ProgressStages::End),
+];
+
+fn setup_chapters_menu(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    save_data: Res<SaveData>,
+) {
+    if !save_data.has_completed_run() {
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(4.0)),
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::all(Val::Px(15.0)),
+                    position: UiRect::all(Val::Percent(20.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.4, 0.4, 0.4).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(2),
+                focus_policy: FocusPolicy::Block,
+                ..default()
+            },
+            ChaptersMenuRoot,
+        ))
+        .with_children(|parent| {
+            for (label, stage) in CHAPTERS {
+                let chapter_button = MenuButton {
+                    event: Some(MenuEvent::StartChapter(stage)),
+                    ..Default::default()
+                };
+                add_menu_button(parent, &assets_server, label, chapter_button);
+            }
+            let back_button = MenuButton {
+                event: Some(MenuEvent::CloseChapters),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "BACK", back_button);
+        });
+}
+
+fn cleanup_chapters_menu(mut commands: Commands, query: Query<Entity, With<ChaptersMenuRoot>>) {
+    for e in &query {
+        debug!("Cleaned up after chapters menu");
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+struct ChallengesMenuRoot;
+
+fn setup_challenges_menu(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    save_data: Res<SaveData>,
+) {
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(4.0)),
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::all(Val::Px(15.0)),
+                    position: UiRect::all(Val::Percent(20.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.4, 0.4, 0.4).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(2),
+                focus_policy: FocusPolicy::Block,
+                ..default()
+            },
+            ChallengesMenuRoot,
+        ))
+        .with_children(|parent| {
+            for challenge in CHALLENGES {
+                let challenge_button = MenuButton {
+                    event: Some(MenuEvent::StartChallenge(challenge)),
+                    ..Default::default()
+                };
+                add_menu_button(parent, &assets_server, challenge.title(), challenge_button);
+                let best = match save_data.challenge_bests.get(challenge.id()) {
+                    Some(best) => {
+                        let medal = challenge.medal(*best).map_or("NO MEDAL", |medal| medal.name());
+                        format!("BEST {} - {medal}", challenge.format_score(*best))
+                    }
+                    None => "NOT ATTEMPTED".to_string(),
+                };
+                add_summary_text(parent, &font, challenge.description().to_uppercase());
+                add_summary_text(parent, &font, best);
+            }
+            let back_button = MenuButton {
+                event: Some(MenuEvent::CloseChallenges),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "BACK", back_button);
+        });
+}
+
+fn cleanup_challenges_menu(
+    mut commands: Commands,
+    query: Query<Entity, With<ChallengesMenuRoot>>,
+) {
+    for e in &query {
+        debug!("Cleaned up after challenges menu");
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+struct CustomizeMenuRoot;
+
+/// Holds a row for each part of the ship, rebuilt whenever a colour changes
+#[derive(Component)]
+struct ShipLookRows;
+
+fn setup_customize_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(4.0)),
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::all(Val::Px(15.0)),
+                    position: UiRect::all(Val::Percent(20.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.4, 0.4, 0.4).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(2),
+                focus_policy: FocusPolicy::Block,
+                ..default()
+            },
+            CustomizeMenuRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        gap: Size::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ShipLookRows,
+            ));
+            let back_button = MenuButton {
+                event: Some(MenuEvent::CloseCustomize),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "BACK", back_button);
+        });
+}
+
+fn cleanup_customize_menu(mut commands: Commands, query: Query<Entity, With<CustomizeMenuRoot>>) {
+    for e in &query {
+        debug!("Cleaned up after customize menu");
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+/// One row per part of the ship, with a swatch of its colour and a button to go to the next one
+fn refresh_ship_look_rows(
+    mut commands: Commands,
+    query: Query<Entity, With<ShipLookRows>>,
+    save_data: Res<SaveData>,
+    assets_server: Res<AssetServer>,
+) {
+    let Ok(rows) = query.get_single() else { return; };
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+    let look = &save_data.ship_look;
+
+    commands.entity(rows).despawn_descendants();
+    commands.entity(rows).with_children(|parent| {
+        for part in ShipPart::ALL {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        gap: Size::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let text_style = TextStyle {
+                        font: font.clone(),
+                        font_size: FONT_HEIGHT * 0.6,
+                        color: TEXT_COLOR,
+                    };
+                    parent.spawn(TextBundle {
+                        text: Text::from_section(part.name(), text_style.clone()),
+                        style: Style {
+                            min_size: Size::width(Val::Px(BUTTON_WIDTH * 0.4)),
+                            ..default()
+                        },
+                        ..default()
+                    });
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            size: Size::all(Val::Px(FONT_HEIGHT * 0.6)),
+                            ..default()
+                        },
+                        background_color: look.color(part).into(),
+                        ..default()
+                    });
+                    parent.spawn(TextBundle {
+                        text: Text::from_section(look.color_name(part), text_style),
+                        style: Style {
+                            min_size: Size::width(Val::Px(BUTTON_WIDTH * 0.5)),
+                            ..default()
+                        },
+                        ..default()
+                    });
+                    let button = MenuButton {
+                        event: Some(MenuEvent::CycleShipColor(part)),
+                        ..Default::default()
+                    };
+                    add_small_menu_button(parent, &assets_server, "NEXT", button);
+                });
+        }
+    });
+}
+
+/// Changes to the ship's colours are saved as soon as they're made, and show on the next run
+fn process_customize_menu_event(
+    mut reader: EventReader<MenuEvent>,
+    current_state: Res<State<GameState>>,
+    mut next_customize_state: ResMut<NextState<CustomizeState>>,
+    mut save_data: ResMut<SaveData>,
+) {
+    for ev in reader.iter() {
+        match *ev {
+            MenuEvent::Customize if current_state.0 == GameState::MainMenu => {
+                next_customize_state.set(CustomizeState::InCustomize)
+            }
+            MenuEvent::CloseCustomize => next_customize_state.set(CustomizeState::None),
+            MenuEvent::CycleShipColor(part) => {
+                save_data.ship_look.cycle(part);
+                save_data.save();
+            }
+            _ => (),
+        }
+    }
+}
+
+#[derive(Component)]
+struct IntroMenuRoot;
+
+fn setup_intro_menu(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    text_pack: Res<TextPack>,
+    texts: Res<Assets<TextAsset>>,
+) {
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+
+    let pack_text = PackText {
+        file: PackFile::Story,
+        font_size: 30.0,
+        highlight_color: Color::ORANGE_RED,
+        color: TEXT_COLOR,
+    };
+    let text = pack_text.sections(font.clone(), &text_pack, &texts);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::width(Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(15.0)),
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            IntroMenuRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_sections(text),
+                    ..Default::default()
+                },
+                pack_text,
+            ));
             let continue_button = MenuButton {
                 event: Some(MenuEvent::Continue),
                 ..Default::default()
@@ -586,11 +1569,21 @@ fn cleanup_intro_menu(mut commands: Commands, query: Query<Entity, With<IntroMen
 #[derive(Component)]
 struct OutroMenuRoot;
 
-fn setup_outro_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
+fn setup_outro_menu(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    text_pack: Res<TextPack>,
+    texts: Res<Assets<TextAsset>>,
+) {
     let font = assets_server.load("font/BebasNeueRegular.otf");
 
-    let outro = include_str!("outro.txt");
-    let text = markup_to_text_sections(outro, font.clone(), 30.0, Color::ORANGE_RED, TEXT_COLOR);
+    let pack_text = PackText {
+        file: PackFile::Outro,
+        font_size: 30.0,
+        highlight_color: Color::ORANGE_RED,
+        color: TEXT_COLOR,
+    };
+    let text = pack_text.sections(font.clone(), &text_pack, &texts);
 
     commands
         .spawn((
@@ -609,81 +1602,496 @@ fn setup_outro_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
             OutroMenuRoot,
         ))
         .with_children(|parent| {
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_sections(text),
+                    ..Default::default()
+                },
+                pack_text,
+            ));
+            let continue_button = MenuButton {
+                event: Some(MenuEvent::Continue),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "CONTINUE", continue_button);
+        });
+}
+
+/// Shown instead of the story's ending when a challenge is over. Uses the same root as the outro
+/// so it's cleaned up the same way
+fn setup_challenge_results(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    result: Res<ChallengeResult>,
+    save_data: Res<SaveData>,
+) {
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+    let challenge = result.challenge;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::width(Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(15.0)),
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            OutroMenuRoot,
+        ))
+        .with_children(|parent| {
+            let (medal_label, medal_color) = match (result.score, result.medal) {
+                (None, _) => ("FAILED", Color::RED),
+                (Some(_), None) => ("NO MEDAL", TEXT_COLOR),
+                (Some(_), Some(medal)) => (medal.name(), medal.color()),
+            };
             parent.spawn(TextBundle {
-                text: Text::from_sections(text),
+                text: Text::from_section(
+                    challenge.title(),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 80.0,
+                        color: Color::ORANGE_RED,
+                    },
+                ),
                 ..Default::default()
             });
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    medal_label,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 60.0,
+                        color: medal_color,
+                    },
+                ),
+                ..Default::default()
+            });
+            if let Some(score) = result.score {
+                add_summary_text(parent, &font, challenge.format_score(score));
+            }
+            if result.new_best {
+                add_summary_text(parent, &font, "NEW BEST".to_string());
+            } else if let Some(best) = save_data.challenge_bests.get(challenge.id()) {
+                add_summary_text(parent, &font, format!("BEST {}", challenge.format_score(*best)));
+            }
             let continue_button = MenuButton {
                 event: Some(MenuEvent::Continue),
                 ..Default::default()
             };
-            add_menu_button(parent, &assets_server, "CONTINUE", continue_button);
+            add_menu_button(parent, &assets_server, "CONTINUE", continue_button);
+        });
+}
+
+fn cleanup_outro_menu(mut commands: Commands, query: Query<Entity, With<OutroMenuRoot>>) {
+    for e in &query {
+        debug!("Cleaned up after outro");
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+struct EndScreenMenuRoot;
+
+const SUMMARY_FONT_HEIGHT: f32 = 30.0;
+const SUMMARY_BAR_LENGTH: f32 = 300.0;
+
+fn format_run_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// A line of text in the end screen's run summary
+fn add_summary_text(builder: &mut ChildBuilder, font: &Handle<Font>, value: String) {
+    builder.spawn(TextBundle {
+        text: Text::from_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size: SUMMARY_FONT_HEIGHT,
+                color: TEXT_COLOR,
+            },
+        ),
+        ..Default::default()
+    });
+}
+
+/// Laid out like the HUD's reagent bars, with the label drawn over a bar filled up to `fraction`
+fn add_summary_bar(
+    builder: &mut ChildBuilder,
+    font: &Handle<Font>,
+    label: String,
+    fraction: f32,
+    color: Color,
+) {
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(SUMMARY_BAR_LENGTH), Val::Px(SUMMARY_FONT_HEIGHT)),
+                ..default()
+            },
+            background_color: Color::DARK_GRAY.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(
+                        Val::Percent(100.0 * fraction.clamp(0.0, 1.0)),
+                        Val::Percent(100.0),
+                    ),
+                    ..default()
+                },
+                background_color: color.into(),
+                ..default()
+            });
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    label,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: SUMMARY_FONT_HEIGHT,
+                        color: TEXT_COLOR,
+                    },
+                ),
+                style: Style {
+                    margin: UiRect::left(Val::Px(5.0)),
+                    ..default()
+                },
+                ..Default::default()
+            });
+        });
+}
+
+fn setup_endscreen_menu(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    run_stats: Res<RunStats>,
+    daily_run: Option<Res<DailyRun>>,
+    registry: Res<ReagentRegistry>,
+) {
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::width(Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(15.0)),
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            EndScreenMenuRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    "WARLORD",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 120.0,
+                        color: Color::ORANGE_RED,
+                    },
+                ),
+                ..Default::default()
+            });
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    "Thank you for playing!",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 30.0,
+                        color: Color::ORANGE_RED,
+                    },
+                ),
+                ..Default::default()
+            });
+            if let Some(daily_run) = daily_run {
+                add_summary_text(parent, &font, format!("DAILY RUN {}", daily_run.date));
+            }
+            // Run summary
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::all(Val::Px(40.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                gap: Size::all(Val::Px(4.0)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            add_summary_text(
+                                parent,
+                                &font,
+                                format!("TIME {}", format_run_time(run_stats.time)),
+                            );
+                            for (stage, split) in run_stats.stage_splits() {
+                                let Some((name, _)) =
+                                    CHAPTERS.iter().find(|(_, chapter)| *chapter == stage) else { continue; };
+                                add_summary_text(
+                                    parent,
+                                    &font,
+                                    format!("{name} {}", format_run_time(split)),
+                                );
+                            }
+                            add_summary_text(
+                                parent,
+                                &font,
+                                format!("ROCKS DESTROYED {}", run_stats.rocks_destroyed),
+                            );
+                        });
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                gap: Size::all(Val::Px(4.0)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            // The biggest haul fills its bar, the rest are relative to it
+                            let most = run_stats
+                                .reagents_gathered
+                                .iter()
+                                .copied()
+                                .fold(0.0, f32::max)
+                                .max(1.0);
+                            for (reagent, definition) in registry.reagents() {
+                                let amount = run_stats.gathered(reagent);
+                                add_summary_bar(
+                                    parent,
+                                    &font,
+                                    format!("{} {amount:.1}", definition.name),
+                                    amount / most,
+                                    definition.color,
+                                );
+                            }
+                        });
+                });
+            let restart_button = MenuButton {
+                event: Some(MenuEvent::Restart),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "MAIN MENU", restart_button);
+        });
+}
+
+fn cleanup_endscreen_menu(mut commands: Commands, query: Query<Entity, With<EndScreenMenuRoot>>) {
+    for e in &query {
+        debug!("Cleaned up after endscreen");
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+struct HudEditMenuRoot;
+
+/// Holds a row for each reagent bar, rebuilt whenever the layout changes
+#[derive(Component)]
+struct HudEditRows;
+
+fn setup_hud_edit_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::all(Val::Px(4.0)),
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::all(Val::Px(15.0)),
+                    position: UiRect::all(Val::Percent(20.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.4, 0.4, 0.4).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(2),
+                focus_policy: FocusPolicy::Block,
+                ..default()
+            },
+            HudEditMenuRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        gap: Size::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                HudEditRows,
+            ));
+            let back_button = MenuButton {
+                event: Some(MenuEvent::CloseHudEdit),
+                ..Default::default()
+            };
+            add_menu_button(parent, &assets_server, "BACK", back_button);
         });
 }
 
-fn cleanup_outro_menu(mut commands: Commands, query: Query<Entity, With<OutroMenuRoot>>) {
+fn cleanup_hud_edit_menu(mut commands: Commands, query: Query<Entity, With<HudEditMenuRoot>>) {
     for e in &query {
-        debug!("Cleaned up after outro");
+        debug!("Cleaned up after hud edit menu");
         commands.entity(e).despawn_recursive();
     }
 }
 
-#[derive(Component)]
-struct EndScreenMenuRoot;
-
-fn setup_endscreen_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
+/// A button sized to its label, so a few fit on one row
+fn add_small_menu_button(
+    builder: &mut ChildBuilder,
+    assets_server: &AssetServer,
+    label: &str,
+    menu_button: MenuButton,
+) {
     let font = assets_server.load("font/BebasNeueRegular.otf");
-    commands
+
+    builder
         .spawn((
-            NodeBundle {
+            ButtonBundle {
                 style: Style {
-                    size: Size::width(Val::Percent(100.0)),
-                    align_items: AlignItems::Center,
+                    size: Size::new(Val::Auto, Val::Px(FONT_HEIGHT * 0.6)),
                     justify_content: JustifyContent::Center,
-                    flex_direction: FlexDirection::Column,
-                    gap: Size::all(Val::Px(15.0)),
+                    align_items: AlignItems::Center,
+                    padding: UiRect::horizontal(Val::Px(10.0)),
                     ..default()
                 },
-                background_color: Color::BLACK.into(),
+                background_color: menu_button.base_color.into(),
                 ..default()
             },
-            EndScreenMenuRoot,
+            menu_button,
         ))
         .with_children(|parent| {
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    "WARLORD",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 120.0,
-                        color: Color::ORANGE_RED,
-                    },
-                ),
-                ..Default::default()
-            });
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    "Thank you for playing!",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 30.0,
-                        color: Color::ORANGE_RED,
-                    },
-                ),
-                ..Default::default()
-            });
-            let restart_button = MenuButton {
-                event: Some(MenuEvent::Restart),
-                ..Default::default()
-            };
-            add_menu_button(parent, &assets_server, "MAIN MENU", restart_button);
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font,
+                    font_size: FONT_HEIGHT * 0.6,
+                    color: TEXT_COLOR,
+                },
+            ));
         });
 }
 
-fn cleanup_endscreen_menu(mut commands: Commands, query: Query<Entity, With<EndScreenMenuRoot>>) {
-    for e in &query {
-        debug!("Cleaned up after endscreen");
-        commands.entity(e).despawn_recursive();
+/// One row per reagent, top first, with buttons to move it up, pin it to the top or take it off
+/// the screen
+fn refresh_hud_edit_rows(
+    mut commands: Commands,
+    query: Query<Entity, With<HudEditRows>>,
+    save_data: Res<SaveData>,
+    registry: Res<ReagentRegistry>,
+    assets_server: Res<AssetServer>,
+) {
+    let Ok(rows) = query.get_single() else { return; };
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+    let layout = &save_data.hud_layout;
+
+    commands.entity(rows).despawn_descendants();
+    commands.entity(rows).with_children(|parent| {
+        for reagent in layout.ordered(&registry) {
+            let definition = registry.get(reagent);
+            let collapsed = layout.is_collapsed(reagent, &registry);
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        gap: Size::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle {
+                        text: Text::from_section(
+                            definition.name.clone(),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: FONT_HEIGHT * 0.6,
+                                color: if collapsed {
+                                    Color::DARK_GRAY
+                                } else {
+                                    definition.color
+                                },
+                            },
+                        ),
+                        style: Style {
+                            min_size: Size::width(Val::Px(BUTTON_WIDTH * 0.6)),
+                            ..default()
+                        },
+                        ..default()
+                    });
+                    let buttons = [
+                        ("UP", MenuEvent::MoveReagentBarUp(reagent)),
+                        ("PIN", MenuEvent::PinReagentBar(reagent)),
+                        (
+                            if collapsed { "SHOW" } else { "HIDE" },
+                            MenuEvent::ToggleReagentBar(reagent),
+                        ),
+                    ];
+                    for (label, event) in buttons {
+                        let button = MenuButton {
+                            event: Some(event),
+                            ..Default::default()
+                        };
+                        add_small_menu_button(parent, &assets_server, label, button);
+                    }
+                });
+        }
+    });
+}
+
+/// Changes to the layout are saved as soon as they're made
+fn process_hud_edit_menu_event(
+    mut reader: EventReader<MenuEvent>,
+    current_state: Res<State<GameState>>,
+    mut next_hud_edit_state: ResMut<NextState<HudEditState>>,
+    mut save_data: ResMut<SaveData>,
+    registry: Res<ReagentRegistry>,
+) {
+    for ev in reader.iter() {
+        match *ev {
+            MenuEvent::EditHud if current_state.0 == GameState::Paused => {
+                next_hud_edit_state.set(HudEditState::InHudEdit)
+            }
+            MenuEvent::CloseHudEdit => next_hud_edit_state.set(HudEditState::None),
+            MenuEvent::MoveReagentBarUp(reagent) => {
+                save_data.hud_layout.move_up(reagent, &registry);
+                save_data.save();
+            }
+            MenuEvent::PinReagentBar(reagent) => {
+                save_data.hud_layout.pin(reagent, &registry);
+                save_data.save();
+            }
+            MenuEvent::ToggleReagentBar(reagent) => {
+                save_data.hud_layout.toggle_collapsed(reagent, &registry);
+                save_data.save();
+            }
+            _ => (),
+        }
     }
 }
 
@@ -737,12 +2145,17 @@ fn handle_button_interaction(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_menu_event(
     mut reader: EventReader<MenuEvent>,
     current_state: Res<State<GameState>>,
     mut next_state: ResMut<NextState<GameState>>,
     mut next_game_stage: ResMut<NextState<ProgressStages>>,
     mut next_settings_state: ResMut<NextState<SettingsState>>,
+    mut next_stats_state: ResMut<NextState<StatsState>>,
+    mut next_hint_log_state: ResMut<NextState<HintLogState>>,
+    mut next_photo_mode_state: ResMut<NextState<PhotoModeState>>,
+    challenge_result: Option<Res<ChallengeResult>>,
     mut exit: EventWriter<AppExit>,
 ) {
     for ev in reader.iter() {
@@ -758,6 +2171,50 @@ fn process_menu_event(
                 }
             }
             MenuEvent::Settings => next_settings_state.set(SettingsState::InSettings),
+            MenuEvent::Stats => {
+                if current_state.0 == GameState::Paused {
+                    next_stats_state.set(StatsState::InStats)
+                }
+            }
+            MenuEvent::CloseStats => next_stats_state.set(StatsState::None),
+            MenuEvent::HintLog => {
+                if current_state.0 == GameState::Paused {
+                    next_hint_log_state.set(HintLogState::InHintLog)
+                }
+            }
+            MenuEvent::CloseHintLog => next_hint_log_state.set(HintLogState::None),
+            MenuEvent::PhotoMode => {
+                if current_state.0 == GameState::Paused {
+                    next_photo_mode_state.set(PhotoModeState::On)
+                }
+            }
+            MenuEvent::EditHud
+            | MenuEvent::CloseHudEdit
+            | MenuEvent::MoveReagentBarUp(_)
+            | MenuEvent::PinReagentBar(_)
+            | MenuEvent::ToggleReagentBar(_) => {
+                // Handled in process_hud_edit_menu_event
+            }
+            MenuEvent::Chapters | MenuEvent::StartChapter(_) | MenuEvent::CloseChapters => {
+                // Handled in process_chapters_menu_event
+            }
+            MenuEvent::Practice | MenuEvent::ResetPractice | MenuEvent::LeavePractice => {
+                // Handled in practice.rs
+            }
+            MenuEvent::DailyRun => {
+                // Handled in daily.rs
+            }
+            MenuEvent::NewGamePlus => {
+                // Handled in new_game_plus.rs
+            }
+            MenuEvent::Customize | MenuEvent::CycleShipColor(_) | MenuEvent::CloseCustomize => {
+                // Handled in process_customize_menu_event
+            }
+            MenuEvent::Challenges
+            | MenuEvent::StartChallenge(_)
+            | MenuEvent::CloseChallenges => {
+                // Handled in process_challenges_menu_event
+            }
             MenuEvent::Start => {
                 if current_state.0 == GameState::MainMenu {
                     next_state.set(GameState::Intro);
@@ -768,7 +2225,12 @@ fn process_menu_event(
                 if current_state.0 == GameState::Intro {
                     next_state.set(GameState::InGame)
                 } else if current_state.0 == GameState::Outro {
-                    next_state.set(GameState::EndScreen)
+                    // Challenges go back to the menu, there's no run summary for them
+                    if challenge_result.is_some() {
+                        next_state.set(GameState::MainMenu)
+                    } else {
+                        next_state.set(GameState::EndScreen)
+                    }
                 }
             }
             MenuEvent::Restart => {
@@ -780,10 +2242,63 @@ fn process_menu_event(
     }
 }
 
+fn process_chapters_menu_event(
+    mut commands: Commands,
+    mut reader: EventReader<MenuEvent>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_chapters_state: ResMut<NextState<ChaptersState>>,
+) {
+    for ev in reader.iter() {
+        match ev {
+            MenuEvent::Chapters => next_chapters_state.set(ChaptersState::InChapters),
+            MenuEvent::CloseChapters => next_chapters_state.set(ChaptersState::None),
+            MenuEvent::StartChapter(stage) if current_state.0 == GameState::MainMenu => {
+                commands.insert_resource(ChapterStart { stage: *stage });
+                next_state.set(GameState::Intro);
+                next_chapters_state.set(ChaptersState::None);
+            }
+            _ => (),
+        }
+    }
+}
+
+fn process_challenges_menu_event(
+    mut commands: Commands,
+    mut reader: EventReader<MenuEvent>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_challenges_state: ResMut<NextState<ChallengesState>>,
+) {
+    for ev in reader.iter() {
+        match ev {
+            MenuEvent::Challenges => next_challenges_state.set(ChallengesState::InChallenges),
+            MenuEvent::CloseChallenges => next_challenges_state.set(ChallengesState::None),
+            MenuEvent::StartChallenge(challenge) if current_state.0 == GameState::MainMenu => {
+                debug!("Starting challenge {challenge:?}");
+                commands.insert_resource(GameRng::new(challenge.seed()));
+                commands.insert_resource(ChapterStart {
+                    stage: challenge.start_stage(),
+                });
+                commands.insert_resource(ActiveChallenge {
+                    challenge: *challenge,
+                });
+                next_state.set(GameState::Intro);
+                next_challenges_state.set(ChallengesState::None);
+            }
+            _ => (),
+        }
+    }
+}
+
 fn process_settings_menu_event(
     mut reader: EventReader<SettingsMenuEvent>,
     mut next_settings_state: ResMut<NextState<SettingsState>>,
     mut volume: ResMut<VolumeSettings>,
+    mut graphics_settings: ResMut<GraphicsSettings>,
+    mut coop_settings: ResMut<CoopSettings>,
+    mut hud_settings: ResMut<HudSettings>,
+    mut weapon_settings: ResMut<WeaponSettings>,
 ) {
     for ev in reader.iter() {
         match ev {
@@ -793,12 +2308,37 @@ fn process_settings_menu_event(
             SettingsMenuEvent::ToggleMute => {
                 volume.mute = !volume.mute;
             }
+            SettingsMenuEvent::ToggleStarfield => {
+                graphics_settings.starfield = match graphics_settings.starfield {
+                    StarfieldMode::Shader => StarfieldMode::Static,
+                    StarfieldMode::Static => StarfieldMode::Shader,
+                };
+            }
+            SettingsMenuEvent::CyclePhysicsQuality => {
+                graphics_settings.physics = graphics_settings.physics.next();
+            }
+            SettingsMenuEvent::ToggleCoop => {
+                coop_settings.enabled = !coop_settings.enabled;
+            }
+            SettingsMenuEvent::ToggleSharedInventory => {
+                coop_settings.shared_inventory = !coop_settings.shared_inventory;
+            }
+            SettingsMenuEvent::ToggleAimLine => {
+                hud_settings.aim_line = !hud_settings.aim_line;
+            }
+            SettingsMenuEvent::ToggleCounterThrust => {
+                weapon_settings.counter_thrust = !weapon_settings.counter_thrust;
+            }
             SettingsMenuEvent::SoundEffectVolume { delta } => {
                 volume.sound_effects = (volume.sound_effects + delta).clamp(0.0, 1.0);
             }
             SettingsMenuEvent::MusicVolume { delta } => {
                 volume.music = (volume.music + delta).clamp(0.0, 1.0);
             }
+            SettingsMenuEvent::HudOpacity { delta } => {
+                // All the way down is what hiding the HUD is for
+                hud_settings.opacity = (hud_settings.opacity + delta).clamp(0.1, 1.0);
+            }
         }
     }
 }
@@ -816,15 +2356,62 @@ fn setup_menu_controller(mut commands: Commands) {
     ));
 }
 
+/// The screens that open on top of the menus, each closed by the menu button before anything else
+#[derive(SystemParam)]
+struct MenuOverlays<'w> {
+    current_photo_mode_state: Res<'w, State<PhotoModeState>>,
+    next_photo_mode_state: ResMut<'w, NextState<PhotoModeState>>,
+    current_chapters_state: Res<'w, State<ChaptersState>>,
+    next_chapters_state: ResMut<'w, NextState<ChaptersState>>,
+    current_challenges_state: Res<'w, State<ChallengesState>>,
+    next_challenges_state: ResMut<'w, NextState<ChallengesState>>,
+    current_stats_state: Res<'w, State<StatsState>>,
+    next_stats_state: ResMut<'w, NextState<StatsState>>,
+    current_hint_log_state: Res<'w, State<HintLogState>>,
+    next_hint_log_state: ResMut<'w, NextState<HintLogState>>,
+    current_hud_edit_state: Res<'w, State<HudEditState>>,
+    next_hud_edit_state: ResMut<'w, NextState<HudEditState>>,
+    current_customize_state: Res<'w, State<CustomizeState>>,
+    next_customize_state: ResMut<'w, NextState<CustomizeState>>,
+}
+
+impl MenuOverlays<'_> {
+    /// Closes whichever overlay is open. Whether there was one
+    fn close(&mut self) -> bool {
+        if self.current_photo_mode_state.0 == PhotoModeState::On {
+            self.next_photo_mode_state.set(PhotoModeState::Off);
+        } else if self.current_chapters_state.0 == ChaptersState::InChapters {
+            self.next_chapters_state.set(ChaptersState::None);
+        } else if self.current_challenges_state.0 == ChallengesState::InChallenges {
+            self.next_challenges_state.set(ChallengesState::None);
+        } else if self.current_stats_state.0 == StatsState::InStats {
+            self.next_stats_state.set(StatsState::None);
+        } else if self.current_hint_log_state.0 == HintLogState::InHintLog {
+            self.next_hint_log_state.set(HintLogState::None);
+        } else if self.current_hud_edit_state.0 == HudEditState::InHudEdit {
+            self.next_hud_edit_state.set(HudEditState::None);
+        } else if self.current_customize_state.0 == CustomizeState::InCustomize {
+            self.next_customize_state.set(CustomizeState::None);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
 fn handle_menu_input(
     query: Query<&ActionState<crate::input::MenuAction>, With<MenuController>>,
     current_state: Res<State<GameState>>,
     mut next_state: ResMut<NextState<GameState>>,
     current_settings_state: Res<State<SettingsState>>,
     mut next_settings_state: ResMut<NextState<SettingsState>>,
+    mut overlays: MenuOverlays,
 ) {
     let action_state = query.single();
     if action_state.just_pressed(crate::input::MenuAction::Menu) {
+        if overlays.close() {
+            return;
+        }
         match current_settings_state.0 {
             SettingsState::InSettings => {
                 next_settings_state.set(SettingsState::None);
@@ -833,6 +2420,9 @@ fn handle_menu_input(
             SettingsState::None => (),
         }
         match current_state.0 {
+            GameState::Loading => {
+                // Do nothing
+            }
             GameState::MainMenu => {
                 // Do nothing
             }
@@ -882,6 +2472,137 @@ pub enum SettingsState {
     InSettings,
 }
 
+fn hide_chapters_menu(mut query: Query<&mut Visibility, With<ChaptersMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_chapters_menu(mut query: Query<&mut Visibility, With<ChaptersMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Visible;
+    }
+}
+
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum ChaptersState {
+    #[default]
+    None,
+    InChapters,
+}
+
+fn hide_challenges_menu(mut query: Query<&mut Visibility, With<ChallengesMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_challenges_menu(mut query: Query<&mut Visibility, With<ChallengesMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Visible;
+    }
+}
+
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum ChallengesState {
+    #[default]
+    None,
+    InChallenges,
+}
+
+fn hide_stats_menu(mut query: Query<&mut Visibility, With<StatsMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_stats_menu(mut query: Query<&mut Visibility, With<StatsMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Visible;
+    }
+}
+
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum StatsState {
+    #[default]
+    None,
+    InStats,
+}
+
+/// Leaving the pause menu closes the stats page with it
+fn close_stats_menu(mut next_stats_state: ResMut<NextState<StatsState>>) {
+    next_stats_state.set(StatsState::None);
+}
+
+fn hide_hint_log_menu(mut query: Query<&mut Visibility, With<HintLogMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_hint_log_menu(mut query: Query<&mut Visibility, With<HintLogMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Visible;
+    }
+}
+
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum HintLogState {
+    #[default]
+    None,
+    InHintLog,
+}
+
+fn close_hint_log_menu(mut next_hint_log_state: ResMut<NextState<HintLogState>>) {
+    next_hint_log_state.set(HintLogState::None);
+}
+
+fn hide_hud_edit_menu(mut query: Query<&mut Visibility, With<HudEditMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_hud_edit_menu(mut query: Query<&mut Visibility, With<HudEditMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Visible;
+    }
+}
+
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum HudEditState {
+    #[default]
+    None,
+    InHudEdit,
+}
+
+fn close_hud_edit_menu(mut next_hud_edit_state: ResMut<NextState<HudEditState>>) {
+    next_hud_edit_state.set(HudEditState::None);
+}
+
+fn hide_customize_menu(mut query: Query<&mut Visibility, With<CustomizeMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_customize_menu(mut query: Query<&mut Visibility, With<CustomizeMenuRoot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Visible;
+    }
+}
+
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum CustomizeState {
+    #[default]
+    None,
+    InCustomize,
+}
+
+fn close_customize_menu(mut next_customize_state: ResMut<NextState<CustomizeState>>) {
+    next_customize_state.set(CustomizeState::None);
+}
+
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
@@ -889,27 +2610,98 @@ impl Plugin for MenuPlugin {
         app.add_event::<MenuEvent>()
             .add_event::<SettingsMenuEvent>()
             .add_state::<SettingsState>()
+            .add_state::<ChaptersState>()
+            .add_state::<ChallengesState>()
+            .add_state::<StatsState>()
+            .add_state::<HintLogState>()
+            .add_state::<HudEditState>()
+            .add_state::<CustomizeState>()
             .add_startup_system(setup_menu_controller)
             .add_system(handle_button_interaction)
             .add_system(process_menu_event)
+            .add_system(process_chapters_menu_event)
+            .add_system(process_challenges_menu_event)
+            .add_system(process_hud_edit_menu_event)
+            .add_system(process_customize_menu_event)
             .add_system(handle_menu_input)
             .add_system(hide_pause_menu.in_schedule(OnEnter(GameState::InGame)))
             .add_system(show_pause_menu.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(update_pause_summary.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(hide_pause_menu.in_schedule(OnEnter(PhotoModeState::On)))
+            .add_system(show_pause_menu.in_schedule(OnExit(PhotoModeState::On)))
             .add_system(hide_settings_menu.in_schedule(OnEnter(SettingsState::None)))
             .add_system(show_settings_menu.in_schedule(OnEnter(SettingsState::InSettings)))
             .add_system(setup_settings_menu.in_schedule(OnEnter(GameState::MainMenu)))
             .add_system(cleanup_settings_menu.in_schedule(OnEnter(GameState::Outro)))
             .add_system(setup_main_menu.in_schedule(OnEnter(GameState::MainMenu)))
             .add_system(cleanup_main_menu.in_schedule(OnExit(GameState::MainMenu)))
+            .add_system(setup_chapters_menu.in_schedule(OnEnter(GameState::MainMenu)))
+            .add_system(cleanup_chapters_menu.in_schedule(OnExit(GameState::MainMenu)))
+            .add_system(hide_chapters_menu.in_schedule(OnEnter(ChaptersState::None)))
+            .add_system(show_chapters_menu.in_schedule(OnEnter(ChaptersState::InChapters)))
+            .add_system(setup_challenges_menu.in_schedule(OnEnter(GameState::MainMenu)))
+            .add_system(cleanup_challenges_menu.in_schedule(OnExit(GameState::MainMenu)))
+            .add_system(hide_challenges_menu.in_schedule(OnEnter(ChallengesState::None)))
+            .add_system(show_challenges_menu.in_schedule(OnEnter(ChallengesState::InChallenges)))
+            .add_system(hide_stats_menu.in_schedule(OnEnter(StatsState::None)))
+            .add_system(show_stats_menu.in_schedule(OnEnter(StatsState::InStats)))
+            .add_system(close_stats_menu.in_schedule(OnExit(GameState::Paused)))
+            .add_system(setup_stats_menu.in_schedule(OnExit(GameState::Intro)))
+            .add_system(cleanup_stats_menu.in_schedule(OnEnter(GameState::Outro)))
+            .add_system(update_stats_menu_display.in_set(OnUpdate(StatsState::InStats)))
+            .add_system(hide_hint_log_menu.in_schedule(OnEnter(HintLogState::None)))
+            .add_system(show_hint_log_menu.in_schedule(OnEnter(HintLogState::InHintLog)))
+            .add_system(update_hint_log_display.in_schedule(OnEnter(HintLogState::InHintLog)))
+            .add_system(close_hint_log_menu.in_schedule(OnExit(GameState::Paused)))
+            .add_system(setup_hint_log_menu.in_schedule(OnExit(GameState::Intro)))
+            .add_system(cleanup_hint_log_menu.in_schedule(OnEnter(GameState::Outro)))
+            .add_system(hide_hud_edit_menu.in_schedule(OnEnter(HudEditState::None)))
+            .add_system(show_hud_edit_menu.in_schedule(OnEnter(HudEditState::InHudEdit)))
+            .add_system(refresh_hud_edit_rows.in_schedule(OnEnter(HudEditState::InHudEdit)))
+            .add_system(
+                refresh_hud_edit_rows
+                    .run_if(resource_changed::<SaveData>())
+                    .in_set(OnUpdate(HudEditState::InHudEdit)),
+            )
+            .add_system(close_hud_edit_menu.in_schedule(OnExit(GameState::Paused)))
+            .add_system(setup_hud_edit_menu.in_schedule(OnExit(GameState::Intro)))
+            .add_system(cleanup_hud_edit_menu.in_schedule(OnEnter(GameState::Outro)))
+            .add_system(setup_customize_menu.in_schedule(OnEnter(GameState::MainMenu)))
+            .add_system(cleanup_customize_menu.in_schedule(OnExit(GameState::MainMenu)))
+            .add_system(close_customize_menu.in_schedule(OnExit(GameState::MainMenu)))
+            .add_system(hide_customize_menu.in_schedule(OnEnter(CustomizeState::None)))
+            .add_system(show_customize_menu.in_schedule(OnEnter(CustomizeState::InCustomize)))
+            .add_system(refresh_ship_look_rows.in_schedule(OnEnter(CustomizeState::InCustomize)))
+            .add_system(
+                refresh_ship_look_rows
+                    .run_if(resource_changed::<SaveData>())
+                    .in_set(OnUpdate(CustomizeState::InCustomize)),
+            )
             .add_system(setup_intro_menu.in_schedule(OnEnter(GameState::Intro)))
             .add_system(cleanup_intro_menu.in_schedule(OnExit(GameState::Intro)))
             .add_system(setup_pause_menu.in_schedule(OnExit(GameState::Intro)))
             .add_system(cleanup_pause_menu.in_schedule(OnEnter(GameState::Outro)))
-            .add_system(setup_outro_menu.in_schedule(OnEnter(GameState::Outro)))
+            .add_system(
+                setup_outro_menu
+                    .run_if(not(resource_exists::<Practice>()))
+                    .run_if(not(resource_exists::<ChallengeResult>()))
+                    .in_schedule(OnEnter(GameState::Outro)),
+            )
+            .add_system(
+                setup_challenge_results
+                    .run_if(resource_exists::<ChallengeResult>())
+                    .in_schedule(OnEnter(GameState::Outro)),
+            )
             .add_system(cleanup_outro_menu.in_schedule(OnExit(GameState::Outro)))
             .add_system(setup_endscreen_menu.in_schedule(OnEnter(GameState::EndScreen)))
             .add_system(cleanup_endscreen_menu.in_schedule(OnExit(GameState::EndScreen)))
             .add_system(process_settings_menu_event.in_set(OnUpdate(SettingsState::InSettings)))
-            .add_system(update_settings_menu_displays.in_set(OnUpdate(SettingsState::InSettings)));
+            .add_system(update_settings_menu_displays.in_set(OnUpdate(SettingsState::InSettings)))
+            .add_system(
+                update_graphics_settings_display.in_set(OnUpdate(SettingsState::InSettings)),
+            )
+            .add_system(update_coop_settings_display.in_set(OnUpdate(SettingsState::InSettings)))
+            .add_system(update_hud_settings_display.in_set(OnUpdate(SettingsState::InSettings)))
+            .add_system(update_weapon_settings_display.in_set(OnUpdate(SettingsState::InSettings)));
     }
 }
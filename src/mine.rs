@@ -0,0 +1,268 @@
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier2d::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    despawn::DespawnQueue,
+    enemy::PirateShip,
+    game_time::GameTime,
+    hull::{Hull, HullDamageEvent},
+    input::Action,
+    particles::{Particle, ParticleBundle},
+    physics::MINE_COLLISION_GROUPS,
+    player::{LeadPlayer, Player},
+    rock::{Rock, RockDestroyed},
+    sound::SoundEvent,
+    state::GameState,
+    ui::EnabledControls,
+    util::cosmetic::{random_direction, random_range},
+};
+
+const MAX_MINES: usize = 5;
+
+/// A proximity mine left behind the ship. Once armed, anything that wanders into its sensor
+/// sets it off, destroying every rock in the blast
+#[derive(Component, Debug)]
+pub struct Mine {
+    pub arm_timer: Timer,
+    pub blast_radius: f32,
+    /// Damage dealt to hulls caught in the blast
+    pub damage: f32,
+}
+
+impl Default for Mine {
+    fn default() -> Self {
+        Self {
+            arm_timer: Timer::from_seconds(1.5, TimerMode::Once),
+            blast_radius: 5.0,
+            damage: 40.0,
+        }
+    }
+}
+
+/// Keeps the ship from laying a whole minefield at once
+#[derive(Component, Debug)]
+pub struct MineLayer {
+    pub cooldown_timer: Timer,
+}
+
+impl Default for MineLayer {
+    fn default() -> Self {
+        let mut cooldown_timer = Timer::from_seconds(1.0, TimerMode::Once);
+        // Ready to go straight away
+        cooldown_timer.tick(cooldown_timer.duration());
+        Self { cooldown_timer }
+    }
+}
+
+#[derive(Resource, Debug)]
+struct MineAppearance {
+    mesh: Handle<Mesh>,
+    unarmed_material: Handle<StandardMaterial>,
+    armed_material: Handle<StandardMaterial>,
+    blast_mesh: Handle<Mesh>,
+    blast_material: Handle<StandardMaterial>,
+}
+
+fn setup_mine_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mesh = meshes.add(
+        shape::Icosphere {
+            radius: 0.35,
+            subdivisions: 0,
+        }
+        .try_into()
+        .unwrap(),
+    );
+    let unarmed_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.3, 0.3, 0.3),
+        ..Default::default()
+    });
+    let armed_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(1.0, 0.2, 0.1),
+        emissive: Color::rgb(1.0, 0.1, 0.0) * 3.0,
+        ..Default::default()
+    });
+    let blast_mesh = meshes.add(
+        shape::Quad {
+            size: Vec2::splat(0.4),
+            ..Default::default()
+        }
+        .into(),
+    );
+    let blast_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(1.0, 0.6, 0.1),
+        emissive: Color::rgb(1.0, 0.4, 0.0) * 4.0,
+        ..Default::default()
+    });
+
+    commands.insert_resource(MineAppearance {
+        mesh,
+        unarmed_material,
+        armed_material,
+        blast_mesh,
+        blast_material,
+    });
+}
+
+fn lay_mines(
+    mut commands: Commands,
+    mut player_query: Query<(&Player, &Transform, &mut MineLayer, &ActionState<Action>)>,
+    mine_query: Query<(), With<Mine>>,
+    enabled_controls: Res<EnabledControls>,
+    mine_appearance: Res<MineAppearance>,
+    game_time: Res<GameTime>,
+) {
+    for (player, transform, mut mine_layer, action_state) in &mut player_query {
+        mine_layer.cooldown_timer.tick(game_time.delta());
+        if !enabled_controls.contains(EnabledControls::Shoot) {
+            continue;
+        }
+        if !action_state.just_pressed(Action::LayMine) || !mine_layer.cooldown_timer.finished() {
+            continue;
+        }
+        if mine_query.iter().count() >= MAX_MINES {
+            debug!("Already have {MAX_MINES} mines out");
+            continue;
+        }
+        mine_layer.cooldown_timer.reset();
+
+        let pos = transform.translation.truncate() - Vec2::from_angle(player.facing) * 2.0;
+        debug!("Laying a mine at {pos:?}");
+        let mine = Mine::default();
+        let sensor_radius = mine.blast_radius * 0.5;
+        commands.spawn((
+            mine,
+            RigidBody::KinematicVelocityBased,
+            Collider::ball(sensor_radius),
+            // Switched on once the mine arms, so it doesn't go off in the ship's face
+            ColliderDisabled,
+            MINE_COLLISION_GROUPS,
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+            Velocity::zero(),
+            PbrBundle {
+                mesh: mine_appearance.mesh.clone(),
+                material: mine_appearance.unarmed_material.clone(),
+                transform: Transform::from_xyz(pos.x, pos.y, transform.translation.z),
+                ..Default::default()
+            },
+        ));
+    }
+}
+
+fn arm_mines(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Mine, &mut Handle<StandardMaterial>)>,
+    mine_appearance: Res<MineAppearance>,
+    game_time: Res<GameTime>,
+) {
+    for (entity, mut mine, mut material) in &mut query {
+        if mine.arm_timer.finished() {
+            continue;
+        }
+        mine.arm_timer.tick(game_time.delta());
+        if mine.arm_timer.just_finished() {
+            debug!("Mine {entity:?} armed");
+            commands.entity(entity).remove::<ColliderDisabled>();
+            *material = mine_appearance.armed_material.clone();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn detonate_mines(
+    mut commands: Commands,
+    mut reader: EventReader<CollisionEvent>,
+    mine_query: Query<(&Mine, &Transform)>,
+    rock_query: Query<(Entity, &Transform), With<Rock>>,
+    mut pirate_query: Query<(Entity, &Transform, &mut Hull), With<PirateShip>>,
+    player_query: Query<&Transform, LeadPlayer>,
+    mine_appearance: Res<MineAppearance>,
+    mut rock_destroyed_writer: EventWriter<RockDestroyed>,
+    mut hull_damage_writer: EventWriter<HullDamageEvent>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+) {
+    let mut detonated = HashSet::new();
+    for ev in reader.iter() {
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        for mine_entity in [*e1, *e2] {
+            if mine_query.contains(mine_entity) {
+                detonated.insert(mine_entity);
+            }
+        }
+    }
+
+    // A rock can be caught in more than one blast, but must only be destroyed once
+    let mut destroyed = HashSet::new();
+    for mine_entity in detonated {
+        let Ok((mine, transform)) = mine_query.get(mine_entity) else { continue; };
+        // Something else already took it out this frame
+        if !despawn_queue.despawn(mine_entity) {
+            continue;
+        }
+        let center = transform.translation;
+        debug!("Mine {mine_entity:?} went off at {center:?}");
+
+        for (rock_entity, rock_transform) in &rock_query {
+            if rock_transform.translation.distance(center) > mine.blast_radius {
+                continue;
+            }
+            if destroyed.insert(rock_entity) {
+                rock_destroyed_writer.send(RockDestroyed {
+                    entity: rock_entity,
+                    position: rock_transform.translation,
+                });
+            }
+        }
+        for (pirate_entity, pirate_transform, mut hull) in &mut pirate_query {
+            if pirate_transform.translation.distance(center) > mine.blast_radius {
+                continue;
+            }
+            hull.damage(mine.damage);
+            hull_damage_writer.send(HullDamageEvent {
+                entity: pirate_entity,
+                amount: mine.damage,
+            });
+        }
+
+        const NUM_PARTICLES: u32 = 16;
+        for _ in 0..NUM_PARTICLES {
+            commands.spawn(ParticleBundle {
+                mesh: mine_appearance.blast_mesh.clone(),
+                material: mine_appearance.blast_material.clone(),
+                particle: Particle {
+                    lifetime_timer: Timer::from_seconds(0.5, TimerMode::Once),
+                },
+                velocity: Velocity {
+                    linvel: random_direction() * mine.blast_radius * 2.0,
+                    angvel: random_range(-PI, PI),
+                },
+                transform: Transform::from_translation(center),
+                ..Default::default()
+            });
+        }
+        if let Ok(player_transform) = player_query.get_single() {
+            let diff = center - player_transform.translation;
+            sound_event_writer.send(SoundEvent::RockDestroyed { relative_pos: diff });
+        }
+    }
+}
+
+pub struct MinePlugin;
+
+impl Plugin for MinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_mine_appearance).add_systems(
+            (lay_mines, arm_mines, detonate_mines)
+                .chain()
+                .in_set(OnUpdate(GameState::InGame)),
+        );
+    }
+}
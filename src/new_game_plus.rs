@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+
+use crate::{
+    campaign::Campaign,
+    cli::LaunchOptions,
+    inventory::ReagentRegistry,
+    menu::MenuEvent,
+    state::{GameState, ProgressStages},
+};
+
+/// Set from the main menu once the game's been finished. The run follows
+/// `assets/campaign_plus.txt` instead of the usual campaign, so everything that's different
+/// about it lives in that file
+#[derive(Resource, Debug, Default)]
+pub struct NewGamePlus;
+
+fn start_new_game_plus(
+    mut commands: Commands,
+    mut reader: EventReader<MenuEvent>,
+    registry: Res<ReagentRegistry>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_game_stage: ResMut<NextState<ProgressStages>>,
+) {
+    for ev in reader.iter() {
+        if let MenuEvent::NewGamePlus = ev {
+            if current_state.0 == GameState::MainMenu {
+                debug!("Starting New Game+");
+                commands.insert_resource(NewGamePlus);
+                commands.insert_resource(Campaign::load_new_game_plus(&registry));
+                next_state.set(GameState::Intro);
+                next_game_stage.set(ProgressStages::default());
+            }
+        }
+    }
+}
+
+/// Puts the usual campaign back for whatever's played next
+fn end_new_game_plus(
+    mut commands: Commands,
+    registry: Res<ReagentRegistry>,
+    options: Option<Res<LaunchOptions>>,
+) {
+    commands.remove_resource::<NewGamePlus>();
+    commands.insert_resource(Campaign::load(&registry, options.as_deref()));
+}
+
+pub struct NewGamePlusPlugin;
+
+impl Plugin for NewGamePlusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(start_new_game_plus.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(
+                end_new_game_plus
+                    .run_if(resource_exists::<NewGamePlus>())
+                    .in_schedule(OnExit(GameState::EndScreen)),
+            );
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::{prelude::*, render::view::RenderLayers};
+
+use crate::{camera::MainCamera, state::GameState};
+
+/// A repeating image in the background that scrolls past slower than the camera moves.
+/// It's made of a 3x3 grid of tiles, which is moved by up to a tile as the camera travels, so
+/// it always covers the view
+#[derive(Component, Debug)]
+pub struct ParallaxLayer {
+    /// How much the layer moves with the camera. Smaller numbers look further away
+    pub factor: f32,
+    pub tile_size: f32,
+}
+
+/// Spawns a parallax layer of `image` tiles onto the starfield camera's render layer
+pub fn spawn_parallax_layer(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    image: Handle<Image>,
+    layer: ParallaxLayer,
+    z: f32,
+) -> Entity {
+    let mesh = meshes.add(shape::Quad::new(Vec2::splat(layer.tile_size)).into());
+    let material = materials.add(StandardMaterial {
+        base_color_texture: Some(image),
+        unlit: true,
+        // The images are stars on black, so adding them lets everything behind show through
+        alpha_mode: AlphaMode::Add,
+        ..Default::default()
+    });
+    let tile_size = layer.tile_size;
+
+    commands
+        .spawn((
+            layer,
+            SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, z)),
+            RenderLayers::layer(3),
+        ))
+        .with_children(|parent| {
+            for x in -1..=1 {
+                for y in -1..=1 {
+                    parent.spawn((
+                        PbrBundle {
+                            mesh: mesh.clone(),
+                            material: material.clone(),
+                            transform: Transform::from_xyz(
+                                x as f32 * tile_size,
+                                y as f32 * tile_size,
+                                0.0,
+                            ),
+                            ..Default::default()
+                        },
+                        RenderLayers::layer(3),
+                    ));
+                }
+            }
+        })
+        .id()
+}
+
+fn scroll_parallax_layers(
+    main_camera_query: Query<&GlobalTransform, With<MainCamera>>,
+    mut query: Query<(&ParallaxLayer, &mut Transform)>,
+) {
+    let Ok(main_camera) = main_camera_query.get_single() else { return; };
+    let camera_pos = main_camera.translation().truncate();
+    for (layer, mut transform) in &mut query {
+        // The starfield camera never moves, so the layer moves the other way instead
+        let offset = (-camera_pos * layer.factor) % layer.tile_size;
+        transform.translation.x = offset.x;
+        transform.translation.y = offset.y;
+    }
+}
+
+pub struct ParallaxPlugin;
+
+impl Plugin for ParallaxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(scroll_parallax_layers.in_set(OnUpdate(GameState::InGame)));
+    }
+}
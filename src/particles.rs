@@ -1,13 +1,17 @@
-use std::{f32::consts::PI, time::Duration};
+use std::f32::consts::PI;
 
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
+    campaign::StageThresholdReached,
+    game_time::GameTime,
     player::PlayerMoveEvent,
-    rock::RockDestroyed,
+    rock::{RockClash, RockDestroyed},
+    save_data::{SaveData, ShipPart},
     state::GameState,
-    util::{random_direction, random_in_circle, random_range},
+    status_effect::{StatusEffects, StatusKind},
+    util::cosmetic::{random_direction, random_in_circle, random_range},
     weapon::{FireMainGunEvent, SlugDecayedEvent},
 };
 
@@ -46,6 +50,15 @@ struct ParticleHandles {
 
     pub rock_destroyed_particle_mat: Handle<StandardMaterial>,
     pub rock_destroyed_particle_mesh: Handle<Mesh>,
+
+    pub threshold_reached_particle_mat: Handle<StandardMaterial>,
+    pub threshold_reached_particle_mesh: Handle<Mesh>,
+
+    pub burning_particle_mat: Handle<StandardMaterial>,
+    pub burning_particle_mesh: Handle<Mesh>,
+
+    pub rock_clash_particle_mat: Handle<StandardMaterial>,
+    pub rock_clash_particle_mesh: Handle<Mesh>,
 }
 
 fn setup_particle_handles(
@@ -105,6 +118,46 @@ fn setup_particle_handles(
         .into(),
     );
 
+    let threshold_reached_particle_mat = materials.add(StandardMaterial {
+        base_color: Color::WHITE * 10.0,
+        emissive: Color::GOLD * 10.0,
+        ..Default::default()
+    });
+    let threshold_reached_particle_mesh = meshes.add(
+        shape::Icosphere {
+            subdivisions: 0,
+            radius: 0.15,
+        }
+        .try_into()
+        .unwrap(),
+    );
+
+    let burning_particle_mat = materials.add(StandardMaterial {
+        base_color: Color::ORANGE * 5.0,
+        emissive: Color::ORANGE_RED * 5.0,
+        ..Default::default()
+    });
+    let burning_particle_mesh = meshes.add(
+        shape::Quad {
+            size: Vec2::splat(0.15),
+            ..Default::default()
+        }
+        .into(),
+    );
+
+    let rock_clash_particle_mat = materials.add(StandardMaterial {
+        base_color: Color::WHITE * 8.0,
+        emissive: Color::YELLOW * 8.0,
+        ..Default::default()
+    });
+    let rock_clash_particle_mesh = meshes.add(
+        shape::Quad {
+            size: Vec2::splat(0.08),
+            ..Default::default()
+        }
+        .into(),
+    );
+
     commands.insert_resource(ParticleHandles {
         player_move_particle_mat,
         player_move_particle_mesh,
@@ -117,6 +170,15 @@ fn setup_particle_handles(
 
         rock_destroyed_particle_mat,
         rock_destroyed_particle_mesh,
+
+        threshold_reached_particle_mat,
+        threshold_reached_particle_mesh,
+
+        burning_particle_mat,
+        burning_particle_mesh,
+
+        rock_clash_particle_mat,
+        rock_clash_particle_mesh,
     });
 }
 
@@ -126,7 +188,7 @@ fn spawn_player_move_particles(
     mut bundle: Local<Option<ParticleBundle>>,
     handles: Res<ParticleHandles>,
     mut cooldown: Local<Timer>,
-    time: Res<Time>,
+    game_time: Res<GameTime>,
 ) {
     let bundle = match bundle.as_ref() {
         Some(b) => b.clone(),
@@ -144,7 +206,7 @@ fn spawn_player_move_particles(
         *cooldown = Timer::from_seconds(1.0 / 7.0, TimerMode::Repeating);
     }
 
-    cooldown.tick(Duration::from_secs_f32(time.delta_seconds()));
+    cooldown.tick(game_time.delta());
     if cooldown.just_finished() {
         let Some(ev) = reader.iter().next() else { return };
         const RADIUS: f32 = 1.0;
@@ -165,6 +227,18 @@ fn spawn_player_move_particles(
     }
 }
 
+/// The trail behind the ship is the colour picked in the main menu
+fn apply_trail_color(
+    handles: Res<ParticleHandles>,
+    save_data: Res<SaveData>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Some(material) = materials.get_mut(&handles.player_move_particle_mat) else { return; };
+    let color = save_data.ship_look.color(ShipPart::Trail);
+    material.base_color = color;
+    material.emissive = color;
+}
+
 fn spawn_fire_main_gun_particles(
     mut commands: Commands,
     mut reader: EventReader<FireMainGunEvent>,
@@ -312,10 +386,110 @@ fn spawn_rock_destroyed_particles(
     }
 }
 
-fn tick_particles(mut query: Query<&mut Particle>, time: Res<Time>) {
+fn spawn_threshold_reached_particles(
+    mut commands: Commands,
+    mut reader: EventReader<StageThresholdReached>,
+    handles: Res<ParticleHandles>,
+) {
+    let Some(ev) = reader.iter().next() else { return };
+    let bundle = ParticleBundle {
+        mesh: handles.threshold_reached_particle_mesh.clone(),
+        material: handles.threshold_reached_particle_mat.clone(),
+        ..Default::default()
+    };
+    // A ring going out evenly, with a little jitter so it doesn't look drawn on
+    const NUM_PARTICLES: u32 = 32;
+    for i in 0..NUM_PARTICLES {
+        let angle = i as f32 / NUM_PARTICLES as f32 * 2.0 * PI + random_range(-0.05, 0.05);
+        let vel = Vec2::from_angle(angle) * random_range(8.0, 12.0);
+        let lifetime = random_range(0.8, 1.2);
+
+        commands.spawn(ParticleBundle {
+            particle: Particle {
+                lifetime_timer: Timer::from_seconds(lifetime, TimerMode::Once),
+            },
+            velocity: Velocity::linear(vel),
+            transform: Transform::from_translation(ev.position),
+            ..bundle.clone()
+        });
+    }
+}
+
+/// Embers drifting up off anything that's on fire
+fn spawn_burning_particles(
+    mut commands: Commands,
+    query: Query<(&StatusEffects, &Transform)>,
+    handles: Res<ParticleHandles>,
+    mut cooldown: Local<Timer>,
+    game_time: Res<GameTime>,
+) {
+    if cooldown.duration().is_zero() {
+        *cooldown = Timer::from_seconds(0.08, TimerMode::Repeating);
+    }
+    cooldown.tick(game_time.delta());
+    if !cooldown.just_finished() {
+        return;
+    }
+    for (effects, transform) in &query {
+        if !effects.has(StatusKind::Burning) {
+            continue;
+        }
+        let pt = random_in_circle(0.8);
+        commands.spawn(ParticleBundle {
+            mesh: handles.burning_particle_mesh.clone(),
+            material: handles.burning_particle_mat.clone(),
+            particle: Particle {
+                lifetime_timer: Timer::from_seconds(0.5, TimerMode::Once),
+            },
+            velocity: Velocity {
+                linvel: random_direction() * 1.5,
+                angvel: random_range(-PI, PI),
+            },
+            transform: Transform::from_translation(
+                transform.translation + Vec3::new(pt.x, pt.y, 0.5),
+            ),
+            ..Default::default()
+        });
+    }
+}
+
+/// Sparks flying out from two rocks hitting each other, more of them for harder hits
+fn spawn_rock_clash_particles(
+    mut commands: Commands,
+    mut reader: EventReader<RockClash>,
+    handles: Res<ParticleHandles>,
+) {
+    for ev in reader.iter() {
+        let bundle = ParticleBundle {
+            mesh: handles.rock_clash_particle_mesh.clone(),
+            material: handles.rock_clash_particle_mat.clone(),
+            ..Default::default()
+        };
+        let num_particles = (4.0 + 12.0 * ev.strength) as u32;
+        for _ in 0..num_particles {
+            let vel = ev.velocity + random_direction() * random_range(3.0, 8.0) * ev.strength;
+            let lifetime = random_range(0.2, 0.4);
+
+            commands.spawn(ParticleBundle {
+                particle: Particle {
+                    lifetime_timer: Timer::from_seconds(lifetime, TimerMode::Once),
+                },
+                velocity: Velocity {
+                    linvel: vel,
+                    angvel: random_range(-PI, PI),
+                },
+                // In front of the rocks, so the sparks aren't hidden inside them
+                transform: Transform::from_translation(ev.position + Vec3::Z),
+                ..bundle.clone()
+            });
+        }
+    }
+}
+
+fn tick_particles(mut query: Query<&mut Particle>, game_time: Res<GameTime>) {
     for mut p in &mut query {
         p.lifetime_timer
-            .tick(Duration::from_secs_f32(time.delta_seconds()));
+            .tick(game_time.delta());
     }
 }
 
@@ -331,16 +505,21 @@ pub struct ParticlePlugin;
 
 impl Plugin for ParticlePlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_particle_handles).add_systems(
-            (
-                spawn_player_move_particles,
-                spawn_fire_main_gun_particles,
-                spawn_slug_decayed_gun_particles,
-                spawn_rock_destroyed_particles,
-                tick_particles,
-                cull_particles,
-            )
-                .in_set(OnUpdate(GameState::InGame)),
-        );
+        app.add_startup_system(setup_particle_handles)
+            .add_system(apply_trail_color.run_if(resource_changed::<SaveData>()))
+            .add_systems(
+                (
+                    spawn_player_move_particles,
+                    spawn_fire_main_gun_particles,
+                    spawn_slug_decayed_gun_particles,
+                    spawn_rock_destroyed_particles,
+                    spawn_threshold_reached_particles,
+                    spawn_burning_particles,
+                    spawn_rock_clash_particles,
+                    tick_particles,
+                    cull_particles,
+                )
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
     }
 }
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use leafwing_input_manager::{prelude::ActionState, InputManagerBundle};
+
+use crate::{
+    camera::{MainCamera, SmoothFollow},
+    input::{default_photo_mode_input_map, PhotoModeAction},
+    screenshot::ScreenshotEvent,
+};
+
+/// In world units per second, multiplied by the zoom so panning feels the same at any zoom
+const PAN_SPEED: f32 = 1.0;
+/// How many times the view zooms in or out per second
+const ZOOM_SPEED: f32 = 2.0;
+const ROLL_SPEED: f32 = 1.0;
+const MIN_SCALE: f32 = 3.0;
+const MAX_SCALE: f32 = 60.0;
+
+/// Photo mode can only be entered while paused, so the game is already frozen
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum PhotoModeState {
+    #[default]
+    Off,
+    On,
+}
+
+/// How the main camera was set up before photo mode, so it can be put back afterwards
+#[derive(Resource, Debug)]
+struct SavedCamera {
+    transform: Transform,
+    scale: f32,
+    target: Option<Entity>,
+}
+
+#[derive(Component)]
+struct PhotoModeController;
+
+fn setup_photo_mode_controller(mut commands: Commands) {
+    commands.spawn((
+        InputManagerBundle {
+            action_state: ActionState::default(),
+            input_map: default_photo_mode_input_map(),
+        },
+        PhotoModeController,
+    ));
+}
+
+fn enter_photo_mode(
+    mut commands: Commands,
+    mut camera_query: Query<(&Transform, &Projection, &mut SmoothFollow), With<MainCamera>>,
+) {
+    let Ok((transform, projection, mut smooth_follow)) = camera_query.get_single_mut() else { return; };
+    let Projection::Orthographic(orthographic) = projection else { return; };
+
+    commands.insert_resource(SavedCamera {
+        transform: *transform,
+        scale: orthographic.scale,
+        target: smooth_follow.target,
+    });
+    smooth_follow.target = None;
+}
+
+fn exit_photo_mode(
+    mut commands: Commands,
+    saved_camera: Option<Res<SavedCamera>>,
+    mut camera_query: Query<(&mut Transform, &mut Projection, &mut SmoothFollow), With<MainCamera>>,
+) {
+    let Some(saved_camera) = saved_camera else { return; };
+    commands.remove_resource::<SavedCamera>();
+    let Ok((mut transform, mut projection, mut smooth_follow)) = camera_query.get_single_mut() else { return; };
+
+    *transform = saved_camera.transform;
+    if let Projection::Orthographic(orthographic) = projection.as_mut() {
+        orthographic.scale = saved_camera.scale;
+    }
+    smooth_follow.target = saved_camera.target;
+}
+
+fn photo_mode_camera_controls(
+    controller_query: Query<&ActionState<PhotoModeAction>, With<PhotoModeController>>,
+    mut camera_query: Query<(&mut Transform, &mut Projection), With<MainCamera>>,
+    mut screenshot_writer: EventWriter<ScreenshotEvent>,
+    // The game time is stopped while paused
+    time: Res<Time>,
+) {
+    let Ok(action_state) = controller_query.get_single() else { return; };
+    let Ok((mut transform, mut projection)) = camera_query.get_single_mut() else { return; };
+    let Projection::Orthographic(orthographic) = projection.as_mut() else { return; };
+    let dt = time.delta_seconds();
+
+    let mut pan = Vec2::ZERO;
+    if action_state.pressed(PhotoModeAction::PanUp) {
+        pan.y += 1.0;
+    }
+    if action_state.pressed(PhotoModeAction::PanDown) {
+        pan.y -= 1.0;
+    }
+    if action_state.pressed(PhotoModeAction::PanLeft) {
+        pan.x -= 1.0;
+    }
+    if action_state.pressed(PhotoModeAction::PanRight) {
+        pan.x += 1.0;
+    }
+    // Pan relative to the camera, so up is still up on screen when it's rolled
+    let pan = pan.normalize_or_zero() * PAN_SPEED * orthographic.scale * dt;
+    let offset = transform.rotation * pan.extend(0.0);
+    transform.translation += offset;
+
+    if action_state.pressed(PhotoModeAction::ZoomIn) {
+        orthographic.scale /= ZOOM_SPEED.powf(dt);
+    }
+    if action_state.pressed(PhotoModeAction::ZoomOut) {
+        orthographic.scale *= ZOOM_SPEED.powf(dt);
+    }
+    orthographic.scale = orthographic.scale.clamp(MIN_SCALE, MAX_SCALE);
+
+    if action_state.pressed(PhotoModeAction::RollLeft) {
+        transform.rotate_local_z(ROLL_SPEED * dt);
+    }
+    if action_state.pressed(PhotoModeAction::RollRight) {
+        transform.rotate_local_z(-ROLL_SPEED * dt);
+    }
+
+    if action_state.just_pressed(PhotoModeAction::Screenshot) {
+        screenshot_writer.send(ScreenshotEvent { include_hud: false });
+    }
+}
+
+pub struct PhotoModePlugin;
+
+impl Plugin for PhotoModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<PhotoModeState>()
+            .add_startup_system(setup_photo_mode_controller)
+            .add_system(enter_photo_mode.in_schedule(OnEnter(PhotoModeState::On)))
+            .add_system(exit_photo_mode.in_schedule(OnExit(PhotoModeState::On)))
+            .add_system(photo_mode_camera_controls.in_set(OnUpdate(PhotoModeState::On)));
+    }
+}
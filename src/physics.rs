@@ -1,11 +1,276 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::state::GameState;
+use crate::{
+    background::GraphicsSettings,
+    game_time::GameTime,
+    state::GameState,
+    util::{load_config_file, parse_config_lines, parse_floats, warn_unknown_config},
+};
+
+const PHYSICS_FILE: &str = "assets/physics.txt";
+
+/// Gameplay systems in `FixedUpdate` and the physics both advance in steps of this size
+pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+
+// Every class of entity gets its own collision group, and only the pairs listed
+// in the filters below ever touch or generate collision events
+pub const PLAYER_GROUP: Group = Group::GROUP_1;
+pub const SLUG_GROUP: Group = Group::GROUP_2;
+pub const ROCK_GROUP: Group = Group::GROUP_3;
+pub const COLLECTIBLE_GROUP: Group = Group::GROUP_4;
+pub const SHIELD_GROUP: Group = Group::GROUP_5;
+pub const PIRATE_GROUP: Group = Group::GROUP_6;
+pub const COMET_GROUP: Group = Group::GROUP_7;
+pub const SHOCKWAVE_GROUP: Group = Group::GROUP_8;
+pub const MINE_GROUP: Group = Group::GROUP_9;
+
+pub const PLAYER_COLLISION_GROUPS: CollisionGroups = CollisionGroups::new(
+    PLAYER_GROUP,
+    ROCK_GROUP
+        .union(SLUG_GROUP)
+        .union(COLLECTIBLE_GROUP)
+        .union(PIRATE_GROUP)
+        .union(SHOCKWAVE_GROUP),
+);
+pub const SLUG_COLLISION_GROUPS: CollisionGroups = CollisionGroups::new(
+    SLUG_GROUP,
+    PLAYER_GROUP
+        .union(ROCK_GROUP)
+        .union(SHIELD_GROUP)
+        .union(PIRATE_GROUP)
+        .union(COMET_GROUP),
+);
+pub const ROCK_COLLISION_GROUPS: CollisionGroups = CollisionGroups::new(
+    ROCK_GROUP,
+    PLAYER_GROUP
+        .union(SLUG_GROUP)
+        .union(ROCK_GROUP)
+        .union(SHIELD_GROUP)
+        .union(PIRATE_GROUP)
+        .union(MINE_GROUP),
+);
+pub const COLLECTIBLE_COLLISION_GROUPS: CollisionGroups =
+    CollisionGroups::new(COLLECTIBLE_GROUP, PLAYER_GROUP.union(SHIELD_GROUP));
+pub const SHIELD_COLLISION_GROUPS: CollisionGroups = CollisionGroups::new(
+    SHIELD_GROUP,
+    ROCK_GROUP.union(SLUG_GROUP).union(COLLECTIBLE_GROUP),
+);
+pub const PIRATE_COLLISION_GROUPS: CollisionGroups = CollisionGroups::new(
+    PIRATE_GROUP,
+    PLAYER_GROUP
+        .union(SLUG_GROUP)
+        .union(ROCK_GROUP)
+        .union(PIRATE_GROUP)
+        .union(MINE_GROUP),
+);
+pub const COMET_COLLISION_GROUPS: CollisionGroups = CollisionGroups::new(COMET_GROUP, SLUG_GROUP);
+pub const SHOCKWAVE_COLLISION_GROUPS: CollisionGroups =
+    CollisionGroups::new(SHOCKWAVE_GROUP, PLAYER_GROUP);
+pub const MINE_COLLISION_GROUPS: CollisionGroups =
+    CollisionGroups::new(MINE_GROUP, ROCK_GROUP.union(PIRATE_GROUP));
+
+/// Slugs still report hitting rocks but don't bounce off them physically, `weapon.rs` decides
+/// what happens to them instead
+pub const SLUG_SOLVER_GROUPS: SolverGroups =
+    SolverGroups::new(SLUG_GROUP, Group::ALL.difference(ROCK_GROUP));
+
+/// Pulls anything with `AffectedByFields` towards it, harder the closer it gets
+#[derive(Component, Debug, Clone, Copy)]
+pub struct GravityField {
+    /// The pull at one unit away. It falls off with the square of the distance
+    pub strength: f32,
+    /// Nothing further than this is pulled at all
+    pub radius: f32,
+}
+
+/// The pull stops getting stronger this close in, so nothing gets flung out of the middle
+const MIN_FIELD_DISTANCE: f32 = 2.0;
+
+impl GravityField {
+    /// How fast something at `offset` from the middle of the field is pulled towards it
+    pub fn acceleration(&self, offset: Vec2) -> Vec2 {
+        let distance = offset.length();
+        if distance > self.radius {
+            return Vec2::ZERO;
+        }
+        let pull = self.strength / distance.max(MIN_FIELD_DISTANCE).powi(2);
+        -offset.normalize_or_zero() * pull
+    }
+}
+
+/// The pull of every field at `position`, for anything that wants to predict a path through them
+pub fn field_acceleration<'a>(
+    fields: impl IntoIterator<Item = (&'a GravityField, &'a GlobalTransform)>,
+    position: Vec2,
+) -> Vec2 {
+    fields
+        .into_iter()
+        .map(|(field, transform)| field.acceleration(position - transform.translation().truncate()))
+        .sum()
+}
+
+/// Added to projectiles that the physics hazards can bend, rather than only the solid things
+/// they run into
+#[derive(Component, Debug, Default)]
+pub struct AffectedByFields;
+
+fn apply_gravity_fields(
+    field_query: Query<(&GravityField, &GlobalTransform)>,
+    mut query: Query<(&Transform, &mut Velocity), With<AffectedByFields>>,
+    game_time: Res<GameTime>,
+) {
+    for (transform, mut velocity) in &mut query {
+        let acceleration = field_acceleration(&field_query, transform.translation.truncate());
+        velocity.linvel += acceleration * game_time.delta_seconds();
+    }
+}
+
+/// How carefully the physics is stepped, picked in the graphics settings. Higher costs more but
+/// lets less slip through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PhysicsQuality {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl PhysicsQuality {
+    /// The one after this, going back round to the start
+    pub fn next(self) -> Self {
+        match self {
+            Self::Low => Self::Medium,
+            Self::Medium => Self::High,
+            Self::High => Self::Low,
+        }
+    }
+}
+
+/// The knobs each `PhysicsQuality` sets. The one in use is kept as a resource
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsSettings {
+    /// How many pieces each physics step is split into
+    pub substeps: usize,
+    /// Rocks moving faster than this get continuous collision detection, like the slugs have,
+    /// so fast slugs can't pass straight through them
+    pub rock_ccd_speed: f32,
+    /// How many times the solver goes over the contacts each step
+    pub solver_iterations: usize,
+}
+
+/// The settings for each quality, read from `assets/physics.txt`
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct PhysicsPresets {
+    pub low: PhysicsSettings,
+    pub medium: PhysicsSettings,
+    pub high: PhysicsSettings,
+}
+
+impl Default for PhysicsPresets {
+    fn default() -> Self {
+        Self::from_text(include_str!("../assets/physics.txt"))
+    }
+}
+
+impl PhysicsPresets {
+    /// The physics file next to the game, falling back to the bundled copy if it's missing. The
+    /// web build only has the copy it was built with
+    pub fn load() -> Self {
+        load_config_file(
+            PHYSICS_FILE,
+            include_str!("../assets/physics.txt"),
+            |text| Ok(Self::from_text(text)),
+        )
+    }
+
+    /// Each line is a quality followed by its numbers. Lines that can't be read are skipped,
+    /// leaving that quality as rapier's own defaults without any rock CCD
+    pub fn from_text(text: &str) -> Self {
+        let fallback = PhysicsSettings {
+            substeps: 1,
+            rock_ccd_speed: f32::INFINITY,
+            solver_iterations: 4,
+        };
+        let mut presets = Self {
+            low: fallback,
+            medium: fallback,
+            high: fallback,
+        };
+        for (key, value) in parse_config_lines(text) {
+            let preset = match key {
+                "low" => &mut presets.low,
+                "medium" => &mut presets.medium,
+                "high" => &mut presets.high,
+                _ => {
+                    warn_unknown_config("physics quality", key);
+                    continue;
+                }
+            };
+            let Some([substeps, rock_ccd_speed, solver_iterations]) = parse_floats(value) else {
+                warn!("Couldn't read {key} physics settings {value:?}");
+                continue;
+            };
+            *preset = PhysicsSettings {
+                substeps: (substeps as usize).max(1),
+                rock_ccd_speed,
+                solver_iterations: (solver_iterations as usize).max(1),
+            };
+        }
+        presets
+    }
+
+    pub fn get(&self, quality: PhysicsQuality) -> PhysicsSettings {
+        match quality {
+            PhysicsQuality::Low => self.low,
+            PhysicsQuality::Medium => self.medium,
+            PhysicsQuality::High => self.high,
+        }
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct PhysicsPlugin {
     pub debug: bool,
+    /// Use these instead of the quality picked in the graphics settings
+    pub settings: Option<PhysicsSettings>,
+}
+
+/// Set when the `PhysicsPlugin` was given its own settings, so the graphics settings are left
+/// out of it
+#[derive(Resource, Debug)]
+struct FixedPhysicsSettings;
+
+/// Follows the quality in the graphics settings, unless the plugin was given its own
+fn pick_physics_settings(
+    graphics_settings: Res<GraphicsSettings>,
+    presets: Res<PhysicsPresets>,
+    fixed: Option<Res<FixedPhysicsSettings>>,
+    mut settings: ResMut<PhysicsSettings>,
+) {
+    if fixed.is_some() || !graphics_settings.is_changed() {
+        return;
+    }
+    let picked = presets.get(graphics_settings.physics);
+    if *settings != picked {
+        *settings = picked;
+    }
+}
+
+fn apply_physics_settings(
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut rapier_context: ResMut<RapierContext>,
+    settings: Res<PhysicsSettings>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    debug!("Using physics settings {:?}", *settings);
+    if let TimestepMode::Interpolated { substeps, .. } = &mut rapier_config.timestep_mode {
+        *substeps = settings.substeps;
+    }
+    let parameters = &mut rapier_context.integration_parameters;
+    parameters.max_velocity_iterations = settings.solver_iterations;
 }
 
 fn pause_physics(mut rapier_config: ResMut<RapierConfiguration>) {
@@ -18,6 +283,16 @@ fn resume_physics(mut rapier_config: ResMut<RapierConfiguration>) {
     rapier_config.query_pipeline_active = true;
 }
 
+/// Slow motion slows the simulation down along with the gameplay clock
+fn apply_time_scale(mut rapier_config: ResMut<RapierConfiguration>, game_time: Res<GameTime>) {
+    let TimestepMode::Interpolated { time_scale, .. } = &mut rapier_config.timestep_mode else {
+        return;
+    };
+    if *time_scale != game_time.time_scale() {
+        *time_scale = game_time.time_scale();
+    }
+}
+
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(RapierPhysicsPlugin::<NoUserData>::default());
@@ -26,9 +301,31 @@ impl Plugin for PhysicsPlugin {
         }
         app.insert_resource(RapierConfiguration {
             gravity: Vec2::ZERO,
+            // Step the physics at the same rate as the gameplay systems in `FixedUpdate`,
+            // interpolating transforms in between so rendering stays smooth
+            timestep_mode: TimestepMode::Interpolated {
+                dt: FIXED_TIMESTEP,
+                time_scale: 1.0,
+                substeps: 1,
+            },
             ..Default::default()
-        });
-        app.add_system(pause_physics.in_schedule(OnExit(GameState::InGame)))
-            .add_system(resume_physics.in_schedule(OnEnter(GameState::InGame)));
+        })
+        .insert_resource(FixedTime::new_from_secs(FIXED_TIMESTEP));
+
+        let presets = PhysicsPresets::load();
+        let settings = self
+            .settings
+            .unwrap_or_else(|| presets.get(PhysicsQuality::default()));
+        if self.settings.is_some() {
+            app.insert_resource(FixedPhysicsSettings);
+        }
+        app.insert_resource(settings)
+            .insert_resource(presets)
+            .add_systems((pick_physics_settings, apply_physics_settings).chain())
+            .add_system(pause_physics.in_schedule(OnExit(GameState::InGame)))
+            .add_system(resume_physics.in_schedule(OnEnter(GameState::InGame)))
+            .add_systems(
+                (apply_time_scale, apply_gravity_fields).in_set(OnUpdate(GameState::InGame)),
+            );
     }
 }
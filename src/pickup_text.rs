@@ -0,0 +1,153 @@
+use bevy::{prelude::*, render::view::RenderLayers, text::Text2dBounds};
+
+use crate::{
+    camera::MainCamera,
+    collectible::CollectionEvent,
+    inventory::Inventory,
+    player::LeadPlayer,
+    state::GameState,
+    ui::UIMarker,
+};
+
+/// Enough for a burst of minerals from a big rock. If they're all in use the oldest is reused
+const POOL_SIZE: usize = 24;
+const FONT_SIZE: f32 = 22.0;
+const LIFETIME_SECONDS: f32 = 1.0;
+/// How far the text floats up the screen over its lifetime, in pixels
+const RISE: f32 = 40.0;
+
+/// A "+2 MINERALS" label that floats up from wherever something was picked up.
+/// These are spawned once and reused, since minerals tend to get picked up in bursts
+#[derive(Component, Debug)]
+pub struct PickupText {
+    world_position: Vec3,
+    timer: Timer,
+    active: bool,
+}
+
+impl Default for PickupText {
+    fn default() -> Self {
+        Self {
+            world_position: Vec3::ZERO,
+            timer: Timer::from_seconds(LIFETIME_SECONDS, TimerMode::Once),
+            active: false,
+        }
+    }
+}
+
+fn setup_pickup_text_pool(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    for _ in 0..POOL_SIZE {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_alignment(TextAlignment::Center),
+                text_2d_bounds: Text2dBounds {
+                    size: Vec2::new(200.0, FONT_SIZE),
+                },
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            PickupText::default(),
+            UIMarker,
+            RenderLayers::layer(1),
+        ));
+    }
+}
+
+fn format_amount(amount: f32) -> String {
+    if amount.fract() == 0.0 {
+        format!("{amount:.0}")
+    } else {
+        format!("{amount:.1}")
+    }
+}
+
+fn show_pickup_text(
+    mut reader: EventReader<CollectionEvent>,
+    mut text_query: Query<(&mut PickupText, &mut Text, &mut Visibility)>,
+    inventory_query: Query<&Inventory, LeadPlayer>,
+) {
+    let Ok(inventory) = inventory_query.get_single() else { return; };
+    for ev in reader.iter() {
+        // Take a free label, or the one that's been on screen longest
+        let Some((mut pickup_text, mut text, mut visibility)) = text_query
+            .iter_mut()
+            .min_by(|(a, _, _), (b, _, _)| {
+                a.active.cmp(&b.active).then(
+                    a.timer
+                        .percent_left()
+                        .total_cmp(&b.timer.percent_left()),
+                )
+            })
+        else {
+            warn!("There are no pickup text labels to show");
+            return;
+        };
+
+        let entry = inventory.reagent(ev.reagent);
+        pickup_text.world_position = ev.position;
+        pickup_text.timer.reset();
+        pickup_text.active = true;
+        let section = &mut text.sections[0];
+        section.value.clear();
+        section.value.push('+');
+        section.value.push_str(&format_amount(ev.amount));
+        section.value.push(' ');
+        section.value.push_str(entry.name());
+        section.style.color = entry.color();
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn animate_pickup_text(
+    mut text_query: Query<(&mut PickupText, &mut Text, &mut Transform, &mut Visibility)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    time: Res<Time>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return; };
+    let Some(size) = camera.logical_viewport_size() else { return; };
+    for (mut pickup_text, mut text, mut transform, mut visibility) in &mut text_query {
+        if !pickup_text.active {
+            continue;
+        }
+        pickup_text.timer.tick(time.delta());
+        if pickup_text.timer.finished() {
+            pickup_text.active = false;
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        // Stays over the pickup spot as the camera moves
+        let Some(viewport_pos) =
+            camera.world_to_viewport(camera_transform, pickup_text.world_position) else { continue; };
+        // The UI camera has its origin in the middle of the screen
+        let anchor = viewport_pos - size / 2.0;
+        transform.translation.x = anchor.x;
+        transform.translation.y = anchor.y + RISE * pickup_text.timer.percent();
+        transform.translation.z = 3.0;
+        text.sections[0]
+            .style
+            .color
+            .set_a(pickup_text.timer.percent_left());
+    }
+}
+
+pub struct PickupTextPlugin;
+
+impl Plugin for PickupTextPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup_pickup_text_pool.in_schedule(OnExit(GameState::Intro)))
+            .add_systems(
+                (show_pickup_text, animate_pickup_text)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
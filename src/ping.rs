@@ -0,0 +1,326 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::{
+    prelude::*,
+    render::view::RenderLayers,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    window::PrimaryWindow,
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    camera::MainCamera,
+    game_time::GameTime,
+    input::Action,
+    player::LeadPlayer,
+    sound::SoundEvent,
+    state::GameState,
+    ui::{CustomUICamera, UIMarker},
+};
+
+/// The oldest ping is taken down to make room for a new one past this
+const MAX_PINGS: usize = 3;
+/// Pinging this close to an existing ping takes it down instead
+const REMOVE_RADIUS: f32 = 3.0;
+const BEACON_RADIUS: f32 = 1.2;
+const PULSES_PER_SECOND: f32 = 1.0;
+const PING_COLOR: Color = Color::rgb(0.2, 1.0, 0.9);
+const ORIGIN_COLOR: Color = Color::rgb(1.0, 0.85, 0.5);
+/// The origin beacon is a bigger ring than the pings, and it doesn't pulse
+const ORIGIN_SCALE: f32 = 2.5;
+const CHEVRON_SIZE: f32 = 12.0;
+/// How far in from the edge of the screen the chevrons sit
+const EDGE_PADDING: f32 = 40.0;
+
+/// A marker the player dropped in the world with `Action::PingWaypoint`
+#[derive(Component, Debug)]
+struct Ping {
+    /// When it was placed, in game time, so the oldest can be found
+    placed: f32,
+}
+
+/// Points from the edge of the screen towards a ping that's out of view
+#[derive(Component, Debug)]
+struct PingChevron;
+
+/// Marks where the run started, so there's always somewhere to find the way back to
+#[derive(Component, Debug)]
+struct OriginBeacon;
+
+/// Like the ping chevrons, for the origin beacon
+#[derive(Component, Debug)]
+struct OriginChevron;
+
+#[derive(Resource, Debug, Default)]
+struct PingAppearance {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    origin_material: Handle<StandardMaterial>,
+}
+
+fn setup_ping_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color: PING_COLOR,
+        emissive: PING_COLOR * 2.0,
+        unlit: true,
+        ..Default::default()
+    });
+    let origin_material = materials.add(StandardMaterial {
+        base_color: ORIGIN_COLOR,
+        emissive: ORIGIN_COLOR * 2.0,
+        unlit: true,
+        ..Default::default()
+    });
+    let mesh = meshes.add(
+        shape::Torus {
+            radius: BEACON_RADIUS,
+            ring_radius: 0.08,
+            ..Default::default()
+        }
+        .into(),
+    );
+    commands.insert_resource(PingAppearance {
+        mesh,
+        material,
+        origin_material,
+    });
+}
+
+fn spawn_origin_beacon(mut commands: Commands, ping_appearance: Res<PingAppearance>) {
+    commands.spawn((
+        PbrBundle {
+            mesh: ping_appearance.mesh.clone(),
+            material: ping_appearance.origin_material.clone(),
+            transform: Transform::from_rotation(Quat::from_rotation_x(FRAC_PI_2))
+                .with_scale(Vec3::splat(ORIGIN_SCALE)),
+            ..Default::default()
+        },
+        OriginBeacon,
+    ));
+}
+
+fn setup_ping_chevrons(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mesh = Mesh2dHandle(meshes.add(shape::RegularPolygon::new(CHEVRON_SIZE, 3).into()));
+    let material = materials.add(ColorMaterial::from(PING_COLOR));
+    for _ in 0..MAX_PINGS {
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, 5.0),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            PingChevron,
+            UIMarker,
+            RenderLayers::layer(1),
+        ));
+    }
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh,
+            material: materials.add(ColorMaterial::from(ORIGIN_COLOR)),
+            transform: Transform::from_xyz(0.0, 0.0, 5.0),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        OriginChevron,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+/// Where a chevron pointing off screen in `direction` sits, and which way it's turned. The
+/// triangle points up to begin with
+fn edge_placement(direction: Vec2, half_size: Vec2) -> (Vec2, Quat) {
+    // Push out along the direction until it meets the edge of the screen
+    let to_edge = f32::min(
+        half_size.x / direction.x.abs().max(0.001),
+        half_size.y / direction.y.abs().max(0.001),
+    );
+    let rotation = Quat::from_rotation_z(direction.y.atan2(direction.x) - FRAC_PI_2);
+    (direction * to_edge, rotation)
+}
+
+/// Drops a ping where the cursor is, or takes down the one that's already there
+#[allow(clippy::too_many_arguments)]
+fn ping_waypoints(
+    mut commands: Commands,
+    player_query: Query<&ActionState<Action>, LeadPlayer>,
+    ping_query: Query<(Entity, &Ping, &Transform)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    ping_appearance: Res<PingAppearance>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    game_time: Res<GameTime>,
+) {
+    let Ok(action_state) = player_query.get_single() else { return; };
+    if !action_state.just_pressed(Action::PingWaypoint) {
+        return;
+    }
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return; };
+    let Some(cursor) = window_query
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    let Some(position) = camera.viewport_to_world_2d(camera_transform, cursor) else { return; };
+    sound_event_writer.send(SoundEvent::ButtonClick);
+
+    let existing = ping_query.iter().find(|(_, _, transform)| {
+        transform.translation.truncate().distance(position) < REMOVE_RADIUS
+    });
+    if let Some((entity, _, _)) = existing {
+        commands.entity(entity).despawn_recursive();
+        return;
+    }
+    if ping_query.iter().count() >= MAX_PINGS {
+        if let Some((oldest, _, _)) = ping_query
+            .iter()
+            .min_by(|(_, a, _), (_, b, _)| a.placed.total_cmp(&b.placed))
+        {
+            commands.entity(oldest).despawn_recursive();
+        }
+    }
+    commands.spawn((
+        PbrBundle {
+            mesh: ping_appearance.mesh.clone(),
+            material: ping_appearance.material.clone(),
+            // The torus lies flat to begin with, this turns it to face the camera
+            transform: Transform::from_translation(position.extend(0.0))
+                .with_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+            ..Default::default()
+        },
+        Ping {
+            placed: game_time.elapsed_seconds(),
+        },
+    ));
+}
+
+/// Ripples outwards so it stands out against the rocks
+fn pulse_pings(mut query: Query<&mut Transform, With<Ping>>, time: Res<Time>) {
+    let pulse = (time.elapsed_seconds() * PULSES_PER_SECOND).fract();
+    let scale = 0.6 + 0.6 * pulse;
+    for mut transform in &mut query {
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_ping_chevrons(
+    mut chevron_query: Query<
+        (&mut Transform, &mut Visibility),
+        (With<PingChevron>, Without<CustomUICamera>, Without<Ping>),
+    >,
+    ping_query: Query<&Transform, (With<Ping>, Without<PingChevron>)>,
+    player_query: Query<&Transform, (LeadPlayer, Without<Ping>, Without<PingChevron>)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return; };
+    let Some(size) = ui_camera
+        .get_single()
+        .ok()
+        .and_then(|camera| camera.logical_viewport_size())
+    else {
+        return;
+    };
+    let half_size = size / 2.0 - EDGE_PADDING;
+    let bob = 1.0 + 0.1 * (time.elapsed_seconds() * PULSES_PER_SECOND * TAU).sin();
+
+    // Only the pings out of view need pointing at
+    let mut directions = ping_query.iter().filter_map(|transform| {
+        if let Some(pos) = camera.world_to_viewport(camera_transform, transform.translation) {
+            if pos.x >= 0.0 && pos.y >= 0.0 && pos.x <= size.x && pos.y <= size.y {
+                return None;
+            }
+        }
+        let offset = transform.translation - player_transform.translation;
+        Some(offset.truncate().normalize_or_zero())
+    });
+    for (mut transform, mut visibility) in &mut chevron_query {
+        let Some(direction) = directions.next() else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Visible;
+        let (position, rotation) = edge_placement(direction, half_size);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        transform.rotation = rotation;
+        transform.scale = Vec3::splat(bob);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_origin_chevron(
+    mut chevron_query: Query<
+        (&mut Transform, &mut Visibility),
+        (With<OriginChevron>, Without<CustomUICamera>),
+    >,
+    player_query: Query<&Transform, (LeadPlayer, Without<OriginChevron>)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+) {
+    let Ok((mut transform, mut visibility)) = chevron_query.get_single_mut() else { return; };
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return; };
+    let Some(size) = ui_camera
+        .get_single()
+        .ok()
+        .and_then(|camera| camera.logical_viewport_size())
+    else {
+        return;
+    };
+    if let Some(pos) = camera.world_to_viewport(camera_transform, Vec3::ZERO) {
+        if pos.x >= 0.0 && pos.y >= 0.0 && pos.x <= size.x && pos.y <= size.y {
+            *visibility = Visibility::Hidden;
+            return;
+        }
+    }
+    *visibility = Visibility::Visible;
+    let direction = -player_transform.translation.truncate().normalize_or_zero();
+    let (position, rotation) = edge_placement(direction, size / 2.0 - EDGE_PADDING);
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+    transform.rotation = rotation;
+}
+
+#[allow(clippy::type_complexity)]
+fn cleanup_pings(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<Ping>, With<OriginBeacon>)>>,
+) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct PingPlugin;
+
+impl Plugin for PingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_ping_appearance)
+            .add_systems(
+                (setup_ping_chevrons, spawn_origin_beacon).in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_systems(
+                (ping_waypoints, pulse_pings, update_ping_chevrons, update_origin_chevron)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(cleanup_pings.in_schedule(OnEnter(GameState::Outro)));
+    }
+}
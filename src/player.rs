@@ -10,10 +10,20 @@ use leafwing_input_manager::prelude::*;
 
 use crate::{
     camera::{FocusPoint, MainCamera},
+    consumable::Consumables,
+    controller::PlayerController,
+    game_time::GameTime,
     heat::Heat,
+    hull::{Faction, Hull},
     inventory::Inventory,
+    lod::{CameraLod, LodLevel},
+    mine::MineLayer,
+    physics::PLAYER_COLLISION_GROUPS,
+    replay::{is_not_playing_back, ReplaySystem},
+    save_data::{SaveData, ShipPart},
     shield::ShieldEmitter,
     state::GameState,
+    status_effect::{StatusEffects, StatusKind},
     weapon::{CargoDumper, MainGun},
 };
 
@@ -24,16 +34,22 @@ pub struct PlayerBundle {
     pub global_transform: GlobalTransform,
     pub rigid_body: RigidBody,
     pub collider: Collider,
+    pub collision_groups: CollisionGroups,
     pub locked_axes: LockedAxes,
+    pub transform_interpolation: TransformInterpolation,
     pub velocity: Velocity,
     pub external_impulse: ExternalImpulse,
     pub active_events: ActiveEvents,
     pub focus_point: FocusPoint,
     pub main_gun: MainGun,
     pub heat: Heat,
+    pub hull: Hull,
+    pub faction: Faction,
     pub inventory: Inventory,
+    pub consumables: Consumables,
     pub shield_emitter: ShieldEmitter,
     pub cargo_dumper: CargoDumper,
+    pub mine_layer: MineLayer,
     pub visibility: Visibility,
     pub computed_visibility: ComputedVisibility,
     pub action_state: ActionState<crate::input::Action>,
@@ -48,16 +64,22 @@ impl Default for PlayerBundle {
             global_transform: GlobalTransform::default(),
             rigid_body: RigidBody::Dynamic,
             collider: Collider::ball(1.0),
+            collision_groups: PLAYER_COLLISION_GROUPS,
             locked_axes: LockedAxes::ROTATION_LOCKED,
+            transform_interpolation: TransformInterpolation::default(),
             velocity: Velocity::default(),
             external_impulse: ExternalImpulse::default(),
             active_events: ActiveEvents::COLLISION_EVENTS,
             focus_point: FocusPoint::default(),
             main_gun: MainGun::default(),
             heat: Heat::default(),
+            hull: Hull::default(),
+            faction: Faction::Player,
             shield_emitter: ShieldEmitter::default(),
             cargo_dumper: CargoDumper::default(),
+            mine_layer: MineLayer::default(),
             inventory: Inventory::default(),
+            consumables: Consumables::default(),
             visibility: Visibility::Visible,
             computed_visibility: ComputedVisibility::default(),
             action_state: ActionState::default(),
@@ -74,6 +96,14 @@ pub struct Player {
     pub rotation_speed: f32,
 }
 
+/// The gamepad player in co-op. Most of the world only reacts to the first player, this one
+/// flies, aims and shoots alongside them
+#[derive(Component, Debug, Default)]
+pub struct SecondPlayer;
+
+/// Filters down to the first player, for everything that only deals with one
+pub type LeadPlayer = (With<Player>, Without<SecondPlayer>);
+
 impl Default for Player {
     fn default() -> Self {
         Self {
@@ -86,14 +116,27 @@ impl Default for Player {
 }
 
 fn rotate_player(
-    mut query: Query<(&mut Player, &mut FocusPoint, &GlobalTransform)>,
+    mut query: Query<
+        (
+            &mut Player,
+            &mut FocusPoint,
+            &GlobalTransform,
+            Option<&PlayerController>,
+        ),
+        Without<SecondPlayer>,
+    >,
     camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
     primary_window_query: Query<&Window, With<PrimaryWindow>>,
     other_window_query: Query<&Window, Without<PrimaryWindow>>,
-    time: Res<Time>,
+    game_time: Res<GameTime>,
 ) {
     let Ok((main_camera, camera_transform)) = camera_query.get_single() else { return };
-    let Ok((mut player, mut focus_point, player_transform)) = query.get_single_mut() else { return };
+    let Ok((mut player, mut focus_point, player_transform, controller)) =
+        query.get_single_mut() else { return };
+    // Scripts do their own aiming
+    if controller.is_some_and(PlayerController::is_scripted) {
+        return;
+    }
 
     let Some(window) = (match main_camera.target {
         RenderTarget::Window(window_ref) => match window_ref {
@@ -123,18 +166,38 @@ fn rotate_player(
         Vec2::from_angle(desired_rotation),
     );
     let rotation_amount = f32::abs(diff) * player.rotation_speed;
-    player.facing += diff.signum() * rotation_amount * time.delta_seconds();
+    // Aiming keeps up in slow motion
+    player.facing += diff.signum() * rotation_amount * game_time.real_delta_seconds();
+}
+
+/// The second player has no cursor, so they aim with the right stick. Letting go of the stick
+/// keeps them facing the same way
+fn aim_second_player(
+    mut query: Query<(&mut Player, &ActionState<crate::input::Action>), With<SecondPlayer>>,
+    game_time: Res<GameTime>,
+) {
+    for (mut player, action_state) in &mut query {
+        let Some(axis_pair) = action_state.axis_pair(crate::input::Action::Aim) else { continue; };
+        let aim = axis_pair.xy();
+        if aim.length_squared() < 0.01 {
+            continue;
+        }
+        let diff = Vec2::angle_between(Vec2::from_angle(player.facing), aim);
+        let rotation_amount = f32::abs(diff) * player.rotation_speed;
+        player.facing += diff.signum() * rotation_amount * game_time.real_delta_seconds();
+    }
 }
 
-fn player_friction(mut query: Query<(&Player, &Velocity, &mut ExternalImpulse)>, time: Res<Time>) {
-    if let Ok((_player, velocity, mut ext_impulse)) = query.get_single_mut() {
+fn player_friction(
+    mut query: Query<(&Player, &Velocity, &mut ExternalImpulse)>,
+    fixed_time: Res<FixedTime>,
+) {
+    for (_player, velocity, mut ext_impulse) in &mut query {
         const MAX_DECELERATION: f32 = 2.0; // TODO: make this configuarable
         let speed = velocity.linvel.length();
         let dir = velocity.linvel.normalize_or_zero();
         let deceleration = f32::min(MAX_DECELERATION, speed);
-        ext_impulse.impulse += -dir * deceleration * time.delta_seconds();
-    } else {
-        info!("get_single_mut didn't find exactly 1!")
+        ext_impulse.impulse += -dir * deceleration * fixed_time.period.as_secs_f32();
     }
 }
 
@@ -142,6 +205,32 @@ pub struct PlayerMoveEvent {
     pub position: Vec3,
 }
 
+/// The direction the player is asking to move in, with a length of 0 or 1
+pub fn movement_input(action_state: &ActionState<crate::input::Action>) -> Vec2 {
+    let mut desired_thrust = Vec2::ZERO;
+    desired_thrust += Vec2::Y
+        * action_state
+            .value(crate::input::Action::MoveUp)
+            .clamp(0.0, 1.0);
+    desired_thrust += Vec2::NEG_Y
+        * action_state
+            .value(crate::input::Action::MoveDown)
+            .clamp(0.0, 1.0);
+    desired_thrust += Vec2::X
+        * action_state
+            .value(crate::input::Action::MoveRight)
+            .clamp(0.0, 1.0);
+    desired_thrust += Vec2::NEG_X
+        * action_state
+            .value(crate::input::Action::MoveLeft)
+            .clamp(0.0, 1.0);
+    // Only the second player's gamepad has a stick bound to this
+    if let Some(axis_pair) = action_state.axis_pair(crate::input::Action::Move) {
+        desired_thrust += axis_pair.xy();
+    }
+    desired_thrust.normalize_or_zero()
+}
+
 fn move_player(
     mut query: Query<(
         &Player,
@@ -149,36 +238,22 @@ fn move_player(
         &mut ExternalImpulse,
         &ActionState<crate::input::Action>,
         &Transform,
+        Option<&StatusEffects>,
     )>,
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
     mut writer: EventWriter<PlayerMoveEvent>,
 ) {
-    for (player, velocity, mut ext_impulse, action_state, transform) in &mut query {
-        let mut desired_thrust = Vec2::ZERO;
-        desired_thrust += Vec2::Y
-            * action_state
-                .value(crate::input::Action::MoveUp)
-                .clamp(0.0, 1.0);
-        desired_thrust += Vec2::NEG_Y
-            * action_state
-                .value(crate::input::Action::MoveDown)
-                .clamp(0.0, 1.0);
-        desired_thrust += Vec2::X
-            * action_state
-                .value(crate::input::Action::MoveRight)
-                .clamp(0.0, 1.0);
-        desired_thrust += Vec2::NEG_X
-            * action_state
-                .value(crate::input::Action::MoveLeft)
-                .clamp(0.0, 1.0);
-        desired_thrust = desired_thrust.normalize_or_zero();
+    for (player, velocity, mut ext_impulse, action_state, transform, effects) in &mut query {
+        let desired_thrust = movement_input(action_state);
 
         // let direction = Vec2::new(f32::cos(player.facing), f32::sin(player.facing));
-        let desired_velocity = desired_thrust * player.max_speed;
+        let slowed = effects.map_or(0.0, |effects| effects.strength(StatusKind::Slowed));
+        let desired_velocity = desired_thrust * player.max_speed * (1.0 - slowed);
 
         let accel_needed = desired_velocity - velocity.linvel;
-        ext_impulse.impulse +=
-            accel_needed.normalize_or_zero() * player.acceleration * time.delta_seconds();
+        ext_impulse.impulse += accel_needed.normalize_or_zero()
+            * player.acceleration
+            * fixed_time.period.as_secs_f32();
 
         if desired_thrust.length_squared() > 0.0 {
             writer.send(PlayerMoveEvent {
@@ -203,13 +278,16 @@ impl Default for PlayerModel {
     }
 }
 
+/// The meshes and materials every player model shares. The body and the lights are coloured to
+/// the player's liking, so each model gets its own materials for those, see `PlayerModelMaterials`
 #[derive(Resource, Debug, Default)]
 struct PlayerModelHandles {
     pub body_mesh: Handle<Mesh>,
-    pub body_mat: Handle<StandardMaterial>,
 
     pub light_mesh: Handle<Mesh>,
-    pub light_mat: Handle<StandardMaterial>,
+
+    pub scorch_mesh: Handle<Mesh>,
+    pub scorch_mat: Handle<StandardMaterial>,
 }
 
 fn setup_player_model_handles(
@@ -217,12 +295,6 @@ fn setup_player_model_handles(
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    let body_mat = materials.add(StandardMaterial {
-        base_color: Color::rgb(0.2, 0.2, 0.2),
-        metallic: 0.99,
-        perceptual_roughness: 0.2,
-        ..Default::default()
-    });
     let body_mesh = meshes.add(
         shape::UVSphere {
             radius: 1.0,
@@ -239,76 +311,174 @@ fn setup_player_model_handles(
         }
         .into(),
     );
-    let light_mat = materials.add(StandardMaterial {
-        base_color: Color::RED,
+
+    let scorch_mesh = meshes.add(
+        shape::UVSphere {
+            radius: 0.3,
+            sectors: 12,
+            stacks: 8,
+        }
+        .into(),
+    );
+    let scorch_mat = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.05, 0.04, 0.03),
+        perceptual_roughness: 1.0,
         ..Default::default()
     });
 
     commands.insert_resource(PlayerModelHandles {
-        body_mat,
         body_mesh,
-        light_mat,
         light_mesh,
+        scorch_mesh,
+        scorch_mat,
     });
 }
 
 #[derive(Component, Debug, Default)]
-struct PlayerModelLight;
+struct PlayerModelLight {
+    index: u32,
+}
+
+/// A scorch mark on the hull, shown once the player has taken enough damage
+#[derive(Component, Debug, Default)]
+struct ScorchMark {
+    /// The fraction of the hull that has to be lost before this one shows
+    damage_threshold: f32,
+}
+
+/// A player model's own body and light materials, on the player entity. The colours are the
+/// ones picked in the main menu, before heat and damage have had their way with them
+#[derive(Component, Debug)]
+struct PlayerModelMaterials {
+    body: Handle<StandardMaterial>,
+    body_color: Color,
+    light: Handle<StandardMaterial>,
+    light_color: Color,
+}
 
 fn setup_player_model(
     mut commands: Commands,
     query: Query<Entity, Added<Player>>,
     handles: Res<PlayerModelHandles>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    save_data: Res<SaveData>,
 ) {
-    let Ok(player) = query.get_single() else { return; };
-    debug!("Player component added to entity {player:?}");
-
-    debug!("Adding base model to player");
-    commands.entity(player).with_children(|parent| {
-        parent
-            .spawn((
-                PbrBundle {
-                    mesh: handles.body_mesh.clone(),
-                    material: handles.body_mat.clone(),
-                    ..Default::default()
-                },
-                PlayerModel::default(),
-            ))
-            .with_children(|parent| {
-                const NUM_LIGHTS: u32 = 50;
-                let phi: f32 = PI * (f32::sqrt(5.0) - 1.0);
-
-                for i in 0..NUM_LIGHTS {
-                    let y = 1.0 - (i as f32 / (NUM_LIGHTS - 1) as f32) * 2.0;
-                    let radius = f32::sqrt(1.0 - y * y);
-
-                    let theta = phi * i as f32;
-
-                    let x = f32::cos(theta) * radius;
-                    let z = f32::sin(theta) * radius;
-
-                    debug!("Adding light to player base model");
-                    parent.spawn((
-                        PbrBundle {
-                            mesh: handles.light_mesh.clone(),
-                            material: handles.light_mat.clone(),
-                            transform: Transform::from_xyz(x, y, z),
-                            ..Default::default()
-                        },
-                        PlayerModelLight,
-                    ));
-                }
-            });
-    });
+    for player in &query {
+        debug!("Player component added to entity {player:?}");
+
+        let body_color = save_data.ship_look.color(ShipPart::Body);
+        let light_color = save_data.ship_look.color(ShipPart::Lights);
+        let model_materials = PlayerModelMaterials {
+            body: materials.add(StandardMaterial {
+                base_color: body_color,
+                metallic: 0.99,
+                perceptual_roughness: 0.2,
+                ..Default::default()
+            }),
+            body_color,
+            light: materials.add(StandardMaterial {
+                base_color: light_color,
+                ..Default::default()
+            }),
+            light_color,
+        };
+
+        debug!("Adding base model to player");
+        commands.entity(player).with_children(|parent| {
+            parent
+                .spawn((
+                    PbrBundle {
+                        mesh: handles.body_mesh.clone(),
+                        material: model_materials.body.clone(),
+                        ..Default::default()
+                    },
+                    PlayerModel::default(),
+                ))
+                .with_children(|parent| {
+                    const NUM_LIGHTS: u32 = 50;
+                    let phi: f32 = PI * (f32::sqrt(5.0) - 1.0);
+
+                    for i in 0..NUM_LIGHTS {
+                        let y = 1.0 - (i as f32 / (NUM_LIGHTS - 1) as f32) * 2.0;
+                        let radius = f32::sqrt(1.0 - y * y);
+
+                        let theta = phi * i as f32;
+
+                        let x = f32::cos(theta) * radius;
+                        let z = f32::sin(theta) * radius;
+
+                        debug!("Adding light to player base model");
+                        parent.spawn((
+                            PbrBundle {
+                                mesh: handles.light_mesh.clone(),
+                                material: model_materials.light.clone(),
+                                transform: Transform::from_xyz(x, y, z),
+                                ..Default::default()
+                            },
+                            PlayerModelLight { index: i },
+                        ));
+                    }
+
+                    const NUM_SCORCH_MARKS: u32 = 12;
+                    for i in 0..NUM_SCORCH_MARKS {
+                        // Spread out the same way as the lights, offset so they don't line up
+                        let y = 1.0 - ((i as f32 + 0.5) / NUM_SCORCH_MARKS as f32) * 2.0;
+                        let radius = f32::sqrt(1.0 - y * y);
+                        let theta = phi * i as f32 + 1.0;
+                        let normal =
+                            Vec3::new(f32::cos(theta) * radius, y, f32::sin(theta) * radius);
+                        // Stepping through in a jumbled order so the marks don't appear top to
+                        // bottom
+                        let order = (i * 5) % NUM_SCORCH_MARKS;
+
+                        parent.spawn((
+                            PbrBundle {
+                                mesh: handles.scorch_mesh.clone(),
+                                material: handles.scorch_mat.clone(),
+                                // Flattened against the surface of the body
+                                transform: Transform::from_translation(normal * 0.95)
+                                    .with_rotation(Quat::from_rotation_arc(Vec3::Z, normal))
+                                    .with_scale(Vec3::new(1.0, 1.0, 0.2)),
+                                visibility: Visibility::Hidden,
+                                ..Default::default()
+                            },
+                            ScorchMark {
+                                damage_threshold: (order + 1) as f32
+                                    / (NUM_SCORCH_MARKS + 1) as f32,
+                            },
+                        ));
+                    }
+                });
+        });
+        commands.entity(player).insert(model_materials);
+    }
 }
 
-fn rotate_player_model(mut query: Query<(&PlayerModel, &mut Transform)>, time: Res<Time>) {
+/// When zoomed far out only every few lights are shown, since they're too small to make out anyway
+fn player_model_light_lod(
+    mut query: Query<(&PlayerModelLight, &mut Visibility)>,
+    new_lights: Query<(), Added<PlayerModelLight>>,
+    camera_lod: Res<CameraLod>,
+) {
+    if !camera_lod.is_changed() && new_lights.is_empty() {
+        return;
+    }
+    const LOW_DETAIL_STRIDE: u32 = 5;
+    for (light, mut visibility) in &mut query {
+        *visibility = match camera_lod.0 {
+            LodLevel::Low if light.index % LOW_DETAIL_STRIDE != 0 => Visibility::Hidden,
+            _ => Visibility::Inherited,
+        };
+    }
+}
+
+fn rotate_player_model(mut query: Query<(&PlayerModel, &mut Transform)>, game_time: Res<GameTime>) {
     for (player_model, mut transform) in &mut query {
         let rot = Quat::from_euler(
             EulerRot::YZX,
-            player_model.current_angvel.y * time.delta_seconds(),
-            player_model.current_angvel.z * time.delta_seconds(),
-            player_model.current_angvel.x * time.delta_seconds(),
+            player_model.current_angvel.y * game_time.delta_seconds(),
+            player_model.current_angvel.z * game_time.delta_seconds(),
+            player_model.current_angvel.x * game_time.delta_seconds(),
         );
 
         transform.rotate(rot);
@@ -316,25 +486,53 @@ fn rotate_player_model(mut query: Query<(&PlayerModel, &mut Transform)>, time: R
 }
 
 fn player_model_heat_effect(
-    heat_query: Query<&Heat, Without<PlayerModel>>,
-    mut model_query: Query<&mut PlayerModel>,
-    handles: Res<PlayerModelHandles>,
+    heat_query: Query<(&Heat, &PlayerModelMaterials), Without<PlayerModel>>,
+    mut model_query: Query<(&Parent, &mut PlayerModel)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    let Ok(heat) = heat_query.get_single() else { return; };
-    let Ok(mut player_model) = model_query.get_single_mut() else { return; };
-
-    const BASE_COLOR: Color = Color::GRAY;
     const HOT_COLOR: Color = Color::rgb(15.0, 5.0, 1.0);
 
     const ROTATION_FACTOR: f32 = 5.0;
 
-    let Some(mut light_mat) = materials.get_mut(&handles.light_mat) else { return; };
-    let t = heat.fraction();
+    // Each model spins up and glows with its own ship's heat
+    for (parent, mut player_model) in &mut model_query {
+        let Ok((heat, model_materials)) = heat_query.get(parent.get()) else { continue; };
+        let t = heat.fraction();
+        player_model.current_angvel = player_model.base_angvel * (1.0 + t * ROTATION_FACTOR);
 
-    light_mat.base_color = BASE_COLOR * (1.0 - t) + HOT_COLOR * t;
+        let Some(light_mat) = materials.get_mut(&model_materials.light) else { continue; };
+        light_mat.base_color = model_materials.light_color * (1.0 - t) + HOT_COLOR * t;
+    }
+}
 
-    player_model.current_angvel = player_model.base_angvel * (1.0 + t * ROTATION_FACTOR);
+/// The hull gets darker and more scorched as it takes damage, and cleans up again when repaired
+fn player_model_damage_wear(
+    hull_query: Query<Ref<Hull>, LeadPlayer>,
+    mut scorch_query: Query<(&ScorchMark, &mut Visibility)>,
+    new_marks: Query<(), Added<ScorchMark>>,
+    materials_query: Query<&PlayerModelMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Ok(hull) = hull_query.get_single() else { return; };
+    if !hull.is_changed() && new_marks.is_empty() {
+        return;
+    }
+    let damage = 1.0 - hull.fraction();
+
+    for (scorch_mark, mut visibility) in &mut scorch_query {
+        *visibility = if damage >= scorch_mark.damage_threshold {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    const WORN_COLOR: Color = Color::rgb(0.09, 0.07, 0.06);
+    for model_materials in &materials_query {
+        let Some(body_mat) = materials.get_mut(&model_materials.body) else { continue; };
+        body_mat.base_color = model_materials.body_color * (1.0 - damage) + WORN_COLOR * damage;
+        body_mat.perceptual_roughness = 0.2 + 0.5 * damage;
+    }
 }
 
 pub struct PlayerPlugin;
@@ -343,16 +541,26 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<PlayerMoveEvent>()
             .add_startup_system(setup_player_model_handles)
+            .add_system(
+                rotate_player
+                    .run_if(is_not_playing_back)
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(aim_second_player.in_set(OnUpdate(GameState::InGame)))
             .add_systems(
-                (rotate_player, player_friction, move_player)
+                (player_friction, move_player)
                     .chain()
-                    .in_set(OnUpdate(GameState::InGame)),
+                    .after(ReplaySystem)
+                    .distributive_run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
             .add_systems(
                 (
                     setup_player_model,
+                    player_model_light_lod,
                     rotate_player_model,
                     player_model_heat_effect,
+                    player_model_damage_wear,
                 )
                     .in_set(OnUpdate(GameState::InGame)),
             );
@@ -0,0 +1,226 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    heat::Heat,
+    hull::Hull,
+    inventory::Inventory,
+    menu::MenuEvent,
+    player::Player,
+    rock::{spawn_rock, Rock},
+    shield::ShieldEmitter,
+    state::GameState,
+    ui::EnabledControls,
+    ultimate::UltimateCharge,
+    util::cosmetic::random_range,
+    weapon::{CargoDumper, MainGun},
+};
+
+/// Stationary targets sit in a ring this far from the middle of the arena
+const STATIONARY_RING: f32 = 12.0;
+const STATIONARY_TARGETS: usize = 8;
+/// Moving targets circle around the middle this far out
+const MOVING_RING: f32 = 22.0;
+const MOVING_TARGETS: usize = 6;
+const MOVING_TARGET_SPEED: f32 = 6.0;
+
+/// Set from the main menu. The run plays out as usual, except that it's a small arena of target
+/// rocks, every tool is unlocked, the reagents never run out and the stages never move on
+#[derive(Resource, Debug, Default)]
+pub struct Practice {
+    /// Whether the arena has been set out yet. That has to wait until the player has spawned
+    set_up: bool,
+}
+
+/// A rock set out for target practice. Moving ones go round in a circle
+#[derive(Component, Debug)]
+pub struct PracticeTarget {
+    anchor: Vec2,
+    moving: bool,
+}
+
+fn start_practice(
+    mut commands: Commands,
+    mut reader: EventReader<MenuEvent>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for ev in reader.iter() {
+        if let MenuEvent::Practice = ev {
+            if current_state.0 == GameState::MainMenu {
+                commands.insert_resource(Practice::default());
+                next_state.set(GameState::Intro);
+            }
+        }
+    }
+}
+
+/// The story doesn't apply to practice
+fn skip_intro(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::InGame);
+}
+
+fn spawn_targets(commands: &mut Commands) {
+    let rings = [
+        (STATIONARY_RING, STATIONARY_TARGETS, false),
+        (MOVING_RING, MOVING_TARGETS, true),
+    ];
+    for (radius, count, moving) in rings {
+        for i in 0..count {
+            let angle = TAU * i as f32 / count as f32;
+            let anchor = Vec2::from_angle(angle) * radius;
+            let transform = Transform::from_xyz(anchor.x, anchor.y, 3.0);
+            let angvel = Vec3::new(
+                random_range(-PI, PI),
+                random_range(-PI, PI),
+                random_range(-PI, PI),
+            );
+            let rock = spawn_rock(commands, transform, Velocity::zero(), angvel);
+            commands
+                .entity(rock)
+                .insert(PracticeTarget { anchor, moving });
+        }
+    }
+}
+
+/// Runs once the player exists, since it's set up by commands when leaving the intro
+fn setup_practice(
+    mut commands: Commands,
+    mut practice: ResMut<Practice>,
+    mut player_query: Query<
+        (
+            &mut Heat,
+            &mut MainGun,
+            &mut ShieldEmitter,
+            &mut CargoDumper,
+        ),
+        With<Player>,
+    >,
+    mut enabled_controls: ResMut<EnabledControls>,
+) {
+    if practice.set_up {
+        return;
+    }
+    practice.set_up = true;
+    spawn_targets(&mut commands);
+    debug!("Unlocking everything for practice");
+    for (mut heat, mut main_gun, mut shield_emitter, mut cargo_dumper) in &mut player_query {
+        heat.set_enabled(true);
+        heat.set_threshold_visible(true);
+        main_gun.enabled = true;
+        main_gun.swarm_unlocked = true;
+        shield_emitter.enabled = true;
+        shield_emitter.arc_unlocked = true;
+        cargo_dumper.enabled = true;
+    }
+    *enabled_controls = EnabledControls::all();
+}
+
+fn refill_reagents(
+    mut player_query: Query<&mut Inventory, With<Player>>,
+    mut ultimate_charge: ResMut<UltimateCharge>,
+) {
+    for mut inventory in &mut player_query {
+        let missing: Vec<_> = inventory
+            .reagents()
+            .map(|(reagent, entry)| (reagent, entry.limit() - entry.current()))
+            .filter(|(_, missing)| *missing > 0.0)
+            .collect();
+        for (reagent, missing) in missing {
+            inventory.reagent_mut(reagent).add(missing);
+        }
+    }
+    ultimate_charge.fill();
+}
+
+/// Keeps the stationary targets where they were put and the moving ones on their circle,
+/// however they get knocked about
+fn move_targets(mut target_query: Query<(&PracticeTarget, &Transform, &mut Velocity)>) {
+    for (target, transform, mut velocity) in &mut target_query {
+        if !target.moving {
+            velocity.linvel = Vec2::ZERO;
+            continue;
+        }
+        let offset = transform.translation.truncate();
+        let tangent = offset.perp().normalize_or_zero();
+        // Steer back onto the circle if it's been pushed off
+        let correction = offset.normalize_or_zero() * (target.anchor.length() - offset.length());
+        velocity.linvel = tangent * MOVING_TARGET_SPEED + correction;
+    }
+}
+
+fn handle_practice_menu_events(
+    mut commands: Commands,
+    mut reader: EventReader<MenuEvent>,
+    target_query: Query<Entity, (With<PracticeTarget>, With<Rock>)>,
+    mut player_query: Query<(&mut Transform, &mut Velocity, &mut Hull), With<Player>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for ev in reader.iter() {
+        match ev {
+            MenuEvent::ResetPractice => {
+                debug!("Resetting the practice targets");
+                for entity in &target_query {
+                    commands.entity(entity).despawn_recursive();
+                }
+                spawn_targets(&mut commands);
+                for (mut transform, mut velocity, mut hull) in &mut player_query {
+                    transform.translation.x = 0.0;
+                    transform.translation.y = 0.0;
+                    *velocity = Velocity::zero();
+                    let damage = hull.limit() - hull.current();
+                    hull.repair(damage);
+                }
+                next_state.set(GameState::InGame);
+            }
+            // The outro is where everything from the run gets cleaned up
+            MenuEvent::LeavePractice => next_state.set(GameState::Outro),
+            _ => (),
+        }
+    }
+}
+
+/// Goes straight back to the main menu instead of showing the ending
+fn leave_outro(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::MainMenu);
+}
+
+fn end_practice(mut commands: Commands) {
+    commands.remove_resource::<Practice>();
+}
+
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(start_practice.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(
+                skip_intro
+                    .run_if(resource_exists::<Practice>())
+                    .in_set(OnUpdate(GameState::Intro)),
+            )
+            .add_system(
+                setup_practice
+                    .run_if(resource_exists::<Practice>())
+                    .in_schedule(OnEnter(GameState::InGame)),
+            )
+            .add_systems(
+                (refill_reagents, move_targets)
+                    .distributive_run_if(resource_exists::<Practice>())
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(
+                handle_practice_menu_events
+                    .run_if(resource_exists::<Practice>())
+                    .in_set(OnUpdate(GameState::Paused)),
+            )
+            .add_system(
+                leave_outro
+                    .run_if(resource_exists::<Practice>())
+                    .in_schedule(OnEnter(GameState::Outro)),
+            )
+            .add_system(end_practice.in_schedule(OnExit(GameState::Outro)));
+    }
+}
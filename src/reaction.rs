@@ -1,12 +1,14 @@
 use bevy::prelude::*;
 
 use crate::{
+    game_time::GameTime,
     heat::Heat,
-    inventory::{Inventory, Reagent, ReagentEvent},
+    inventory::{Inventory, Reagent, ReagentEvent, ReagentRegistry, ReagentSource},
     state::GameState,
+    status_effect::{StatusEffects, StatusKind},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Reaction {
     pub reagent1: Reagent,
     pub reagent2: Option<Reagent>,
@@ -16,16 +18,17 @@ pub struct Reaction {
 }
 
 impl Reaction {
+    /// Returns how much of `reagent1` was used up, which is nothing if the reaction couldn't run
     pub fn tick(
         &self,
         inventory: &mut Inventory,
         heat: &Heat,
         dt: f32,
         mut send: impl FnMut(ReagentEvent),
-    ) {
+    ) -> f32 {
         if self.needs_heat && !heat.can_react() {
             // The reaction needs heat, but we don't have enough
-            return;
+            return 0.0;
         }
 
         if let Some(reagent2) = self.reagent2 {
@@ -43,6 +46,7 @@ impl Reaction {
                 send(ReagentEvent {
                     reagent: result,
                     delta: amount_reacted,
+                    source: ReagentSource::Reaction,
                 });
             }
             {
@@ -51,6 +55,7 @@ impl Reaction {
                 send(ReagentEvent {
                     reagent: self.reagent1,
                     delta: -amount_reacted,
+                    source: ReagentSource::Reaction,
                 });
             }
             {
@@ -59,8 +64,10 @@ impl Reaction {
                 send(ReagentEvent {
                     reagent: reagent2,
                     delta: -amount_reacted,
+                    source: ReagentSource::Reaction,
                 });
             }
+            amount_reacted
         } else {
             // one-reagent reaction
             let mut amount_reacted = {
@@ -75,6 +82,7 @@ impl Reaction {
                 send(ReagentEvent {
                     reagent: result,
                     delta: amount_reacted,
+                    source: ReagentSource::Reaction,
                 });
             }
 
@@ -83,7 +91,9 @@ impl Reaction {
             send(ReagentEvent {
                 reagent: self.reagent1,
                 delta: -amount_reacted,
+                source: ReagentSource::Reaction,
             });
+            amount_reacted
         }
     }
 }
@@ -93,31 +103,60 @@ pub struct Reactions {
     pub reactions: Vec<Reaction>,
 }
 
-impl Default for Reactions {
-    fn default() -> Self {
-        let reactions = vec![Reaction {
-            reagent1: Reagent::Minerals,
-            reagent2: None,
-            needs_heat: true,
-            rate: 0.5,
-            result: Some(Reagent::Exotic),
-        }];
+impl Reactions {
+    /// The reactions from the reagents file, which every run starts with
+    pub fn from_registry(registry: &ReagentRegistry) -> Self {
+        Reactions {
+            reactions: registry.reactions.clone(),
+        }
+    }
+}
+
+/// Which of the `Reactions` went ahead on the last tick, for the HUD to show
+#[derive(Component, Debug, Default)]
+pub struct ReactionActivity {
+    active: Vec<bool>,
+}
+
+impl ReactionActivity {
+    pub fn is_active(&self, reaction: usize) -> bool {
+        self.active.get(reaction).copied().unwrap_or(false)
+    }
+}
 
-        Reactions { reactions }
+fn add_reaction_activity(
+    mut commands: Commands,
+    query: Query<Entity, (Added<Inventory>, Without<ReactionActivity>)>,
+) {
+    for entity in &query {
+        commands.entity(entity).insert(ReactionActivity::default());
     }
 }
 
 fn perform_reactions(
-    mut query: Query<(&mut Inventory, &Heat)>,
+    mut query: Query<(
+        &mut Inventory,
+        &Heat,
+        Option<&mut ReactionActivity>,
+        Option<&StatusEffects>,
+    )>,
     reactions: Res<Reactions>,
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
+    game_time: Res<GameTime>,
     mut writer: EventWriter<ReagentEvent>,
 ) {
-    for (mut inventory, heat) in &mut query {
-        for reaction in reactions.reactions.iter() {
-            reaction.tick(&mut inventory, &heat, time.delta_seconds(), |ev| {
-                writer.send(ev)
-            });
+    let dt = fixed_time.period.as_secs_f32() * game_time.time_scale();
+    for (mut inventory, heat, mut activity, effects) in &mut query {
+        if let Some(activity) = activity.as_mut() {
+            activity.active.resize(reactions.reactions.len(), false);
+        }
+        let accelerated = effects.map_or(0.0, |effects| effects.strength(StatusKind::Accelerated));
+        let dt = dt * (1.0 + accelerated);
+        for (i, reaction) in reactions.reactions.iter().enumerate() {
+            let amount_reacted = reaction.tick(&mut inventory, heat, dt, |ev| writer.send(ev));
+            if let Some(activity) = activity.as_mut() {
+                activity.active[i] = amount_reacted > 0.0;
+            }
         }
     }
 }
@@ -126,6 +165,10 @@ pub struct ReactionPlugin;
 
 impl Plugin for ReactionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(perform_reactions.in_set(OnUpdate(GameState::InGame)));
+        app.add_system(add_reaction_activity).add_system(
+            perform_reactions
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .run_if(in_state(GameState::InGame)),
+        );
     }
 }
@@ -0,0 +1,200 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{
+    collectible::CollectionEvent,
+    inventory::{Reagent, ReagentEvent, ReagentRegistry, ReagentSource},
+    state::GameState,
+    ui::{CustomUICamera, EnabledControls, UIMarker, BAR_PADDING, CONTROL_FONT_SIZE},
+};
+
+const FONT_SIZE: f32 = 20.0;
+const MAX_LINES: usize = 8;
+/// How long a line stays up, including the fade
+const LINE_SECONDS: f32 = 4.0;
+const FADE_SECONDS: f32 = 1.0;
+/// Reactions change the inventory a tiny bit every tick, so they're added up over this long
+/// before they get a line of their own
+const REACTION_SECONDS: f32 = 1.0;
+/// Pickups of the same thing this close together share a line
+const MERGE_SECONDS: f32 = 0.5;
+
+#[derive(Debug)]
+struct TickerLine {
+    reagent: Reagent,
+    source: ReagentSource,
+    amount: f32,
+    age: f32,
+}
+
+/// The recent changes to the inventory, newest last
+#[derive(Resource, Debug)]
+struct ReagentTicker {
+    lines: VecDeque<TickerLine>,
+    /// What the reactions have done since the last reaction lines, indexed by `Reagent`
+    reactions: Vec<f32>,
+    reaction_timer: Timer,
+}
+
+impl Default for ReagentTicker {
+    fn default() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            reactions: Vec::new(),
+            reaction_timer: Timer::from_seconds(REACTION_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+impl ReagentTicker {
+    fn push(&mut self, reagent: Reagent, source: ReagentSource, amount: f32) {
+        if let Some(last) = self.lines.back_mut() {
+            if last.reagent == reagent && last.source == source && last.age < MERGE_SECONDS {
+                last.amount += amount;
+                last.age = 0.0;
+                return;
+            }
+        }
+        self.lines.push_back(TickerLine {
+            reagent,
+            source,
+            amount,
+            age: 0.0,
+        });
+        while self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+}
+
+/// Sits in the bottom right corner, above the controls
+#[derive(Component, Debug)]
+struct ReagentTickerText;
+
+fn setup_reagent_ticker(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ReagentTicker::default());
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            )
+            .with_alignment(TextAlignment::Right),
+            text_anchor: Anchor::BottomRight,
+            ..Default::default()
+        },
+        ReagentTickerText,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+/// Pickups come from the collection events rather than the reagent events, which would have
+/// them as well. Those are only used for the reactions
+fn record_reagent_changes(
+    mut collection_reader: EventReader<CollectionEvent>,
+    mut reagent_reader: EventReader<ReagentEvent>,
+    mut ticker: ResMut<ReagentTicker>,
+) {
+    for ev in collection_reader.iter() {
+        ticker.push(ev.reagent, ReagentSource::Collected, ev.amount);
+    }
+    for ev in reagent_reader.iter() {
+        if ev.source != ReagentSource::Reaction {
+            continue;
+        }
+        let index = ev.reagent.index();
+        if ticker.reactions.len() <= index {
+            ticker.reactions.resize(index + 1, 0.0);
+        }
+        ticker.reactions[index] += ev.delta;
+    }
+}
+
+fn update_reagent_ticker(
+    mut ticker: ResMut<ReagentTicker>,
+    mut text_query: Query<(&mut Text, &mut Transform), With<ReagentTickerText>>,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    registry: Res<ReagentRegistry>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for line in &mut ticker.lines {
+        line.age += dt;
+    }
+    ticker.lines.retain(|line| line.age < LINE_SECONDS);
+
+    ticker.reaction_timer.tick(time.delta());
+    if ticker.reaction_timer.just_finished() {
+        let reactions = std::mem::take(&mut ticker.reactions);
+        for (reagent, _) in registry.reagents() {
+            let amount = reactions.get(reagent.index()).copied().unwrap_or_default();
+            // Too small to show up as anything but -0.0
+            if amount.abs() >= 0.05 {
+                ticker.push(reagent, ReagentSource::Reaction, amount);
+            }
+        }
+    }
+
+    let Ok((mut text, mut transform)) = text_query.get_single_mut() else { return; };
+    let Some(size) = ui_camera
+        .get_single()
+        .ok()
+        .and_then(|camera| camera.logical_viewport_size())
+    else {
+        return;
+    };
+    let control_rows = EnabledControls::all().iter().count() as f32;
+    transform.translation.x = size.x / 2.0 - BAR_PADDING;
+    transform.translation.y =
+        -size.y / 2.0 + (CONTROL_FONT_SIZE + BAR_PADDING) * control_rows + BAR_PADDING;
+
+    let style = text.sections[0].style.clone();
+    text.sections = ticker
+        .lines
+        .iter()
+        .map(|line| {
+            let definition = registry.get(line.reagent);
+            let sign = if line.amount < 0.0 { '-' } else { '+' };
+            let source = match line.source {
+                ReagentSource::Collected => "",
+                ReagentSource::Reaction => " (REACTION)",
+            };
+            let alpha = ((LINE_SECONDS - line.age) / FADE_SECONDS).min(1.0);
+            TextSection::new(
+                format!(
+                    "{sign}{:.1} {}{source}\n",
+                    line.amount.abs(),
+                    definition.name
+                ),
+                TextStyle {
+                    color: definition.color.with_a(alpha),
+                    ..style.clone()
+                },
+            )
+        })
+        .collect();
+    // An empty ticker still needs a section to keep the style in
+    if text.sections.is_empty() {
+        text.sections.push(TextSection::new("", style));
+    }
+}
+
+pub struct ReagentTickerPlugin;
+
+impl Plugin for ReagentTickerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup_reagent_ticker.in_schedule(OnExit(GameState::Intro)))
+            .add_systems(
+                (record_reagent_changes, update_reagent_ticker)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
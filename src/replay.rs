@@ -0,0 +1,295 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+use leafwing_input_manager::{prelude::*, Actionlike};
+
+use crate::{
+    cli::LaunchOptions,
+    input::Action,
+    player::{LeadPlayer, Player, SecondPlayer},
+    state::GameState,
+    util::GameRng,
+};
+
+const REPLAY_HEADER: &str = "warlord-replay 1";
+
+/// Recording and playback happen at the start of each fixed tick, so anything that reads the
+/// player's actions in the fixed update should run after this
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReplaySystem;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayTick {
+    /// One bit per `Action`, set if it was held during the tick
+    pub actions: u32,
+    /// The aim comes from the mouse rather than an action, so it's stored separately
+    pub facing: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub ticks: Vec<ReplayTick>,
+}
+
+impl Replay {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{REPLAY_HEADER}\nseed {}\n", self.seed);
+        for tick in &self.ticks {
+            text.push_str(&format!("{} {}\n", tick.actions, tick.facing));
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        if lines.next() != Some(REPLAY_HEADER) {
+            return Err("Not a replay file, or from an incompatible version".to_owned());
+        }
+        let seed = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or("The replay is missing its seed")?;
+
+        let mut ticks = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let invalid = || format!("Invalid replay tick {i}: {line}");
+            let (actions, facing) = line.split_once(' ').ok_or_else(invalid)?;
+            ticks.push(ReplayTick {
+                actions: actions.parse().map_err(|_| invalid())?,
+                facing: facing.parse().map_err(|_| invalid())?,
+            });
+        }
+        Ok(Self { seed, ticks })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::from_text(&text)
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+}
+
+#[derive(Resource, Debug)]
+pub enum ReplayMode {
+    Record { path: PathBuf, replay: Replay },
+    Playback { replay: Replay, next_tick: usize },
+}
+
+pub fn is_recording(mode: Option<Res<ReplayMode>>) -> bool {
+    matches!(mode.as_deref(), Some(ReplayMode::Record { .. }))
+}
+
+pub fn is_playing_back(mode: Option<Res<ReplayMode>>) -> bool {
+    matches!(mode.as_deref(), Some(ReplayMode::Playback { .. }))
+}
+
+pub fn is_not_playing_back(mode: Option<Res<ReplayMode>>) -> bool {
+    !is_playing_back(mode)
+}
+
+/// Screenshots, hiding the HUD and pings aren't part of the run, so they're left out of replays.
+/// Pings go wherever the cursor is, which isn't recorded either
+fn recorded_actions() -> impl Iterator<Item = Action> {
+    // The sticks are only bound for the second player, who isn't recorded
+    Action::variants().filter(|action| {
+        !matches!(
+            action,
+            Action::Screenshot
+                | Action::ToggleHud
+                | Action::PingWaypoint
+                | Action::Move
+                | Action::Aim
+        )
+    })
+}
+
+fn setup_replay(mut commands: Commands, options: Option<Res<LaunchOptions>>) {
+    let Some(options) = options else { return; };
+    if let Some(path) = &options.replay {
+        match Replay::load(path) {
+            Ok(replay) => {
+                info!("Playing back {} ticks from {}", replay.ticks.len(), path.display());
+                commands.insert_resource(ReplayMode::Playback {
+                    replay,
+                    next_tick: 0,
+                });
+            }
+            Err(err) => error!("Couldn't load the replay {}: {err}", path.display()),
+        }
+    } else if let Some(path) = &options.record {
+        info!("Recording a replay to {}", path.display());
+        commands.insert_resource(ReplayMode::Record {
+            path: path.clone(),
+            replay: Replay::default(),
+        });
+    }
+}
+
+/// Runs when a new run starts, before anything random happens in it
+fn start_replay(
+    mut commands: Commands,
+    mode: Option<ResMut<ReplayMode>>,
+    options: Option<Res<LaunchOptions>>,
+) {
+    let Some(mut mode) = mode else { return; };
+    match mode.as_mut() {
+        ReplayMode::Record { replay, .. } => {
+            // Every run gets its own seed, unless one was asked for
+            let seed = options
+                .and_then(|options| options.seed)
+                .unwrap_or_else(rand::random);
+            *replay = Replay {
+                seed,
+                ticks: Vec::new(),
+            };
+            commands.insert_resource(GameRng::new(seed));
+        }
+        ReplayMode::Playback { replay, next_tick } => {
+            *next_tick = 0;
+            commands.insert_resource(GameRng::new(replay.seed));
+        }
+    }
+}
+
+fn record_replay_tick(
+    mut mode: ResMut<ReplayMode>,
+    query: Query<(&Player, &ActionState<Action>), Without<SecondPlayer>>,
+) {
+    let ReplayMode::Record { replay, .. } = mode.as_mut() else { return; };
+    let Ok((player, action_state)) = query.get_single() else { return; };
+
+    let actions = recorded_actions()
+        .filter(|action| action_state.pressed(*action))
+        .fold(0, |actions, action| actions | 1 << action.index());
+    replay.ticks.push(ReplayTick {
+        actions,
+        facing: player.facing,
+    });
+}
+
+fn play_back_replay_tick(
+    mut mode: ResMut<ReplayMode>,
+    mut query: Query<(&mut Player, &mut ActionState<Action>), Without<SecondPlayer>>,
+) {
+    let ReplayMode::Playback { replay, next_tick } = mode.as_mut() else { return; };
+    let Ok((mut player, mut action_state)) = query.get_single_mut() else { return; };
+
+    let Some(tick) = replay.ticks.get(*next_tick) else {
+        if *next_tick == replay.ticks.len() {
+            info!("Replay finished");
+            action_state.release_all();
+            *next_tick += 1;
+        }
+        return;
+    };
+    *next_tick += 1;
+
+    // Moving and firing go by the action's value, which pressing it by hand leaves alone
+    for action in recorded_actions() {
+        if tick.actions & 1 << action.index() != 0 {
+            action_state.press(action);
+            action_state.action_data_mut(action).value = 1.0;
+        } else {
+            action_state.release(action);
+            action_state.action_data_mut(action).value = 0.0;
+        }
+    }
+    player.facing = tick.facing;
+}
+
+/// Without an input map the player's actions aren't updated from the keyboard and mouse, so
+/// the replay is the only thing driving them
+fn detach_player_input(
+    mut commands: Commands,
+    query: Query<Entity, (LeadPlayer, With<InputMap<Action>>)>,
+) {
+    for e in &query {
+        commands.entity(e).remove::<InputMap<Action>>();
+    }
+}
+
+fn save_replay(mode: &ReplayMode) {
+    let ReplayMode::Record { path, replay } = mode else { return; };
+    if replay.ticks.is_empty() {
+        return;
+    }
+    match replay.save(path) {
+        Ok(()) => info!("Saved a replay of {} ticks to {}", replay.ticks.len(), path.display()),
+        Err(err) => error!("Couldn't save the replay to {}: {err}", path.display()),
+    }
+}
+
+fn save_replay_at_end_of_run(mode: Option<Res<ReplayMode>>) {
+    if let Some(mode) = mode {
+        save_replay(&mode);
+    }
+}
+
+/// Quitting part way through a run still keeps the recording
+fn save_replay_on_exit(mut reader: EventReader<AppExit>, mode: Option<Res<ReplayMode>>) {
+    if reader.iter().count() == 0 {
+        return;
+    }
+    if let Some(mode) = mode {
+        save_replay(&mode);
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>()
+            .add_startup_system(setup_replay)
+            .add_system(start_replay.in_schedule(OnEnter(GameState::Intro)))
+            .add_systems(
+                (
+                    record_replay_tick.run_if(is_recording),
+                    play_back_replay_tick.run_if(is_playing_back),
+                )
+                    .in_set(ReplaySystem)
+                    .distributive_run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(detach_player_input.run_if(is_playing_back))
+            .add_system(save_replay_at_end_of_run.in_schedule(OnEnter(GameState::Outro)))
+            .add_system(save_replay_on_exit.in_base_set(CoreSet::Last));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn survives_a_round_trip_through_text() {
+        let replay = Replay {
+            seed: 1234,
+            ticks: vec![
+                ReplayTick {
+                    actions: 0,
+                    facing: 0.0,
+                },
+                ReplayTick {
+                    actions: 0b101,
+                    facing: -1.25,
+                },
+            ],
+        };
+        assert_eq!(Replay::from_text(&replay.to_text()), Ok(replay));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(Replay::from_text("").is_err());
+        assert!(Replay::from_text("warlord-replay 0\nseed 1\n").is_err());
+        assert!(Replay::from_text(&format!("{REPLAY_HEADER}\n0 0\n")).is_err());
+        assert!(Replay::from_text(&format!("{REPLAY_HEADER}\nseed 1\n3\n")).is_err());
+        assert!(Replay::from_text(&format!("{REPLAY_HEADER}\nseed 1\nup 0\n")).is_err());
+    }
+}
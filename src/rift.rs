@@ -0,0 +1,132 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collectible::{Collectible, CollectibleBundle},
+    heat::Heat,
+    inventory::{Reagent, ReagentRegistry},
+    player::Player,
+    rock::RotatingRock,
+    sound::SoundEvent,
+    state::GameState,
+    util::{cosmetic, random_direction, GameRng},
+};
+
+/// How much continuum a rift gives straight away, skipping the reactions
+const RIFT_CONTINUUM: f32 = 15.0;
+/// How hard the ship is thrown away from the rift as it closes
+const RIFT_KNOCKBACK: f32 = 25.0;
+/// The heat dumped into the ship, enough to push most of the way to overheating
+const RIFT_HEAT: f32 = 60.0;
+
+/// Sent by the rock spawner now and then during the continuum stage
+pub struct SpawnRiftEvent {
+    pub position: Vec2,
+}
+
+/// The player flew into a rift. The continuum is handed over as a `CollectionEvent`, this is
+/// for the backlash
+pub struct RiftCollected {
+    pub player: Entity,
+    pub position: Vec3,
+}
+
+#[derive(Resource, Debug)]
+struct RiftAppearance {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+fn setup_rift_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    registry: Res<ReagentRegistry>,
+) {
+    let color = registry.get(Reagent::CONTINUUM).color;
+    let material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        emissive: color * 6.0,
+        ..Default::default()
+    });
+    let mesh = meshes.add(
+        shape::Torus {
+            radius: 0.7,
+            ring_radius: 0.1,
+            subdivisions_segments: 24,
+            subdivisions_sides: 8,
+        }
+        .into(),
+    );
+    commands.insert_resource(RiftAppearance { mesh, material });
+}
+
+fn spawn_rifts(
+    mut commands: Commands,
+    mut reader: EventReader<SpawnRiftEvent>,
+    rift_appearance: Res<RiftAppearance>,
+) {
+    for SpawnRiftEvent { position } in reader.iter() {
+        debug!("Opening a rift at {position:?}");
+        // Tumbles fast, so it doesn't look like anything else out there
+        let angvel = Vec3::new(
+            cosmetic::random_range(-PI, PI),
+            cosmetic::random_range(-PI, PI),
+            cosmetic::random_range(-PI, PI),
+        ) * 3.0;
+        commands
+            .spawn(CollectibleBundle {
+                transform: Transform::from_translation(position.extend(3.0)),
+                collider: Collider::ball(0.8),
+                collectible: Collectible::Rift {
+                    amount: RIFT_CONTINUUM,
+                },
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                parent.spawn((
+                    RotatingRock { angvel },
+                    PbrBundle {
+                        mesh: rift_appearance.mesh.clone(),
+                        material: rift_appearance.material.clone(),
+                        ..Default::default()
+                    },
+                ));
+            });
+    }
+}
+
+/// Throws the ship away from the rift and floods it with heat
+fn rift_backlash(
+    mut reader: EventReader<RiftCollected>,
+    mut query: Query<(&Transform, &mut Velocity, &mut Heat), With<Player>>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("rift_backlash");
+    for ev in reader.iter() {
+        let Ok((transform, mut velocity, mut heat)) = query.get_mut(ev.player) else { continue; };
+        let away = (transform.translation - ev.position).truncate();
+        let direction = if away.length_squared() > 0.0 {
+            away.normalize()
+        } else {
+            random_direction(rng)
+        };
+        velocity.linvel += direction * RIFT_KNOCKBACK;
+        heat.add(RIFT_HEAT);
+        sound_event_writer.send(SoundEvent::Warp);
+    }
+}
+
+pub struct RiftPlugin;
+
+impl Plugin for RiftPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnRiftEvent>()
+            .add_event::<RiftCollected>()
+            .add_startup_system(setup_rift_appearance)
+            .add_systems((spawn_rifts, rift_backlash).in_set(OnUpdate(GameState::InGame)));
+    }
+}
@@ -2,48 +2,151 @@ use std::f32::consts::PI;
 
 use bevy::prelude::*;
 use bevy::utils::Duration;
-use bevy_rapier2d::prelude::{Collider, CollisionEvent, RigidBody, Velocity};
+use bevy_rapier2d::prelude::{
+    ActiveEvents, Ccd, Collider, CollisionEvent, RigidBody, TransformInterpolation, Velocity,
+};
 
 use crate::camera::MainCamera;
-use crate::collectible::{Collectible, CollectibleBundle, MineralAppearance};
+use crate::campaign::Campaign;
+use crate::combo::Combo;
+use crate::comet::SpawnCometEvent;
+use crate::collectible::{
+    Collectible, CollectibleBundle, RepairKitAppearance, SpawnCollectibleEvent,
+};
+use crate::consumable::{ConsumableAppearance, ConsumableKind};
+use crate::despawn::DespawnQueue;
+use crate::gas_cloud::SpawnGasCloudEvent;
+use crate::game_time::GameTime;
+use crate::hull::Faction;
+use crate::instancing::{InstanceBatchBundle, InstancedVisual};
 use crate::inventory::Reagent;
-use crate::player::Player;
+use crate::lod::LodLevel;
+use crate::physics::{PhysicsSettings, ROCK_COLLISION_GROUPS};
+use crate::player::{LeadPlayer, Player};
+use crate::practice::Practice;
+use crate::replay::ReplaySystem;
+use crate::rift::SpawnRiftEvent;
 use crate::sound::SoundEvent;
-use crate::state::GameState;
-use crate::util::{random_direction, random_range};
+use crate::state::{GameState, ProgressStages};
+use crate::status_effect::{ApplyStatus, StatusEffects, StatusExpired, StatusKind};
+use crate::util::{cosmetic, random_direction, random_in_circle, random_range, GameRng};
 use crate::weapon::Slug;
 
 #[derive(Component, Debug, Default)]
 pub struct Rock;
 
+/// How dense the asteroid field is. Changes are picked up by the `RockSpawner` and `RockLimit`
+/// while the game is running
+#[derive(Resource, Debug, Clone)]
+pub struct RockSettings {
+    /// The most rocks that can exist at once
+    pub rock_limit: u32,
+    /// The number of rocks in a cluster is drawn randomly from this range
+    pub min_cluster_size: u32,
+    pub max_cluster_size: u32,
+    /// Seconds between attempts to spawn a cluster
+    pub spawn_interval: f32,
+    /// The chance of each rock in a cluster being a mineral instead
+    pub chance_of_mineral: f32,
+    /// Each time a cluster spawns, there's this chance of a comet streaking past as well
+    pub comet_chance: f32,
+    pub comet_speed: f32,
+    /// Each time a cluster spawns, there's this chance of a gas cloud drifting in as well
+    pub gas_cloud_chance: f32,
+    pub gas_cloud_speed: f32,
+    /// Each time a cluster spawns during the continuum stage, there's this chance of a rift
+    /// opening up near it
+    pub rift_chance: f32,
+    /// The chance of a destroyed rock leaving a repair kit behind, once the hull can take damage
+    pub repair_kit_chance: f32,
+    /// The chance of a destroyed rock leaving a consumable behind, once the ship has heat to
+    /// worry about
+    pub consumable_chance: f32,
+}
+
+impl Default for RockSettings {
+    fn default() -> Self {
+        Self {
+            rock_limit: 150,
+            min_cluster_size: 15,
+            max_cluster_size: 25,
+            spawn_interval: 5.0,
+            chance_of_mineral: 0.05,
+            comet_chance: 0.1,
+            comet_speed: 30.0,
+            gas_cloud_chance: 0.15,
+            gas_cloud_speed: 1.5,
+            rift_chance: 0.08,
+            repair_kit_chance: 0.02,
+            consumable_chance: 0.03,
+        }
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct RockSpawner {
     /// The number of rocks in a cluster is drawn randomly from this range
     pub min_cluster_size: u32,
     pub max_cluster_size: u32,
-    /// Clusters of rocks will spawn within this range of the main camera
-    pub min_spawn_distance: f32,
-    pub max_spawn_distance: f32,
+    /// Clusters of rocks spawn this far outside the edge of the camera's view, so they're never
+    /// visible popping in
+    pub min_spawn_margin: f32,
+    pub max_spawn_margin: f32,
+    /// Clusters are placed around where the camera will be this many seconds from now
+    pub lookahead: f32,
+    /// The chance that a cluster is placed ahead of the player rather than in a random direction
+    pub travel_bias: f32,
     /// The RockSpawner tries to spawn rocks whenever this timer finishes
     pub spawn_timer: Timer,
+    pub chance_of_mineral: f32,
+    /// Each time the spawn timer finishes, there's this chance of a comet streaking past as well
+    pub comet_chance: f32,
+    pub comet_speed: f32,
+    /// Each time the spawn timer finishes, there's this chance of a gas cloud drifting in as well
+    pub gas_cloud_chance: f32,
+    pub gas_cloud_speed: f32,
+    /// Each time the spawn timer finishes during the continuum stage, there's this chance of a
+    /// rift opening up as well
+    pub rift_chance: f32,
 }
 
-impl Default for RockSpawner {
-    fn default() -> Self {
+impl RockSpawner {
+    pub fn from_settings(settings: &RockSettings) -> Self {
         Self {
-            min_cluster_size: 15,
-            max_cluster_size: 25,
-            min_spawn_distance: 35.0,
-            max_spawn_distance: 50.0,
-            spawn_timer: Timer::from_seconds(5.0, TimerMode::Repeating),
+            min_cluster_size: settings.min_cluster_size,
+            max_cluster_size: settings.max_cluster_size,
+            min_spawn_margin: 2.0,
+            max_spawn_margin: 10.0,
+            lookahead: 2.0,
+            travel_bias: 0.7,
+            spawn_timer: Timer::from_seconds(settings.spawn_interval, TimerMode::Repeating),
+            chance_of_mineral: settings.chance_of_mineral,
+            comet_chance: settings.comet_chance,
+            comet_speed: settings.comet_speed,
+            gas_cloud_chance: settings.gas_cloud_chance,
+            gas_cloud_speed: settings.gas_cloud_speed,
+            rift_chance: settings.rift_chance,
         }
     }
+
+    fn apply_settings(&mut self, settings: &RockSettings) {
+        self.min_cluster_size = settings.min_cluster_size;
+        self.max_cluster_size = settings.max_cluster_size;
+        self.spawn_timer
+            .set_duration(Duration::from_secs_f32(settings.spawn_interval));
+        self.chance_of_mineral = settings.chance_of_mineral;
+        self.comet_chance = settings.comet_chance;
+        self.comet_speed = settings.comet_speed;
+        self.gas_cloud_chance = settings.gas_cloud_chance;
+        self.gas_cloud_speed = settings.gas_cloud_speed;
+        self.rift_chance = settings.rift_chance;
+    }
 }
 
-#[derive(Resource, Debug)]
-struct RockAppearance {
-    mesh: Handle<Mesh>,
-    material: Handle<StandardMaterial>,
+impl Default for RockSpawner {
+    fn default() -> Self {
+        Self::from_settings(&RockSettings::default())
+    }
 }
 
 struct SpawnEvent {
@@ -57,30 +160,71 @@ pub struct RotatingRock {
     pub angvel: Vec3,
 }
 
-fn rotate_rocks(mut query: Query<(&mut Transform, &RotatingRock)>, time: Res<Time>) {
+fn rotate_rocks(mut query: Query<(&mut Transform, &RotatingRock)>, game_time: Res<GameTime>) {
     for (mut transform, rotating_rock) in &mut query {
         let rot = Quat::from_euler(
             EulerRot::YZX,
-            rotating_rock.angvel.y * time.delta_seconds(),
-            rotating_rock.angvel.z * time.delta_seconds(),
-            rotating_rock.angvel.x * time.delta_seconds(),
+            rotating_rock.angvel.y * game_time.delta_seconds(),
+            rotating_rock.angvel.z * game_time.delta_seconds(),
+            rotating_rock.angvel.x * game_time.delta_seconds(),
         );
         transform.rotate(rot);
     }
 }
 
+/// How many rocks there are, and how many there can be. The count is kept by `count_rocks`
+/// from rocks being added and removed, so nothing else needs to keep it up to date
 #[derive(Resource, Debug)]
 pub struct RockLimit {
     current: u32,
     limit: u32,
 }
 
+#[allow(dead_code)]
+impl RockLimit {
+    pub fn new(limit: u32) -> Self {
+        Self { current: 0, limit }
+    }
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+}
+
 impl Default for RockLimit {
     fn default() -> Self {
-        Self {
-            current: 0,
-            limit: 150,
-        }
+        Self::new(RockSettings::default().rock_limit)
+    }
+}
+
+/// Runs whatever state the game is in, so rocks cleaned up at the end of a run are still
+/// counted off. Goes before `spawn_rocks` so the limit is checked against an up to date count
+fn count_rocks(
+    added_query: Query<(), Added<Rock>>,
+    mut removed: RemovedComponents<Rock>,
+    rock_limit: Option<ResMut<RockLimit>>,
+) {
+    let removed = removed.iter().count() as u32;
+    let Some(mut rock_limit) = rock_limit else { return; };
+    let added = added_query.iter().count() as u32;
+    if added > 0 || removed > 0 {
+        rock_limit.current = (rock_limit.current + added).saturating_sub(removed);
+    }
+}
+
+fn apply_rock_settings(
+    settings: Res<RockSettings>,
+    mut rock_limit: ResMut<RockLimit>,
+    mut spawner_query: Query<&mut RockSpawner>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    rock_limit.limit = settings.rock_limit;
+    for mut spawner in &mut spawner_query {
+        spawner.apply_settings(&settings);
     }
 }
 
@@ -96,66 +240,177 @@ impl Default for Cull {
 }
 
 fn cull_far_away_entities(
-    mut commands: Commands,
-    query: Query<(Entity, &Cull, &GlobalTransform, Option<&Rock>), Without<MainCamera>>,
+    query: Query<(Entity, &Cull, &GlobalTransform), Without<MainCamera>>,
     camera_query: Query<&GlobalTransform, With<MainCamera>>,
-    mut rock_limit: ResMut<RockLimit>,
+    mut despawn_queue: ResMut<DespawnQueue>,
 ) {
     let Ok(main_camera) = camera_query.get_single() else { return; };
-    for (e, cull, transform, rock) in &query {
+    for (e, cull, transform) in &query {
         let dist2 = Vec2::distance_squared(
             transform.translation().truncate(),
             main_camera.translation().truncate(),
         );
-        if dist2 > cull.max_distance * cull.max_distance {
-            commands.entity(e).despawn_recursive();
-            if rock.is_some() {
-                rock_limit.current -= 1;
-            }
+        if dist2 > cull.max_distance * cull.max_distance && despawn_queue.despawn(e) {
             debug!("Despawned entity {e:?}");
         }
     }
 }
 
+/// Rocks are 1x1 cubes, so the total area of a cluster is about `number_of_rocks`.
+/// A circle of that area has the following radius, scaled up so rocks don't overlap too much.
+fn cluster_radius(number_of_rocks: u32) -> f32 {
+    2.0 * f32::sqrt(number_of_rocks as f32 * 4.0 / PI)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_rocks_tick(
     mut query: Query<&mut RockSpawner, Without<MainCamera>>,
-    camera_query: Query<&GlobalTransform, With<MainCamera>>,
-    time: Res<Time>,
+    camera_query: Query<(&GlobalTransform, &Projection), With<MainCamera>>,
+    player_query: Query<&Velocity, LeadPlayer>,
+    fixed_time: Res<FixedTime>,
     mut writer: EventWriter<SpawnEvent>,
+    mut comet_writer: EventWriter<SpawnCometEvent>,
+    mut gas_cloud_writer: EventWriter<SpawnGasCloudEvent>,
+    mut rift_writer: EventWriter<SpawnRiftEvent>,
+    stage: Res<State<ProgressStages>>,
+    mut game_rng: ResMut<GameRng>,
 ) {
-    let Ok(main_camera) = camera_query.get_single() else { return; };
+    let rng = game_rng.stream("spawn_rocks_tick");
+    let Ok((main_camera, Projection::Orthographic(projection))) = camera_query.get_single() else { return; };
+    let camera_pos = main_camera.translation().truncate();
+    // Anything further than this from the camera is out of view
+    let view_radius = projection.area.size().length() / 2.0;
+    let velocity = player_query
+        .get_single()
+        .map(|v| v.linvel)
+        .unwrap_or(Vec2::ZERO);
+
     for mut spawner in &mut query {
-        spawner
-            .spawn_timer
-            .tick(Duration::from_secs_f32(time.delta_seconds()));
+        spawner.spawn_timer.tick(fixed_time.period);
         for _ in 0..spawner.spawn_timer.times_finished_this_tick() {
-            let dir = random_direction();
-            let dist = random_range(spawner.min_spawn_distance, spawner.max_spawn_distance);
-            let num = random_range(spawner.min_cluster_size, spawner.max_cluster_size);
+            let num = random_range(rng, spawner.min_cluster_size, spawner.max_cluster_size);
+            let cluster_radius = cluster_radius(num);
+
+            let dir =
+                if velocity.length() > 1.0 && random_range(rng, 0.0, 1.0) < spawner.travel_bias {
+                    Vec2::from_angle(random_range(rng, -PI / 3.0, PI / 3.0))
+                        .rotate(velocity.normalize())
+                } else {
+                    random_direction(rng)
+                };
+            let margin = random_range(rng, spawner.min_spawn_margin, spawner.max_spawn_margin);
+            let predicted_pos = camera_pos + velocity * spawner.lookahead;
+            let mut centre_of_region =
+                predicted_pos + dir * (view_radius + cluster_radius + margin);
+
+            // Predicting ahead can put the cluster back in view, so push it out again
+            let offset = centre_of_region - camera_pos;
+            let min_distance = view_radius + cluster_radius;
+            if offset.length() < min_distance {
+                centre_of_region = camera_pos + offset.normalize_or_zero() * min_distance;
+            }
+
             writer.send(SpawnEvent {
                 number_of_rocks: num,
-                centre_of_region: dir * dist + main_camera.translation().truncate(),
-                chance_of_mineral: 0.05,
+                centre_of_region,
+                chance_of_mineral: spawner.chance_of_mineral,
             });
+
+            if random_range(rng, 0.0, 1.0) < spawner.comet_chance {
+                // Aim roughly at the camera so the comet crosses the screen
+                let dir = random_direction(rng);
+                let dist = view_radius + spawner.max_spawn_margin;
+                let target = camera_pos + random_in_circle(rng, 10.0);
+                let position = dir * dist + camera_pos;
+                comet_writer.send(SpawnCometEvent {
+                    position,
+                    velocity: (target - position).normalize_or_zero() * spawner.comet_speed,
+                });
+            }
+
+            if random_range(rng, 0.0, 1.0) < spawner.gas_cloud_chance {
+                // Gas clouds drift in alongside the cluster, slowly enough to be avoided
+                let position = centre_of_region + random_in_circle(rng, cluster_radius);
+                gas_cloud_writer.send(SpawnGasCloudEvent {
+                    position,
+                    velocity: random_direction(rng) * spawner.gas_cloud_speed,
+                });
+            }
+
+            if stage.0 == ProgressStages::Continuum
+                && random_range(rng, 0.0, 1.0) < spawner.rift_chance
+            {
+                // Out past the cluster, so there's a choice to be made about going for it
+                let position = centre_of_region + random_direction(rng) * (cluster_radius + 5.0);
+                rift_writer.send(SpawnRiftEvent { position });
+            }
         }
     }
 }
 
-fn spawn_first_cluster(mut writer: EventWriter<SpawnEvent>) {
+fn spawn_first_cluster(
+    mut writer: EventWriter<SpawnEvent>,
+    settings: Res<RockSettings>,
+    practice: Option<Res<Practice>>,
+) {
+    // Practice sets out its own targets
+    if practice.is_some() {
+        return;
+    }
     writer.send(SpawnEvent {
         number_of_rocks: 50,
         centre_of_region: Vec2::ZERO,
-        chance_of_mineral: 0.05,
+        chance_of_mineral: settings.chance_of_mineral,
     });
 }
 
+/// Spawns a single rock. It counts towards the `RockLimit` from the next frame
+pub fn spawn_rock(
+    commands: &mut Commands,
+    transform: Transform,
+    velocity: Velocity,
+    angvel: Vec3,
+) -> Entity {
+    let rock_visuals = commands
+        .spawn((
+            RotatingRock { angvel },
+            InstancedVisual::Rock,
+            SpatialBundle::default(),
+        ))
+        .id();
+
+    commands
+        .spawn((
+            Rock,
+            RigidBody::Dynamic,
+            TransformInterpolation::default(),
+            Collider::ball(f32::sqrt(3.0 / 4.0)),
+            ROCK_COLLISION_GROUPS,
+            // For the thuds of rocks hitting each other
+            ActiveEvents::COLLISION_EVENTS,
+            // Turned on by `update_rock_ccd` once the rock is going fast enough
+            Ccd::disabled(),
+            velocity,
+            Cull::default(),
+            transform,
+            GlobalTransform::from(transform),
+            Visibility::Visible,
+            ComputedVisibility::default(),
+        ))
+        .add_child(rock_visuals)
+        .id()
+}
+
 fn spawn_rocks(
     mut commands: Commands,
     mut reader: EventReader<SpawnEvent>,
-    rock_appearance: Res<RockAppearance>,
-    mineral_appearance: Res<MineralAppearance>,
-    mut rock_limit: ResMut<RockLimit>,
+    mut collectible_writer: EventWriter<SpawnCollectibleEvent>,
+    rock_limit: Res<RockLimit>,
+    mut game_rng: ResMut<GameRng>,
 ) {
+    let rng = game_rng.stream("spawn_rocks");
+    // The rocks spawned here aren't counted until next frame
+    let mut spawned = 0;
     for SpawnEvent {
         number_of_rocks,
         centre_of_region,
@@ -163,18 +418,17 @@ fn spawn_rocks(
     } in reader.iter()
     {
         debug!("Trying to spawn a cluster of rocks at {centre_of_region:?} with {number_of_rocks} rocks.");
-        if number_of_rocks + rock_limit.current > rock_limit.limit {
-            debug!("Couldn't spawn {} rocks. There are currently {} rocks and that would exceed the limit of {}", number_of_rocks, rock_limit.current, rock_limit.limit);
+        let current = rock_limit.current + spawned;
+        if number_of_rocks + current > rock_limit.limit {
+            debug!("Couldn't spawn {} rocks. There are currently {} rocks and that would exceed the limit of {}", number_of_rocks, current, rock_limit.limit);
             return;
         }
+        spawned += number_of_rocks;
         for _ in 0..*number_of_rocks {
-            // Rocks are 1x1 cubes, so the total area of the rocks to be spawned is about
-            // `number_of_rocks`. A circle of that area has the following radius.
-            // Should tune this so rocks don't overlap too much
-            let radius = 2.0 * f32::sqrt(*number_of_rocks as f32 * 4.0 / PI);
+            let radius = cluster_radius(*number_of_rocks);
             let pos = loop {
-                let x = random_range(-radius, radius);
-                let y = random_range(-radius, radius);
+                let x = random_range(rng, -radius, radius);
+                let y = random_range(rng, -radius, radius);
 
                 if x * x + y * y < radius * radius {
                     break Vec2::new(x, y);
@@ -182,94 +436,63 @@ fn spawn_rocks(
             };
             let rot = Quat::from_euler(
                 EulerRot::XYZ,
-                random_range(-PI, PI),
-                random_range(-PI, PI),
-                random_range(-PI, PI),
+                random_range(rng, -PI, PI),
+                random_range(rng, -PI, PI),
+                random_range(rng, -PI, PI),
             );
             let transform =
                 Transform::from_xyz(centre_of_region.x + pos.x, centre_of_region.y + pos.y, 3.0)
                     .with_rotation(rot);
 
-            let velocity =
-                Velocity::linear(Vec2::new(random_range(-1.0, 1.0), random_range(-1.0, 1.0)));
+            let velocity = Velocity::linear(Vec2::new(
+                random_range(rng, -1.0, 1.0),
+                random_range(rng, -1.0, 1.0),
+            ));
 
             // Spawn the visual component separately, so it can rotate in 3d
             // without interference from rapier
             let angvel = Vec3::new(
-                random_range(-PI, PI),
-                random_range(-PI, PI),
-                random_range(-PI, PI),
+                cosmetic::random_range(-PI, PI),
+                cosmetic::random_range(-PI, PI),
+                cosmetic::random_range(-PI, PI),
             );
-            let roll = random_range(0.0, 1.0);
+            let roll = random_range(rng, 0.0, 1.0);
             if roll > *chance_of_mineral {
-                rock_limit.current += 1;
-                let rock_visuals = commands
-                    .spawn((
-                        RotatingRock { angvel },
-                        PbrBundle {
-                            mesh: rock_appearance.mesh.clone(),
-                            material: rock_appearance.material.clone(),
-                            visibility: Visibility::Visible,
-                            ..Default::default()
-                        },
-                    ))
-                    .id();
-
-                commands
-                    .spawn((
-                        Rock,
-                        RigidBody::Dynamic,
-                        Collider::ball(f32::sqrt(3.0 / 4.0)),
-                        velocity,
-                        Cull::default(),
-                        transform,
-                        GlobalTransform::from(transform),
-                        Visibility::Visible,
-                        ComputedVisibility::default(),
-                    ))
-                    .add_child(rock_visuals);
+                spawn_rock(&mut commands, transform, velocity, angvel);
             } else {
                 debug!("Mineral spawned!");
-
-                commands
-                    .spawn(CollectibleBundle {
-                        transform,
-                        velocity,
-                        collectible: Collectible::CollectibleReagent {
-                            reagent: Reagent::Minerals,
-                            amount: 1.0,
-                        },
-                        ..Default::default()
-                    })
-                    .with_children(|parent| {
-                        parent.spawn((
-                            RotatingRock { angvel },
-                            PbrBundle {
-                                mesh: mineral_appearance.mesh.clone(),
-                                material: mineral_appearance.material.clone(),
-                                visibility: Visibility::Visible,
-                                ..Default::default()
-                            },
-                        ));
-                    });
+                collectible_writer.send(SpawnCollectibleEvent::new(
+                    Reagent::MINERALS,
+                    1.0,
+                    transform.translation,
+                    velocity.linvel,
+                ));
             }
         }
     }
 }
 
-fn setup_rock_appearance(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
+// Rocks are drawn in one go by the instancing batch, see `InstancedVisual::Rock`
+fn setup_rock_appearance(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
     let side_length = 1.0;
-    let rock_mat = materials.add(Color::DARK_GRAY.into());
     let rock_mesh = meshes.add(shape::Cube { size: side_length }.into());
+    let rock_billboard = meshes.add(shape::Quad::new(Vec2::splat(side_length)).into());
 
-    commands.insert_resource(RockAppearance {
-        mesh: rock_mesh,
-        material: rock_mat,
-    });
+    commands.spawn(InstanceBatchBundle::new(
+        InstancedVisual::Rock,
+        rock_mesh,
+        Color::DARK_GRAY,
+        Color::BLACK,
+    ));
+    commands.spawn(
+        InstanceBatchBundle::new(
+            InstancedVisual::Rock,
+            rock_billboard,
+            Color::DARK_GRAY,
+            Color::BLACK,
+        )
+        .with_lod(LodLevel::Low),
+    );
 }
 
 #[derive(Debug)]
@@ -278,35 +501,60 @@ pub struct RockDestroyed {
     pub position: Vec3,
 }
 
+/// Rocks this close to one that's shot might catch fire
+const IGNITE_RADIUS: f32 = 6.0;
+/// How many of the rocks around one that's shot are volatile enough to catch, unless the
+/// campaign says otherwise
+const VOLATILE_CHANCE: f32 = 0.25;
+const BURN_SECONDS: f32 = 3.0;
+
+#[allow(clippy::too_many_arguments)]
 fn handle_rock_collisions(
     mut reader: EventReader<CollisionEvent>,
     rock_query: Query<&Transform, With<Rock>>,
-    slug_query: Query<&Slug, Without<Rock>>,
-    player_query: Query<&Player, (Without<Rock>, Without<Slug>)>,
+    slug_query: Query<&Faction, (With<Slug>, Without<Rock>)>,
+    nearby_query: Query<(Entity, &Transform), With<Rock>>,
     mut rock_destroyed_writer: EventWriter<RockDestroyed>,
-    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut status_writer: EventWriter<ApplyStatus>,
+    campaign: Option<Res<Campaign>>,
+    mut game_rng: ResMut<GameRng>,
 ) {
+    let rng = game_rng.stream("handle_rock_collisions");
+    let volatile_chance = campaign
+        .and_then(|campaign| campaign.settings().volatile_chance)
+        .unwrap_or(VOLATILE_CHANCE);
+    // Pirates shoot too, but only the player's slugs mine
+    let players_slug = |entity| matches!(slug_query.get(entity), Ok(Faction::Player));
     for ev in reader.iter() {
         match ev {
             CollisionEvent::Started(e1, e2, _flags) => {
-                if rock_query.get(*e1).is_ok() && slug_query.get(*e2).is_ok() {
-                    if let Ok(rock_transform) = rock_query.get(*e1) {
-                        rock_destroyed_writer.send(RockDestroyed {
-                            entity: *e1,
-                            position: rock_transform.translation,
-                        })
-                    }
-                } else if rock_query.get(*e2).is_ok() && slug_query.get(*e1).is_ok() {
-                    if let Ok(rock_transform) = rock_query.get(*e2) {
-                        rock_destroyed_writer.send(RockDestroyed {
-                            entity: *e2,
-                            position: rock_transform.translation,
-                        })
+                let rock = if rock_query.get(*e1).is_ok() && players_slug(*e2) {
+                    *e1
+                } else if rock_query.get(*e2).is_ok() && players_slug(*e1) {
+                    *e2
+                } else {
+                    continue;
+                };
+                let Ok(rock_transform) = rock_query.get(rock) else { continue; };
+                rock_destroyed_writer.send(RockDestroyed {
+                    entity: rock,
+                    position: rock_transform.translation,
+                });
+
+                let position = rock_transform.translation.truncate();
+                for (entity, transform) in &nearby_query {
+                    if entity == rock
+                        || transform.translation.truncate().distance(position) > IGNITE_RADIUS
+                        || random_range(rng, 0.0, 1.0) > volatile_chance
+                    {
+                        continue;
                     }
-                } else if rock_query.get(*e2).is_ok() && player_query.get(*e1).is_ok() {
-                    sound_event_writer.send(SoundEvent::RockCollision);
-                } else if rock_query.get(*e1).is_ok() && player_query.get(*e2).is_ok() {
-                    sound_event_writer.send(SoundEvent::RockCollision);
+                    status_writer.send(ApplyStatus {
+                        entity,
+                        kind: StatusKind::Burning,
+                        seconds: BURN_SECONDS,
+                        strength: 1.0,
+                    });
                 }
             }
             _ => {}
@@ -314,55 +562,344 @@ fn handle_rock_collisions(
     }
 }
 
+/// Burning rocks break apart once the fire runs out, and set fire to the ship if it runs into
+/// them before then
+fn burn_rocks(
+    mut collision_reader: EventReader<CollisionEvent>,
+    mut expired_reader: EventReader<StatusExpired>,
+    rock_query: Query<(&Transform, Option<&StatusEffects>), With<Rock>>,
+    player_query: Query<(), With<Player>>,
+    mut rock_destroyed_writer: EventWriter<RockDestroyed>,
+    mut status_writer: EventWriter<ApplyStatus>,
+) {
+    for ev in expired_reader.iter() {
+        if ev.kind != StatusKind::Burning {
+            continue;
+        }
+        let Ok((transform, _)) = rock_query.get(ev.entity) else { continue; };
+        rock_destroyed_writer.send(RockDestroyed {
+            entity: ev.entity,
+            position: transform.translation,
+        });
+    }
+    for ev in collision_reader.iter() {
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        let (rock, player) = if player_query.contains(*e2) {
+            (*e1, *e2)
+        } else {
+            (*e2, *e1)
+        };
+        if !player_query.contains(player) {
+            continue;
+        }
+        let Ok((_, Some(effects))) = rock_query.get(rock) else { continue; };
+        if effects.has(StatusKind::Burning) {
+            status_writer.send(ApplyStatus {
+                entity: player,
+                kind: StatusKind::Burning,
+                seconds: BURN_SECONDS,
+                strength: 1.0,
+            });
+        }
+    }
+}
+
+/// Fast rocks are checked along their whole path each step, so a fast slug can't slip through
+/// one between steps. It's too expensive to leave on for every rock
+fn update_rock_ccd(
+    mut query: Query<(&Velocity, &mut Ccd), With<Rock>>,
+    physics_settings: Res<PhysicsSettings>,
+) {
+    for (velocity, mut ccd) in &mut query {
+        let enabled = velocity.linvel.length() > physics_settings.rock_ccd_speed;
+        if ccd.enabled != enabled {
+            ccd.enabled = enabled;
+        }
+    }
+}
+
+/// Rocks hitting each other or the player slower than this don't make a sound
+const MIN_IMPACT_SPEED: f32 = 2.0;
+/// Impacts this fast or faster play at full volume
+const FULL_IMPACT_SPEED: f32 = 15.0;
+/// Impacts further than this from the player can't be heard
+const MAX_IMPACT_DISTANCE: f32 = 40.0;
+/// A cluster settling can produce dozens of collisions at once, so only a few get played
+const MAX_IMPACT_SOUNDS_PER_SECOND: u32 = 8;
+
+/// Plays a thud whenever a rock hits another rock or the player hard enough, louder for
+/// faster impacts and quieter the further away it happens
+fn play_rock_impact_sounds(
+    mut reader: EventReader<CollisionEvent>,
+    rock_query: Query<(&Transform, &Velocity), With<Rock>>,
+    player_query: Query<(&Transform, &Velocity), (LeadPlayer, Without<Rock>)>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut throttle: Local<Option<(Timer, u32)>>,
+    game_time: Res<GameTime>,
+) {
+    let (timer, played) = throttle
+        .get_or_insert_with(|| (Timer::from_seconds(1.0, TimerMode::Repeating), 0));
+    timer.tick(game_time.delta());
+    if timer.just_finished() {
+        *played = 0;
+    }
+    let Ok((player_transform, player_velocity)) = player_query.get_single() else { return; };
+
+    for ev in reader.iter() {
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        let (rock_entity, other) = if rock_query.contains(*e1) {
+            (*e1, *e2)
+        } else {
+            (*e2, *e1)
+        };
+        let Ok((rock_transform, rock_velocity)) = rock_query.get(rock_entity) else { continue; };
+        let other_velocity = if let Ok((_, velocity)) = rock_query.get(other) {
+            velocity.linvel
+        } else if player_query.contains(other) {
+            player_velocity.linvel
+        } else {
+            continue;
+        };
+
+        let relative_speed = (rock_velocity.linvel - other_velocity).length();
+        if relative_speed < MIN_IMPACT_SPEED {
+            continue;
+        }
+        let relative_pos = rock_transform.translation - player_transform.translation;
+        let distance = relative_pos.truncate().length();
+        if distance > MAX_IMPACT_DISTANCE {
+            continue;
+        }
+        if *played >= MAX_IMPACT_SOUNDS_PER_SECOND {
+            return;
+        }
+        *played += 1;
+
+        let strength = ((relative_speed - MIN_IMPACT_SPEED)
+            / (FULL_IMPACT_SPEED - MIN_IMPACT_SPEED))
+            .clamp(0.2, 1.0)
+            * (1.0 - distance / MAX_IMPACT_DISTANCE);
+        sound_event_writer.send(SoundEvent::RockImpact {
+            relative_pos,
+            strength,
+        });
+    }
+}
+
+/// Two rocks hitting each other at least this fast throw off sparks
+const CLASH_SPEED: f32 = 10.0;
+/// The sparks and crack are at their biggest for clashes this fast or faster
+const FULL_CLASH_SPEED: f32 = 25.0;
+/// The chance of a clash knocking a shard of mineral off one of the rocks
+const SHARD_CHANCE: f32 = 0.15;
+/// Shards are smaller than the minerals a destroyed rock leaves behind
+const SHARD_AMOUNT: f32 = 0.25;
+/// How fast a shard flies off, on top of the rocks' shared velocity
+const SHARD_SPEED: f32 = 3.0;
+/// The crack is louder than the thud, so there's a tighter limit on it
+const MAX_CLASH_SOUNDS_PER_SECOND: u32 = 3;
+
+/// Two rocks hit each other hard. Sent from where they touched
+#[derive(Debug)]
+pub struct RockClash {
+    pub position: Vec3,
+    /// How the pair were moving together, which the sparks carry on with
+    pub velocity: Vec2,
+    /// From 0 to 1, how hard they hit
+    pub strength: f32,
+}
+
+/// Picks out the hardest of the rock on rock impacts for sparks, a crack, and now and then a
+/// shard of mineral
+#[allow(clippy::too_many_arguments)]
+fn clash_rocks(
+    mut reader: EventReader<CollisionEvent>,
+    rock_query: Query<(&Transform, &Velocity), With<Rock>>,
+    player_query: Query<&Transform, (LeadPlayer, Without<Rock>)>,
+    mut clash_writer: EventWriter<RockClash>,
+    mut collectible_writer: EventWriter<SpawnCollectibleEvent>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut throttle: Local<Option<(Timer, u32)>>,
+    game_time: Res<GameTime>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("clash_rocks");
+    let (timer, played) =
+        throttle.get_or_insert_with(|| (Timer::from_seconds(1.0, TimerMode::Repeating), 0));
+    timer.tick(game_time.delta());
+    if timer.just_finished() {
+        *played = 0;
+    }
+
+    for ev in reader.iter() {
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        let Ok([(transform1, velocity1), (transform2, velocity2)]) =
+            rock_query.get_many([*e1, *e2])
+        else {
+            continue;
+        };
+        let relative_speed = (velocity1.linvel - velocity2.linvel).length();
+        if relative_speed < CLASH_SPEED {
+            continue;
+        }
+        // The rocks all weigh the same, so this is the pair's momentum shared between them
+        let velocity = (velocity1.linvel + velocity2.linvel) / 2.0;
+        let position = (transform1.translation + transform2.translation) / 2.0;
+        let strength =
+            ((relative_speed - CLASH_SPEED) / (FULL_CLASH_SPEED - CLASH_SPEED)).clamp(0.2, 1.0);
+        clash_writer.send(RockClash {
+            position,
+            velocity,
+            strength,
+        });
+
+        let heard = player_query.get_single().is_ok_and(|player_transform| {
+            let distance = position.distance(player_transform.translation);
+            distance < MAX_IMPACT_DISTANCE
+        });
+        if heard && *played < MAX_CLASH_SOUNDS_PER_SECOND {
+            *played += 1;
+            sound_event_writer.send(SoundEvent::RockCollision);
+        }
+
+        if random_range(rng, 0.0, 1.0) < SHARD_CHANCE {
+            // Out sideways from the line between the two rocks
+            let normal = (transform2.translation - transform1.translation)
+                .truncate()
+                .normalize_or_zero();
+            let side = random_range(rng, -1.0_f32, 1.0).signum();
+            let shard_velocity = velocity + normal.perp() * side * SHARD_SPEED;
+            collectible_writer.send(
+                SpawnCollectibleEvent::new(
+                    Reagent::MINERALS,
+                    SHARD_AMOUNT,
+                    position,
+                    shard_velocity,
+                )
+                .with_scale(0.5),
+            );
+        }
+    }
+}
+
+/// Minerals from a rock destroyed within this distance of the player are pulled towards it
+const MAGNET_RADIUS: f32 = 8.0;
+const MAGNET_SECONDS: f32 = 0.6;
+/// How fast magnetized minerals close in on the player, on top of the player's own velocity
+const MAGNET_SPEED: f32 = 12.0;
+/// How quickly a magnetized mineral turns towards the player, per second
+const MAGNET_STEERING: f32 = 8.0;
+
+/// Steers a mineral towards the player for a moment after it's spawned, so destroying a rock
+/// up close doesn't scatter the minerals behind the ship
+#[derive(Component, Debug)]
+pub struct Magnetized {
+    timer: Timer,
+}
+
+impl Default for Magnetized {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(MAGNET_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+fn magnetize_minerals(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Magnetized, &Transform, &mut Velocity), Without<Player>>,
+    player_query: Query<(&Transform, &Velocity), LeadPlayer>,
+    game_time: Res<GameTime>,
+) {
+    let Ok((player_transform, player_velocity)) = player_query.get_single() else { return; };
+    let player_pos = player_transform.translation.truncate();
+    for (entity, mut magnetized, transform, mut velocity) in &mut query {
+        magnetized.timer.tick(game_time.delta());
+        if magnetized.timer.finished() {
+            commands.entity(entity).remove::<Magnetized>();
+            continue;
+        }
+        let to_player = player_pos - transform.translation.truncate();
+        let target = to_player.normalize_or_zero() * MAGNET_SPEED + player_velocity.linvel;
+        let t = (MAGNET_STEERING * game_time.delta_seconds()).min(1.0);
+        let linvel = velocity.linvel;
+        velocity.linvel = linvel + (target - linvel) * t;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_destruction_event(
     mut commands: Commands,
     mut reader: EventReader<RockDestroyed>,
     rock_query: Query<&Transform, With<Rock>>,
-    player_query: Query<&Transform, (With<Player>, Without<Rock>)>,
-    mineral_appearance: Res<MineralAppearance>,
-    mut rock_limit: ResMut<RockLimit>,
+    player_query: Query<&Transform, (LeadPlayer, Without<Rock>)>,
+    mut collectible_writer: EventWriter<SpawnCollectibleEvent>,
     mut sound_event_writer: EventWriter<SoundEvent>,
+    rock_settings: Res<RockSettings>,
+    repair_kit_appearance: Res<RepairKitAppearance>,
+    consumable_appearance: Res<ConsumableAppearance>,
+    progress: Res<State<ProgressStages>>,
+    combo: Res<Combo>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+    mut game_rng: ResMut<GameRng>,
 ) {
+    let rng = game_rng.stream("handle_destruction_event");
     for ev in reader.iter() {
         let Ok(rock_transform) = rock_query.get(ev.entity) else { continue; };
-
-        commands.entity(ev.entity).despawn_recursive();
-        rock_limit.current -= 1;
+        // Already destroyed or culled this frame by something else
+        if !despawn_queue.despawn(ev.entity) {
+            continue;
+        }
+        let mut magnetize = false;
         if let Ok(player_transform) = player_query.get_single() {
             let diff = rock_transform.translation - player_transform.translation;
             sound_event_writer.send(SoundEvent::RockDestroyed { relative_pos: diff });
+            magnetize = diff.truncate().length() < MAGNET_RADIUS;
         }
-        for _ in 0..3 {
-            let transform = Transform::from_translation(rock_transform.translation)
-                .with_scale(Vec3::splat(0.5));
-            let velocity = Velocity::linear(random_direction());
-            let angvel = Vec3::new(
-                random_range(-PI, PI),
-                random_range(-PI, PI),
-                random_range(-PI, PI),
-            );
-
-            commands
-                .spawn(CollectibleBundle {
-                    transform,
-                    velocity,
-                    collectible: Collectible::CollectibleReagent {
-                        reagent: Reagent::Minerals,
-                        amount: 0.5,
-                    },
-                    ..Default::default()
-                })
-                .with_children(|parent| {
-                    parent.spawn((
-                        RotatingRock { angvel },
-                        PbrBundle {
-                            mesh: mineral_appearance.mesh.clone(),
-                            material: mineral_appearance.material.clone(),
-                            visibility: Visibility::Visible,
-                            ..Default::default()
-                        },
-                    ));
-                });
+        for _ in 0..3 * combo.mineral_multiplier() {
+            let mut mineral = SpawnCollectibleEvent::new(
+                Reagent::MINERALS,
+                0.5,
+                rock_transform.translation,
+                random_direction(rng),
+            )
+            .with_scale(0.5);
+            if magnetize {
+                mineral = mineral.magnetized();
+            }
+            collectible_writer.send(mineral);
+        }
+        // Nothing can damage the hull until the pirates show up
+        if progress.0 >= ProgressStages::ShieldAndStrange
+            && random_range(rng, 0.0, 1.0) < rock_settings.repair_kit_chance
+        {
+            commands.spawn(CollectibleBundle {
+                transform: Transform::from_translation(rock_transform.translation),
+                velocity: Velocity {
+                    linvel: random_direction(rng) * 0.5,
+                    angvel: random_range(rng, -PI, PI),
+                },
+                mesh: repair_kit_appearance.mesh.clone(),
+                material: repair_kit_appearance.material.clone(),
+                collectible: Collectible::Repair { amount: 25.0 },
+                ..Default::default()
+            });
+        }
+        if progress.0 >= ProgressStages::GunAndHeat
+            && random_range(rng, 0.0, 1.0) < rock_settings.consumable_chance
+        {
+            let kind = ConsumableKind::ALL[random_range(rng, 0, ConsumableKind::ALL.len() - 1)];
+            commands.spawn(CollectibleBundle {
+                transform: Transform::from_translation(rock_transform.translation),
+                velocity: Velocity {
+                    linvel: random_direction(rng) * 0.5,
+                    angvel: random_range(rng, -PI, PI),
+                },
+                mesh: consumable_appearance.mesh.clone(),
+                material: consumable_appearance.material(kind),
+                collectible: Collectible::Consumable { kind },
+                ..Default::default()
+            });
         }
     }
 }
@@ -371,18 +908,32 @@ pub struct RockPlugin;
 
 impl Plugin for RockPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_rock_appearance)
+        app.init_resource::<RockSettings>()
+            .add_startup_system(setup_rock_appearance)
             .add_system(spawn_first_cluster.in_schedule(OnExit(GameState::Intro)))
             .add_event::<SpawnEvent>()
             .add_event::<RockDestroyed>()
+            .add_event::<RockClash>()
+            .add_system(count_rocks.before(spawn_rocks))
+            .add_system(
+                spawn_rocks_tick
+                    .after(ReplaySystem)
+                    .run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
             .add_systems(
                 (
-                    spawn_rocks_tick,
+                    apply_rock_settings,
                     spawn_rocks,
                     cull_far_away_entities,
                     rotate_rocks,
+                    update_rock_ccd,
                     handle_rock_collisions,
+                    burn_rocks,
+                    play_rock_impact_sounds,
+                    clash_rocks,
                     handle_destruction_event,
+                    magnetize_minerals,
                 )
                     .in_set(OnUpdate(GameState::InGame)),
             );
@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy::prelude::*;
+
+use crate::{
+    challenge::ActiveChallenge,
+    inventory::{Reagent, ReagentRegistry},
+    practice::Practice,
+    state::GameState,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_FILE: &str = "warlord.save";
+
+/// Progress, the HUD layout and the ship's colours, kept between sessions. There's nowhere to
+/// keep it on the web, so it only lasts until the page is closed there
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct SaveData {
+    pub completed_runs: u32,
+    /// The best score for each challenge, keyed by `Challenge::id`
+    pub challenge_bests: BTreeMap<String, f32>,
+    /// The fastest daily run in seconds, keyed by date
+    pub daily_bests: BTreeMap<String, f32>,
+    pub hud_layout: HudLayout,
+    pub ship_look: ShipLook,
+}
+
+/// How the player has arranged the reagent bars, by reagent id
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HudLayout {
+    /// Top first. Reagents that aren't in here go after the ones that are, in the registry's order
+    pub order: Vec<String>,
+    /// Bars the player doesn't want on screen
+    pub collapsed: BTreeSet<String>,
+}
+
+impl HudLayout {
+    /// Every reagent, top first
+    pub fn ordered(&self, registry: &ReagentRegistry) -> Vec<Reagent> {
+        let mut reagents: Vec<_> = self
+            .order
+            .iter()
+            .filter_map(|id| registry.find(id))
+            .collect();
+        for (reagent, _) in registry.reagents() {
+            if !reagents.contains(&reagent) {
+                reagents.push(reagent);
+            }
+        }
+        reagents
+    }
+
+    fn set_order(&mut self, reagents: &[Reagent], registry: &ReagentRegistry) {
+        self.order = reagents
+            .iter()
+            .map(|&reagent| registry.get(reagent).id.clone())
+            .collect();
+    }
+
+    pub fn move_up(&mut self, reagent: Reagent, registry: &ReagentRegistry) {
+        let mut reagents = self.ordered(registry);
+        let Some(i) = reagents.iter().position(|&r| r == reagent) else { return; };
+        if i > 0 {
+            reagents.swap(i - 1, i);
+        }
+        self.set_order(&reagents, registry);
+    }
+
+    /// Puts the bar at the very top
+    pub fn pin(&mut self, reagent: Reagent, registry: &ReagentRegistry) {
+        let mut reagents = self.ordered(registry);
+        reagents.retain(|&r| r != reagent);
+        reagents.insert(0, reagent);
+        self.set_order(&reagents, registry);
+    }
+
+    pub fn toggle_collapsed(&mut self, reagent: Reagent, registry: &ReagentRegistry) {
+        let id = &registry.get(reagent).id;
+        if !self.collapsed.remove(id) {
+            self.collapsed.insert(id.clone());
+        }
+    }
+
+    pub fn is_collapsed(&self, reagent: Reagent, registry: &ReagentRegistry) -> bool {
+        self.collapsed.contains(&registry.get(reagent).id)
+    }
+}
+
+/// The lights glow this colour while the ship is cool, and heat up to orange from there
+const SHIP_LIGHTS: [(&str, Color); 6] = [
+    ("GREY", Color::GRAY),
+    ("RED", Color::RED),
+    ("CYAN", Color::CYAN),
+    ("GREEN", Color::LIME_GREEN),
+    ("GOLD", Color::GOLD),
+    ("VIOLET", Color::VIOLET),
+];
+/// The hull darkens from this as it takes damage, so they're all fairly dark to begin with
+const SHIP_BODIES: [(&str, Color); 6] = [
+    ("GUNMETAL", Color::rgb(0.2, 0.2, 0.2)),
+    ("CRIMSON", Color::rgb(0.3, 0.05, 0.05)),
+    ("NAVY", Color::rgb(0.05, 0.08, 0.3)),
+    ("JADE", Color::rgb(0.05, 0.25, 0.12)),
+    ("BRASS", Color::rgb(0.35, 0.27, 0.1)),
+    ("PEARL", Color::rgb(0.6, 0.6, 0.62)),
+];
+const SHIP_TRAILS: [(&str, Color); 5] = [
+    ("PURPLE", Color::PURPLE),
+    ("CYAN", Color::CYAN),
+    ("ORANGE", Color::ORANGE),
+    ("GREEN", Color::GREEN),
+    ("WHITE", Color::WHITE),
+];
+
+/// The parts of the ship that can be recoloured from the main menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShipPart {
+    Lights,
+    Body,
+    Trail,
+}
+
+impl ShipPart {
+    pub const ALL: [ShipPart; 3] = [ShipPart::Lights, ShipPart::Body, ShipPart::Trail];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ShipPart::Lights => "LIGHTS",
+            ShipPart::Body => "BODY",
+            ShipPart::Trail => "TRAIL",
+        }
+    }
+
+    fn palette(self) -> &'static [(&'static str, Color)] {
+        match self {
+            ShipPart::Lights => &SHIP_LIGHTS,
+            ShipPart::Body => &SHIP_BODIES,
+            ShipPart::Trail => &SHIP_TRAILS,
+        }
+    }
+}
+
+/// How the player has coloured their ship, as a pick from each part's palette. The defaults are
+/// how the ship has always looked
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShipLook {
+    lights: usize,
+    body: usize,
+    trail: usize,
+}
+
+impl ShipLook {
+    fn pick(&self, part: ShipPart) -> usize {
+        match part {
+            ShipPart::Lights => self.lights,
+            ShipPart::Body => self.body,
+            ShipPart::Trail => self.trail,
+        }
+    }
+
+    fn pick_mut(&mut self, part: ShipPart) -> &mut usize {
+        match part {
+            ShipPart::Lights => &mut self.lights,
+            ShipPart::Body => &mut self.body,
+            ShipPart::Trail => &mut self.trail,
+        }
+    }
+
+    pub fn color(&self, part: ShipPart) -> Color {
+        part.palette()[self.pick(part)].1
+    }
+
+    pub fn color_name(&self, part: ShipPart) -> &'static str {
+        part.palette()[self.pick(part)].0
+    }
+
+    /// Moves on to the next colour in the part's palette, round to the first after the last
+    pub fn cycle(&mut self, part: ShipPart) {
+        let pick = self.pick_mut(part);
+        *pick = (*pick + 1) % part.palette().len();
+    }
+
+    /// Colours that aren't in the palette any more are left at the default
+    fn set_color_name(&mut self, part: ShipPart, name: &str) {
+        if let Some(i) = part.palette().iter().position(|(n, _)| *n == name) {
+            *self.pick_mut(part) = i;
+        }
+    }
+}
+
+impl SaveData {
+    pub fn has_completed_run(&self) -> bool {
+        self.completed_runs > 0
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("completed_runs {}\n", self.completed_runs);
+        for (id, best) in &self.challenge_bests {
+            text.push_str(&format!("challenge_{id} {best}\n"));
+        }
+        for (date, best) in &self.daily_bests {
+            text.push_str(&format!("daily_{date} {best}\n"));
+        }
+        if !self.hud_layout.order.is_empty() {
+            text.push_str(&format!("hud_order {}\n", self.hud_layout.order.join(" ")));
+        }
+        for id in &self.hud_layout.collapsed {
+            text.push_str(&format!("hud_collapsed {id}\n"));
+        }
+        for part in ShipPart::ALL {
+            let key = part.name().to_lowercase();
+            text.push_str(&format!("ship_{key} {}\n", self.ship_look.color_name(part)));
+        }
+        text
+    }
+
+    /// Anything that can't be read is left at its default, so an old or damaged save file
+    /// doesn't lose the rest
+    pub fn from_text(text: &str) -> Self {
+        let mut save_data = Self::default();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once(' ') else { continue; };
+            if key == "completed_runs" {
+                save_data.completed_runs = value.parse().unwrap_or_default();
+            } else if let Some(id) = key.strip_prefix("challenge_") {
+                if let Ok(best) = value.parse() {
+                    save_data.challenge_bests.insert(id.to_string(), best);
+                }
+            } else if let Some(date) = key.strip_prefix("daily_") {
+                if let Ok(best) = value.parse() {
+                    save_data.daily_bests.insert(date.to_string(), best);
+                }
+            } else if key == "hud_order" {
+                save_data.hud_layout.order = value.split_whitespace().map(String::from).collect();
+            } else if key == "hud_collapsed" {
+                save_data.hud_layout.collapsed.insert(value.trim().to_string());
+            } else if let Some(part) = key.strip_prefix("ship_") {
+                let part = ShipPart::ALL
+                    .into_iter()
+                    .find(|p| p.name().eq_ignore_ascii_case(part));
+                if let Some(part) = part {
+                    save_data.ship_look.set_color_name(part, value.trim());
+                }
+            }
+        }
+        save_data
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load() -> Self {
+        match std::fs::read_to_string(SAVE_FILE) {
+            Ok(text) => Self::from_text(&text),
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) {
+        if let Err(err) = std::fs::write(SAVE_FILE, self.to_text()) {
+            error!("Couldn't save progress to {SAVE_FILE}: {err}");
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self) {}
+}
+
+fn load_save_data(mut commands: Commands) {
+    let save_data = SaveData::load();
+    debug!("Loaded save data {save_data:?}");
+    commands.insert_resource(save_data);
+}
+
+fn record_completed_run(
+    mut save_data: ResMut<SaveData>,
+    practice: Option<Res<Practice>>,
+    challenge: Option<Res<ActiveChallenge>>,
+) {
+    // Leaving practice and finishing a challenge go through the outro too
+    if practice.is_some() || challenge.is_some() {
+        return;
+    }
+    save_data.completed_runs += 1;
+    save_data.save();
+}
+
+pub struct SaveDataPlugin;
+
+impl Plugin for SaveDataPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveData>()
+            .add_startup_system(load_save_data)
+            // Outside of practice and challenges, the outro is only reached by escaping the
+            // shockwave
+            .add_system(record_completed_run.in_schedule(OnEnter(GameState::Outro)));
+    }
+}
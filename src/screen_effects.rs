@@ -0,0 +1,276 @@
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        render_resource::{AsBindGroup, ShaderRef},
+        view::RenderLayers,
+    },
+    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    game_time::GameTime,
+    heat::Heat,
+    hull::HullDamageEvent,
+    input::LockedActionPressed,
+    player::{LeadPlayer, Player},
+    state::GameState,
+    ui::{CustomUICamera, EnabledControls, UIMarker},
+};
+
+/// Covers the whole screen, on the UI camera's layer
+#[derive(Component, Debug, Default)]
+pub struct FullscreenOverlay;
+
+/// Shimmering haze around the edges of the screen while the ship is running hot
+#[derive(AsBindGroup, TypeUuid, Debug, Clone, Default)]
+#[uuid = "2f6c8e1a-94b3-4d57-a0e2-7b1c5d3f9e48"]
+pub struct HeatHazeMaterial {
+    /// 0 when the haze is invisible, 1 when the ship is about to overheat
+    #[uniform(0)]
+    pub intensity: f32,
+    #[uniform(0)]
+    pub time: f32,
+}
+
+impl Material2d for HeatHazeMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/heat_haze.wgsl".into()
+    }
+}
+
+fn setup_heat_haze(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<HeatHazeMaterial>>,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(shape::Quad::new(Vec2::ONE).into())),
+            material: materials.add(HeatHazeMaterial::default()),
+            // Behind the rest of the HUD
+            transform: Transform::from_xyz(0.0, 0.0, 0.0),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        FullscreenOverlay,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+fn resize_fullscreen_overlays(
+    mut query: Query<&mut Transform, (With<FullscreenOverlay>, Without<CustomUICamera>)>,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else { return; };
+    let Some(size) = ui_camera.logical_viewport_size() else { return; };
+    for mut transform in &mut query {
+        transform.scale = size.extend(1.0);
+    }
+}
+
+fn update_heat_haze(
+    mut query: Query<(&Handle<HeatHazeMaterial>, &mut Visibility)>,
+    player_query: Query<&Heat, LeadPlayer>,
+    mut materials: ResMut<Assets<HeatHazeMaterial>>,
+    time: Res<Time>,
+) {
+    let Ok(heat) = player_query.get_single() else { return; };
+    // The haze only starts once the heat is past the point where reactions can happen
+    let threshold = heat.reaction_threshold();
+    let intensity = if heat.enabled() && heat.fraction() > threshold {
+        (heat.fraction() - threshold) / (1.0 - threshold)
+    } else {
+        0.0
+    };
+
+    for (handle, mut visibility) in &mut query {
+        if intensity <= 0.0 {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Visible;
+        let Some(material) = materials.get_mut(handle) else { continue; };
+        material.intensity = intensity;
+        material.time = time.elapsed_seconds_wrapped();
+    }
+}
+
+/// Darkens the edges of the screen with a colour that flashes up and fades away
+#[derive(AsBindGroup, TypeUuid, Debug, Clone, Default)]
+#[uuid = "b84e27c5-1d6a-4f93-8e0b-3c9a5f7d2e16"]
+pub struct VignetteMaterial {
+    #[uniform(0)]
+    pub color: Color,
+    #[uniform(0)]
+    pub intensity: f32,
+}
+
+impl Material2d for VignetteMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/vignette.wgsl".into()
+    }
+}
+
+/// Flashes the vignette. Anything can send one of these to warn the player
+#[derive(Debug, Clone, Copy)]
+pub struct VignettePulse {
+    pub color: Color,
+    /// How strongly the edges of the screen light up, from 0 to 1
+    pub strength: f32,
+}
+
+pub const HULL_DAMAGE_VIGNETTE: Color = Color::rgb(0.9, 0.05, 0.05);
+pub const HEAT_VIGNETTE: Color = Color::rgb(1.0, 0.5, 0.0);
+pub const SHIELD_VIGNETTE: Color = Color::rgb(0.1, 0.4, 1.0);
+
+#[derive(Component, Debug)]
+pub struct Vignette {
+    color: Color,
+    intensity: f32,
+    /// How much intensity is lost per second
+    pub fade_rate: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            color: Color::NONE,
+            intensity: 0.0,
+            fade_rate: 1.5,
+        }
+    }
+}
+
+fn setup_vignette(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<VignetteMaterial>>,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(shape::Quad::new(Vec2::ONE).into())),
+            material: materials.add(VignetteMaterial::default()),
+            // In front of the heat haze, still behind the rest of the HUD
+            transform: Transform::from_xyz(0.0, 0.0, 0.1),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        Vignette::default(),
+        FullscreenOverlay,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+fn pulse_vignette_on_hull_damage(
+    mut reader: EventReader<HullDamageEvent>,
+    player_query: Query<(), With<Player>>,
+    mut writer: EventWriter<VignettePulse>,
+) {
+    for ev in reader.iter() {
+        if player_query.contains(ev.entity) {
+            writer.send(VignettePulse {
+                color: HULL_DAMAGE_VIGNETTE,
+                strength: (ev.amount / 20.0).clamp(0.3, 1.0),
+            });
+        }
+    }
+}
+
+/// Keeps pulsing for as long as the ship is close to overheating
+fn pulse_vignette_on_high_heat(
+    player_query: Query<&Heat, LeadPlayer>,
+    mut writer: EventWriter<VignettePulse>,
+    mut timer: Local<Option<Timer>>,
+    game_time: Res<GameTime>,
+) {
+    const WARNING_FRACTION: f32 = 0.9;
+    let Ok(heat) = player_query.get_single() else { return; };
+    let timer = timer.get_or_insert_with(|| Timer::from_seconds(0.8, TimerMode::Repeating));
+    if !heat.enabled() || heat.fraction() < WARNING_FRACTION {
+        timer.reset();
+        return;
+    }
+    timer.tick(game_time.delta());
+    if timer.just_finished() {
+        writer.send(VignettePulse {
+            color: HEAT_VIGNETTE,
+            strength: 0.6,
+        });
+    }
+}
+
+/// There's no shield energy to run out of yet, so this warns when the shield is called for
+/// before it's been unlocked
+fn pulse_vignette_on_shield_unavailable(
+    mut reader: EventReader<LockedActionPressed>,
+    mut writer: EventWriter<VignettePulse>,
+) {
+    for ev in reader.iter() {
+        if ev.control == EnabledControls::Shield {
+            writer.send(VignettePulse {
+                color: SHIELD_VIGNETTE,
+                strength: 0.5,
+            });
+        }
+    }
+}
+
+fn update_vignette(
+    mut reader: EventReader<VignettePulse>,
+    mut query: Query<(&mut Vignette, &Handle<VignetteMaterial>, &mut Visibility)>,
+    mut materials: ResMut<Assets<VignetteMaterial>>,
+    game_time: Res<GameTime>,
+) {
+    let pulses: Vec<_> = reader.iter().copied().collect();
+    for (mut vignette, handle, mut visibility) in &mut query {
+        // The strongest warning wins
+        if let Some(pulse) = pulses
+            .iter()
+            .max_by(|a, b| a.strength.total_cmp(&b.strength))
+        {
+            if pulse.strength >= vignette.intensity {
+                vignette.color = pulse.color;
+                vignette.intensity = pulse.strength;
+            }
+        }
+        vignette.intensity =
+            (vignette.intensity - vignette.fade_rate * game_time.delta_seconds()).max(0.0);
+
+        if vignette.intensity <= 0.0 {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Visible;
+        let Some(material) = materials.get_mut(handle) else { continue; };
+        material.color = vignette.color;
+        material.intensity = vignette.intensity;
+    }
+}
+
+pub struct ScreenEffectsPlugin;
+
+impl Plugin for ScreenEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<VignettePulse>()
+            .add_plugin(Material2dPlugin::<HeatHazeMaterial>::default())
+            .add_plugin(Material2dPlugin::<VignetteMaterial>::default())
+            .add_systems((setup_heat_haze, setup_vignette).in_schedule(OnExit(GameState::Intro)))
+            .add_systems(
+                (resize_fullscreen_overlays, update_heat_haze)
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_systems(
+                (
+                    pulse_vignette_on_hull_damage,
+                    pulse_vignette_on_high_heat,
+                    pulse_vignette_on_shield_unavailable,
+                    update_vignette,
+                )
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
@@ -0,0 +1,500 @@
+use std::{
+    io::Cursor,
+    num::NonZeroU32,
+    path::PathBuf,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    core_pipeline::{
+        bloom::BloomSettings,
+        tonemapping::{DebandDither, Tonemapping},
+    },
+    prelude::*,
+    render::{
+        camera::{CameraRenderGraph, RenderTarget},
+        primitives::Frustum,
+        render_asset::RenderAssets,
+        render_resource::*,
+        renderer::{render_system, RenderDevice, RenderQueue},
+        view::{ColorGrading, RenderLayers, VisibleEntities},
+        Extract, ExtractSchedule, RenderApp, RenderSet,
+    },
+    tasks::AsyncComputeTaskPool,
+    window::{PrimaryWindow, WindowRef},
+};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::input::Action;
+
+const SCREENSHOT_DIR: &str = "screenshots";
+/// The render layer the HUD is drawn on
+const HUD_LAYER: u8 = 1;
+/// How many frames the screenshot cameras get to render before the image is read back, so
+/// that the render target has been prepared by then
+const CAPTURE_DELAY_FRAMES: u32 = 2;
+
+/// Send this to save what's currently on screen to the screenshots folder. On the web the
+/// screenshot is downloaded instead
+pub struct ScreenshotEvent {
+    pub include_hud: bool,
+}
+
+#[derive(Resource, Debug)]
+pub struct ScreenshotSettings {
+    /// Whether screenshots taken with the hotkey show the HUD
+    pub include_hud: bool,
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self { include_hud: true }
+    }
+}
+
+#[derive(Component, Debug)]
+struct ScreenshotCapture {
+    image: Handle<Image>,
+    path: PathBuf,
+    /// Counts down to the frame the image is read back on. `None` once that's been asked for
+    frames_until_capture: Option<u32>,
+}
+
+/// Renders the same view as one of the game's cameras, but into a screenshot image
+#[derive(Component, Debug)]
+struct ScreenshotCamera {
+    capture: Entity,
+}
+
+struct ScreenshotData {
+    capture: Entity,
+    width: u32,
+    height: u32,
+    bytes: Vec<u8>,
+}
+
+#[derive(Resource)]
+struct ScreenshotReceiver(Mutex<Receiver<ScreenshotData>>);
+
+type CameraComponents<'a> = (
+    Entity,
+    &'a Camera,
+    &'a CameraRenderGraph,
+    Option<&'a Projection>,
+    Option<&'a OrthographicProjection>,
+    Option<&'a Camera3d>,
+    Option<&'a Camera2d>,
+    Option<&'a Tonemapping>,
+    Option<&'a DebandDither>,
+    Option<&'a ColorGrading>,
+    Option<&'a BloomSettings>,
+    Option<&'a RenderLayers>,
+);
+
+fn take_screenshot_on_hotkey(
+    query: Query<&ActionState<Action>>,
+    settings: Res<ScreenshotSettings>,
+    mut writer: EventWriter<ScreenshotEvent>,
+) {
+    if query
+        .iter()
+        .any(|action_state| action_state.just_pressed(Action::Screenshot))
+    {
+        writer.send(ScreenshotEvent {
+            include_hud: settings.include_hud,
+        });
+    }
+}
+
+fn screenshot_path() -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    PathBuf::from(SCREENSHOT_DIR).join(format!("warlord-{timestamp}.png"))
+}
+
+/// Copies every camera that draws to the window so that it draws into an image as well
+fn start_screenshots(
+    mut commands: Commands,
+    mut reader: EventReader<ScreenshotEvent>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<CameraComponents, Without<ScreenshotCamera>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    // Only take one, even if more were asked for this frame
+    let Some(event) = reader.iter().last() else { return; };
+    let hud_layers = RenderLayers::layer(HUD_LAYER);
+    let Ok(window) = window_query.get_single() else { return; };
+
+    let size = Extent3d {
+        width: window.physical_width(),
+        height: window.physical_height(),
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("screenshot image"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..Default::default()
+    };
+    image.resize(size);
+    let image = images.add(image);
+
+    let path = screenshot_path();
+    debug!("Taking a screenshot, saving it to {}", path.display());
+    let capture = commands
+        .spawn(ScreenshotCapture {
+            image: image.clone(),
+            path,
+            frames_until_capture: Some(CAPTURE_DELAY_FRAMES),
+        })
+        .id();
+
+    for (
+        source,
+        camera,
+        render_graph,
+        projection,
+        orthographic_projection,
+        camera_3d,
+        camera_2d,
+        tonemapping,
+        dither,
+        color_grading,
+        bloom_settings,
+        render_layers,
+    ) in &camera_query
+    {
+        if !camera.is_active || !matches!(camera.target, RenderTarget::Window(WindowRef::Primary)) {
+            continue;
+        }
+        let shows_hud = render_layers.is_some_and(|layers| layers.intersects(&hud_layers));
+        if !event.include_hud && shows_hud {
+            continue;
+        }
+
+        let mut camera = camera.clone();
+        camera.target = RenderTarget::Image(image.clone());
+        let mut entity = commands.spawn((
+            ScreenshotCamera { capture },
+            camera,
+            CameraRenderGraph::new((**render_graph).clone()),
+            // Parented to the camera it's copying so it keeps the same view
+            Transform::IDENTITY,
+            GlobalTransform::default(),
+            VisibleEntities::default(),
+            Frustum::default(),
+            // The HUD's bars are bevy_ui, so they're drawn with the HUD camera's copy and no other
+            UiCameraConfig { show_ui: shows_hud },
+        ));
+        if let Some(projection) = projection {
+            entity.insert(projection.clone());
+        }
+        if let Some(orthographic_projection) = orthographic_projection {
+            entity.insert(orthographic_projection.clone());
+        }
+        if let Some(camera_3d) = camera_3d {
+            entity.insert(camera_3d.clone());
+        }
+        if let Some(camera_2d) = camera_2d {
+            entity.insert(camera_2d.clone());
+        }
+        if let Some(tonemapping) = tonemapping {
+            entity.insert(*tonemapping);
+        }
+        if let Some(dither) = dither {
+            entity.insert(*dither);
+        }
+        if let Some(color_grading) = color_grading {
+            entity.insert(*color_grading);
+        }
+        if let Some(bloom_settings) = bloom_settings {
+            entity.insert(bloom_settings.clone());
+        }
+        if let Some(render_layers) = render_layers {
+            entity.insert(*render_layers);
+        }
+        entity.set_parent(source);
+    }
+}
+
+fn tick_screenshot_captures(mut query: Query<&mut ScreenshotCapture>) {
+    for mut capture in &mut query {
+        capture.frames_until_capture = match capture.frames_until_capture {
+            Some(0) | None => None,
+            Some(frames) => Some(frames - 1),
+        };
+    }
+}
+
+fn encode_png(data: ScreenshotData) -> Result<Vec<u8>, String> {
+    let image = image::RgbaImage::from_raw(data.width, data.height, data.bytes)
+        .ok_or("The screenshot data doesn't match its size")?;
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .map_err(|err| err.to_string())?;
+    Ok(png.into_inner())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_png(path: PathBuf, data: ScreenshotData) {
+    let png = match encode_png(data) {
+        Ok(png) => png,
+        Err(err) => {
+            error!("Couldn't encode the screenshot: {err}");
+            return;
+        }
+    };
+    if let Some(dir) = path.parent() {
+        if let Err(err) = std::fs::create_dir_all(dir) {
+            error!("Couldn't create the screenshots folder: {err}");
+            return;
+        }
+    }
+    match std::fs::write(&path, png) {
+        Ok(()) => info!("Saved a screenshot to {}", path.display()),
+        Err(err) => error!("Couldn't save a screenshot to {}: {err}", path.display()),
+    }
+}
+
+/// There's no file system on the web, so have the browser download the screenshot instead
+#[cfg(target_arch = "wasm32")]
+fn save_png(path: PathBuf, data: ScreenshotData) {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    fn download(file_name: &str, png: &[u8]) -> Result<(), JsValue> {
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(png));
+        let mut options = web_sys::BlobPropertyBag::new();
+        options.type_("image/png");
+        let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or("There's no document to download the screenshot from")?;
+        let anchor = document
+            .create_element("a")?
+            .dyn_into::<web_sys::HtmlAnchorElement>()?;
+        anchor.set_href(&url);
+        anchor.set_download(file_name);
+        anchor.click();
+        web_sys::Url::revoke_object_url(&url)
+    }
+
+    let png = match encode_png(data) {
+        Ok(png) => png,
+        Err(err) => {
+            error!("Couldn't encode the screenshot: {err}");
+            return;
+        }
+    };
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("warlord.png");
+    match download(file_name, &png) {
+        Ok(()) => info!("Downloaded a screenshot as {file_name}"),
+        Err(err) => error!("Couldn't download the screenshot: {err:?}"),
+    }
+}
+
+fn save_screenshots(
+    mut commands: Commands,
+    receiver: Res<ScreenshotReceiver>,
+    capture_query: Query<&ScreenshotCapture>,
+    camera_query: Query<(Entity, &ScreenshotCamera)>,
+) {
+    let receiver = receiver.0.lock().unwrap();
+    for data in receiver.try_iter() {
+        for (e, camera) in &camera_query {
+            if camera.capture == data.capture {
+                commands.entity(e).despawn_recursive();
+            }
+        }
+        let Ok(capture) = capture_query.get(data.capture) else { continue; };
+        let path = capture.path.clone();
+        commands.entity(data.capture).despawn_recursive();
+        // Encoding the png takes a while, so don't hold up the frame for it
+        AsyncComputeTaskPool::get()
+            .spawn(async move { save_png(path, data) })
+            .detach();
+    }
+}
+
+struct ScreenshotRequest {
+    capture: Entity,
+    image: Handle<Image>,
+}
+
+#[derive(Resource, Default)]
+struct ScreenshotRequests(Vec<ScreenshotRequest>);
+
+fn extract_screenshot_requests(
+    mut requests: ResMut<ScreenshotRequests>,
+    query: Extract<Query<(Entity, &ScreenshotCapture)>>,
+) {
+    for (capture, screenshot_capture) in &query {
+        if screenshot_capture.frames_until_capture == Some(0) {
+            requests.0.push(ScreenshotRequest {
+                capture,
+                image: screenshot_capture.image.clone(),
+            });
+        }
+    }
+}
+
+struct PendingReadback {
+    capture: Entity,
+    buffer: Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: usize,
+    /// Set once the buffer has been mapped, to whether that worked
+    mapped: Arc<Mutex<Option<bool>>>,
+}
+
+#[derive(Resource, Default)]
+struct PendingReadbacks(Vec<PendingReadback>);
+
+#[derive(Resource)]
+struct ScreenshotSender(Sender<ScreenshotData>);
+
+fn copy_screenshot_images(
+    mut requests: ResMut<ScreenshotRequests>,
+    mut pending: ResMut<PendingReadbacks>,
+    images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    for request in requests.0.drain(..) {
+        let Some(gpu_image) = images.get(&request.image) else {
+            warn!("The screenshot image wasn't ready in time, skipping it");
+            continue;
+        };
+        let width = gpu_image.size.x as u32;
+        let height = gpu_image.size.y as u32;
+        let padded_bytes_per_row = RenderDevice::align_copy_bytes_per_row(width as usize * 4);
+
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("screenshot buffer"),
+            size: (padded_bytes_per_row * height as usize) as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("screenshot encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            gpu_image.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row as u32),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        render_queue.submit([encoder.finish()]);
+
+        let mapped = Arc::new(Mutex::new(None));
+        let mapped_result = mapped.clone();
+        render_device.map_buffer(&buffer.slice(..), MapMode::Read, move |result| {
+            *mapped_result.lock().unwrap() = Some(result.is_ok());
+        });
+        pending.0.push(PendingReadback {
+            capture: request.capture,
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            mapped,
+        });
+    }
+}
+
+/// The buffers get mapped some time after the copy has been submitted, usually a frame or two
+fn read_back_screenshots(mut pending: ResMut<PendingReadbacks>, sender: Res<ScreenshotSender>) {
+    pending.0.retain(|readback| {
+        let Some(mapped) = *readback.mapped.lock().unwrap() else { return true; };
+        if !mapped {
+            error!("Couldn't read the screenshot back from the GPU");
+            return false;
+        }
+
+        let row_bytes = readback.width as usize * 4;
+        let mut bytes = Vec::with_capacity(row_bytes * readback.height as usize);
+        {
+            let data = readback.buffer.slice(..).get_mapped_range();
+            for row in data.chunks(readback.padded_bytes_per_row) {
+                bytes.extend_from_slice(&row[..row_bytes]);
+            }
+        }
+        readback.buffer.unmap();
+        // Nothing is drawn over large parts of the screen, so they're left transparent
+        for pixel in bytes.chunks_mut(4) {
+            pixel[3] = 255;
+        }
+
+        let _ = sender.0.send(ScreenshotData {
+            capture: readback.capture,
+            width: readback.width,
+            height: readback.height,
+            bytes,
+        });
+        false
+    });
+}
+
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = channel();
+        app.add_event::<ScreenshotEvent>()
+            .init_resource::<ScreenshotSettings>()
+            .insert_resource(ScreenshotReceiver(Mutex::new(receiver)))
+            .add_systems(
+                (
+                    take_screenshot_on_hotkey,
+                    start_screenshots,
+                    tick_screenshot_captures,
+                    save_screenshots,
+                )
+                    .chain(),
+            );
+
+        // There's nothing to capture when running headless
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else { return; };
+        render_app
+            .insert_resource(ScreenshotSender(sender))
+            .init_resource::<ScreenshotRequests>()
+            .init_resource::<PendingReadbacks>()
+            .add_system(extract_screenshot_requests.in_schedule(ExtractSchedule))
+            .add_systems(
+                (copy_screenshot_images, read_back_screenshots)
+                    .chain()
+                    .in_set(RenderSet::Render)
+                    .after(render_system),
+            );
+    }
+}
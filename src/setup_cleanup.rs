@@ -1,45 +1,52 @@
 use bevy::prelude::*;
 
 use crate::{
+    background::{spawn_background, BackgroundAssets},
     camera::{MainCamera, MainCameraBundle, SmoothFollow},
+    campaign::Campaign,
     collectible::Collectible,
+    comet::Comet,
+    drone::Drone,
+    enemy::{PirateShip, PirateSpawner},
+    gas_cloud::GasCloud,
+    heat::Heat,
+    inventory::{Inventory, ReagentRegistry},
+    mine::Mine,
+    parallax::ParallaxLayer,
     particles::Particle,
     player::{self, Player},
+    practice::Practice,
     reaction::Reactions,
-    rock::{Rock, RockLimit, RockSpawner},
-    starfield_shader::{
-        StarfieldBundle, StarfieldCamera, StarfieldCameraBundle, StarfieldMaterial, StarfieldMesh,
-    },
+    shield::ShieldEmitter,
+    rock::{Rock, RockLimit, RockSettings, RockSpawner},
+    starfield_shader::{StarfieldCamera, StarfieldCameraBundle, StarfieldMesh},
     state::{GameState, ProgressStages},
-    ui::{CustomUICamera, CustomUICameraBundle},
-    weapon::Slug,
+    swarm::Dart,
+    turret::Turret,
+    ui::{CustomUICamera, CustomUICameraBundle, EnabledControls},
+    weapon::{CargoDumper, MainGun, PooledSlug, SlugPool},
+    wormhole::Wormhole,
 };
 
-fn setup_starfield(
-    mut commands: Commands,
-    mut starfields: ResMut<Assets<StarfieldMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-) {
-    let distant_stars = starfields.add(StarfieldMaterial {
-        parallax_factor: 0.25,
-        ..Default::default()
-    });
-
-    commands.spawn(StarfieldBundle {
-        mesh: meshes.add(shape::Quad::default().into()),
-        material: distant_stars,
-        transform: Transform::from_xyz(0.0, 0.0, -1.0),
-        ..Default::default()
-    });
+fn setup_starfield(mut commands: Commands, mut background_assets: BackgroundAssets) {
+    spawn_background(&mut commands, &mut background_assets);
 
     commands.spawn(StarfieldCameraBundle {
         ..Default::default()
     });
 }
 
+#[allow(clippy::type_complexity)]
 fn cleanup_starfield(
     mut commands: Commands,
-    query: Query<Entity, Or<(With<StarfieldMesh>, With<StarfieldCamera>)>>,
+    query: Query<
+        Entity,
+        Or<(
+            With<StarfieldMesh>,
+            With<StarfieldCamera>,
+            With<ParallaxLayer>,
+        )>,
+    >,
 ) {
     for e in &query {
         commands.entity(e).despawn_recursive();
@@ -53,13 +60,22 @@ fn setup_main_camera(mut commands: Commands) {
     // Don't need to cleanup, beacuse this should last for the liftime of the program
 }
 
+fn setup_ambient_light(mut commands: Commands) {
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 1.0,
+    });
+}
+
 fn setup_player(
     mut commands: Commands,
     mut main_camera_query: Query<&mut SmoothFollow, With<MainCamera>>,
+    registry: Res<ReagentRegistry>,
 ) {
     let player = commands
         .spawn(player::PlayerBundle {
             transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            inventory: Inventory::new(&registry),
             ..Default::default()
         })
         .id();
@@ -87,6 +103,7 @@ fn cleanup_player(
     };
 
     smooth_follow.target = None;
+    smooth_follow.partner = None;
 }
 
 fn cleanup_collectibles(mut commands: Commands, query: Query<Entity, With<Collectible>>) {
@@ -95,9 +112,16 @@ fn cleanup_collectibles(mut commands: Commands, query: Query<Entity, With<Collec
     }
 }
 
-fn setup_rocks(mut commands: Commands) {
-    commands.insert_resource(RockLimit::default());
-    commands.spawn(RockSpawner::default());
+fn setup_rocks(
+    mut commands: Commands,
+    settings: Res<RockSettings>,
+    practice: Option<Res<Practice>>,
+) {
+    commands.insert_resource(RockLimit::new(settings.rock_limit));
+    // Practice only has its own targets
+    if practice.is_none() {
+        commands.spawn(RockSpawner::from_settings(&settings));
+    }
 }
 
 fn cleanup_rocks(
@@ -109,14 +133,66 @@ fn cleanup_rocks(
     }
 }
 
+fn cleanup_comets(mut commands: Commands, query: Query<Entity, With<Comet>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn cleanup_gas_clouds(mut commands: Commands, query: Query<Entity, With<GasCloud>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn cleanup_wormholes(mut commands: Commands, query: Query<Entity, With<Wormhole>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn cleanup_drones(mut commands: Commands, query: Query<Entity, With<Drone>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn cleanup_turrets(mut commands: Commands, query: Query<Entity, With<Turret>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn cleanup_mines(mut commands: Commands, query: Query<Entity, With<Mine>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn cleanup_darts(mut commands: Commands, query: Query<Entity, With<Dart>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn cleanup_enemies(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<PirateShip>, With<PirateSpawner>)>>,
+) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
 fn cleanup_particles(mut commands: Commands, query: Query<Entity, With<Particle>>) {
     for e in &query {
         commands.entity(e).despawn_recursive();
     }
 }
 
-fn setup_reactions(mut commands: Commands) {
-    commands.insert_resource(Reactions::default());
+fn setup_reactions(mut commands: Commands, registry: Res<ReagentRegistry>) {
+    commands.insert_resource(Reactions::from_registry(&registry));
     // No need to clean this up, this replaces the existing resource if it exists
 }
 
@@ -130,14 +206,82 @@ fn cleanup_ui_camera(mut commands: Commands, query: Query<Entity, With<CustomUIC
     }
 }
 
-fn cleanup_weapons(mut commands: Commands, query: Query<Entity, With<Slug>>) {
+fn cleanup_weapons(
+    mut commands: Commands,
+    query: Query<Entity, With<PooledSlug>>,
+    mut slug_pool: ResMut<SlugPool>,
+) {
     for e in &query {
         commands.entity(e).despawn_recursive();
     }
+    slug_pool.clear();
+}
+
+/// Starts the next run part way through the game, from the chapters menu
+#[derive(Resource, Debug)]
+pub struct ChapterStart {
+    pub stage: ProgressStages,
 }
 
-fn setup_progress_stage(mut progress_stage: ResMut<NextState<ProgressStages>>) {
-    progress_stage.set(ProgressStages::Exploration);
+fn setup_progress_stage(
+    mut progress_stage: ResMut<NextState<ProgressStages>>,
+    chapter_start: Option<Res<ChapterStart>>,
+    practice: Option<Res<Practice>>,
+    campaign: Res<Campaign>,
+) {
+    // Practice never moves through the stages, everything is unlocked from the start instead
+    if practice.is_some() {
+        progress_stage.set(ProgressStages::None);
+        return;
+    }
+    match chapter_start {
+        Some(chapter_start) => progress_stage.set(chapter_start.stage),
+        None => progress_stage.set(campaign.first_stage()),
+    }
+}
+
+/// Gives the player what they would have had by the time they reached the chapter. The stage
+/// being started sets itself up as usual, but the ones before it never run
+fn setup_chapter(
+    mut commands: Commands,
+    chapter_start: Option<Res<ChapterStart>>,
+    campaign: Res<Campaign>,
+    mut player_query: Query<
+        (
+            &mut Inventory,
+            &mut Heat,
+            &mut MainGun,
+            &mut ShieldEmitter,
+            &mut CargoDumper,
+        ),
+        With<Player>,
+    >,
+    mut enabled_controls: ResMut<EnabledControls>,
+) {
+    let Some(chapter_start) = chapter_start else { return; };
+    commands.remove_resource::<ChapterStart>();
+    let stage = chapter_start.stage;
+    debug!("Setting up the player to start from {stage:?}");
+
+    for campaign_stage in campaign.stages_before(stage) {
+        for (mut inventory, mut heat, mut main_gun, mut shield_emitter, mut cargo_dumper) in
+            &mut player_query
+        {
+            for unlock in &campaign_stage.unlocks {
+                unlock.apply(
+                    &mut heat,
+                    &mut main_gun,
+                    &mut shield_emitter,
+                    &mut cargo_dumper,
+                );
+            }
+            for (reagent, fraction) in &campaign_stage.carry {
+                let entry = inventory.reagent_mut(*reagent);
+                entry.add(entry.limit() * fraction - entry.current());
+            }
+        }
+        *enabled_controls |= campaign_stage.controls;
+    }
 }
 
 fn reset_progress_stage(mut progress_stage: ResMut<NextState<ProgressStages>>) {
@@ -148,11 +292,13 @@ pub struct SetupCleanupPlugin;
 
 impl Plugin for SetupCleanupPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_main_camera);
+        app.add_startup_system(setup_main_camera)
+            .add_startup_system(setup_ambient_light);
         app.add_system(reset_progress_stage.in_schedule(OnEnter(GameState::MainMenu)));
+        // Runs once the player spawned when leaving the intro exists
+        app.add_system(setup_chapter.in_schedule(OnEnter(GameState::InGame)));
         app.add_systems(
             (
-                #[cfg(not(target_arch = "wasm32"))]
                 setup_starfield,
                 setup_rocks,
                 setup_reactions,
@@ -164,11 +310,18 @@ impl Plugin for SetupCleanupPlugin {
         );
         app.add_systems(
             (
-                #[cfg(not(target_arch = "wasm32"))]
                 cleanup_starfield,
                 cleanup_player,
                 cleanup_collectibles,
                 cleanup_rocks,
+                cleanup_comets,
+                cleanup_gas_clouds,
+                cleanup_wormholes,
+                cleanup_drones,
+                cleanup_turrets,
+                cleanup_mines,
+                cleanup_darts,
+                cleanup_enemies,
                 cleanup_particles,
                 cleanup_weapons,
                 cleanup_ui_camera,
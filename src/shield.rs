@@ -1,25 +1,90 @@
-use bevy::prelude::*;
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, render::mesh::Indices, render::render_resource::PrimitiveTopology};
 use bevy_rapier2d::prelude::*;
 use leafwing_input_manager::prelude::ActionState;
 
 use crate::{
-    collectible::{Collectible, CollectibleBundle, StrangeMatterAppearance},
-    inventory::Reagent,
+    collectible::{Collectible, SpawnCollectibleEvent},
+    despawn::DespawnQueue,
+    game_time::GameTime,
+    hull::Faction,
+    input::Action,
+    inventory::{Inventory, Reagent},
+    particles::{Particle, ParticleBundle},
+    physics::SHIELD_COLLISION_GROUPS,
     player::Player,
     rock::{Rock, RockDestroyed},
     sound::SoundEvent,
-    state::GameState,
+    state::{GameState, ProgressStages},
+    util::cosmetic::{random_direction, random_range},
+    weapon::Slug,
 };
 
+pub const SHIELD_RADIUS: f32 = 2.5;
+/// How wide the arc shield is, centred on where the ship is facing
+const ARC_ANGLE: f32 = 2.0 * PI / 3.0;
+const ARC_THICKNESS: f32 = 0.3;
+const ARC_SEGMENTS: u32 = 12;
+/// The arc shield is an upgrade, available once the belts are at their densest
+const ARC_UNLOCK_STAGE: ProgressStages = ProgressStages::Continuum;
+/// How long exotic matter takes to be drawn in and turned into strange matter
+const TRANSMUTE_SECONDS: f32 = 0.5;
+/// Rising tones played while it's transmuting, including the first one
+const TRANSMUTE_TONES: u32 = 3;
+
 #[derive(Resource)]
 struct ShieldVisuals {
     mesh: Handle<Mesh>,
+    arc_mesh: Handle<Mesh>,
     material: Handle<StandardMaterial>,
+    spark_mesh: Handle<Mesh>,
+    spark_material: Handle<StandardMaterial>,
+}
+
+/// The shape of the shield the emitter puts up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShieldShape {
+    /// All the way around the ship
+    #[default]
+    Sphere,
+    /// Only covers the front of the ship, turning with it
+    Arc,
 }
 
-#[derive(Component, Debug, Default)]
+/// What happens to rocks that hit the shield
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShieldRockMode {
+    /// Every rock that touches the shield is destroyed
+    Shatter,
+    /// Rocks bounce off, unless they hit harder than `ShieldEmitter::shatter_speed`
+    #[default]
+    Deflect,
+}
+
+#[derive(Component, Debug)]
 pub struct ShieldEmitter {
     pub enabled: bool,
+    pub rock_mode: ShieldRockMode,
+    /// In `ShieldRockMode::Deflect`, rocks hitting the shield faster than this still shatter
+    pub shatter_speed: f32,
+    /// How much of a rock's speed into the shield it keeps when it bounces off
+    pub restitution: f32,
+    pub shape: ShieldShape,
+    pub arc_unlocked: bool,
+}
+
+impl Default for ShieldEmitter {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rock_mode: ShieldRockMode::default(),
+            shatter_speed: 15.0,
+            restitution: 0.6,
+            shape: ShieldShape::default(),
+            arc_unlocked: false,
+        }
+    }
 }
 
 #[derive(Component, Default)]
@@ -42,6 +107,7 @@ pub struct ShieldBundle {
     pub computed_visibility: ComputedVisibility,
     pub shield: Shield,
     pub collider: Collider,
+    pub collision_groups: CollisionGroups,
     pub sensor: Sensor,
     pub active_events: ActiveEvents,
 }
@@ -56,13 +122,59 @@ impl Default for ShieldBundle {
             visibility: Default::default(),
             computed_visibility: Default::default(),
             shield: Default::default(),
-            collider: Collider::ball(2.5),
+            collider: Collider::ball(SHIELD_RADIUS),
+            collision_groups: SHIELD_COLLISION_GROUPS,
             sensor: Default::default(),
             active_events: ActiveEvents::COLLISION_EVENTS,
         }
     }
 }
 
+/// The points around the outside of the arc, facing along +x
+fn arc_points(radius: f32) -> impl Iterator<Item = Vec2> {
+    (0..=ARC_SEGMENTS).map(move |i| {
+        let angle = -ARC_ANGLE / 2.0 + ARC_ANGLE * i as f32 / ARC_SEGMENTS as f32;
+        Vec2::from_angle(angle) * radius
+    })
+}
+
+/// A flat band following the edge of the arc shield
+fn arc_mesh() -> Mesh {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let outer = arc_points(SHIELD_RADIUS);
+    let inner = arc_points(SHIELD_RADIUS - ARC_THICKNESS);
+    for (i, (outer, inner)) in outer.zip(inner).enumerate() {
+        let u = i as f32 / ARC_SEGMENTS as f32;
+        positions.push([outer.x, outer.y, 0.0]);
+        positions.push([inner.x, inner.y, 0.0]);
+        uvs.push([u, 0.0]);
+        uvs.push([u, 1.0]);
+    }
+    let mut indices = Vec::new();
+    for i in 0..ARC_SEGMENTS {
+        let start = i * 2;
+        indices.extend([start, start + 1, start + 2, start + 1, start + 3, start + 2]);
+    }
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh
+}
+
+/// A wedge from the middle of the ship out to the arc. It has to be convex, so it can't
+/// just be the band the mesh draws
+fn arc_collider() -> Collider {
+    let points: Vec<Vec2> = std::iter::once(Vec2::ZERO)
+        .chain(arc_points(SHIELD_RADIUS))
+        .collect();
+    Collider::convex_hull(&points).expect("The arc shield's points should make a valid hull")
+}
+
 fn setup_shield_visuals(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -70,11 +182,12 @@ fn setup_shield_visuals(
 ) {
     let mesh = meshes.add(
         shape::UVSphere {
-            radius: 2.5,
+            radius: SHIELD_RADIUS,
             ..Default::default()
         }
         .into(),
     );
+    let arc_mesh = meshes.add(arc_mesh());
 
     let material = materials.add(StandardMaterial {
         alpha_mode: AlphaMode::Blend,
@@ -85,7 +198,20 @@ fn setup_shield_visuals(
         ..Default::default()
     });
 
-    commands.insert_resource(ShieldVisuals { mesh, material });
+    let spark_mesh = meshes.add(shape::Quad::new(Vec2::splat(0.12)).into());
+    let spark_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.6, 1.0, 0.6),
+        emissive: Color::rgb(0.3, 1.0, 0.5) * 4.0,
+        ..Default::default()
+    });
+
+    commands.insert_resource(ShieldVisuals {
+        mesh,
+        arc_mesh,
+        material,
+        spark_mesh,
+        spark_material,
+    });
 }
 
 fn spawn_despawn_shield(
@@ -94,30 +220,44 @@ fn spawn_despawn_shield(
         (
             Entity,
             &ShieldEmitter,
-            &ActionState<crate::input::Action>,
+            &ActionState<Action>,
             Option<&ShieldParent>,
         ),
         With<Player>,
     >,
     shield_query: Query<Entity, (With<Shield>, Without<Player>)>,
     shield_visuals: Res<ShieldVisuals>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut despawn_queue: ResMut<DespawnQueue>,
 ) {
     for (player_entity, shield_emitter, action_state, maybe_shield_parent) in &player_query {
-        if action_state.pressed(crate::input::Action::Shield) {
+        if action_state.pressed(Action::Shield) {
             if shield_emitter.enabled
                 && (maybe_shield_parent.is_none()
                     || shield_query
                         .get(maybe_shield_parent.unwrap().shield)
                         .is_err())
             {
-                // Then there's no shield and we should spawn one
-                let shield = commands
-                    .spawn(ShieldBundle {
+                // Then there's no shield and we should spawn one.
+                // Each shield gets its own copy of the material, so it can be tinted
+                let Some(base_material) = materials.get(&shield_visuals.material).cloned() else {
+                    continue;
+                };
+                let material = materials.add(base_material);
+                let shield_bundle = match shield_emitter.shape {
+                    ShieldShape::Sphere => ShieldBundle {
                         mesh: shield_visuals.mesh.clone(),
-                        material: shield_visuals.material.clone(),
+                        material,
+                        ..Default::default()
+                    },
+                    ShieldShape::Arc => ShieldBundle {
+                        mesh: shield_visuals.arc_mesh.clone(),
+                        material,
+                        collider: arc_collider(),
                         ..Default::default()
-                    })
-                    .id();
+                    },
+                };
+                let shield = commands.spawn((shield_bundle, ShieldTint::default())).id();
                 commands
                     .entity(player_entity)
                     .insert(ShieldParent { shield })
@@ -126,134 +266,386 @@ fn spawn_despawn_shield(
         } else {
             // Then there shouldn't be a shield, and we should remove it if one exists
             if let Some(shield_parent) = maybe_shield_parent {
-                commands.entity(shield_parent.shield).despawn_recursive();
+                despawn_queue.despawn(shield_parent.shield);
                 commands.entity(player_entity).remove::<ShieldParent>();
             }
         }
     }
 }
 
+fn unlock_arc_shield(mut query: Query<&mut ShieldEmitter>, progress: Res<State<ProgressStages>>) {
+    if progress.0 < ARC_UNLOCK_STAGE {
+        return;
+    }
+    for mut shield_emitter in &mut query {
+        if !shield_emitter.arc_unlocked {
+            debug!("Arc shield unlocked");
+            shield_emitter.arc_unlocked = true;
+        }
+    }
+}
+
+fn switch_shield_shape(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut ShieldEmitter, &ActionState<Action>, Option<&ShieldParent>)>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+) {
+    for (entity, mut shield_emitter, action_state, maybe_shield_parent) in &mut query {
+        if !shield_emitter.arc_unlocked || !action_state.just_pressed(Action::SwitchShieldShape) {
+            continue;
+        }
+        shield_emitter.shape = match shield_emitter.shape {
+            ShieldShape::Sphere => ShieldShape::Arc,
+            ShieldShape::Arc => ShieldShape::Sphere,
+        };
+        debug!("Switched to the {:?} shield", shield_emitter.shape);
+        // If the shield is up, it gets put back up in the new shape
+        if let Some(shield_parent) = maybe_shield_parent {
+            despawn_queue.despawn(shield_parent.shield);
+            commands.entity(entity).remove::<ShieldParent>();
+        }
+    }
+}
+
+/// The player's transform doesn't rotate, so the arc is turned to where the ship is facing
+fn orient_arc_shield(
+    player_query: Query<(&Player, &ShieldEmitter, &ShieldParent)>,
+    mut shield_query: Query<&mut Transform, With<Shield>>,
+) {
+    for (player, shield_emitter, shield_parent) in &player_query {
+        if shield_emitter.shape != ShieldShape::Arc {
+            continue;
+        }
+        let Ok(mut transform) = shield_query.get_mut(shield_parent.shield) else { continue; };
+        transform.rotation = Quat::from_rotation_z(player.facing);
+    }
+}
+
+/// Something touched a shield. `player` is the ship whose shield it was
 #[derive(Debug)]
 pub enum ShieldCollision {
     Rock {
+        player: Entity,
         entity: Entity,
         position: Vec3,
     },
     Collectible {
+        player: Entity,
         entity: Entity,
         position: Vec3,
         reagent: Reagent,
         amount: f32,
     },
+    /// A slug fired by someone other than the player
+    HostileSlug { player: Entity, entity: Entity },
 }
 
 fn handle_collision(
     mut collisions: EventReader<CollisionEvent>,
-    shield_query: Query<Entity, With<Shield>>,
+    shield_query: Query<&Parent, With<Shield>>,
     collectible_query: Query<(&Collectible, &Transform), Without<Player>>,
     rock_query: Query<(&Rock, &Transform), (Without<Player>, Without<Collectible>)>,
+    slug_query: Query<&Faction, With<Slug>>,
     mut writer: EventWriter<ShieldCollision>,
 ) {
     for ev in collisions.iter() {
-        match ev {
-            CollisionEvent::Started(e1, e2, _flags) => {
-                if let Ok(_) = shield_query.get(*e1) {
-                    if let Ok((collectible, transform)) = collectible_query.get(*e2) {
-                        match collectible {
-                          Collectible::CollectibleReagent { reagent, amount } => {
-                            writer.send(ShieldCollision::Collectible {
-                                entity: *e2,
-                                position: transform.translation,
-                                reagent: *reagent,
-                                amount: *amount,
-                            });
-                          }
-                          _ => warn!("Shield collided with a collectible with no associated Reagent. That's probably not intentional."),
-                      }
-                    } else if let Ok((_rock, transform)) = rock_query.get(*e2) {
-                        writer.send(ShieldCollision::Rock {
-                            entity: *e2,
-                            position: transform.translation,
-                        })
-                    }
-                } else if let Ok(_) = shield_query.get(*e2) {
-                    if let Ok((collectible, transform)) = collectible_query.get(*e1) {
-                        match collectible {
-                          Collectible::CollectibleReagent { reagent, amount } => {
-                            writer.send(ShieldCollision::Collectible {
-                                entity: *e1,
-                                position: transform.translation,
-                                reagent: *reagent,
-                                amount: *amount,
-                            });
-                          }
-                          _ => warn!("Shield collided with a collectible with no associated Reagent. That's probably not intentional."),
-                      }
-                    } else if let Ok((_rock, transform)) = rock_query.get(*e1) {
-                        writer.send(ShieldCollision::Rock {
-                            entity: *e1,
-                            position: transform.translation,
-                        })
-                    }
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        // The shield only collides with rocks, slugs and collectibles
+        let (player, other) = if let Ok(parent) = shield_query.get(*e1) {
+            (parent.get(), *e2)
+        } else if let Ok(parent) = shield_query.get(*e2) {
+            (parent.get(), *e1)
+        } else {
+            continue;
+        };
+
+        if let Ok((collectible, transform)) = collectible_query.get(other) {
+            match collectible {
+                Collectible::CollectibleReagent { reagent, amount } => {
+                    writer.send(ShieldCollision::Collectible {
+                        player,
+                        entity: other,
+                        position: transform.translation,
+                        reagent: *reagent,
+                        amount: *amount,
+                    });
                 }
+                // Repair kits pass straight through, nothing to transmute
+                Collectible::Repair { .. } => {}
+                // Pods are sealed, the shield can't get at what's inside
+                Collectible::CargoPod { .. } => {}
+                Collectible::Consumable { .. } => {}
+                // Rifts are only closed by flying into them
+                Collectible::Rift { .. } => {}
+                _ => warn!("Shield collided with a collectible with no associated Reagent. That's probably not intentional."),
+            }
+        } else if let Ok((_rock, transform)) = rock_query.get(other) {
+            writer.send(ShieldCollision::Rock {
+                player,
+                entity: other,
+                position: transform.translation,
+            })
+        } else if let Ok(faction) = slug_query.get(other) {
+            if *faction != Faction::Player {
+                writer.send(ShieldCollision::HostileSlug {
+                    player,
+                    entity: other,
+                })
             }
-            _ => {}
         }
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn handle_shield_collisions(
     mut commands: Commands,
     mut reader: EventReader<ShieldCollision>,
     mut rock_destroyed_writer: EventWriter<RockDestroyed>,
     mut sound_event_writer: EventWriter<SoundEvent>,
-    player_query: Query<&Transform, With<Player>>,
-    strange_matter_appearance: Res<StrangeMatterAppearance>,
+    player_query: Query<(&Transform, &Velocity, &ShieldEmitter), With<Player>>,
+    mut slug_query: Query<(&mut Velocity, &mut Faction, &mut Transform), (With<Slug>, Without<Player>)>,
+    mut rock_query: Query<&mut Velocity, (With<Rock>, Without<Player>, Without<Slug>)>,
+    transmuting_query: Query<(), With<Transmuting>>,
+    mut despawn_queue: ResMut<DespawnQueue>,
 ) {
     for ev in reader.iter() {
         match ev {
-            ShieldCollision::Rock { entity, position } => {
-                rock_destroyed_writer.send(RockDestroyed {
-                    entity: *entity,
-                    position: *position,
-                })
+            ShieldCollision::Rock {
+                player,
+                entity,
+                position,
+            } => {
+                let Ok(owner) = player_query.get(*player) else { continue; };
+                let (player_transform, player_velocity, shield_emitter) = owner;
+                let Ok(mut rock_velocity) = rock_query.get_mut(*entity) else { continue; };
+                let normal = (*position - player_transform.translation)
+                    .truncate()
+                    .normalize_or_zero();
+                let relative_velocity = rock_velocity.linvel - player_velocity.linvel;
+                // How fast the rock is moving into the shield
+                let impact_speed = -relative_velocity.dot(normal);
+                if shield_emitter.rock_mode == ShieldRockMode::Shatter
+                    || impact_speed > shield_emitter.shatter_speed
+                {
+                    rock_destroyed_writer.send(RockDestroyed {
+                        entity: *entity,
+                        position: *position,
+                    });
+                    continue;
+                }
+                if impact_speed <= 0.0 {
+                    // Already on its way out
+                    continue;
+                }
+                // The shield is much heavier than any rock, so only the rock's velocity changes.
+                // The impulse per unit mass takes away its speed into the shield and bounces some back
+                let impulse = (1.0 + shield_emitter.restitution) * impact_speed * normal;
+                rock_velocity.linvel += impulse;
+                sound_event_writer.send(SoundEvent::RockCollision);
             }
             ShieldCollision::Collectible {
+                player,
                 entity,
-                position,
                 reagent,
-                amount,
+                ..
             } => {
-                match reagent {
-                    Reagent::Exotic => {
-                        let transform = Transform::from_translation(*position);
-                        commands.entity(*entity).despawn_recursive();
-                        commands.spawn(CollectibleBundle {
-                            transform,
-                            mesh: strange_matter_appearance.mesh.clone(),
-                            material: strange_matter_appearance.material.clone(),
-                            collectible: Collectible::CollectibleReagent {
-                                reagent: Reagent::Strange,
-                                amount: *amount,
-                            },
-                            ..Default::default()
-                        });
-                        if let Ok(player_transform) = player_query.get_single() {
-                            let diff = transform.translation - player_transform.translation;
-                            sound_event_writer
-                                .send(SoundEvent::ShieldTransmute { relative_pos: diff })
+                match *reagent {
+                    Reagent::EXOTIC => {
+                        // Already being drawn in, or picked up by the ship this frame
+                        if transmuting_query.contains(*entity) || despawn_queue.is_queued(*entity) {
+                            continue;
                         }
+                        // It gets drawn in and converted by `channel_transmutations`
+                        commands.entity(*entity).insert(Transmuting::new(*player));
+                        sound_event_writer.send(SoundEvent::TransmuteCharge { step: 0 });
                     }
-                    Reagent::Strange => {
+                    Reagent::STRANGE => {
                         // Do Nothing
                     }
-                    _ => commands.entity(*entity).despawn_recursive(),
+                    _ => {
+                        despawn_queue.despawn(*entity);
+                    }
+                }
+            }
+            ShieldCollision::HostileSlug { player, entity } => {
+                let Ok((player_transform, _, _)) = player_query.get(*player) else { continue; };
+                let Ok((mut velocity, mut faction, mut transform)) = slug_query.get_mut(*entity) else { continue; };
+                // Bounce the slug off the surface of the shield, and it's ours now
+                let normal = (transform.translation - player_transform.translation)
+                    .truncate()
+                    .normalize_or_zero();
+                let v = velocity.linvel;
+                if v.dot(normal) < 0.0 {
+                    velocity.linvel = v - 2.0 * v.dot(normal) * normal;
+                    let dir = velocity.linvel.normalize_or_zero();
+                    transform.rotation = Quat::from_rotation_z(f32::atan2(dir.y, dir.x) + PI / 2.0);
                 }
+                *faction = Faction::Player;
+                sound_event_writer.send(SoundEvent::ShieldTransmute {
+                    relative_pos: transform.translation - player_transform.translation,
+                });
             }
         }
     }
 }
 
+/// How long the shield shows the colour of something it's just transmuted
+const RECENT_TINT_SECONDS: f32 = 3.0;
+
+/// Colours the shield by what it last transmuted, or otherwise by what the ship is mostly
+/// carrying
+#[derive(Component, Debug)]
+pub struct ShieldTint {
+    recent: Option<Reagent>,
+    recent_timer: Timer,
+}
+
+impl Default for ShieldTint {
+    fn default() -> Self {
+        Self {
+            recent: None,
+            recent_timer: Timer::from_seconds(RECENT_TINT_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+impl ShieldTint {
+    pub fn transmuted(&mut self, reagent: Reagent) {
+        self.recent = Some(reagent);
+        self.recent_timer.reset();
+    }
+}
+
+fn tint_shields(
+    mut shield_query: Query<(&mut ShieldTint, &Handle<StandardMaterial>, &Parent), With<Shield>>,
+    inventory_query: Query<&Inventory, With<Player>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    game_time: Res<GameTime>,
+) {
+    for (mut tint, handle, parent) in &mut shield_query {
+        let Ok(inventory) = inventory_query.get(parent.get()) else { continue; };
+        // The reagent that's taking up the most of its space in the hold
+        let dominant = inventory
+            .reagents()
+            .filter(|(_, entry)| entry.current() > 0.0)
+            .max_by(|(_, a), (_, b)| a.fraction().total_cmp(&b.fraction()))
+            .map(|(reagent, _)| reagent);
+
+        tint.recent_timer.tick(game_time.delta());
+        if tint.recent_timer.finished() {
+            tint.recent = None;
+        }
+        let Some(reagent) = tint.recent.or(dominant) else { continue; };
+        let color = inventory.reagent(reagent).color();
+        let Some(material) = materials.get_mut(handle) else { continue; };
+        // Keep the shield's own colour showing through a little
+        let tinted = Color::rgb(0.1, 0.8, 0.8) * 0.3 + color * 0.7;
+        material.base_color = tinted.with_a(0.4);
+        material.emissive = tinted * 3.0;
+    }
+}
+
+/// Pulled onto the surface of the shield, before turning from exotic into strange matter
+#[derive(Component, Debug)]
+pub struct Transmuting {
+    /// The ship whose shield it touched
+    player: Entity,
+    timer: Timer,
+    /// Rising tones played so far
+    tones: u32,
+}
+
+impl Transmuting {
+    pub fn new(player: Entity) -> Self {
+        Self {
+            player,
+            timer: Timer::from_seconds(TRANSMUTE_SECONDS, TimerMode::Once),
+            // The first is played as soon as it touches the shield
+            tones: 1,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn channel_transmutations(
+    mut commands: Commands,
+    mut query: Query<
+        (Entity, &mut Transmuting, &mut Velocity, &Transform, &Collectible),
+        Without<Player>,
+    >,
+    player_query: Query<(&Transform, &Velocity, Option<&ShieldParent>), With<Player>>,
+    mut tint_query: Query<&mut ShieldTint>,
+    shield_visuals: Res<ShieldVisuals>,
+    mut collectible_writer: EventWriter<SpawnCollectibleEvent>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    game_time: Res<GameTime>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+) {
+    for (entity, mut transmuting, mut velocity, transform, collectible) in &mut query {
+        // Letting go of the shield lets go of the matter too
+        let player = player_query.get(transmuting.player);
+        let Ok((player_transform, player_velocity, Some(shield_parent))) = player else {
+            commands.entity(entity).remove::<Transmuting>();
+            continue;
+        };
+        let Collectible::CollectibleReagent { amount, .. } = collectible else { continue; };
+
+        transmuting.timer.tick(game_time.delta());
+        let position = transform.translation;
+        let normal = (position - player_transform.translation)
+            .truncate()
+            .normalize_or_zero();
+        let target = player_transform.translation.truncate() + normal * SHIELD_RADIUS;
+
+        if transmuting.timer.finished() {
+            // Only turns into strange matter if nothing else took it this frame
+            if !despawn_queue.despawn(entity) {
+                continue;
+            }
+            collectible_writer.send(SpawnCollectibleEvent::new(
+                Reagent::STRANGE,
+                *amount,
+                position,
+                player_velocity.linvel,
+            ));
+            sound_event_writer.send(SoundEvent::ShieldTransmute {
+                relative_pos: position - player_transform.translation,
+            });
+            if let Ok(mut tint) = tint_query.get_mut(shield_parent.shield) {
+                tint.transmuted(Reagent::STRANGE);
+            }
+            continue;
+        }
+
+        // Arrives at the surface just as the timer runs out, keeping up with the ship
+        let remaining = transmuting.timer.remaining_secs().max(0.05);
+        velocity.linvel = (target - position.truncate()) / remaining + player_velocity.linvel;
+
+        let tone = (transmuting.timer.percent() * TRANSMUTE_TONES as f32) as u32;
+        if tone >= transmuting.tones {
+            transmuting.tones = tone + 1;
+            sound_event_writer.send(SoundEvent::TransmuteCharge { step: tone });
+        }
+
+        // A beam of sparks between the matter and the shield
+        let along = random_range(0.0, 1.0);
+        let spark_pos = position.truncate().lerp(target, along);
+        commands.spawn(ParticleBundle {
+            mesh: shield_visuals.spark_mesh.clone(),
+            material: shield_visuals.spark_material.clone(),
+            particle: Particle {
+                lifetime_timer: Timer::from_seconds(0.2, TimerMode::Once),
+            },
+            velocity: Velocity {
+                linvel: player_velocity.linvel + random_direction() * 0.5,
+                angvel: random_range(-PI, PI),
+            },
+            transform: Transform::from_translation(spark_pos.extend(position.z)),
+            ..Default::default()
+        });
+    }
+}
+
 pub struct ShieldPlugin;
 
 impl Plugin for ShieldPlugin {
@@ -262,9 +654,14 @@ impl Plugin for ShieldPlugin {
             .add_startup_system(setup_shield_visuals)
             .add_systems(
                 (
+                    unlock_arc_shield,
+                    switch_shield_shape,
                     spawn_despawn_shield,
+                    orient_arc_shield,
                     handle_collision,
                     handle_shield_collisions,
+                    channel_transmutations,
+                    tint_shields,
                 )
                     .in_set(OnUpdate(GameState::InGame)),
             );
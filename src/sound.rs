@@ -1,4 +1,12 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::Duration};
+use bevy_rapier2d::prelude::Velocity;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    input::Action,
+    player::{movement_input, LeadPlayer, Player},
+    state::{GameState, ProgressStages},
+};
 
 #[derive(Debug)]
 pub enum SoundEvent {
@@ -6,9 +14,25 @@ pub enum SoundEvent {
     RockDestroyed { relative_pos: Vec3 },
     Collected,
     NextStage,
+    /// Everything the stage asks for has been collected, a moment before it moves on
+    ThresholdReached,
     CannonFire { direction: f32 },
     ShieldTransmute { relative_pos: Vec3 },
     RockCollision,
+    ContinuumPulse,
+    Repaired,
+    Warp,
+    /// Another rock destroyed in a combo, `step` rocks in
+    Combo { step: u32 },
+    /// A rock is about to hit the player from off screen
+    IncomingRock,
+    /// Exotic matter being drawn into the shield, `step` tones in
+    TransmuteCharge { step: u32 },
+    /// Something was pressed before it was unlocked
+    LockedControl,
+    /// A rock hitting another rock or the player. `strength` goes from 0 to 1
+    RockImpact { relative_pos: Vec3, strength: f32 },
+    UsedConsumable,
 }
 
 #[derive(Resource)]
@@ -23,6 +47,26 @@ pub struct VolumeSettings {
     pub mute: bool,
 }
 
+impl VolumeSettings {
+    /// What sound effects are played at, taking mute into account
+    pub fn sound_effects_volume(&self) -> f32 {
+        if self.mute {
+            0.0
+        } else {
+            2.0 * self.sound_effects
+        }
+    }
+
+    /// What the music is played at, taking mute into account
+    pub fn music_volume(&self) -> f32 {
+        if self.mute {
+            0.0
+        } else {
+            2.0 * self.music
+        }
+    }
+}
+
 impl Default for VolumeSettings {
     fn default() -> Self {
         Self {
@@ -33,6 +77,44 @@ impl Default for VolumeSettings {
     }
 }
 
+/// Pickups within this long of each other count as a streak
+const PICKUP_STREAK_SECONDS: f32 = 2.0;
+/// The streak stops getting higher after this many pickups
+const MAX_PICKUP_STREAK: u32 = 10;
+
+/// Raises the pitch of each pickup in quick succession
+#[derive(Resource, Debug)]
+struct PickupStreak {
+    count: u32,
+    timer: Timer,
+}
+
+impl Default for PickupStreak {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            timer: Timer::from_seconds(PICKUP_STREAK_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+impl PickupStreak {
+    fn tick(&mut self, delta: Duration) {
+        self.timer.tick(delta);
+        if self.timer.finished() {
+            self.count = 0;
+        }
+    }
+
+    /// Counts another pickup, returning the speed to play it at
+    fn next_speed(&mut self) -> f32 {
+        let speed = 1.0 + 0.05 * self.count.min(MAX_PICKUP_STREAK) as f32;
+        self.count += 1;
+        self.timer.reset();
+        speed
+    }
+}
+
 #[derive(Resource)]
 struct BackgroundMusic {
     handle: Handle<AudioSink>,
@@ -50,11 +132,7 @@ fn start_music(
         music,
         PlaybackSettings {
             repeat: true,
-            volume: if volume.mute {
-                0.0
-            } else {
-                2.0 * volume.sound_effects
-            },
+            volume: volume.music_volume(),
             speed: 1.0,
         },
     );
@@ -65,19 +143,21 @@ fn start_music(
     });
 }
 
+/// Keeps the music sink in line with the music bus. The sink only exists once the music has
+/// started playing, so this keeps trying until the volume has actually been applied
 fn set_music_volume(
     volume: Res<VolumeSettings>,
     audio_sinks: Res<Assets<AudioSink>>,
     background_music: Res<BackgroundMusic>,
+    mut applied: Local<Option<f32>>,
 ) {
-    if volume.is_changed() {
-        let Some(sink) = audio_sinks.get(&background_music.handle) else {debug!("Couldn't find background music!"); return};
-        if volume.mute {
-            sink.set_volume(0.0);
-        } else {
-            sink.set_volume(2.0 * volume.music);
-        }
+    let music_volume = volume.music_volume();
+    if *applied == Some(music_volume) {
+        return;
     }
+    let Some(sink) = audio_sinks.get(&background_music.handle) else { return; };
+    sink.set_volume(music_volume);
+    *applied = Some(music_volume);
 }
 
 fn handle_sound_events(
@@ -86,15 +166,14 @@ fn handle_sound_events(
     audio: Res<Audio>,
     listener: Res<VirtualListener>,
     volume: Res<VolumeSettings>,
+    mut pickup_streak: ResMut<PickupStreak>,
+    time: Res<Time>,
 ) {
+    pickup_streak.tick(time.delta());
     for ev in reader.iter() {
         let settings = PlaybackSettings {
             repeat: false,
-            volume: if volume.mute {
-                0.0
-            } else {
-                2.0 * volume.sound_effects
-            },
+            volume: volume.sound_effects_volume(),
             speed: 1.0,
         };
         match ev {
@@ -119,12 +198,17 @@ fn handle_sound_events(
             }
             SoundEvent::Collected => {
                 let sound = asset_server.load("sound/collect.mp3");
-                audio.play_with_settings(sound, settings);
+                audio.play_with_settings(sound, settings.with_speed(pickup_streak.next_speed()));
             }
             SoundEvent::NextStage => {
                 let sound = asset_server.load("sound/nextstage.mp3");
                 audio.play_with_settings(sound, settings);
             }
+            SoundEvent::ThresholdReached => {
+                // Higher than the stage chime that follows it
+                let sound = asset_server.load("sound/nextstage.mp3");
+                audio.play_with_settings(sound, settings.with_speed(1.5));
+            }
             SoundEvent::ShieldTransmute { relative_pos } => {
                 let sound = asset_server.load("sound/transmute.mp3");
                 audio.play_spatial_with_settings(
@@ -139,8 +223,298 @@ fn handle_sound_events(
                 let sound = asset_server.load("sound/hitrock.mp3");
                 audio.play_with_settings(sound, settings);
             }
+            SoundEvent::ContinuumPulse => {
+                // Slowed down so it's deeper than the usual stage chime
+                let sound = asset_server.load("sound/nextstage.mp3");
+                audio.play_with_settings(sound, settings.with_speed(0.5));
+            }
+            SoundEvent::Repaired => {
+                // Sped up to tell it apart from picking up reagents
+                let sound = asset_server.load("sound/collect.mp3");
+                audio.play_with_settings(sound, settings.with_speed(1.5));
+            }
+            SoundEvent::Warp => {
+                let sound = asset_server.load("sound/transmute.mp3");
+                audio.play_with_settings(sound, settings.with_speed(0.7));
+            }
+            SoundEvent::Combo { step } => {
+                // Climbs a little with every rock, topping out after a dozen or so
+                let speed = 1.0 + 0.08 * (*step).min(12) as f32;
+                let sound = asset_server.load("sound/collect.mp3");
+                audio.play_with_settings(sound, settings.with_speed(speed));
+            }
+            SoundEvent::IncomingRock => {
+                // Quiet and high, so it's a nudge rather than another impact
+                let sound = asset_server.load("sound/hitrock.mp3");
+                let volume = settings.volume * 0.4;
+                audio.play_with_settings(sound, settings.with_volume(volume).with_speed(1.8));
+            }
+            SoundEvent::TransmuteCharge { step } => {
+                // Each one is higher than the last, building up to the transmute itself
+                let speed = 1.2 + 0.3 * *step as f32;
+                let volume = settings.volume * 0.5;
+                let sound = asset_server.load("sound/collect.mp3");
+                audio.play_with_settings(sound, settings.with_volume(volume).with_speed(speed));
+            }
+            SoundEvent::UsedConsumable => {
+                // Sped up into a quick hiss, nothing like the pickup chime
+                let sound = asset_server.load("sound/transmute.mp3");
+                audio.play_with_settings(sound, settings.with_speed(1.8));
+            }
+            SoundEvent::LockedControl => {
+                // Low and quiet, a dull thunk rather than anything that sounds like it worked
+                let sound = asset_server.load("sound/hitrock.mp3");
+                let volume = settings.volume * 0.3;
+                audio.play_with_settings(sound, settings.with_volume(volume).with_speed(0.6));
+            }
+            SoundEvent::RockImpact {
+                relative_pos,
+                strength,
+            } => {
+                // Lower for the bigger hits
+                let speed = 1.2 - 0.4 * *strength;
+                let volume = settings.volume * *strength;
+                let sound = asset_server.load("sound/hitrock.mp3");
+                audio.play_spatial_with_settings(
+                    sound,
+                    settings.with_volume(volume).with_speed(speed),
+                    listener.transform,
+                    1.0,
+                    relative_pos.normalize_or_zero(),
+                );
+            }
+        }
+    }
+}
+
+/// There's no dedicated engine recording, so this is the transmute sound slowed right down into
+/// a low hum
+const ENGINE_SOUND: &str = "sound/transmute.mp3";
+/// How quickly the engine sound follows the thrust, per second
+const ENGINE_RESPONSE: f32 = 6.0;
+
+/// The thruster loop. It plays for the whole run and just gets louder and higher as the
+/// ship thrusts and speeds up
+#[derive(Resource)]
+struct EngineSound {
+    handle: Handle<AudioSink>,
+    volume: f32,
+    speed: f32,
+}
+
+fn start_engine_sound(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    let weak_handle = audio.play_with_settings(
+        asset_server.load(ENGINE_SOUND),
+        PlaybackSettings {
+            repeat: true,
+            volume: 0.0,
+            speed: 0.3,
+        },
+    );
+    commands.insert_resource(EngineSound {
+        handle: audio_sinks.get_handle(weak_handle),
+        volume: 0.0,
+        speed: 0.3,
+    });
+}
+
+fn update_engine_sound(
+    engine_sound: Option<ResMut<EngineSound>>,
+    player_query: Query<(&Player, &Velocity, &ActionState<Action>), LeadPlayer>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    volume: Res<VolumeSettings>,
+    time: Res<Time>,
+) {
+    let Some(mut engine_sound) = engine_sound else { return; };
+    let Ok((player, velocity, action_state)) = player_query.get_single() else { return; };
+    let thrust = movement_input(action_state).length();
+    let speed_fraction = (velocity.linvel.length() / player.max_speed).clamp(0.0, 1.0);
+
+    // A quiet hum while drifting, louder while the thrusters are firing
+    let target_volume = volume.sound_effects_volume() * (0.1 * speed_fraction + 0.4 * thrust);
+    let target_speed = 0.3 + 0.2 * speed_fraction + 0.1 * thrust;
+    let t = (ENGINE_RESPONSE * time.delta_seconds()).min(1.0);
+    engine_sound.volume += (target_volume - engine_sound.volume) * t;
+    engine_sound.speed += (target_speed - engine_sound.speed) * t;
+
+    let Some(sink) = audio_sinks.get(&engine_sound.handle) else { return; };
+    sink.set_volume(engine_sound.volume);
+    sink.set_speed(engine_sound.speed);
+}
+
+/// The layers of background ambience under the music. Each stage fades in its own layer. Like
+/// the engine, these are existing sounds slowed right down rather than dedicated recordings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AmbientBed {
+    /// A low hum while the player is finding their feet
+    Hum,
+    /// Radio crackle once there's exotic matter around
+    Crackle,
+    /// Whispers as Continuum gets closer
+    Whispers,
+}
+
+impl AmbientBed {
+    const ALL: [AmbientBed; 3] = [AmbientBed::Hum, AmbientBed::Crackle, AmbientBed::Whispers];
+
+    fn for_stage(stage: ProgressStages) -> Option<Self> {
+        match stage {
+            ProgressStages::None => None,
+            ProgressStages::Exploration | ProgressStages::GunAndHeat => Some(AmbientBed::Hum),
+            ProgressStages::CollectExotic | ProgressStages::ShieldAndStrange => {
+                Some(AmbientBed::Crackle)
+            }
+            ProgressStages::Continuum | ProgressStages::End => Some(AmbientBed::Whispers),
+        }
+    }
+
+    fn sound(&self) -> &'static str {
+        match self {
+            AmbientBed::Hum => "sound/rock.mp3",
+            AmbientBed::Crackle => "sound/hitrock.mp3",
+            AmbientBed::Whispers => "sound/nextstage.mp3",
+        }
+    }
+
+    fn speed(&self) -> f32 {
+        match self {
+            AmbientBed::Hum => 0.25,
+            AmbientBed::Crackle => 0.6,
+            AmbientBed::Whispers => 0.35,
+        }
+    }
+
+    /// How loud the layer is compared to the music
+    fn level(&self) -> f32 {
+        match self {
+            AmbientBed::Hum => 0.4,
+            AmbientBed::Crackle => 0.15,
+            AmbientBed::Whispers => 0.3,
+        }
+    }
+}
+
+const AMBIENT_CROSSFADE_SECONDS: f32 = 4.0;
+
+#[derive(Debug)]
+struct AmbientLayer {
+    bed: AmbientBed,
+    handle: Handle<AudioSink>,
+    /// From 0 when silent to 1 when fully faded in
+    fade: f32,
+}
+
+/// Every layer loops for the whole run. Switching stages just crossfades between them
+#[derive(Resource, Debug)]
+struct AmbientMixer {
+    layers: Vec<AmbientLayer>,
+}
+
+fn start_ambient_beds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    let layers = AmbientBed::ALL
+        .iter()
+        .map(|bed| {
+            let weak_handle = audio.play_with_settings(
+                asset_server.load(bed.sound()),
+                PlaybackSettings {
+                    repeat: true,
+                    volume: 0.0,
+                    speed: bed.speed(),
+                },
+            );
+            AmbientLayer {
+                bed: *bed,
+                handle: audio_sinks.get_handle(weak_handle),
+                fade: 0.0,
+            }
+        })
+        .collect();
+    commands.insert_resource(AmbientMixer { layers });
+}
+
+fn update_ambient_beds(
+    mixer: Option<ResMut<AmbientMixer>>,
+    stage: Res<State<ProgressStages>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    volume: Res<VolumeSettings>,
+    time: Res<Time>,
+) {
+    let Some(mut mixer) = mixer else { return; };
+    let current = AmbientBed::for_stage(stage.0);
+    let step = time.delta_seconds() / AMBIENT_CROSSFADE_SECONDS;
+    for layer in &mut mixer.layers {
+        let target = if current == Some(layer.bed) { 1.0 } else { 0.0 };
+        layer.fade = if layer.fade < target {
+            (layer.fade + step).min(target)
+        } else {
+            (layer.fade - step).max(target)
+        };
+        let Some(sink) = audio_sinks.get(&layer.handle) else { continue; };
+        sink.set_volume(volume.music_volume() * layer.bed.level() * layer.fade);
+    }
+}
+
+/// The sinks that only play during a run
+fn run_sinks<'a>(
+    engine_sound: Option<&'a EngineSound>,
+    mixer: Option<&'a AmbientMixer>,
+) -> impl Iterator<Item = &'a Handle<AudioSink>> {
+    let ambient_sinks = mixer
+        .into_iter()
+        .flat_map(|mixer| mixer.layers.iter().map(|layer| &layer.handle));
+    engine_sound
+        .map(|engine_sound| &engine_sound.handle)
+        .into_iter()
+        .chain(ambient_sinks)
+}
+
+fn pause_run_sounds(
+    engine_sound: Option<Res<EngineSound>>,
+    mixer: Option<Res<AmbientMixer>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    for handle in run_sinks(engine_sound.as_deref(), mixer.as_deref()) {
+        if let Some(sink) = audio_sinks.get(handle) {
+            sink.pause();
+        }
+    }
+}
+
+fn resume_run_sounds(
+    engine_sound: Option<Res<EngineSound>>,
+    mixer: Option<Res<AmbientMixer>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    for handle in run_sinks(engine_sound.as_deref(), mixer.as_deref()) {
+        if let Some(sink) = audio_sinks.get(handle) {
+            sink.play();
+        }
+    }
+}
+
+fn stop_run_sounds(
+    mut commands: Commands,
+    engine_sound: Option<Res<EngineSound>>,
+    mixer: Option<Res<AmbientMixer>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    for handle in run_sinks(engine_sound.as_deref(), mixer.as_deref()) {
+        if let Some(sink) = audio_sinks.get(handle) {
+            sink.stop();
         }
     }
+    commands.remove_resource::<EngineSound>();
+    commands.remove_resource::<AmbientMixer>();
 }
 
 fn setup_sound(mut commands: Commands) {
@@ -155,9 +529,19 @@ impl Plugin for SoundPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SoundEvent>()
             .insert_resource(VolumeSettings::default())
+            .init_resource::<PickupStreak>()
             .add_startup_system(setup_sound)
             .add_startup_system(start_music)
             .add_system(set_music_volume)
-            .add_system(handle_sound_events);
+            .add_system(handle_sound_events)
+            .add_systems(
+                (start_engine_sound, start_ambient_beds).in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_systems(
+                (update_engine_sound, update_ambient_beds).in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(pause_run_sounds.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(resume_run_sounds.in_schedule(OnExit(GameState::Paused)))
+            .add_system(stop_run_sounds.in_schedule(OnEnter(GameState::Outro)));
     }
 }
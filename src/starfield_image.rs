@@ -0,0 +1,84 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Draws a square image of stars on black, which tiles seamlessly. Much cheaper to draw than
+/// the starfield shader, since it's only generated once
+#[derive(Debug, Clone)]
+pub struct BasicStarField {
+    /// Width and height of the image in pixels
+    pub size: u32,
+    pub num_stars: u32,
+    pub tint: Color,
+    /// The same seed always gives the same image. This uses its own random numbers so it
+    /// doesn't change the rest of a seeded run
+    pub seed: u64,
+}
+
+impl Default for BasicStarField {
+    fn default() -> Self {
+        Self {
+            size: 1024,
+            num_stars: 600,
+            tint: Color::WHITE,
+            seed: 0,
+        }
+    }
+}
+
+impl BasicStarField {
+    pub fn generate(&self) -> Image {
+        let size = self.size as i32;
+        let mut pixels = vec![0.0f32; (size * size * 3) as usize];
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let tint = self.tint.as_rgba_f32();
+
+        for _ in 0..self.num_stars {
+            let x = rng.gen_range(0..size);
+            let y = rng.gen_range(0..size);
+            // Most stars are faint, a few are bright and a bit bigger
+            let brightness: f32 = rng.gen_range(0.0f32..1.0).powi(3);
+            let radius = if brightness > 0.7 { 2 } else { 1 };
+            // Stars are slightly warmer or cooler than the tint
+            let warmth = rng.gen_range(-0.15..0.15);
+            let color = [tint[0] + warmth, tint[1], tint[2] - warmth];
+
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let falloff = 1.0 - (dx * dx + dy * dy) as f32 / ((radius + 1) as f32).powi(2);
+                    if falloff <= 0.0 {
+                        continue;
+                    }
+                    // Wrap around the edges so the image tiles
+                    let px = (x + dx).rem_euclid(size);
+                    let py = (y + dy).rem_euclid(size);
+                    let i = ((py * size + px) * 3) as usize;
+                    for c in 0..3 {
+                        pixels[i + c] += color[c] * brightness * falloff;
+                    }
+                }
+            }
+        }
+
+        let data = pixels
+            .chunks(3)
+            .flat_map(|p| {
+                let [r, g, b] = [p[0], p[1], p[2]].map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8);
+                [r, g, b, 255]
+            })
+            .collect();
+
+        Image::new(
+            Extent3d {
+                width: self.size,
+                height: self.size,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+}
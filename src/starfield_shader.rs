@@ -6,7 +6,7 @@ use bevy::{
     reflect::TypeUuid,
     render::{
         camera::{CameraOutputMode, CameraRenderGraph, ScalingMode},
-        render_resource::{AsBindGroup, BlendState, LoadOp, ShaderRef, ShaderType},
+        render_resource::{AsBindGroup, BlendState, LoadOp, ShaderRef},
         view::RenderLayers,
     },
 };
@@ -70,6 +70,8 @@ impl Default for StarfieldCameraBundle {
         Self {
             starfield_camera: StarfieldCamera,
             camera: Camera {
+                // Has to match the main camera, so they draw to the same texture
+                #[cfg(not(target_arch = "wasm32"))]
                 hdr: true,
                 output_mode: CameraOutputMode::Write {
                     blend_state: Some(BlendState::ALPHA_BLENDING),
@@ -89,7 +91,11 @@ impl Default for StarfieldCameraBundle {
             transform: Transform::from_xyz(0.0, 0.0, 10.0).looking_to(Vec3::NEG_Z, Vec3::Y),
             global_transform: Default::default(),
             camera_3d: Camera3d {
+                #[cfg(not(target_arch = "wasm32"))]
                 clear_color: ClearColorConfig::None,
+                // The first camera to draw each frame clears it
+                #[cfg(target_arch = "wasm32")]
+                clear_color: ClearColorConfig::Custom(Color::BLACK),
                 depth_load_op: Camera3dDepthLoadOp::Load,
                 ..Default::default()
             },
@@ -102,7 +108,15 @@ impl Default for StarfieldCameraBundle {
     }
 }
 
-#[derive(AsBindGroup, TypeUuid, Debug, Clone, ShaderType)]
+/// The black-body colours of stars at 6000K, 10000K, 60000K and 800000K
+pub const DEFAULT_STAR_PALETTE: [Vec4; 4] = [
+    Vec4::new(1.0, 0.965, 0.929, 1.0),
+    Vec4::new(0.791, 0.855, 1.0, 1.0),
+    Vec4::new(0.559, 0.703, 1.0, 1.0),
+    Vec4::new(0.391, 0.574, 1.0, 1.0),
+];
+
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
 #[uuid = "c58cc961-65cf-4eef-b3be-e12b99f55ec5"]
 // #[uniform(0, StarfieldMaterialUniform)]
 pub struct StarfieldMaterial {
@@ -114,6 +128,22 @@ pub struct StarfieldMaterial {
     pub resolution: Vec3,
     #[uniform(0)]
     pub time: f32,
+    #[uniform(0)]
+    pub tint: Color,
+    /// How many stars there are, relative to the shader's usual amount
+    #[uniform(0)]
+    pub density: f32,
+    /// How quickly the stars flicker. 0 means they hold still
+    #[uniform(0)]
+    pub twinkle_speed: f32,
+    /// How far the stars are twisted around the middle of the screen. 0 leaves them be
+    #[uniform(0)]
+    pub warp: f32,
+    /// The colours of the faint, common, bright and giant stars, in linear RGB
+    #[uniform(0)]
+    pub palette: [Vec4; 4],
+    /// Layers in front of the first are added on top of it, so they don't hide it
+    pub additive: bool,
 }
 
 impl Default for StarfieldMaterial {
@@ -123,22 +153,45 @@ impl Default for StarfieldMaterial {
             parallax_factor: 1.0,
             resolution: Vec3::ZERO,
             time: 0.0,
+            tint: Color::WHITE,
+            density: 1.0,
+            twinkle_speed: 0.0,
+            warp: 0.0,
+            palette: DEFAULT_STAR_PALETTE,
+            additive: false,
         }
     }
 }
 
+/// The WGSL shader works everywhere. The original GLSL shader is kept as a fallback, behind the
+/// `glsl-starfield` feature, for native drivers that have trouble with the WGSL version. The web
+/// build always uses WGSL
+const USE_GLSL_STARFIELD: bool =
+    cfg!(all(feature = "glsl-starfield", not(target_arch = "wasm32")));
+
 impl Material for StarfieldMaterial {
     fn vertex_shader() -> ShaderRef {
-        "shaders/starfield.vert".into()
+        if USE_GLSL_STARFIELD {
+            "shaders/starfield.vert".into()
+        } else {
+            ShaderRef::Default
+        }
     }
 
     fn fragment_shader() -> ShaderRef {
-        // "shaders/starfield.wgsl".into()
-        "shaders/starfield.frag".into()
+        if USE_GLSL_STARFIELD {
+            "shaders/starfield.frag".into()
+        } else {
+            "shaders/starfield.wgsl".into()
+        }
     }
 
     fn alpha_mode(&self) -> AlphaMode {
-        AlphaMode::Opaque
+        if self.additive {
+            AlphaMode::Add
+        } else {
+            AlphaMode::Opaque
+        }
     }
 
     fn specialize(
@@ -147,8 +200,11 @@ impl Material for StarfieldMaterial {
         _layout: &bevy::render::mesh::MeshVertexBufferLayout,
         _key: bevy::pbr::MaterialPipelineKey<Self>,
     ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
-        descriptor.vertex.entry_point = "main".into();
-        descriptor.fragment.as_mut().unwrap().entry_point = "main".into();
+        // GLSL shaders always use `main` as their entry point
+        if USE_GLSL_STARFIELD {
+            descriptor.vertex.entry_point = "main".into();
+            descriptor.fragment.as_mut().unwrap().entry_point = "main".into();
+        }
         Ok(())
     }
 }
@@ -176,12 +232,17 @@ impl Material for StarfieldMaterial {
 // }
 
 fn update_starfield_on_resize(
-    starfield_camera_query: Query<&Projection, (With<StarfieldCamera>, Changed<Projection>)>,
+    starfield_camera_query: Query<Ref<Projection>, With<StarfieldCamera>>,
+    added_query: Query<(), Added<StarfieldMesh>>,
     mut starfield_query: Query<&mut Transform, With<StarfieldMesh>>,
     mut starfields: ResMut<Assets<StarfieldMaterial>>,
 ) {
     let Ok(proj) = starfield_camera_query.get_single() else { return; };
-    let Projection::Orthographic(proj) = proj else { return };
+    // Layers spawned after the camera need sizing too
+    if !proj.is_changed() && added_query.is_empty() {
+        return;
+    }
+    let Projection::Orthographic(proj) = &*proj else { return };
     let Rect { min, max } = proj.area;
     let size = Vec2::abs(max - min);
 
@@ -1,20 +1,23 @@
-use std::time::Duration;
-
-use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+use bevy::{
+    ecs::schedule::run_enter_schedule, prelude::*, render::view::RenderLayers, sprite::Anchor,
+};
+use bevy_rapier2d::prelude::*;
 
 use crate::{
-    heat::Heat,
-    inventory::{Inventory, Reagent},
-    reaction::{Reaction, Reactions},
-    shield::ShieldEmitter,
+    camera::{CameraShake, MainCamera},
+    campaign::Campaign,
+    game_time::GameTime,
+    physics::SHOCKWAVE_COLLISION_GROUPS,
+    player::{LeadPlayer, Player},
     sound::SoundEvent,
-    ui::{CustomUICamera, EnabledControls},
-    weapon::{CargoDumper, MainGun},
+    ui::{CustomUICamera, UIMarker},
 };
 
 #[derive(States, Default, Debug, Clone, Hash, Eq, PartialEq)]
 pub enum GameState {
+    /// Preloading assets before the main menu is shown
     #[default]
+    Loading,
     MainMenu,
     Intro,
     InGame,
@@ -23,7 +26,7 @@ pub enum GameState {
     Paused,
 }
 
-#[derive(States, Default, Debug, Clone, Hash, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(States, Default, Debug, Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub enum ProgressStages {
     #[default]
     None,
@@ -35,189 +38,349 @@ pub enum ProgressStages {
     End,
 }
 
-fn enter_exploration_stage(mut query: Query<&mut Inventory>) {
-    for mut inventory in &mut query {
-        inventory
-            .reagent_mut(Reagent::Minerals)
-            .set_threshold(Some(0.9));
-    }
-}
-
-fn update_exploration_stage(
-    query: Query<&Inventory>,
-    mut stage: ResMut<NextState<ProgressStages>>,
-) {
-    for inventory in &query {
-        let entry = inventory.reagent(Reagent::Minerals);
-        debug_assert!(
-            entry.threshold().is_some(),
-            "Minerals threshold is unset! It should be set here"
-        );
-        if entry.fraction() >= entry.threshold().unwrap() {
-            stage.set(ProgressStages::GunAndHeat);
+impl ProgressStages {
+    /// What the stage banner calls it
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Exploration => "EXPLORATION",
+            Self::GunAndHeat => "WEAPONS AND HEAT",
+            Self::CollectExotic => "EXOTIC MATTER",
+            Self::ShieldAndStrange => "SHIELD AND STRANGE MATTER",
+            Self::Continuum => "THE CONTINUUM",
+            Self::End => "ESCAPE",
         }
     }
 }
 
-fn exit_exploration_stage(mut query: Query<&mut Inventory>) {
-    for mut inventory in &mut query {
-        let entry = inventory.reagent_mut(Reagent::Minerals);
-        entry.set_threshold(None);
-        entry.add(-entry.current());
-    }
+/// The banner across the middle of the screen announcing a new stage. It slides in from the
+/// left, holds, then fades out
+#[derive(Component, Debug)]
+struct StageBanner {
+    age: f32,
 }
 
-fn enter_gun_and_heat_stage(
-    mut query: Query<(&mut Heat, &mut MainGun)>,
-    mut enabled_controls: ResMut<EnabledControls>,
-    mut sound_event_writer: EventWriter<SoundEvent>,
+const BANNER_SLIDE_SECONDS: f32 = 0.4;
+const BANNER_HOLD_SECONDS: f32 = 2.5;
+const BANNER_FADE_SECONDS: f32 = 0.8;
+/// How far left of its resting place the banner starts
+const BANNER_SLIDE_DISTANCE: f32 = 400.0;
+/// Above the middle of the screen, so it doesn't cover the ship
+const BANNER_HEIGHT: f32 = 150.0;
+const BANNER_COLOR: Color = Color::rgb(1.0, 0.9, 0.1);
+
+fn show_stage_banner(
+    mut commands: Commands,
+    stage: Res<State<ProgressStages>>,
+    campaign: Option<Res<Campaign>>,
+    banner_query: Query<Entity, With<StageBanner>>,
+    asset_server: Res<AssetServer>,
 ) {
-    for (mut heat, mut main_gun) in &mut query {
-        heat.set_enabled(true);
-        // heat.set_threshold_visible(false);
-        main_gun.enabled = true;
+    for e in &banner_query {
+        commands.entity(e).despawn_recursive();
     }
-    *enabled_controls |= EnabledControls::Shoot;
-    sound_event_writer.send(SoundEvent::NextStage);
+    let title = stage.0.title();
+    if title.is_empty() {
+        return;
+    }
+    let unlocks: Vec<_> = campaign
+        .as_ref()
+        .and_then(|campaign| campaign.stage(stage.0))
+        .map(|campaign_stage| {
+            campaign_stage
+                .unlocks
+                .iter()
+                .map(|unlock| unlock.name())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    let mut sections = vec![TextSection::new(
+        title,
+        TextStyle {
+            font: font.clone(),
+            font_size: 72.0,
+            color: BANNER_COLOR,
+        },
+    )];
+    if !unlocks.is_empty() {
+        sections.push(TextSection::new(
+            format!("\nNEW SYSTEMS ONLINE: {}", unlocks.join(", ")),
+            TextStyle {
+                font,
+                font_size: 36.0,
+                color: Color::WHITE,
+            },
+        ));
+    }
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_sections(sections).with_alignment(TextAlignment::Center),
+            text_anchor: Anchor::Center,
+            transform: Transform::from_xyz(-BANNER_SLIDE_DISTANCE, BANNER_HEIGHT, 2.0),
+            ..Default::default()
+        },
+        StageBanner { age: 0.0 },
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
 }
 
-fn update_gun_and_heat_stage(
-    query: Query<&Inventory>,
-    mut stage: ResMut<NextState<ProgressStages>>,
+fn animate_stage_banners(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut StageBanner, &mut Text, &mut Transform)>,
+    game_time: Res<GameTime>,
 ) {
-    for inventory in &query {
-        let entry = inventory.reagent(Reagent::Exotic);
-        if entry.current() > 0.0 {
-            stage.set(ProgressStages::CollectExotic);
+    for (e, mut banner, mut text, mut transform) in &mut query {
+        banner.age += game_time.delta_seconds();
+        let slide = (banner.age / BANNER_SLIDE_SECONDS).min(1.0);
+        // Eases out, so it comes to a stop rather than hitting one
+        let slide = 1.0 - (1.0 - slide).powi(3);
+        transform.translation.x = -BANNER_SLIDE_DISTANCE * (1.0 - slide);
+
+        let fading = banner.age - BANNER_SLIDE_SECONDS - BANNER_HOLD_SECONDS;
+        if fading >= BANNER_FADE_SECONDS {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        let alpha = slide * (1.0 - (fading / BANNER_FADE_SECONDS).max(0.0));
+        for section in &mut text.sections {
+            section.style.color.set_a(alpha);
         }
     }
 }
 
-fn exit_gun_and_heat_stage(mut query: Query<&mut Heat>) {
-    for mut heat in &mut query {
-        heat.set_threshold_visible(true);
-    }
+#[derive(Component, Debug)]
+pub struct FadeOut {
+    timer: Timer,
 }
 
-fn enter_collect_exotic_stage(
-    mut query: Query<&mut Inventory>,
-    mut sound_event_writer: EventWriter<SoundEvent>,
-) {
-    for mut inventory in &mut query {
-        inventory
-            .reagent_mut(Reagent::Exotic)
-            .set_threshold(Some(0.9));
-    }
-    sound_event_writer.send(SoundEvent::NextStage);
+/// How long the player has to outrun the shockwave for
+const ESCAPE_DURATION: f32 = 20.0;
+/// How long the shockwave waits before it starts expanding, to give the player a head start
+const SHOCKWAVE_DELAY: f32 = 2.0;
+/// A little slower than the player's top speed, so it can be outrun but not by much
+const SHOCKWAVE_SPEED: f32 = 13.0;
+/// The camera starts shaking when the shockwave gets this close to the player
+const SHAKE_DISTANCE: f32 = 25.0;
+const MAX_SHAKE: f32 = 0.4;
+
+#[derive(Resource, Debug)]
+pub struct EscapeSequence {
+    /// Where the shockwave comes from, and where the player is sent back to if it catches them
+    checkpoint: Vec3,
+    timer: Timer,
 }
 
-fn update_collect_exotic_stage(
-    query: Query<&Inventory>,
-    mut stage: ResMut<NextState<ProgressStages>>,
+#[derive(Component, Debug)]
+pub struct Shockwave {
+    radius: f32,
+    speed: f32,
+    delay_timer: Timer,
+}
+
+#[derive(Component, Debug)]
+struct EscapeCountdown;
+
+#[derive(Resource, Debug)]
+struct ShockwaveVisuals {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+fn setup_shockwave_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    for inventory in &query {
-        let entry = inventory.reagent(Reagent::Exotic);
-        debug_assert!(
-            entry.threshold().is_some(),
-            "Exotic threshold is unset! It should be set here"
-        );
-        if entry.fraction() >= entry.threshold().unwrap() {
-            stage.set(ProgressStages::ShieldAndStrange);
+    let mesh = meshes.add(
+        shape::UVSphere {
+            radius: 1.0,
+            ..Default::default()
         }
-    }
-}
+        .into(),
+    );
+
+    let material = materials.add(StandardMaterial {
+        alpha_mode: AlphaMode::Blend,
+        base_color: Color::rgba(1.0, 0.9, 0.1, 0.25),
+        emissive: Color::rgb(1.0, 0.9, 0.1) * 3.0,
+        double_sided: true,
+        cull_mode: None,
+        ..Default::default()
+    });
 
-fn exit_collect_exotic_stage(mut query: Query<&mut Inventory>) {
-    for mut inventory in &mut query {
-        inventory.reagent_mut(Reagent::Exotic).set_threshold(None);
-    }
+    commands.insert_resource(ShockwaveVisuals { mesh, material });
 }
 
-fn enter_shield_and_strange_stage(
-    mut query: Query<(&mut Inventory, &mut ShieldEmitter, &mut CargoDumper)>,
-    mut enabled_controls: ResMut<EnabledControls>,
+fn enter_end_stage(
+    mut commands: Commands,
+    escape_sequence: Option<Res<EscapeSequence>>,
+    mut player_query: Query<(&mut Transform, &mut Velocity), LeadPlayer>,
+    shockwave_visuals: Res<ShockwaveVisuals>,
+    asset_server: Res<AssetServer>,
     mut sound_event_writer: EventWriter<SoundEvent>,
 ) {
-    for (mut inventory, mut shield_emitter, mut cargo_dumper) in &mut query {
-        inventory
-            .reagent_mut(Reagent::Strange)
-            .set_threshold(Some(0.9));
-        shield_emitter.enabled = true;
-        cargo_dumper.enabled = true;
-    }
-    *enabled_controls |= EnabledControls::Dump | EnabledControls::Shield;
+    let Ok((mut player_transform, mut velocity)) = player_query.get_single_mut() else { return; };
+
+    let checkpoint = if let Some(escape_sequence) = escape_sequence {
+        // We've been here before, so the shockwave caught the player. Send them back to the checkpoint
+        player_transform.translation = escape_sequence.checkpoint;
+        *velocity = Velocity::zero();
+        escape_sequence.checkpoint
+    } else {
+        player_transform.translation
+    };
+
+    commands.insert_resource(EscapeSequence {
+        checkpoint,
+        timer: Timer::from_seconds(ESCAPE_DURATION, TimerMode::Once),
+    });
+
+    commands.spawn((
+        Shockwave {
+            radius: 0.0,
+            speed: SHOCKWAVE_SPEED,
+            delay_timer: Timer::from_seconds(SHOCKWAVE_DELAY, TimerMode::Once),
+        },
+        PbrBundle {
+            mesh: shockwave_visuals.mesh.clone(),
+            material: shockwave_visuals.material.clone(),
+            transform: Transform::from_xyz(checkpoint.x, checkpoint.y, checkpoint.z)
+                .with_scale(Vec3::ZERO),
+            ..Default::default()
+        },
+    ));
+
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: 60.0,
+                    color: Color::rgb(1.0, 0.9, 0.1),
+                },
+            ),
+            text_anchor: Anchor::TopCenter,
+            ..Default::default()
+        },
+        EscapeCountdown,
+        RenderLayers::layer(1),
+    ));
+
     sound_event_writer.send(SoundEvent::NextStage);
 }
 
-fn update_shield_and_strange_stage(
-    query: Query<&Inventory>,
-    mut stage: ResMut<NextState<ProgressStages>>,
+fn expand_shockwave(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Shockwave, &mut Transform)>,
+    game_time: Res<GameTime>,
 ) {
-    for inventory in &query {
-        let entry = inventory.reagent(Reagent::Strange);
-        debug_assert!(
-            entry.threshold().is_some(),
-            "Strange threshold is unset! It should be set here"
-        );
-        if entry.fraction() >= entry.threshold().unwrap() {
-            stage.set(ProgressStages::Continuum);
+    for (e, mut shockwave, mut transform) in &mut query {
+        shockwave
+            .delay_timer
+            .tick(game_time.delta());
+        if shockwave.delay_timer.just_finished() {
+            // Only give it a collider once it starts moving, otherwise it would catch the player
+            // immediately after they're sent back to the checkpoint
+            commands.entity(e).insert((
+                Collider::ball(1.0),
+                SHOCKWAVE_COLLISION_GROUPS,
+                Sensor,
+                ActiveEvents::COLLISION_EVENTS,
+            ));
+        }
+        if !shockwave.delay_timer.finished() {
+            continue;
         }
-    }
-}
 
-fn exit_shield_and_strange_stage(mut query: Query<&mut Inventory>) {
-    for mut inventory in &mut query {
-        inventory.reagent_mut(Reagent::Strange).set_threshold(None);
+        shockwave.radius += shockwave.speed * game_time.delta_seconds();
+        transform.scale = Vec3::splat(shockwave.radius);
     }
 }
 
-fn enter_continuum_stage(
-    mut query: Query<&mut Inventory>,
-    mut reactions: ResMut<Reactions>,
+fn handle_shockwave_collisions(
+    mut reader: EventReader<CollisionEvent>,
+    shockwave_query: Query<&Shockwave>,
+    player_query: Query<&Player>,
+    mut stage: ResMut<NextState<ProgressStages>>,
     mut sound_event_writer: EventWriter<SoundEvent>,
 ) {
-    for mut inventory in &mut query {
-        inventory
-            .reagent_mut(Reagent::Continuum)
-            .set_threshold(Some(0.99));
-    }
-    reactions.reactions.push(Reaction {
-        reagent1: Reagent::Exotic,
-        reagent2: Some(Reagent::Strange),
-        needs_heat: true,
-        rate: 1.0,
-        result: Some(Reagent::Continuum),
-    });
-    sound_event_writer.send(SoundEvent::NextStage);
-}
-
-fn update_continuum_stage(query: Query<&Inventory>, mut stage: ResMut<NextState<ProgressStages>>) {
-    for inventory in &query {
-        let entry = inventory.reagent(Reagent::Continuum);
-        debug_assert!(
-            entry.threshold().is_some(),
-            "Continuum threshold is unset! It should be set here"
-        );
-        if entry.fraction() >= entry.threshold().unwrap() {
+    for ev in reader.iter() {
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        if (shockwave_query.get(*e1).is_ok() && player_query.get(*e2).is_ok())
+            || (shockwave_query.get(*e2).is_ok() && player_query.get(*e1).is_ok())
+        {
+            debug!("The shockwave caught the player, going back to the checkpoint");
+            // Re-entering the stage resets the escape sequence from the checkpoint
             stage.set(ProgressStages::End);
+            sound_event_writer.send(SoundEvent::RockCollision);
         }
     }
 }
 
-fn exit_continuum_stage(mut query: Query<&mut Inventory>) {
-    for mut inventory in &mut query {
-        inventory
-            .reagent_mut(Reagent::Continuum)
-            .set_threshold(None);
+fn shake_camera_near_shockwave(
+    shockwave_query: Query<(&Shockwave, &Transform)>,
+    player_query: Query<&Transform, LeadPlayer>,
+    mut camera_query: Query<&mut CameraShake, With<MainCamera>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let Ok(mut camera_shake) = camera_query.get_single_mut() else { return; };
+
+    camera_shake.intensity = 0.0;
+    for (shockwave, transform) in &shockwave_query {
+        let dist = Vec2::distance(
+            player_transform.translation.truncate(),
+            transform.translation.truncate(),
+        ) - shockwave.radius;
+        let t = 1.0 - (dist / SHAKE_DISTANCE).clamp(0.0, 1.0);
+        camera_shake.intensity = camera_shake.intensity.max(t * MAX_SHAKE);
     }
 }
 
-#[derive(Component, Debug)]
-pub struct FadeOut {
-    timer: Timer,
+fn update_escape_countdown(
+    mut query: Query<(&mut Text, &mut Transform), With<EscapeCountdown>>,
+    escape_sequence: Res<EscapeSequence>,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else {return;};
+    let Some(size) = ui_camera.logical_viewport_size() else {return;};
+
+    for (mut text, mut transform) in &mut query {
+        transform.translation = Vec3::new(0.0, size.y / 2.0 - 4.0, 2.0);
+        let remaining = escape_sequence.timer.remaining_secs();
+        text.sections[0].value = if remaining > 0.0 {
+            format!("ESCAPE {:.1}", remaining)
+        } else {
+            "".into()
+        };
+    }
 }
 
-fn enter_end_stage(mut commands: Commands, mut sound_event_writer: EventWriter<SoundEvent>) {
+fn tick_escape_sequence(
+    mut commands: Commands,
+    mut escape_sequence: ResMut<EscapeSequence>,
+    shockwave_query: Query<Entity, With<Shockwave>>,
+    mut camera_query: Query<&mut CameraShake, With<MainCamera>>,
+    game_time: Res<GameTime>,
+) {
+    escape_sequence
+        .timer
+        .tick(game_time.delta());
+    if !escape_sequence.timer.just_finished() {
+        return;
+    }
+
+    // The player made it, so get rid of the shockwave and fade out
+    for e in &shockwave_query {
+        commands.entity(e).despawn_recursive();
+    }
+    if let Ok(mut camera_shake) = camera_query.get_single_mut() {
+        camera_shake.intensity = 0.0;
+    }
     commands.spawn((
         SpriteBundle {
             sprite: Sprite {
@@ -234,26 +397,21 @@ fn enter_end_stage(mut commands: Commands, mut sound_event_writer: EventWriter<S
         },
         RenderLayers::layer(1), // So the ui camera can see it
     ));
-    sound_event_writer.send(SoundEvent::NextStage);
 }
 
 fn update_end_stage(
     mut query: Query<(&mut FadeOut, &mut Sprite)>,
-    current_state: Res<State<GameState>>,
     mut next_state: ResMut<NextState<GameState>>,
     ui_camera: Query<&Camera, With<CustomUICamera>>,
-    time: Res<Time>,
+    game_time: Res<GameTime>,
 ) {
-    if current_state.0 != GameState::InGame {
-        return;
-    }
     let Ok(ui_camera) = ui_camera.get_single() else {return;};
     let Some(size) = ui_camera.logical_viewport_size() else {return;};
 
     for (mut fadeout, mut sprite) in &mut query {
         fadeout
             .timer
-            .tick(Duration::from_secs_f32(time.delta_seconds()));
+            .tick(game_time.delta());
         let a = fadeout.timer.percent();
         sprite.custom_size = Some(size);
         sprite.color = Color::BLACK.with_a(a);
@@ -263,48 +421,85 @@ fn update_end_stage(
     }
 }
 
-fn exit_end_stage(mut commands: Commands, query: Query<Entity, With<FadeOut>>) {
+#[allow(clippy::type_complexity)]
+fn exit_end_stage(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<FadeOut>, With<Shockwave>, With<EscapeCountdown>)>>,
+    mut camera_query: Query<&mut CameraShake, With<MainCamera>>,
+) {
     for e in &query {
         commands.entity(e).despawn_recursive();
     }
+    if let Ok(mut camera_shake) = camera_query.get_single_mut() {
+        camera_shake.intensity = 0.0;
+    }
 }
 
-pub struct StatePlugin;
-
-impl Plugin for StatePlugin {
-    fn build(&self, app: &mut App) {
-        app.add_state::<GameState>().add_state::<ProgressStages>();
-
-        app.add_system(enter_exploration_stage.in_schedule(OnEnter(ProgressStages::Exploration)))
-            .add_system(update_exploration_stage.in_set(OnUpdate(ProgressStages::Exploration)))
-            .add_system(exit_exploration_stage.in_schedule(OnExit(ProgressStages::Exploration)));
-
-        app.add_system(enter_gun_and_heat_stage.in_schedule(OnEnter(ProgressStages::GunAndHeat)))
-            .add_system(update_gun_and_heat_stage.in_set(OnUpdate(ProgressStages::GunAndHeat)))
-            .add_system(exit_gun_and_heat_stage.in_schedule(OnExit(ProgressStages::GunAndHeat)));
-
-        app.add_system(
-            enter_collect_exotic_stage.in_schedule(OnEnter(ProgressStages::CollectExotic)),
-        )
-        .add_system(update_collect_exotic_stage.in_set(OnUpdate(ProgressStages::CollectExotic)))
-        .add_system(exit_collect_exotic_stage.in_schedule(OnExit(ProgressStages::CollectExotic)));
+fn cleanup_escape_sequence(mut commands: Commands) {
+    commands.remove_resource::<EscapeSequence>();
+}
 
-        app.add_system(
-            enter_shield_and_strange_stage.in_schedule(OnEnter(ProgressStages::ShieldAndStrange)),
-        )
-        .add_system(
-            update_shield_and_strange_stage.in_set(OnUpdate(ProgressStages::ShieldAndStrange)),
-        )
-        .add_system(
-            exit_shield_and_strange_stage.in_schedule(OnExit(ProgressStages::ShieldAndStrange)),
+/// The same as `add_state`, except that a stage set while the game state changes, like the first
+/// one when a run starts, is applied on that same frame. Left unordered, whichever transition
+/// happened to run first decided the frame, and replays started a frame out
+fn add_progress_stages(app: &mut App) {
+    app.init_resource::<State<ProgressStages>>()
+        .init_resource::<NextState<ProgressStages>>()
+        .add_systems(
+            (
+                run_enter_schedule::<ProgressStages>.run_if(run_once()),
+                apply_state_transition::<ProgressStages>,
+            )
+                .chain()
+                .after(apply_state_transition::<GameState>)
+                .in_base_set(CoreSet::StateTransitions),
         );
+    for stage in ProgressStages::variants() {
+        app.configure_set(
+            OnUpdate(stage)
+                .in_base_set(CoreSet::Update)
+                .run_if(in_state(stage)),
+        )
+        .add_schedule(OnEnter(stage), Schedule::new())
+        .add_schedule(OnExit(stage), Schedule::new());
+    }
+}
 
-        app.add_system(enter_continuum_stage.in_schedule(OnEnter(ProgressStages::Continuum)))
-            .add_system(update_continuum_stage.in_set(OnUpdate(ProgressStages::Continuum)))
-            .add_system(exit_continuum_stage.in_schedule(OnExit(ProgressStages::Continuum)));
+pub struct StatePlugin;
 
-        app.add_system(enter_end_stage.in_schedule(OnEnter(ProgressStages::End)))
-            .add_system(update_end_stage.in_set(OnUpdate(ProgressStages::End)))
-            .add_system(exit_end_stage.in_schedule(OnExit(ProgressStages::End)));
+impl Plugin for StatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<GameState>();
+        add_progress_stages(app);
+
+        for stage in [
+            ProgressStages::Exploration,
+            ProgressStages::GunAndHeat,
+            ProgressStages::CollectExotic,
+            ProgressStages::ShieldAndStrange,
+            ProgressStages::Continuum,
+            ProgressStages::End,
+        ] {
+            app.add_system(show_stage_banner.in_schedule(OnEnter(stage)));
+        }
+        app.add_system(animate_stage_banners.in_set(OnUpdate(GameState::InGame)));
+
+        app.add_startup_system(setup_shockwave_visuals)
+            .add_system(enter_end_stage.in_schedule(OnEnter(ProgressStages::End)))
+            .add_systems(
+                (
+                    expand_shockwave,
+                    handle_shockwave_collisions,
+                    shake_camera_near_shockwave,
+                    tick_escape_sequence,
+                    update_escape_countdown,
+                    update_end_stage,
+                )
+                    .chain()
+                    .in_set(OnUpdate(ProgressStages::End))
+                    .distributive_run_if(in_state(GameState::InGame)),
+            )
+            .add_system(exit_end_stage.in_schedule(OnExit(ProgressStages::End)))
+            .add_system(cleanup_escape_sequence.in_schedule(OnEnter(GameState::Outro)));
     }
 }
@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collectible::CollectionEvent,
+    comet::Comet,
+    enemy::PirateShip,
+    game_time::GameTime,
+    hull::Faction,
+    inventory::Reagent,
+    rock::{Rock, RockDestroyed},
+    state::{GameState, ProgressStages},
+    weapon::{FireMainGunEvent, Slug, TurretSlug},
+};
+
+/// Numbers for the current run, shown on the pause menu
+#[derive(Resource, Debug, Default, Clone)]
+pub struct RunStats {
+    pub time: Duration,
+    pub rocks_destroyed: u32,
+    /// Indexed by `Reagent`, and only as long as the last reagent that's been picked up
+    pub reagents_gathered: Vec<f32>,
+    pub shots_fired: u32,
+    /// Shots that hit a rock, pirate or comet
+    pub shots_hit: u32,
+    /// The run time when each stage was reached, in order
+    pub stage_starts: Vec<(ProgressStages, Duration)>,
+}
+
+impl RunStats {
+    pub fn gathered(&self, reagent: Reagent) -> f32 {
+        self.reagents_gathered
+            .get(reagent.index())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The fraction of shots that hit something, or `None` before the first shot
+    pub fn accuracy(&self) -> Option<f32> {
+        if self.shots_fired == 0 {
+            return None;
+        }
+        Some(self.shots_hit as f32 / self.shots_fired as f32)
+    }
+
+    /// How long was spent in each stage. The last stage runs until the end of the run
+    pub fn stage_splits(&self) -> impl Iterator<Item = (ProgressStages, Duration)> + '_ {
+        self.stage_starts.iter().enumerate().map(|(i, (stage, start))| {
+            let end = self
+                .stage_starts
+                .get(i + 1)
+                .map_or(self.time, |(_, next_start)| *next_start);
+            (*stage, end.saturating_sub(*start))
+        })
+    }
+}
+
+fn reset_run_stats(mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
+
+fn tick_run_time(mut run_stats: ResMut<RunStats>, game_time: Res<GameTime>) {
+    run_stats.time += game_time.delta();
+}
+
+fn record_stage_starts(progress: Res<State<ProgressStages>>, mut run_stats: ResMut<RunStats>) {
+    if !progress.is_changed() {
+        return;
+    }
+    let stage = progress.0;
+    if stage == ProgressStages::None {
+        return;
+    }
+    if run_stats.stage_starts.last().map(|(last, _)| *last) == Some(stage) {
+        return;
+    }
+    let time = run_stats.time;
+    run_stats.stage_starts.push((stage, time));
+}
+
+fn count_shots(mut reader: EventReader<FireMainGunEvent>, mut run_stats: ResMut<RunStats>) {
+    run_stats.shots_fired += reader.iter().count() as u32;
+}
+
+fn count_reagents(mut reader: EventReader<CollectionEvent>, mut run_stats: ResMut<RunStats>) {
+    for ev in reader.iter() {
+        let index = ev.reagent.index();
+        if run_stats.reagents_gathered.len() <= index {
+            run_stats.reagents_gathered.resize(index + 1, 0.0);
+        }
+        run_stats.reagents_gathered[index] += ev.amount;
+    }
+}
+
+fn count_rocks(mut reader: EventReader<RockDestroyed>, mut run_stats: ResMut<RunStats>) {
+    run_stats.rocks_destroyed += reader.iter().count() as u32;
+}
+
+fn count_hits(
+    mut reader: EventReader<CollisionEvent>,
+    // Turrets fire on their own, so their shots don't count towards the player's accuracy
+    slug_query: Query<&Faction, (With<Slug>, Without<TurretSlug>)>,
+    rock_query: Query<(), With<Rock>>,
+    pirate_query: Query<(), With<PirateShip>>,
+    comet_query: Query<(), With<Comet>>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    for ev in reader.iter() {
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        for (slug, target) in [(*e1, *e2), (*e2, *e1)] {
+            let Ok(faction) = slug_query.get(slug) else { continue; };
+            if *faction != Faction::Player {
+                continue;
+            }
+            if rock_query.contains(target)
+                || pirate_query.contains(target)
+                || comet_query.contains(target)
+            {
+                run_stats.shots_hit += 1;
+            }
+        }
+    }
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_system(reset_run_stats.in_schedule(OnExit(GameState::Intro)))
+            .add_systems(
+                (
+                    tick_run_time,
+                    record_stage_starts,
+                    count_shots,
+                    count_reagents,
+                    count_rocks,
+                    count_hits,
+                )
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
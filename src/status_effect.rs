@@ -0,0 +1,214 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier2d::prelude::*;
+
+use crate::{collectible::Collectible, game_time::GameTime, heat::Heat, state::GameState};
+
+/// Something wearing off over time on a ship or a rock. Hazards and weapons put them on with
+/// `ApplyStatus`, and the systems here take care of what each one does and of running them out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusKind {
+    /// Heats a ship up. Rocks break apart when it burns out
+    Burning,
+    /// Takes `strength` off a ship's top speed, from 0 to 1
+    Slowed,
+    /// Pulls in collectibles from around the ship
+    Magnetized,
+    /// Speeds the ship's reactions up by `strength`, so 1 is twice as fast
+    Accelerated,
+}
+
+impl StatusKind {
+    /// What the HUD calls it
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Burning => "BURNING",
+            Self::Slowed => "SLOWED",
+            Self::Magnetized => "MAGNETIZED",
+            Self::Accelerated => "ACCELERATED",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Burning => Color::ORANGE_RED,
+            Self::Slowed => Color::rgb(0.5, 0.8, 1.0),
+            Self::Magnetized => Color::rgb(0.8, 0.4, 1.0),
+            Self::Accelerated => Color::rgb(0.9, 0.9, 0.2),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ActiveStatus {
+    kind: StatusKind,
+    strength: f32,
+    timer: Timer,
+}
+
+/// Every effect currently on something. Taken off again once they've all run out
+#[derive(Component, Debug, Default)]
+pub struct StatusEffects {
+    active: Vec<ActiveStatus>,
+}
+
+impl StatusEffects {
+    fn get(&self, kind: StatusKind) -> Option<&ActiveStatus> {
+        self.active.iter().find(|status| status.kind == kind)
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.get(kind).is_some()
+    }
+
+    /// How strong the effect is, or nothing when it isn't on
+    pub fn strength(&self, kind: StatusKind) -> f32 {
+        self.get(kind).map_or(0.0, |status| status.strength)
+    }
+
+    /// How much of the effect is left, from 1 when it's just started down to 0
+    pub fn remaining(&self, kind: StatusKind) -> f32 {
+        self.get(kind)
+            .map_or(0.0, |status| status.timer.percent_left())
+    }
+
+    /// The effects that are on, oldest first
+    pub fn kinds(&self) -> impl Iterator<Item = StatusKind> + '_ {
+        self.active.iter().map(|status| status.kind)
+    }
+
+    /// Putting an effect on again keeps whichever is stronger, and whichever has longer left
+    fn apply(&mut self, kind: StatusKind, seconds: f32, strength: f32) {
+        match self.active.iter_mut().find(|status| status.kind == kind) {
+            Some(status) => {
+                status.strength = status.strength.max(strength);
+                if status.timer.remaining_secs() < seconds {
+                    status.timer = Timer::from_seconds(seconds, TimerMode::Once);
+                }
+            }
+            None => self.active.push(ActiveStatus {
+                kind,
+                strength,
+                timer: Timer::from_seconds(seconds, TimerMode::Once),
+            }),
+        }
+    }
+}
+
+/// Puts an effect on an entity, or tops it up if it already has it
+#[derive(Debug, Clone, Copy)]
+pub struct ApplyStatus {
+    pub entity: Entity,
+    pub kind: StatusKind,
+    pub seconds: f32,
+    pub strength: f32,
+}
+
+/// Sent when an effect runs out on its own
+#[derive(Debug, Clone, Copy)]
+pub struct StatusExpired {
+    pub entity: Entity,
+    pub kind: StatusKind,
+}
+
+fn apply_statuses(
+    mut commands: Commands,
+    mut reader: EventReader<ApplyStatus>,
+    mut query: Query<&mut StatusEffects>,
+) {
+    // Anything without effects yet gets all of this frame's at once
+    let mut added: HashMap<Entity, StatusEffects> = HashMap::new();
+    for ev in reader.iter() {
+        match query.get_mut(ev.entity) {
+            Ok(mut effects) => effects.apply(ev.kind, ev.seconds, ev.strength),
+            Err(_) => added
+                .entry(ev.entity)
+                .or_default()
+                .apply(ev.kind, ev.seconds, ev.strength),
+        }
+    }
+    for (entity, effects) in added {
+        // It might not be around any more, a rock can be shot in the same frame it's set alight
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.insert(effects);
+        }
+    }
+}
+
+fn tick_statuses(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut StatusEffects)>,
+    mut writer: EventWriter<StatusExpired>,
+    game_time: Res<GameTime>,
+) {
+    for (entity, mut effects) in &mut query {
+        effects.active.retain_mut(|status| {
+            if status.timer.tick(game_time.delta()).finished() {
+                writer.send(StatusExpired {
+                    entity,
+                    kind: status.kind,
+                });
+                return false;
+            }
+            true
+        });
+        if effects.active.is_empty() {
+            commands.entity(entity).remove::<StatusEffects>();
+        }
+    }
+}
+
+/// How much heat a full strength burn adds every second
+const BURN_HEAT_PER_SECOND: f32 = 10.0;
+
+fn burn(mut query: Query<(&StatusEffects, &mut Heat)>, game_time: Res<GameTime>) {
+    for (effects, mut heat) in &mut query {
+        let strength = effects.strength(StatusKind::Burning);
+        if strength > 0.0 && heat.enabled() {
+            heat.add(BURN_HEAT_PER_SECOND * strength * game_time.delta_seconds());
+        }
+    }
+}
+
+/// Collectibles further away than this aren't pulled in
+const MAGNET_RANGE: f32 = 15.0;
+/// How hard collectibles are pulled in at full strength
+const MAGNET_ACCELERATION: f32 = 25.0;
+
+fn attract_collectibles(
+    magnet_query: Query<(&StatusEffects, &Transform), Without<Collectible>>,
+    mut collectible_query: Query<(&Transform, &mut Velocity), With<Collectible>>,
+    game_time: Res<GameTime>,
+) {
+    for (effects, magnet_transform) in &magnet_query {
+        let strength = effects.strength(StatusKind::Magnetized);
+        if strength <= 0.0 {
+            continue;
+        }
+        let magnet_pos = magnet_transform.translation.truncate();
+        for (transform, mut velocity) in &mut collectible_query {
+            let to_magnet = magnet_pos - transform.translation.truncate();
+            if to_magnet.length() > MAGNET_RANGE {
+                continue;
+            }
+            velocity.linvel += to_magnet.normalize_or_zero()
+                * MAGNET_ACCELERATION
+                * strength
+                * game_time.delta_seconds();
+        }
+    }
+}
+
+pub struct StatusEffectPlugin;
+
+impl Plugin for StatusEffectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyStatus>()
+            .add_event::<StatusExpired>()
+            .add_systems(
+                (apply_statuses, tick_statuses)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_systems((burn, attract_collectibles).in_set(OnUpdate(GameState::InGame)));
+    }
+}
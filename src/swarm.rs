@@ -0,0 +1,271 @@
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier2d::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    despawn::DespawnQueue,
+    game_time::GameTime,
+    input::Action,
+    particles::{Particle, ParticleBundle},
+    player::Player,
+    rock::{Rock, RockDestroyed},
+    state::{GameState, ProgressStages},
+    util::cosmetic::random_range,
+    weapon::{FireMode, MainGun},
+};
+
+/// Swarm mode unlocks once the player is close to escaping and the belts are at their densest
+const UNLOCK_STAGE: ProgressStages = ProgressStages::Continuum;
+
+const DARTS_PER_VOLLEY: usize = 6;
+/// The darts fan out over this angle when they're launched
+const SPREAD: f32 = PI / 2.0;
+const DART_SPEED: f32 = 25.0;
+/// How quickly a dart can change direction, in radians per second
+const DART_TURN_RATE: f32 = 6.0;
+/// Darts only go after rocks this close to them
+const SEEK_RANGE: f32 = 25.0;
+/// Roughly the radius of a rock's collider
+const HIT_DISTANCE: f32 = 0.9;
+
+pub struct SwarmFireEvent {
+    pub position: Vec3,
+    pub facing: f32,
+    /// The ship's velocity, added on to the darts'
+    pub velocity: Vec2,
+}
+
+/// A small self guided projectile that homes in on its own rock
+#[derive(Component, Debug)]
+pub struct Dart {
+    pub target: Option<Entity>,
+    pub velocity: Vec2,
+    pub lifetime_timer: Timer,
+    pub trail_timer: Timer,
+}
+
+#[derive(Resource, Debug)]
+struct DartAppearance {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    trail_mesh: Handle<Mesh>,
+    trail_material: Handle<StandardMaterial>,
+}
+
+fn setup_dart_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mesh = meshes.add(
+        shape::Capsule {
+            depth: 0.3,
+            radius: 0.05,
+            ..Default::default()
+        }
+        .into(),
+    );
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.6, 0.9, 1.0),
+        emissive: Color::rgb(0.4, 0.8, 1.0) * 4.0,
+        ..Default::default()
+    });
+    let trail_mesh = meshes.add(shape::Quad::new(Vec2::new(0.04, 0.3)).into());
+    let trail_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.4, 0.8, 1.0),
+        emissive: Color::rgb(0.2, 0.6, 1.0) * 2.0,
+        ..Default::default()
+    });
+
+    commands.insert_resource(DartAppearance {
+        mesh,
+        material,
+        trail_mesh,
+        trail_material,
+    });
+}
+
+fn unlock_swarm_mode(mut query: Query<&mut MainGun>, progress: Res<State<ProgressStages>>) {
+    if progress.0 < UNLOCK_STAGE {
+        return;
+    }
+    for mut main_gun in &mut query {
+        if !main_gun.swarm_unlocked {
+            debug!("Swarm fire mode unlocked");
+            main_gun.swarm_unlocked = true;
+        }
+    }
+}
+
+fn switch_fire_mode(mut query: Query<(&mut MainGun, &ActionState<Action>), With<Player>>) {
+    for (mut main_gun, action_state) in &mut query {
+        if !main_gun.swarm_unlocked || !action_state.just_pressed(Action::SwitchFireMode) {
+            continue;
+        }
+        main_gun.fire_mode = match main_gun.fire_mode {
+            FireMode::Slug => FireMode::Swarm,
+            FireMode::Swarm => FireMode::Slug,
+        };
+        debug!("Switched to {:?} fire mode", main_gun.fire_mode);
+    }
+}
+
+/// Returns the rocks closest to `position`, nearest first, skipping ones that are already taken
+fn nearest_rocks(
+    position: Vec2,
+    rock_query: &Query<(Entity, &Transform), With<Rock>>,
+    claimed: &HashSet<Entity>,
+) -> Vec<Entity> {
+    let mut rocks: Vec<_> = rock_query
+        .iter()
+        .filter(|(entity, _)| !claimed.contains(entity))
+        .map(|(entity, transform)| (entity, transform.translation.truncate().distance(position)))
+        .filter(|(_, distance)| *distance < SEEK_RANGE)
+        .collect();
+    rocks.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    rocks.into_iter().map(|(entity, _)| entity).collect()
+}
+
+fn launch_darts(
+    mut commands: Commands,
+    mut reader: EventReader<SwarmFireEvent>,
+    dart_query: Query<&Dart>,
+    rock_query: Query<(Entity, &Transform), With<Rock>>,
+    dart_appearance: Res<DartAppearance>,
+) {
+    for ev in reader.iter() {
+        let mut claimed: HashSet<Entity> = dart_query.iter().filter_map(|d| d.target).collect();
+        let mut targets = nearest_rocks(ev.position.truncate(), &rock_query, &claimed).into_iter();
+        for i in 0..DARTS_PER_VOLLEY {
+            let offset = (i as f32 / (DARTS_PER_VOLLEY - 1) as f32 - 0.5) * SPREAD;
+            let direction = Vec2::from_angle(ev.facing + offset);
+            let target = targets.next();
+            if let Some(target) = target {
+                claimed.insert(target);
+            }
+            commands.spawn((
+                Dart {
+                    target,
+                    velocity: direction * DART_SPEED + ev.velocity,
+                    lifetime_timer: Timer::from_seconds(2.5, TimerMode::Once),
+                    trail_timer: Timer::from_seconds(0.02, TimerMode::Repeating),
+                },
+                PbrBundle {
+                    mesh: dart_appearance.mesh.clone(),
+                    material: dart_appearance.material.clone(),
+                    transform: Transform::from_translation(ev.position)
+                        .with_rotation(Quat::from_rotation_z(PI / 2.0 + ev.facing + offset)),
+                    ..Default::default()
+                },
+            ));
+        }
+    }
+}
+
+fn steer_darts(
+    mut dart_query: Query<(Entity, &mut Dart, &mut Transform), Without<Rock>>,
+    rock_query: Query<(Entity, &Transform), With<Rock>>,
+    mut rock_destroyed_writer: EventWriter<RockDestroyed>,
+    game_time: Res<GameTime>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+) {
+    let mut claimed: HashSet<Entity> = dart_query.iter().filter_map(|(_, d, _)| d.target).collect();
+    // Two darts can reach the same rock in one frame if one had to retarget
+    let mut destroyed = HashSet::new();
+    let delta_seconds = game_time.delta_seconds();
+
+    for (entity, mut dart, mut transform) in &mut dart_query {
+        dart.lifetime_timer.tick(game_time.delta());
+        if dart.lifetime_timer.finished() {
+            despawn_queue.despawn(entity);
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let target = dart.target.and_then(|target| rock_query.get(target).ok());
+        let target = match target {
+            Some(target) => Some(target),
+            // The rock got destroyed some other way, go and find another one
+            None => {
+                let next = nearest_rocks(position, &rock_query, &claimed).first().copied();
+                dart.target = next;
+                if let Some(next) = next {
+                    claimed.insert(next);
+                }
+                next.and_then(|next| rock_query.get(next).ok())
+            }
+        };
+
+        if let Some((rock_entity, rock_transform)) = target {
+            let to_target = rock_transform.translation.truncate() - position;
+            if to_target.length() < HIT_DISTANCE {
+                if destroyed.insert(rock_entity) {
+                    rock_destroyed_writer.send(RockDestroyed {
+                        entity: rock_entity,
+                        position: rock_transform.translation,
+                    });
+                }
+                despawn_queue.despawn(entity);
+                continue;
+            }
+            let max_turn = DART_TURN_RATE * delta_seconds;
+            let turn = dart.velocity.angle_between(to_target).clamp(-max_turn, max_turn);
+            dart.velocity = Vec2::from_angle(turn).rotate(dart.velocity);
+        }
+        // Ease back towards cruising speed so the ship's own velocity doesn't carry them off
+        let speed = dart.velocity.length();
+        let new_speed = speed + (DART_SPEED - speed) * (1.0 - f32::powf(0.1, delta_seconds));
+        dart.velocity = dart.velocity.normalize_or_zero() * new_speed;
+
+        transform.translation += (dart.velocity * delta_seconds).extend(0.0);
+        let facing = dart.velocity.y.atan2(dart.velocity.x);
+        transform.rotation = Quat::from_rotation_z(PI / 2.0 + facing);
+    }
+}
+
+fn dart_trails(
+    mut commands: Commands,
+    mut query: Query<(&mut Dart, &Transform)>,
+    dart_appearance: Res<DartAppearance>,
+    game_time: Res<GameTime>,
+) {
+    for (mut dart, transform) in &mut query {
+        dart.trail_timer.tick(game_time.delta());
+        for _ in 0..dart.trail_timer.times_finished_this_tick() {
+            commands.spawn(ParticleBundle {
+                mesh: dart_appearance.trail_mesh.clone(),
+                material: dart_appearance.trail_material.clone(),
+                particle: Particle {
+                    lifetime_timer: Timer::from_seconds(0.25, TimerMode::Once),
+                },
+                velocity: Velocity::zero(),
+                transform: Transform::from_translation(transform.translation)
+                    .with_rotation(transform.rotation)
+                    .with_scale(Vec3::splat(random_range(0.8, 1.0))),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+pub struct SwarmPlugin;
+
+impl Plugin for SwarmPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SwarmFireEvent>()
+            .add_startup_system(setup_dart_appearance)
+            .add_systems(
+                (
+                    unlock_swarm_mode,
+                    switch_fire_mode,
+                    launch_darts,
+                    steer_darts,
+                    dart_trails,
+                )
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
@@ -0,0 +1,224 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadState, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+
+use crate::{
+    cli::LaunchOptions,
+    util::{parse_config_lines, warn_unknown_config},
+};
+
+/// The folder inside `assets` the game's own text is in
+pub const DEFAULT_TEXT_PACK: &str = "text";
+const INDEX_FILE: &str = "index.txt";
+
+/// A text file from the assets folder, markup and all
+#[derive(TypeUuid, Debug)]
+#[uuid = "4c1d8e27-6a3b-4f90-b5e2-7d9a0c3f1e68"]
+pub struct TextAsset(pub String);
+
+#[derive(Default)]
+struct TextAssetLoader;
+
+impl AssetLoader for TextAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let text = std::str::from_utf8(bytes)?.to_string();
+            load_context.set_default_asset(LoadedAsset::new(TextAsset(text)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+}
+
+/// Which of the pack's files a piece of text comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackFile {
+    Story,
+    Outro,
+    /// A hint, by the id the campaign knows it as
+    Hint(String),
+}
+
+/// The story and hints, read through the asset server from a folder with an `index.txt` saying
+/// which file is which. The campaign picks which hint goes with each stage. Edits to the files
+/// show up straight away in debug builds
+#[derive(Resource, Debug)]
+pub struct TextPack {
+    folder: String,
+    index: Handle<TextAsset>,
+    /// Whether the index has been read yet
+    read: bool,
+    story: Option<Handle<TextAsset>>,
+    outro: Option<Handle<TextAsset>>,
+    hints: Vec<(String, Handle<TextAsset>)>,
+}
+
+impl TextPack {
+    fn new(folder: String, asset_server: &AssetServer) -> Self {
+        Self {
+            index: asset_server.load(format!("{folder}/{INDEX_FILE}")),
+            folder,
+            read: false,
+            story: None,
+            outro: None,
+            hints: Vec::new(),
+        }
+    }
+
+    /// Each line of the index is a keyword followed by the file it names. Lines that can't be
+    /// read are skipped
+    fn read_index(&mut self, text: &str, asset_server: &AssetServer) {
+        self.story = None;
+        self.outro = None;
+        self.hints.clear();
+        let folder = self.folder.clone();
+        let load = |file: &str| asset_server.load(format!("{folder}/{}", file.trim()));
+        for (key, value) in parse_config_lines(text) {
+            match key {
+                "story" => self.story = Some(load(value)),
+                "outro" => self.outro = Some(load(value)),
+                "hint" => {
+                    let Some((id, file)) = value.split_once(' ') else {
+                        warn!("Couldn't read hint {value:?}");
+                        continue;
+                    };
+                    let handle = load(file);
+                    self.hints.push((id.to_string(), handle));
+                }
+                _ => warn_unknown_config("text pack entry", key),
+            }
+        }
+        self.read = true;
+    }
+
+    fn files(&self) -> impl Iterator<Item = &Handle<TextAsset>> {
+        self.story
+            .iter()
+            .chain(&self.outro)
+            .chain(self.hints.iter().map(|(_, handle)| handle))
+    }
+
+    /// Whether the index and every file it names have loaded
+    pub fn load_state(&self, asset_server: &AssetServer) -> LoadState {
+        match asset_server.get_load_state(&self.index) {
+            LoadState::Loaded if self.read => {
+                asset_server.get_group_load_state(self.files().map(|handle| handle.id()))
+            }
+            LoadState::Failed => LoadState::Failed,
+            _ => LoadState::Loading,
+        }
+    }
+
+    /// The file's text, or nothing if it's missing or hasn't loaded
+    pub fn text<'a>(&self, file: &PackFile, texts: &'a Assets<TextAsset>) -> &'a str {
+        let handle = match file {
+            PackFile::Story => self.story.as_ref(),
+            PackFile::Outro => self.outro.as_ref(),
+            PackFile::Hint(id) => self
+                .hints
+                .iter()
+                .find(|(hint, _)| hint == id)
+                .map(|(_, handle)| handle),
+        };
+        handle
+            .and_then(|handle| texts.get(handle))
+            .map_or("", |text| text.0.as_str())
+    }
+}
+
+/// Text on screen that comes from the pack. It's filled in again whenever the files change
+#[derive(Component, Debug, Clone)]
+pub struct PackText {
+    pub file: PackFile,
+    pub font_size: f32,
+    pub highlight_color: Color,
+    pub color: Color,
+}
+
+impl PackText {
+    pub fn sections(
+        &self,
+        font: Handle<Font>,
+        text_pack: &TextPack,
+        texts: &Assets<TextAsset>,
+    ) -> Vec<TextSection> {
+        crate::util::markup_to_text_sections(
+            text_pack.text(&self.file, texts),
+            font,
+            self.font_size,
+            self.highlight_color,
+            self.color,
+        )
+    }
+}
+
+fn load_text_pack(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    options: Option<Res<LaunchOptions>>,
+) {
+    let folder = options
+        .and_then(|options| options.text_pack.clone())
+        .unwrap_or_else(|| DEFAULT_TEXT_PACK.to_string());
+    debug!("Reading text from {folder}");
+    commands.insert_resource(TextPack::new(folder, &asset_server));
+}
+
+/// Reads the index as soon as it loads, and again whenever it's edited
+fn read_text_pack_index(
+    mut reader: EventReader<AssetEvent<TextAsset>>,
+    mut text_pack: ResMut<TextPack>,
+    texts: Res<Assets<TextAsset>>,
+    asset_server: Res<AssetServer>,
+) {
+    for ev in reader.iter() {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = ev else {
+            continue;
+        };
+        if *handle != text_pack.index {
+            continue;
+        }
+        let Some(index) = texts.get(handle) else { continue; };
+        text_pack.read_index(&index.0, &asset_server);
+    }
+}
+
+fn refresh_pack_texts(
+    mut query: Query<(&mut Text, &PackText)>,
+    text_pack: Res<TextPack>,
+    texts: Res<Assets<TextAsset>>,
+    asset_server: Res<AssetServer>,
+) {
+    if !text_pack.is_changed() && !texts.is_changed() {
+        return;
+    }
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    for (mut text, pack_text) in &mut query {
+        text.sections = pack_text.sections(font.clone(), &text_pack, &texts);
+    }
+}
+
+pub struct TextPackPlugin;
+
+impl Plugin for TextPackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<TextAsset>()
+            .init_asset_loader::<TextAssetLoader>()
+            .add_startup_system(load_text_pack)
+            .add_systems(
+                (read_text_pack_index, refresh_pack_texts)
+                    .chain()
+                    .distributive_run_if(resource_exists::<TextPack>()),
+            );
+    }
+}
@@ -0,0 +1,283 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    despawn::DespawnQueue,
+    game_time::GameTime,
+    hull::Faction,
+    input::Action,
+    inventory::{Inventory, Reagent},
+    player::{LeadPlayer, Player},
+    rock::Rock,
+    sound::SoundEvent,
+    state::GameState,
+    ui::EnabledControls,
+    weapon::{Slug, SlugBundle, SlugPool, SlugVisuals, TurretSlug},
+};
+
+/// How many minerals it takes to build a turret
+const TURRET_COST: f32 = 3.0;
+const MAX_TURRETS: usize = 3;
+
+/// An automated gun the player can leave behind. It shoots at the closest rock in range until its
+/// lifetime runs out.
+///
+/// Turrets keep track of their own heat instead of using `Heat`, which is the player's ship heat
+/// and takes part in reactions.
+#[derive(Component, Debug)]
+pub struct Turret {
+    pub fire_timer: Timer,
+    pub lifetime_timer: Timer,
+    pub range: f32,
+    pub projectile_speed: f32,
+    pub origin_distance: f32,
+    pub damage: f32,
+    pub heat: f32,
+    pub heat_limit: f32,
+    pub heat_per_shot: f32,
+    /// Heat lost per second
+    pub cooling_rate: f32,
+    /// Set once the turret hits its heat limit, and cleared once it's cooled all the way down
+    pub overheated: bool,
+    /// The way the barrel is pointing, in radians
+    pub facing: f32,
+}
+
+impl Default for Turret {
+    fn default() -> Self {
+        Self {
+            fire_timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+            lifetime_timer: Timer::from_seconds(20.0, TimerMode::Once),
+            range: 12.0,
+            projectile_speed: 45.0,
+            origin_distance: 1.0,
+            damage: 10.0,
+            heat: 0.0,
+            heat_limit: 100.0,
+            heat_per_shot: 15.0,
+            cooling_rate: 20.0,
+            overheated: false,
+            facing: 0.0,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct TurretBarrel;
+
+#[derive(Resource, Debug)]
+struct TurretAppearance {
+    base_mesh: Handle<Mesh>,
+    base_material: Handle<StandardMaterial>,
+    barrel_mesh: Handle<Mesh>,
+    barrel_material: Handle<StandardMaterial>,
+}
+
+fn setup_turret_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let base_mesh = meshes.add(
+        shape::Cylinder {
+            radius: 0.5,
+            height: 0.3,
+            ..Default::default()
+        }
+        .into(),
+    );
+    let base_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.4, 0.4, 0.45),
+        metallic: 0.7,
+        ..Default::default()
+    });
+    let barrel_mesh = meshes.add(shape::Box::new(0.8, 0.15, 0.15).into());
+    let barrel_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.8, 0.3, 0.1),
+        emissive: Color::rgb(0.5, 0.1, 0.0),
+        ..Default::default()
+    });
+
+    commands.insert_resource(TurretAppearance {
+        base_mesh,
+        base_material,
+        barrel_mesh,
+        barrel_material,
+    });
+}
+
+fn deploy_turret(
+    mut commands: Commands,
+    mut player_query: Query<
+        (&Player, &Transform, &mut Inventory, &ActionState<Action>),
+        LeadPlayer,
+    >,
+    turret_query: Query<(), With<Turret>>,
+    enabled_controls: Res<EnabledControls>,
+    turret_appearance: Res<TurretAppearance>,
+) {
+    // Turrets are only any use once the player knows how to shoot
+    if !enabled_controls.contains(EnabledControls::Shoot) {
+        return;
+    }
+    let Ok((player, transform, mut inventory, action_state)) =
+        player_query.get_single_mut() else { return; };
+    if !action_state.just_pressed(Action::DeployTurret) {
+        return;
+    }
+    if turret_query.iter().count() >= MAX_TURRETS {
+        debug!("Already have {MAX_TURRETS} turrets out");
+        return;
+    }
+    if inventory.reagent(Reagent::MINERALS).current() < TURRET_COST {
+        debug!("Not enough minerals to build a turret");
+        return;
+    }
+    inventory.reagent_mut(Reagent::MINERALS).add(-TURRET_COST);
+
+    // Drop it behind the ship so it's not in the way of its own shots
+    let pos = transform.translation.truncate() - Vec2::from_angle(player.facing) * 2.5;
+    debug!("Deploying a turret at {pos:?}");
+    commands
+        .spawn((
+            Turret {
+                facing: player.facing,
+                ..Default::default()
+            },
+            SpatialBundle::from_transform(Transform::from_xyz(
+                pos.x,
+                pos.y,
+                transform.translation.z,
+            )),
+        ))
+        .with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: turret_appearance.base_mesh.clone(),
+                material: turret_appearance.base_material.clone(),
+                // Stand the cylinder up to face the camera
+                transform: Transform::from_rotation(Quat::from_rotation_x(PI / 2.0)),
+                ..Default::default()
+            });
+            parent.spawn((
+                TurretBarrel,
+                PbrBundle {
+                    mesh: turret_appearance.barrel_mesh.clone(),
+                    material: turret_appearance.barrel_material.clone(),
+                    transform: Transform::from_rotation(Quat::from_rotation_z(player.facing)),
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+fn tick_turrets(
+    mut query: Query<(Entity, &mut Turret)>,
+    game_time: Res<GameTime>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+) {
+    for (entity, mut turret) in &mut query {
+        turret.lifetime_timer.tick(game_time.delta());
+        if turret.lifetime_timer.finished() {
+            debug!("Turret {entity:?} ran out of time");
+            despawn_queue.despawn(entity);
+            continue;
+        }
+        turret.fire_timer.tick(game_time.delta());
+        let cooling = turret.cooling_rate * game_time.delta_seconds();
+        turret.heat = (turret.heat - cooling).max(0.0);
+        if turret.heat <= 0.0 {
+            turret.overheated = false;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fire_turrets(
+    mut commands: Commands,
+    mut turret_query: Query<(&mut Turret, &Transform, &Children)>,
+    mut barrel_query: Query<&mut Transform, (With<TurretBarrel>, Without<Turret>)>,
+    rock_query: Query<(&GlobalTransform, &Velocity), With<Rock>>,
+    player_query: Query<&GlobalTransform, LeadPlayer>,
+    slug_visuals: Res<SlugVisuals>,
+    mut slug_pool: ResMut<SlugPool>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+) {
+    for (mut turret, transform, children) in &mut turret_query {
+        let pos = transform.translation.truncate();
+        let target = rock_query
+            .iter()
+            .map(|(rock_transform, velocity)| {
+                (rock_transform.translation().truncate(), velocity.linvel)
+            })
+            .filter(|(rock_pos, _)| rock_pos.distance(pos) < turret.range)
+            .min_by(|(a, _), (b, _)| a.distance(pos).total_cmp(&b.distance(pos)));
+        let Some((rock_pos, rock_velocity)) = target else { continue; };
+
+        // Lead the target by however long the slug will take to get there
+        let flight_time = rock_pos.distance(pos) / turret.projectile_speed;
+        let aim_dir = (rock_pos + rock_velocity * flight_time - pos).normalize_or_zero();
+        if aim_dir == Vec2::ZERO {
+            continue;
+        }
+        turret.facing = aim_dir.y.atan2(aim_dir.x);
+        for child in children {
+            if let Ok(mut barrel_transform) = barrel_query.get_mut(*child) {
+                barrel_transform.rotation = Quat::from_rotation_z(turret.facing);
+            }
+        }
+
+        if !turret.fire_timer.just_finished() || turret.overheated {
+            continue;
+        }
+
+        let origin = pos + aim_dir * turret.origin_distance;
+        let time_to_live = turret.range / turret.projectile_speed;
+        let slug = slug_pool.fire(
+            &mut commands,
+            SlugBundle {
+                slug: Slug {
+                    timer: Timer::from_seconds(time_to_live, TimerMode::Once),
+                    damage: turret.damage,
+                    ricochets_left: 0,
+                    pierces_left: 0,
+                },
+                faction: Faction::Player,
+                velocity: Velocity::linear(aim_dir * turret.projectile_speed),
+                transform: Transform::from_xyz(origin.x, origin.y, transform.translation.z)
+                    .with_rotation(Quat::from_rotation_z(PI / 2.0 + turret.facing)),
+                mesh: slug_visuals.mesh.clone(),
+                material: slug_visuals.material.clone(),
+            },
+            Color::ORANGE,
+        );
+        commands.entity(slug).insert(TurretSlug);
+
+        if let Ok(player_transform) = player_query.get_single() {
+            let diff = transform.translation - player_transform.translation();
+            sound_event_writer.send(SoundEvent::CannonFire {
+                direction: diff.y.atan2(diff.x),
+            });
+        }
+
+        turret.heat += turret.heat_per_shot;
+        if turret.heat >= turret.heat_limit {
+            turret.heat = turret.heat_limit;
+            turret.overheated = true;
+        }
+    }
+}
+
+pub struct TurretPlugin;
+
+impl Plugin for TurretPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_turret_appearance).add_systems(
+            (deploy_turret, tick_turrets, fire_turrets)
+                .chain()
+                .in_set(OnUpdate(GameState::InGame)),
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+use leafwing_input_manager::{prelude::ActionState, Actionlike};
+
+use crate::{
+    game_time::GameTime,
+    input::{Action, MenuAction},
+    physics::FIXED_TIMESTEP,
+    player::{movement_input, LeadPlayer},
+    replay::Replay,
+    shield::SHIELD_RADIUS,
+    state::{GameState, ProgressStages},
+    ui::{CustomUICamera, UIMarker, HINT_HEIGHT},
+};
+
+/// A developer run showing off mining in bursts to keep the heat down, then transmuting with the
+/// shield. It's in the usual replay format, and loops
+const GHOST_RECORDING: &str = include_str!("ghost/tutorial.replay");
+/// The ghost stops showing up once the stages after this one are reached
+const LAST_GHOST_STAGE: ProgressStages = ProgressStages::ShieldAndStrange;
+/// Where the ghost starts each loop, relative to the player
+const GHOST_OFFSET: Vec3 = Vec3::new(-8.0, 5.0, 0.0);
+/// Close to how the player handles, without going through the physics
+const GHOST_MAX_SPEED: f32 = 15.0;
+const GHOST_ACCELERATION: f32 = 10.0;
+const GHOST_COLOR: Color = Color::rgba(0.5, 0.8, 1.0, 0.3);
+const GHOST_SHIELD_COLOR: Color = Color::rgba(0.5, 0.8, 1.0, 0.12);
+const GHOST_FIRE_INTERVAL: f32 = 0.15;
+const GHOST_SLUG_SPEED: f32 = 30.0;
+const GHOST_SLUG_LIFETIME: f32 = 0.5;
+const LABEL_FONT_SIZE: f32 = 25.0;
+const LABEL_PADDING: f32 = 4.0;
+
+/// Toggled from the hint panel
+#[derive(Resource, Debug)]
+pub struct TutorialGhostSettings {
+    pub enabled: bool,
+}
+
+impl Default for TutorialGhostSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Resource, Debug)]
+struct GhostRecording(Replay);
+
+#[derive(Resource, Debug)]
+struct GhostAppearance {
+    body_mesh: Handle<Mesh>,
+    nose_mesh: Handle<Mesh>,
+    shield_mesh: Handle<Mesh>,
+    slug_mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    shield_material: Handle<StandardMaterial>,
+}
+
+/// Flies through the recording like the player would, but nothing in the world can see it
+#[derive(Component, Debug, Default)]
+struct Ghost {
+    next_tick: usize,
+    velocity: Vec2,
+    fire_cooldown: f32,
+}
+
+#[derive(Component, Debug, Default)]
+struct GhostShield;
+
+/// Just for show, these go through everything
+#[derive(Component, Debug)]
+struct GhostSlug {
+    velocity: Vec2,
+    lifetime: f32,
+}
+
+#[derive(Component, Debug, Default)]
+struct GhostToggleLabel;
+
+fn load_ghost_recording(mut commands: Commands) {
+    match Replay::from_text(GHOST_RECORDING) {
+        Ok(replay) => commands.insert_resource(GhostRecording(replay)),
+        Err(err) => error!("Couldn't load the tutorial ghost: {err}"),
+    }
+}
+
+fn setup_ghost_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let sphere = |radius: f32| -> Mesh {
+        shape::UVSphere {
+            radius,
+            sectors: 24,
+            stacks: 16,
+        }
+        .into()
+    };
+    commands.insert_resource(GhostAppearance {
+        body_mesh: meshes.add(sphere(1.0)),
+        nose_mesh: meshes.add(sphere(0.25)),
+        shield_mesh: meshes.add(sphere(SHIELD_RADIUS)),
+        slug_mesh: meshes.add(sphere(0.15)),
+        material: materials.add(StandardMaterial {
+            base_color: GHOST_COLOR,
+            unlit: true,
+            alpha_mode: AlphaMode::Blend,
+            ..Default::default()
+        }),
+        shield_material: materials.add(StandardMaterial {
+            base_color: GHOST_SHIELD_COLOR,
+            unlit: true,
+            alpha_mode: AlphaMode::Blend,
+            double_sided: true,
+            cull_mode: None,
+            ..Default::default()
+        }),
+    });
+}
+
+fn spawn_ghost(mut commands: Commands, appearance: Res<GhostAppearance>) {
+    commands
+        .spawn((
+            PbrBundle {
+                mesh: appearance.body_mesh.clone(),
+                material: appearance.material.clone(),
+                transform: Transform::from_translation(GHOST_OFFSET + Vec3::Z),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            Ghost::default(),
+            ActionState::<Action>::default(),
+        ))
+        .with_children(|parent| {
+            // Shows which way it's aiming, since the ghost turns to face it
+            parent.spawn(PbrBundle {
+                mesh: appearance.nose_mesh.clone(),
+                material: appearance.material.clone(),
+                transform: Transform::from_xyz(1.2, 0.0, 0.0),
+                ..Default::default()
+            });
+            parent.spawn((
+                PbrBundle {
+                    mesh: appearance.shield_mesh.clone(),
+                    material: appearance.shield_material.clone(),
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+                GhostShield,
+            ));
+        });
+}
+
+fn setup_ghost_label(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: LABEL_FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            ),
+            text_anchor: Anchor::TopRight,
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        GhostToggleLabel,
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+fn toggle_ghost(
+    action_query: Query<&ActionState<MenuAction>>,
+    mut settings: ResMut<TutorialGhostSettings>,
+) {
+    if action_query
+        .iter()
+        .any(|action_state| action_state.just_pressed(MenuAction::ToggleGhost))
+    {
+        settings.enabled = !settings.enabled;
+        debug!("Tutorial ghost enabled: {}", settings.enabled);
+    }
+}
+
+fn play_ghost(
+    mut commands: Commands,
+    mut ghost_query: Query<(&mut Ghost, &mut Transform, &mut ActionState<Action>, &Children)>,
+    mut shield_query: Query<&mut Visibility, With<GhostShield>>,
+    player_query: Query<&Transform, (LeadPlayer, Without<Ghost>)>,
+    recording: Res<GhostRecording>,
+    appearance: Res<GhostAppearance>,
+) {
+    let replay = &recording.0;
+    for (mut ghost, mut transform, mut action_state, children) in &mut ghost_query {
+        let tick = match replay.ticks.get(ghost.next_tick) {
+            Some(tick) => *tick,
+            None => {
+                // Start the next loop next to wherever the player has got to
+                let Some(first) = replay.ticks.first() else { continue; };
+                ghost.next_tick = 0;
+                ghost.velocity = Vec2::ZERO;
+                if let Ok(player_transform) = player_query.get_single() {
+                    transform.translation = player_transform.translation + GHOST_OFFSET;
+                }
+                *first
+            }
+        };
+        ghost.next_tick += 1;
+
+        for action in Action::variants() {
+            if tick.actions & 1 << action.index() != 0 {
+                action_state.press(action);
+            } else {
+                action_state.release(action);
+            }
+        }
+        transform.rotation = Quat::from_rotation_z(tick.facing);
+
+        let desired_velocity = movement_input(&action_state) * GHOST_MAX_SPEED;
+        let change = desired_velocity - ghost.velocity;
+        ghost.velocity += change.clamp_length_max(GHOST_ACCELERATION * FIXED_TIMESTEP);
+        transform.translation += (ghost.velocity * FIXED_TIMESTEP).extend(0.0);
+
+        for &child in children {
+            if let Ok(mut visibility) = shield_query.get_mut(child) {
+                *visibility = if action_state.pressed(Action::Shield) {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+
+        ghost.fire_cooldown -= FIXED_TIMESTEP;
+        if action_state.pressed(Action::FireMainGun) && ghost.fire_cooldown <= 0.0 {
+            ghost.fire_cooldown = GHOST_FIRE_INTERVAL;
+            let direction = Vec2::from_angle(tick.facing);
+            commands.spawn((
+                PbrBundle {
+                    mesh: appearance.slug_mesh.clone(),
+                    material: appearance.material.clone(),
+                    transform: Transform::from_translation(
+                        transform.translation + (direction * 1.5).extend(0.0),
+                    ),
+                    ..Default::default()
+                },
+                GhostSlug {
+                    velocity: direction * GHOST_SLUG_SPEED + ghost.velocity,
+                    lifetime: GHOST_SLUG_LIFETIME,
+                },
+            ));
+        }
+    }
+}
+
+fn move_ghost_slugs(
+    mut commands: Commands,
+    mut slug_query: Query<(Entity, &mut GhostSlug, &mut Transform)>,
+    game_time: Res<GameTime>,
+) {
+    let dt = game_time.delta_seconds();
+    for (entity, mut slug, mut transform) in &mut slug_query {
+        slug.lifetime -= dt;
+        if slug.lifetime <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation += (slug.velocity * dt).extend(0.0);
+    }
+}
+
+fn ghost_showing(settings: &TutorialGhostSettings, progress: &State<ProgressStages>) -> bool {
+    settings.enabled && progress.0 <= LAST_GHOST_STAGE
+}
+
+fn update_ghost_visibility(
+    mut ghost_query: Query<&mut Visibility, With<Ghost>>,
+    settings: Res<TutorialGhostSettings>,
+    progress: Res<State<ProgressStages>>,
+) {
+    let visibility = if ghost_showing(&settings, &progress) {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for mut ghost_visibility in &mut ghost_query {
+        *ghost_visibility = visibility;
+    }
+}
+
+/// Sits under the hint, for as long as there's a ghost to turn on or off
+#[allow(clippy::type_complexity)]
+fn update_ghost_label(
+    mut label_query: Query<
+        (&mut Text, &mut Transform, &mut Visibility),
+        (With<GhostToggleLabel>, Without<CustomUICamera>),
+    >,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    settings: Res<TutorialGhostSettings>,
+    progress: Res<State<ProgressStages>>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else { return; };
+    let Some(size) = ui_camera.logical_viewport_size() else { return; };
+    for (mut text, mut transform, mut visibility) in &mut label_query {
+        if progress.0 > LAST_GHOST_STAGE {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Visible;
+        text.sections[0].value = if settings.enabled {
+            "H  HIDE GHOST".to_string()
+        } else {
+            "H  SHOW GHOST".to_string()
+        };
+        // The hint's background is a little bigger than its text
+        transform.translation.x = size.x / 2.0 - LABEL_PADDING;
+        transform.translation.y = size.y / 2.0 - HINT_HEIGHT - 15.0 - 2.0 * LABEL_PADDING;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn cleanup_ghost(mut commands: Commands, query: Query<Entity, Or<(With<Ghost>, With<GhostSlug>)>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct TutorialGhostPlugin;
+
+impl Plugin for TutorialGhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TutorialGhostSettings>()
+            .add_startup_systems((load_ghost_recording, setup_ghost_appearance))
+            .add_system(
+                spawn_ghost
+                    .run_if(resource_exists::<GhostRecording>())
+                    .in_schedule(OnExit(GameState::Intro)),
+            )
+            .add_system(setup_ghost_label.in_schedule(OnExit(GameState::Intro)))
+            .add_systems(
+                (
+                    toggle_ghost,
+                    move_ghost_slugs,
+                    update_ghost_visibility,
+                    update_ghost_label,
+                )
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(
+                play_ghost
+                    .run_if(resource_exists::<GhostRecording>())
+                    .run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(cleanup_ghost.in_schedule(OnEnter(GameState::Outro)));
+    }
+}
@@ -0,0 +1,440 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+use leafwing_input_manager::{
+    axislike::{AxisType, DualAxis, SingleAxis},
+    prelude::*,
+    user_input::InputKind,
+};
+
+use crate::{
+    camera::MainCamera,
+    input::{default_input_map, Action, ActiveInputDevice},
+    player::{LeadPlayer, Player},
+    state::GameState,
+    ui::{EnabledControls, UIMarker},
+};
+
+/// A button prompt floating near the player, shown once when a control is first unlocked
+#[derive(Component, Debug)]
+pub struct TutorialPrompt {
+    control: EnabledControls,
+    /// Starts once the player has used the control
+    fade_timer: Option<Timer>,
+}
+
+/// The controls that have already had a prompt this run
+#[derive(Resource, Debug)]
+struct PromptedControls(EnabledControls);
+
+impl Default for PromptedControls {
+    fn default() -> Self {
+        Self(EnabledControls::empty())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PromptLayout {
+    /// Up, left, down and right laid out like a WASD cluster
+    Cluster,
+    Row,
+}
+
+struct PromptDefinition {
+    control: EnabledControls,
+    label: &'static str,
+    actions: &'static [Action],
+    layout: PromptLayout,
+}
+
+// Looking around follows the mouse, so there's nothing to prompt for
+const PROMPTS: [PromptDefinition; 4] = [
+    PromptDefinition {
+        control: EnabledControls::Move,
+        label: "Move",
+        actions: &[
+            Action::MoveUp,
+            Action::MoveLeft,
+            Action::MoveDown,
+            Action::MoveRight,
+        ],
+        layout: PromptLayout::Cluster,
+    },
+    PromptDefinition {
+        control: EnabledControls::Shoot,
+        label: "Fire",
+        actions: &[Action::FireMainGun],
+        layout: PromptLayout::Row,
+    },
+    PromptDefinition {
+        control: EnabledControls::Shield,
+        label: "Shield",
+        actions: &[Action::Shield],
+        layout: PromptLayout::Row,
+    },
+    PromptDefinition {
+        control: EnabledControls::Dump,
+        label: "Drop Cargo",
+        actions: &[Action::DumpCargo],
+        layout: PromptLayout::Row,
+    },
+];
+
+const FONT_SIZE: f32 = 22.0;
+const KEYCAP_SIZE: f32 = 28.0;
+const KEYCAP_GAP: f32 = 3.0;
+const KEYCAP_ALPHA: f32 = 0.6;
+/// How far above the player the first prompt sits, in logical pixels
+const PROMPT_OFFSET: f32 = 70.0;
+const PROMPT_SPACING: f32 = 2.0 * KEYCAP_SIZE + 3.0 * KEYCAP_GAP;
+const FADE_SECONDS: f32 = 1.0;
+
+fn is_gamepad_input(kind: &InputKind) -> bool {
+    matches!(
+        kind,
+        InputKind::GamepadButton(_) | InputKind::SingleAxis(_) | InputKind::DualAxis(_)
+    )
+}
+
+fn gamepad_axis_glyph(axis: &SingleAxis) -> String {
+    match axis.axis_type {
+        AxisType::Gamepad(GamepadAxisType::LeftStickX | GamepadAxisType::LeftStickY) => {
+            "LS".to_owned()
+        }
+        AxisType::Gamepad(GamepadAxisType::RightStickX | GamepadAxisType::RightStickY) => {
+            "RS".to_owned()
+        }
+        AxisType::Gamepad(GamepadAxisType::LeftZ) => "LT".to_owned(),
+        AxisType::Gamepad(GamepadAxisType::RightZ) => "RT".to_owned(),
+        other => format!("{other:?}"),
+    }
+}
+
+/// A short label for an input, as printed on its keycap
+fn input_glyph(kind: &InputKind) -> String {
+    match kind {
+        InputKind::Keyboard(key) => format!("{key:?}"),
+        InputKind::KeyLocation(scan_code) => format!("#{}", scan_code.0),
+        InputKind::Modifier(modifier) => format!("{modifier:?}"),
+        InputKind::Mouse(MouseButton::Left) => "LMB".to_owned(),
+        InputKind::Mouse(MouseButton::Right) => "RMB".to_owned(),
+        InputKind::Mouse(MouseButton::Middle) => "MMB".to_owned(),
+        InputKind::Mouse(MouseButton::Other(n)) => format!("M{n}"),
+        InputKind::MouseWheel(_) => "WHEEL".to_owned(),
+        InputKind::MouseMotion(_) => "MOUSE".to_owned(),
+        InputKind::GamepadButton(button) => match button {
+            GamepadButtonType::South => "A".to_owned(),
+            GamepadButtonType::East => "B".to_owned(),
+            GamepadButtonType::West => "X".to_owned(),
+            GamepadButtonType::North => "Y".to_owned(),
+            GamepadButtonType::LeftTrigger => "LB".to_owned(),
+            GamepadButtonType::LeftTrigger2 => "LT".to_owned(),
+            GamepadButtonType::RightTrigger => "RB".to_owned(),
+            GamepadButtonType::RightTrigger2 => "RT".to_owned(),
+            GamepadButtonType::LeftThumb => "L3".to_owned(),
+            GamepadButtonType::RightThumb => "R3".to_owned(),
+            GamepadButtonType::DPadUp => "UP".to_owned(),
+            GamepadButtonType::DPadDown => "DOWN".to_owned(),
+            GamepadButtonType::DPadLeft => "LEFT".to_owned(),
+            GamepadButtonType::DPadRight => "RIGHT".to_owned(),
+            other => format!("{other:?}"),
+        },
+        InputKind::SingleAxis(axis) => gamepad_axis_glyph(axis),
+        InputKind::DualAxis(DualAxis { x, .. }) => gamepad_axis_glyph(x),
+        other => format!("{other:?}"),
+    }
+}
+
+/// Finds the first binding for `action` on the given device, falling back to any binding at all
+fn action_glyph(
+    input_map: &InputMap<Action>,
+    action: Action,
+    device: ActiveInputDevice,
+) -> Option<String> {
+    let wants_gamepad = device == ActiveInputDevice::Gamepad;
+    let kinds: Vec<InputKind> = input_map
+        .get(action)
+        .iter()
+        .filter_map(|input| match input {
+            UserInput::Single(kind) => Some(*kind),
+            // Chords and virtual axes don't fit on a single keycap
+            _ => None,
+        })
+        .collect();
+    kinds
+        .iter()
+        .find(|kind| is_gamepad_input(kind) == wants_gamepad)
+        .or_else(|| kinds.first())
+        .map(input_glyph)
+}
+
+/// Every enabled control with the keys it's on, for the pause menu. Looking around has no
+/// prompt of its own, so it's listed here by hand
+pub fn enabled_control_bindings(
+    enabled_controls: EnabledControls,
+    input_map: &InputMap<Action>,
+    device: ActiveInputDevice,
+) -> Vec<(&'static str, String)> {
+    let mut bindings = Vec::new();
+    for definition in PROMPTS.iter().filter(|d| enabled_controls.contains(d.control)) {
+        let glyphs: Vec<String> = definition
+            .actions
+            .iter()
+            .map(|action| {
+                action_glyph(input_map, *action, device).unwrap_or_else(|| "?".to_owned())
+            })
+            .collect();
+        bindings.push((definition.label, glyphs.join(" ")));
+        if definition.control == EnabledControls::Move
+            && enabled_controls.contains(EnabledControls::Look)
+        {
+            let look = match device {
+                ActiveInputDevice::KeyboardMouse => None,
+                ActiveInputDevice::Gamepad => action_glyph(input_map, Action::Aim, device),
+            };
+            bindings.push(("Look", look.unwrap_or_else(|| "MOUSE".to_owned())));
+        }
+    }
+    bindings
+}
+
+fn keycap_width(glyph: &str) -> f32 {
+    f32::max(KEYCAP_SIZE, glyph.len() as f32 * FONT_SIZE * 0.45 + 10.0)
+}
+
+fn spawn_keycap(parent: &mut ChildBuilder, font: &Handle<Font>, glyph: &str, position: Vec2) {
+    let width = keycap_width(glyph);
+    parent
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    anchor: Anchor::Center,
+                    color: Color::rgba(0.3, 0.3, 0.3, KEYCAP_ALPHA),
+                    custom_size: Some(Vec2::new(width, KEYCAP_SIZE)),
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(position.x, position.y, 1.0),
+                ..Default::default()
+            },
+            RenderLayers::layer(1),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        glyph.to_uppercase(),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: FONT_SIZE,
+                            color: Color::ORANGE_RED,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                    ..Default::default()
+                },
+                RenderLayers::layer(1),
+            ));
+        });
+}
+
+/// Lays out the keycaps and label for a prompt, centered on its anchor
+fn build_prompt(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    definition: &PromptDefinition,
+    input_map: &InputMap<Action>,
+    device: ActiveInputDevice,
+) {
+    let glyphs: Vec<String> = definition
+        .actions
+        .iter()
+        .map(|action| action_glyph(input_map, *action, device).unwrap_or_else(|| "?".to_owned()))
+        .collect();
+
+    let step = KEYCAP_SIZE + KEYCAP_GAP;
+    let right_edge = match definition.layout {
+        PromptLayout::Cluster => {
+            let [up, left, down, right] = glyphs.as_slice() else { return; };
+            spawn_keycap(parent, font, up, Vec2::new(0.0, step / 2.0));
+            spawn_keycap(parent, font, left, Vec2::new(-step, -step / 2.0));
+            spawn_keycap(parent, font, down, Vec2::new(0.0, -step / 2.0));
+            spawn_keycap(parent, font, right, Vec2::new(step, -step / 2.0));
+            step + KEYCAP_SIZE / 2.0
+        }
+        PromptLayout::Row => {
+            let total: f32 = glyphs.iter().map(|glyph| keycap_width(glyph) + KEYCAP_GAP).sum();
+            let mut x = -total / 2.0;
+            for glyph in &glyphs {
+                let width = keycap_width(glyph);
+                spawn_keycap(parent, font, glyph, Vec2::new(x + width / 2.0, 0.0));
+                x += width + KEYCAP_GAP;
+            }
+            x
+        }
+    };
+
+    parent.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                definition.label,
+                TextStyle {
+                    font: font.clone(),
+                    font_size: FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            ),
+            text_anchor: Anchor::CenterLeft,
+            transform: Transform::from_xyz(right_edge + KEYCAP_GAP * 2.0, 0.0, 2.0),
+            ..Default::default()
+        },
+        RenderLayers::layer(1),
+    ));
+}
+
+fn reset_prompted_controls(mut prompted: ResMut<PromptedControls>) {
+    *prompted = PromptedControls::default();
+}
+
+fn spawn_tutorial_prompts(
+    mut commands: Commands,
+    enabled_controls: Res<EnabledControls>,
+    mut prompted: ResMut<PromptedControls>,
+    player_query: Query<Option<&InputMap<Action>>, LeadPlayer>,
+    active_device: Res<ActiveInputDevice>,
+    asset_server: Res<AssetServer>,
+) {
+    let new_controls = *enabled_controls - prompted.0;
+    if new_controls.is_empty() {
+        return;
+    }
+    prompted.0 |= new_controls;
+
+    // Replays take the input map off the player, so fall back to the defaults
+    let default_map = default_input_map();
+    let input_map = player_query.get_single().ok().flatten().unwrap_or(&default_map);
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    for definition in PROMPTS.iter().filter(|d| new_controls.contains(d.control)) {
+        debug!("Showing a tutorial prompt for {:?}", definition.control);
+        commands
+            .spawn((
+                TutorialPrompt {
+                    control: definition.control,
+                    fade_timer: None,
+                },
+                SpatialBundle::default(),
+                UIMarker,
+                RenderLayers::layer(1),
+            ))
+            .with_children(|parent| {
+                build_prompt(parent, &font, definition, input_map, *active_device);
+            });
+    }
+}
+
+/// Swaps keycaps over when the player switches between keyboard and gamepad
+fn rebuild_prompts_on_device_change(
+    mut commands: Commands,
+    prompt_query: Query<(Entity, &TutorialPrompt)>,
+    player_query: Query<Option<&InputMap<Action>>, LeadPlayer>,
+    active_device: Res<ActiveInputDevice>,
+    asset_server: Res<AssetServer>,
+) {
+    if !active_device.is_changed() {
+        return;
+    }
+    let default_map = default_input_map();
+    let input_map = player_query.get_single().ok().flatten().unwrap_or(&default_map);
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    for (entity, prompt) in &prompt_query {
+        let Some(definition) = PROMPTS.iter().find(|d| d.control == prompt.control) else { continue; };
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).with_children(|parent| {
+            build_prompt(parent, &font, definition, input_map, *active_device);
+        });
+    }
+}
+
+fn start_fading_used_prompts(
+    mut prompt_query: Query<&mut TutorialPrompt>,
+    player_query: Query<&ActionState<Action>, LeadPlayer>,
+) {
+    let Ok(action_state) = player_query.get_single() else { return; };
+    for mut prompt in &mut prompt_query {
+        if prompt.fade_timer.is_some() {
+            continue;
+        }
+        let Some(definition) = PROMPTS.iter().find(|d| d.control == prompt.control) else { continue; };
+        if definition.actions.iter().any(|a| action_state.just_pressed(*a)) {
+            prompt.fade_timer = Some(Timer::from_seconds(FADE_SECONDS, TimerMode::Once));
+        }
+    }
+}
+
+fn fade_tutorial_prompts(
+    mut commands: Commands,
+    mut prompt_query: Query<(Entity, &mut TutorialPrompt)>,
+    children_query: Query<&Children>,
+    mut sprite_query: Query<&mut Sprite>,
+    mut text_query: Query<&mut Text>,
+    time: Res<Time>,
+) {
+    for (entity, mut prompt) in &mut prompt_query {
+        let Some(timer) = prompt.fade_timer.as_mut() else { continue; };
+        timer.tick(time.delta());
+        if timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = timer.percent_left();
+        for descendant in children_query.iter_descendants(entity) {
+            if let Ok(mut sprite) = sprite_query.get_mut(descendant) {
+                sprite.color.set_a(alpha * KEYCAP_ALPHA);
+            }
+            if let Ok(mut text) = text_query.get_mut(descendant) {
+                for section in &mut text.sections {
+                    section.style.color.set_a(alpha);
+                }
+            }
+        }
+    }
+}
+
+/// Keeps the prompts stacked just above the player on screen
+fn reposition_tutorial_prompts(
+    mut prompt_query: Query<(&mut Transform, &TutorialPrompt), Without<Player>>,
+    player_query: Query<&GlobalTransform, LeadPlayer>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return; };
+    let Some(size) = camera.logical_viewport_size() else { return; };
+    let Some(viewport_pos) =
+        camera.world_to_viewport(camera_transform, player_transform.translation()) else { return; };
+    // The UI camera has its origin in the middle of the screen
+    let anchor = viewport_pos - size / 2.0;
+
+    let mut prompts: Vec<_> = prompt_query.iter_mut().collect();
+    prompts.sort_by_key(|(_, prompt)| prompt.control.bits());
+    for (i, (mut transform, _)) in prompts.into_iter().enumerate() {
+        transform.translation.x = anchor.x;
+        transform.translation.y = anchor.y + PROMPT_OFFSET + PROMPT_SPACING * i as f32;
+    }
+}
+
+pub struct TutorialPromptPlugin;
+
+impl Plugin for TutorialPromptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PromptedControls>()
+            .add_system(reset_prompted_controls.in_schedule(OnExit(GameState::Intro)))
+            .add_systems(
+                (
+                    spawn_tutorial_prompts,
+                    rebuild_prompts_on_device_change,
+                    start_fading_used_prompts,
+                    fade_tutorial_prompts,
+                    reposition_tutorial_prompts,
+                )
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
@@ -9,12 +9,27 @@ use bevy::{
     sprite::Anchor,
     text::Text2dBounds,
 };
+use leafwing_input_manager::prelude::ActionState;
 
 use crate::{
+    campaign::{Campaign, StageThresholdReached},
+    collectible::ReagentAppearances,
+    consumable::{ConsumableKind, Consumables},
+    coop::CoopSettings,
+    map::Odometer,
     heat::Heat,
-    inventory::{Inventory, Reagent},
-    player::Player,
+    hull::Hull,
+    input::{Action, LockedActionPressed},
+    inventory::{Inventory, Reagent, ReagentRegistry},
+    photo_mode::PhotoModeState,
+    player::{LeadPlayer, SecondPlayer},
+    reaction::{ReactionActivity, Reactions},
+    save_data::SaveData,
+    sound::SoundEvent,
     state::{GameState, ProgressStages},
+    status_effect::{StatusEffects, StatusKind},
+    text_pack::{PackFile, PackText, TextAsset, TextPack},
+    ultimate::UltimateCharge,
 };
 
 #[derive(Component, Debug, Default)]
@@ -51,6 +66,132 @@ impl Default for CustomUICameraBundle {
     }
 }
 
+/// How much of the HUD is shown, for streamers and screenshots. Covers everything the
+/// `CustomUICamera` draws
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct HudSettings {
+    pub visible: bool,
+    pub opacity: f32,
+    /// Dots out from the gun along the path a slug would take
+    pub aim_line: bool,
+}
+
+impl Default for HudSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            opacity: 1.0,
+            aim_line: false,
+        }
+    }
+}
+
+fn toggle_hud_on_hotkey(query: Query<&ActionState<Action>>, mut hud_settings: ResMut<HudSettings>) {
+    if query
+        .iter()
+        .any(|action_state| action_state.just_pressed(Action::ToggleHud))
+    {
+        hud_settings.visible = !hud_settings.visible;
+    }
+}
+
+/// Photo mode hides the HUD too. The bars are drawn by bevy_ui rather than the `CustomUICamera`,
+/// so they're hidden separately
+fn show_or_hide_hud(
+    hud_settings: Res<HudSettings>,
+    mut ui_camera_query: Query<&mut Camera, With<CustomUICamera>>,
+    mut hud_root_query: Query<&mut Visibility, With<HudRoot>>,
+    photo_mode: Res<State<PhotoModeState>>,
+) {
+    let visible = hud_settings.visible && photo_mode.0 == PhotoModeState::Off;
+    for mut camera in &mut ui_camera_query {
+        if camera.is_active != visible {
+            camera.is_active = visible;
+        }
+    }
+    let visibility = if visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut hud_visibility in &mut hud_root_query {
+        if *hud_visibility != visibility {
+            *hud_visibility = visibility;
+        }
+    }
+}
+
+/// The alpha everything on the HUD had before `fade_hud`, so it can be put back before anything
+/// else sees it. The HUD's own systems keep setting their colours as usual
+#[derive(Resource, Debug, Default)]
+struct HudAlphas {
+    sprites: Vec<(Entity, f32)>,
+    nodes: Vec<(Entity, f32)>,
+    texts: Vec<(Entity, Vec<f32>)>,
+}
+
+fn fade_hud(
+    hud_settings: Res<HudSettings>,
+    mut alphas: ResMut<HudAlphas>,
+    mut sprite_query: Query<(Entity, &mut Sprite, &RenderLayers)>,
+    mut node_query: Query<(Entity, &mut BackgroundColor, &RenderLayers)>,
+    mut text_query: Query<(Entity, &mut Text, &RenderLayers)>,
+) {
+    if hud_settings.opacity >= 1.0 {
+        return;
+    }
+    let hud_layers = RenderLayers::layer(1);
+    for (entity, mut sprite, layers) in &mut sprite_query {
+        if layers.intersects(&hud_layers) {
+            let alpha = sprite.color.a();
+            alphas.sprites.push((entity, alpha));
+            sprite.color.set_a(alpha * hud_settings.opacity);
+        }
+    }
+    for (entity, mut color, layers) in &mut node_query {
+        if layers.intersects(&hud_layers) {
+            let alpha = color.0.a();
+            alphas.nodes.push((entity, alpha));
+            color.0.set_a(alpha * hud_settings.opacity);
+        }
+    }
+    for (entity, mut text, layers) in &mut text_query {
+        if layers.intersects(&hud_layers) {
+            let mut section_alphas = Vec::with_capacity(text.sections.len());
+            for section in &mut text.sections {
+                let alpha = section.style.color.a();
+                section_alphas.push(alpha);
+                section.style.color.set_a(alpha * hud_settings.opacity);
+            }
+            alphas.texts.push((entity, section_alphas));
+        }
+    }
+}
+
+fn unfade_hud(
+    mut alphas: ResMut<HudAlphas>,
+    mut sprite_query: Query<&mut Sprite>,
+    mut node_query: Query<&mut BackgroundColor>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (entity, alpha) in alphas.sprites.drain(..) {
+        if let Ok(mut sprite) = sprite_query.get_mut(entity) {
+            sprite.color.set_a(alpha);
+        }
+    }
+    for (entity, alpha) in alphas.nodes.drain(..) {
+        if let Ok(mut color) = node_query.get_mut(entity) {
+            color.0.set_a(alpha);
+        }
+    }
+    for (entity, section_alphas) in alphas.texts.drain(..) {
+        let Ok(mut text) = text_query.get_mut(entity) else { continue; };
+        for (section, alpha) in text.sections.iter_mut().zip(section_alphas) {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
 #[derive(Component, Debug, Default)]
 struct CurrentHeatBar;
 
@@ -60,15 +201,219 @@ struct HeatBarAnchor;
 #[derive(Component, Debug, Default)]
 struct HeatBarThreshold;
 
+/// Beside the heat bar, how quickly the ship is cooling down. It goes up with speed
+#[derive(Component, Debug, Default)]
+struct HeatCoolingText;
+
+const COOLING_FONT_SIZE: f32 = 20.0;
+
+/// The column of bars down the left hand side. It's laid out by bevy_ui, so it stays in the
+/// corner whatever size the window is and follows the `UiScale`
+#[derive(Component, Debug)]
+struct HudRoot;
+
+/// Where in the column each kind of bar goes, top first
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum HudSlot {
+    Heat,
+    /// Holds the `ReagentBarColumn` and the reaction indicators beside it
+    Reagents,
+    Hull,
+    Ultimate,
+    /// A row of icons for the status effects on the ship
+    Status,
+    /// What the ship is carrying to use later, each with the key it's used with
+    Consumables,
+    /// How far it is back to where the run started, and how far the ship has flown
+    Navigation,
+}
+
+/// The reagent bars themselves, in the order from the `ReagentBarRows`
+#[derive(Component, Debug)]
+struct ReagentBarColumn;
+
+fn hud_slot(slot_query: &Query<(Entity, &HudSlot)>, slot: HudSlot) -> Option<Entity> {
+    slot_query
+        .iter()
+        .find(|(_, s)| **s == slot)
+        .map(|(entity, _)| entity)
+}
+
+fn setup_hud(mut commands: Commands, assets_server: Res<AssetServer>) {
+    let column = |gap| Style {
+        flex_direction: FlexDirection::Column,
+        gap: Size::all(Val::Px(gap)),
+        ..default()
+    };
+    let mut ultimate_slot = Entity::PLACEHOLDER;
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(0.0),
+                        top: Val::Px(0.0),
+                        ..default()
+                    },
+                    padding: UiRect::all(Val::Px(BAR_PADDING)),
+                    ..column(BAR_PADDING)
+                },
+                ..default()
+            },
+            HudRoot,
+            UIMarker,
+            RenderLayers::layer(1),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: column(BAR_PADDING),
+                    ..default()
+                },
+                HudSlot::Heat,
+            ));
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: column(0.0),
+                        ..default()
+                    },
+                    HudSlot::Reagents,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: column(BAR_PADDING),
+                            ..default()
+                        },
+                        ReagentBarColumn,
+                    ));
+                });
+            parent.spawn((
+                NodeBundle {
+                    style: column(BAR_PADDING),
+                    ..default()
+                },
+                HudSlot::Hull,
+            ));
+            ultimate_slot = parent
+                .spawn((
+                    NodeBundle {
+                        style: column(BAR_PADDING),
+                        ..default()
+                    },
+                    HudSlot::Ultimate,
+                ))
+                .id();
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::all(Val::Px(BAR_PADDING)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                HudSlot::Status,
+            ));
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            gap: Size::all(Val::Px(BAR_PADDING)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    HudSlot::Consumables,
+                ))
+                .with_children(|parent| {
+                    let font = assets_server.load("font/BebasNeueRegular.otf");
+                    for kind in ConsumableKind::ALL {
+                        parent
+                            .spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        size: Size::height(Val::Px(STATUS_ICON_HEIGHT)),
+                                        padding: UiRect::horizontal(Val::Px(BAR_PADDING)),
+                                        // Only takes up room once there's something in it
+                                        display: Display::None,
+                                        ..default()
+                                    },
+                                    background_color: kind.definition().color.with_a(0.6).into(),
+                                    ..default()
+                                },
+                                ConsumableSlot { kind },
+                                RenderLayers::layer(1),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section(
+                                        "",
+                                        TextStyle {
+                                            font: font.clone(),
+                                            font_size: STATUS_ICON_HEIGHT,
+                                            color: Color::WHITE,
+                                        },
+                                    ),
+                                    ConsumableSlotText { kind },
+                                    RenderLayers::layer(1),
+                                ));
+                            });
+                    }
+                });
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: column(0.0),
+                        ..default()
+                    },
+                    HudSlot::Navigation,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: assets_server.load("font/BebasNeueRegular.otf"),
+                                font_size: STATUS_ICON_HEIGHT,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        NavigationReadout,
+                        RenderLayers::layer(1),
+                    ));
+                });
+        });
+
+    setup_hud_bar(
+        &mut commands,
+        &assets_server,
+        ultimate_slot,
+        UltimateBarAnchor,
+        CurrentUltimateBar,
+        UltimateBarThreshold,
+        "PULSE",
+        ULTIMATE_CHARGING_COLOR,
+        Color::WHITE,
+        None,
+    );
+}
+
 fn setup_heat_display(
     mut commands: Commands,
     assets_server: Res<AssetServer>,
-    heat_query: Query<&Heat, Added<Heat>>,
+    heat_query: Query<&Heat, (Added<Heat>, LeadPlayer)>,
+    slot_query: Query<(Entity, &HudSlot)>,
 ) {
     let Ok(heat) = heat_query.get_single() else { return; };
-    setup_ui_bar(
+    let Some(slot) = hud_slot(&slot_query, HudSlot::Heat) else { return; };
+    let bar = setup_hud_bar(
         &mut commands,
         &assets_server,
+        slot,
         HeatBarAnchor,
         CurrentHeatBar,
         HeatBarThreshold,
@@ -77,29 +422,146 @@ fn setup_heat_display(
         Color::WHITE,
         Some(heat.reaction_threshold()),
     );
+    // Hangs off the end of the bar, so it's hidden along with it
+    commands.entity(bar).with_children(|parent| {
+        parent.spawn((
+            TextBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: assets_server.load("font/BebasNeueRegular.otf"),
+                        font_size: COOLING_FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                ),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(BAR_LENGTH + BAR_PADDING),
+                        top: Val::Px((FONT_HEIGHT - COOLING_FONT_SIZE) / 2.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ..default()
+            },
+            HeatCoolingText,
+            RenderLayers::layer(1),
+        ));
+    });
 }
 
 fn update_heat_bar(
-    mut heat_bar_query: Query<&mut Transform, With<CurrentHeatBar>>,
-    player_query: Query<&Heat, (With<Player>, Without<CurrentHeatBar>)>,
+    mut heat_bar_query: Query<&mut Style, With<CurrentHeatBar>>,
+    mut cooling_query: Query<&mut Text, With<HeatCoolingText>>,
+    player_query: Query<&Heat, LeadPlayer>,
 ) {
     let player_heat = player_query.single();
-    for mut transform in &mut heat_bar_query {
-        transform.scale.x = player_heat.fraction();
+    for mut style in &mut heat_bar_query {
+        style.size.width = Val::Percent(100.0 * player_heat.fraction());
+    }
+    for mut text in &mut cooling_query {
+        text.sections[0].value = format!("COOLING {:.0}/S", player_heat.effective_decay_rate());
     }
 }
 
-fn reposition_heat_bar(
-    mut heat_bar_query: Query<&mut Transform, (With<HeatBarAnchor>, Without<CustomUICamera>)>,
-    ui_camera: Query<&Camera, With<CustomUICamera>>,
+#[derive(Component, Debug, Default)]
+struct CurrentHullBar;
+
+#[derive(Component, Debug, Default)]
+struct HullBarAnchor;
+
+#[derive(Component, Debug, Default)]
+struct HullBarThreshold;
+
+fn setup_hull_display(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    hull_query: Query<&Hull, (Added<Hull>, LeadPlayer)>,
+    slot_query: Query<(Entity, &HudSlot)>,
 ) {
-    let Ok(ui_camera) = ui_camera.get_single() else {return;};
-    let Some((top_left, _)) = ui_camera.logical_viewport_rect() else {return;};
-    let Some(size) = ui_camera.logical_viewport_size() else {return;};
-    let top_left = top_left + Vec2::new(-size.x / 2.0, size.y / 2.0);
-    for mut transform in &mut heat_bar_query {
-        transform.translation.x = top_left.x;
-        transform.translation.y = top_left.y - BAR_PADDING;
+    let Ok(_hull) = hull_query.get_single() else { return; };
+    let Some(slot) = hud_slot(&slot_query, HudSlot::Hull) else { return; };
+    setup_hud_bar(
+        &mut commands,
+        &assets_server,
+        slot,
+        HullBarAnchor,
+        CurrentHullBar,
+        HullBarThreshold,
+        "HULL",
+        HULL_COLOR,
+        Color::WHITE,
+        None,
+    );
+}
+
+fn update_hull_bar(
+    mut hull_bar_query: Query<&mut Style, With<CurrentHullBar>>,
+    player_query: Query<&Hull, LeadPlayer>,
+) {
+    let Ok(hull) = player_query.get_single() else { return; };
+    for mut style in &mut hull_bar_query {
+        style.size.width = Val::Percent(100.0 * hull.fraction());
+    }
+}
+
+fn update_hull_bar_visibility(
+    mut hull_bar_query: Query<&mut Visibility, With<HullBarAnchor>>,
+    hull_query: Query<&Hull, LeadPlayer>,
+    progress: Res<State<ProgressStages>>,
+) {
+    let Ok(hull) = hull_query.get_single() else { return; };
+
+    for mut visibility in &mut hull_bar_query {
+        // Nothing can damage the hull until the pirates show up
+        if progress.0 >= ProgressStages::ShieldAndStrange || hull.fraction() < 1.0 {
+            *visibility = Visibility::Visible;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+#[derive(Component, Debug, Default)]
+struct CurrentUltimateBar;
+
+#[derive(Component, Debug, Default)]
+struct UltimateBarAnchor;
+
+#[derive(Component, Debug, Default)]
+struct UltimateBarThreshold;
+
+const ULTIMATE_CHARGING_COLOR: Color = Color::rgb(0.4, 0.3, 0.8);
+const ULTIMATE_READY_COLOR: Color = Color::rgb(0.8, 0.9, 1.0);
+
+fn update_ultimate_bar(
+    mut ultimate_bar_query: Query<(&mut Style, &mut BackgroundColor), With<CurrentUltimateBar>>,
+    ultimate_charge: Res<UltimateCharge>,
+) {
+    for (mut style, mut color) in &mut ultimate_bar_query {
+        style.size.width = Val::Percent(100.0 * ultimate_charge.fraction());
+        *color = if ultimate_charge.ready() {
+            ULTIMATE_READY_COLOR
+        } else {
+            ULTIMATE_CHARGING_COLOR
+        }
+        .into();
+    }
+}
+
+fn update_ultimate_bar_visibility(
+    mut ultimate_bar_query: Query<&mut Visibility, With<UltimateBarAnchor>>,
+    ultimate_charge: Res<UltimateCharge>,
+    progress: Res<State<ProgressStages>>,
+) {
+    for mut visibility in &mut ultimate_bar_query {
+        // Strange matter only turns up once the shield does
+        if progress.0 >= ProgressStages::ShieldAndStrange || ultimate_charge.charge > 0.0 {
+            *visibility = Visibility::Visible;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
     }
 }
 
@@ -119,24 +581,29 @@ struct ReagentBarThreshold {
 }
 
 const FONT_HEIGHT: f32 = 40.0;
+const HULL_COLOR: Color = Color::rgb(0.6, 0.6, 0.7);
 const BAR_LENGTH: f32 = 250.0;
-const BAR_PADDING: f32 = 4.0;
+pub const BAR_PADDING: f32 = 4.0;
 
 fn setup_reagent_bars(
     mut commands: Commands,
     assets_server: Res<AssetServer>,
-    inventory_query: Query<&Inventory, Added<Inventory>>,
+    inventory_query: Query<&Inventory, (Added<Inventory>, LeadPlayer)>,
+    column_query: Query<Entity, With<ReagentBarColumn>>,
+    reagent_appearances: Res<ReagentAppearances>,
 ) {
     let Ok(inventory) = inventory_query.get_single() else { return; };
+    let Ok(column) = column_query.get_single() else { return; };
     for (reagent, entry) in inventory.reagents() {
-        setup_ui_bar(
+        setup_hud_bar(
             &mut commands,
             &assets_server,
+            column,
             ReagentBarAnchor { reagent },
             CurrentReagentBar { reagent },
             ReagentBarThreshold { reagent },
             entry.name(),
-            entry.color(),
+            reagent_appearances.get(reagent).color,
             Color::WHITE,
             None,
         );
@@ -145,54 +612,292 @@ fn setup_reagent_bars(
 }
 
 fn update_reagent_bar(
-    mut reagent_bar_query: Query<(&mut Transform, &CurrentReagentBar)>,
-    inventory_query: Query<&Inventory, (With<Player>, Without<CurrentReagentBar>)>,
+    mut reagent_bar_query: Query<(&mut Style, &CurrentReagentBar)>,
+    inventory_query: Query<&Inventory, LeadPlayer>,
 ) {
     let Ok(inventory) = inventory_query.get_single() else { return; };
-    for (mut transform, CurrentReagentBar { reagent }) in &mut reagent_bar_query {
-        transform.scale.x = inventory.reagent(*reagent).fraction();
+    for (mut style, CurrentReagentBar { reagent }) in &mut reagent_bar_query {
+        style.size.width = Val::Percent(100.0 * inventory.reagent(*reagent).fraction());
     }
 }
 
-fn reposition_reagent_bar(
-    mut reagent_bar_query: Query<(&mut Transform, &ReagentBarAnchor), Without<CustomUICamera>>,
-    ui_camera: Query<&Camera, With<CustomUICamera>>,
+/// The bar along the bottom of a status icon, shrinking as the effect wears off
+#[derive(Component, Debug)]
+struct StatusIconTimer {
+    kind: StatusKind,
+}
+
+const STATUS_ICON_HEIGHT: f32 = 24.0;
+
+/// The icons are only rebuilt when an effect comes or goes, otherwise just their timers move
+fn update_status_icons(
+    mut commands: Commands,
+    player_query: Query<Option<&StatusEffects>, LeadPlayer>,
+    slot_query: Query<(Entity, &HudSlot)>,
+    mut timer_query: Query<(&mut Style, &StatusIconTimer)>,
+    mut shown: Local<Vec<StatusKind>>,
+    assets_server: Res<AssetServer>,
 ) {
-    let Ok(ui_camera) = ui_camera.get_single() else {return;};
-    let Some((top_left, _)) = ui_camera.logical_viewport_rect() else {return;};
-    let Some(size) = ui_camera.logical_viewport_size() else {return;};
-    let top_left = top_left + Vec2::new(-size.x / 2.0, size.y / 2.0);
-    for (mut transform, ReagentBarAnchor { reagent }) in &mut reagent_bar_query {
-        let i = *reagent as usize;
-        transform.translation.x = top_left.x;
-        transform.translation.y =
-            top_left.y - BAR_PADDING - (i + 1) as f32 * (FONT_HEIGHT + BAR_PADDING);
+    let Ok(effects) = player_query.get_single() else { return; };
+    let kinds: Vec<_> = effects
+        .map(|effects| effects.kinds().collect())
+        .unwrap_or_default();
+    if *shown != kinds {
+        let Some(slot) = hud_slot(&slot_query, HudSlot::Status) else { return; };
+        let font = assets_server.load("font/BebasNeueRegular.otf");
+        commands.entity(slot).despawn_descendants();
+        commands.entity(slot).with_children(|parent| {
+            for kind in &kinds {
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::height(Val::Px(STATUS_ICON_HEIGHT)),
+                                padding: UiRect::horizontal(Val::Px(BAR_PADDING)),
+                                ..default()
+                            },
+                            background_color: kind.color().with_a(0.6).into(),
+                            ..default()
+                        },
+                        RenderLayers::layer(1),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(
+                                kind.label(),
+                                TextStyle {
+                                    font: font.clone(),
+                                    font_size: STATUS_ICON_HEIGHT,
+                                    color: Color::WHITE,
+                                },
+                            ),
+                            RenderLayers::layer(1),
+                        ));
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    position: UiRect {
+                                        left: Val::Px(0.0),
+                                        bottom: Val::Px(0.0),
+                                        ..default()
+                                    },
+                                    size: Size::new(Val::Percent(100.0), Val::Px(3.0)),
+                                    ..default()
+                                },
+                                background_color: kind.color().into(),
+                                ..default()
+                            },
+                            StatusIconTimer { kind: *kind },
+                            RenderLayers::layer(1),
+                        ));
+                    });
+            }
+        });
+        *shown = kinds;
+        return;
+    }
+
+    let Some(effects) = effects else { return; };
+    for (mut style, timer) in &mut timer_query {
+        style.size.width = Val::Percent(100.0 * effects.remaining(timer.kind));
     }
 }
 
-fn update_heat_bar_visibility(
-    mut heat_bar_query: Query<(&mut Visibility, &HeatBarAnchor)>,
-    heat_query: Query<&Heat, (With<Player>, Without<HeatBarAnchor>)>,
-) {
-    let Ok(heat) = heat_query.get_single() else { return; };
+/// One of the consumables on the HUD, hidden while the ship hasn't got any
+#[derive(Component, Debug)]
+struct ConsumableSlot {
+    kind: ConsumableKind,
+}
 
-    for (mut visibility, HeatBarAnchor) in &mut heat_bar_query {
-        if heat.enabled() {
-            *visibility = Visibility::Visible;
+#[derive(Component, Debug)]
+struct ConsumableSlotText {
+    kind: ConsumableKind,
+}
+
+fn update_consumable_slots(
+    player_query: Query<&Consumables, (LeadPlayer, Changed<Consumables>)>,
+    mut slot_query: Query<(&mut Style, &ConsumableSlot)>,
+    mut text_query: Query<(&mut Text, &ConsumableSlotText)>,
+) {
+    let Ok(consumables) = player_query.get_single() else { return; };
+    for (mut style, ConsumableSlot { kind }) in &mut slot_query {
+        style.display = if consumables.count(*kind) > 0 {
+            Display::Flex
         } else {
-            *visibility = Visibility::Hidden;
+            Display::None
+        };
+    }
+    for (mut text, ConsumableSlotText { kind }) in &mut text_query {
+        // The keys are 1, 2 and 3, in the same order as the slots
+        text.sections[0].value = format!(
+            "{} {} X{}",
+            *kind as usize + 1,
+            kind.definition().name,
+            consumables.count(*kind)
+        );
+    }
+}
+
+/// Distances are in world units, shown as metres
+#[derive(Component, Debug)]
+struct NavigationReadout;
+
+fn update_navigation_readout(
+    mut text_query: Query<&mut Text, With<NavigationReadout>>,
+    player_query: Query<&Transform, LeadPlayer>,
+    odometer: Res<Odometer>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    // Runs start at the origin, where the beacon is
+    let from_origin = player_transform.translation.truncate().length();
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("ORIGIN {from_origin:.0}M   TRIP {:.0}M", odometer.trip);
+    }
+}
+
+/// Lights a reagent bar up for a moment when the stage's threshold is reached
+#[derive(Component, Debug)]
+struct ReagentBarFlash(Timer);
+
+const BAR_FLASH_SECONDS: f32 = 1.0;
+const BAR_FLASHES: f32 = 3.0;
+
+fn start_reagent_bar_flashes(
+    mut commands: Commands,
+    mut reader: EventReader<StageThresholdReached>,
+    bar_query: Query<(Entity, &CurrentReagentBar)>,
+) {
+    for ev in reader.iter() {
+        for (entity, bar) in &bar_query {
+            if ev.reagents.contains(&bar.reagent) {
+                commands.entity(entity).insert(ReagentBarFlash(Timer::from_seconds(
+                    BAR_FLASH_SECONDS,
+                    TimerMode::Once,
+                )));
+            }
         }
     }
 }
 
-fn update_reagent_bar_visibility(
-    mut reagent_bar_query: Query<(&mut Visibility, &ReagentBarAnchor)>,
-    inventory_query: Query<&Inventory, (With<Player>, Without<ReagentBarAnchor>)>,
+/// Blends the bar towards white and back a few times, then puts its own colour back
+fn update_reagent_bar_flashes(
+    mut commands: Commands,
+    mut bar_query: Query<(
+        Entity,
+        &mut BackgroundColor,
+        &CurrentReagentBar,
+        &mut ReagentBarFlash,
+    )>,
+    reagent_appearances: Res<ReagentAppearances>,
+    time: Res<Time>,
+) {
+    for (entity, mut color, bar, mut flash) in &mut bar_query {
+        let base = reagent_appearances.get(bar.reagent).color;
+        if flash.0.tick(time.delta()).finished() {
+            *color = base.into();
+            commands.entity(entity).remove::<ReagentBarFlash>();
+            continue;
+        }
+        let strength = (flash.0.percent() * BAR_FLASHES * std::f32::consts::PI)
+            .sin()
+            .abs();
+        let [r, g, b, a] = base.as_rgba_f32();
+        *color = Color::rgba(
+            r + (1.0 - r) * strength,
+            g + (1.0 - g) * strength,
+            b + (1.0 - b) * strength,
+            a,
+        )
+        .into();
+    }
+}
+
+/// Which row each reagent's bar goes in, indexed by `Reagent`. The bars on screen go at the top
+/// in the order from the `HudLayout`, and the rest after them
+#[derive(Resource, Debug, Default, PartialEq)]
+struct ReagentBarRows {
+    rows: Vec<usize>,
+    shown: Vec<bool>,
+}
+
+impl ReagentBarRows {
+    fn row(&self, reagent: Reagent) -> usize {
+        self.rows
+            .get(reagent.index())
+            .copied()
+            .unwrap_or(reagent.index())
+    }
+
+    fn shown(&self, reagent: Reagent) -> bool {
+        self.shown.get(reagent.index()).copied().unwrap_or(false)
+    }
+}
+
+fn update_reagent_bar_rows(
+    mut bar_rows: ResMut<ReagentBarRows>,
+    inventory_query: Query<&Inventory, LeadPlayer>,
+    registry: Res<ReagentRegistry>,
+    save_data: Res<SaveData>,
 ) {
     let Ok(inventory) = inventory_query.get_single() else { return; };
+    let layout = &save_data.hud_layout;
+    let shown: Vec<_> = registry
+        .reagents()
+        .map(|(reagent, _)| {
+            inventory.reagent(reagent).visibile() && !layout.is_collapsed(reagent, &registry)
+        })
+        .collect();
+    let (on_screen, off_screen): (Vec<_>, Vec<_>) = layout
+        .ordered(&registry)
+        .into_iter()
+        .partition(|reagent| shown[reagent.index()]);
+    let mut rows = vec![0; registry.len()];
+    for (row, reagent) in on_screen.into_iter().chain(off_screen).enumerate() {
+        rows[reagent.index()] = row;
+    }
+    let new_rows = ReagentBarRows { rows, shown };
+    // Only touched when something moves, so the bars and indicators know when to rebuild
+    if *bar_rows != new_rows {
+        *bar_rows = new_rows;
+    }
+}
 
-    for (mut visibility, ReagentBarAnchor { reagent }) in &mut reagent_bar_query {
-        if inventory.reagent(*reagent).visibile() {
+/// Bars that aren't shown are taken out of the layout altogether, so the ones below move up
+fn reorder_reagent_bars(
+    mut commands: Commands,
+    mut reagent_bar_query: Query<(Entity, &mut Style, &ReagentBarAnchor)>,
+    added_query: Query<(), Added<ReagentBarAnchor>>,
+    column_query: Query<Entity, With<ReagentBarColumn>>,
+    bar_rows: Res<ReagentBarRows>,
+) {
+    if !bar_rows.is_changed() && added_query.is_empty() {
+        return;
+    }
+    let Ok(column) = column_query.get_single() else { return; };
+
+    let mut bars = Vec::new();
+    for (entity, mut style, ReagentBarAnchor { reagent }) in &mut reagent_bar_query {
+        style.display = if bar_rows.shown(*reagent) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        bars.push((bar_rows.row(*reagent), entity));
+    }
+    bars.sort();
+    let bars: Vec<_> = bars.into_iter().map(|(_, entity)| entity).collect();
+    commands.entity(column).replace_children(&bars);
+}
+
+fn update_heat_bar_visibility(
+    mut heat_bar_query: Query<(&mut Visibility, &HeatBarAnchor)>,
+    heat_query: Query<&Heat, LeadPlayer>,
+) {
+    let Ok(heat) = heat_query.get_single() else { return; };
+
+    for (mut visibility, HeatBarAnchor) in &mut heat_bar_query {
+        if heat.enabled() {
             *visibility = Visibility::Visible;
         } else {
             *visibility = Visibility::Hidden;
@@ -201,15 +906,15 @@ fn update_reagent_bar_visibility(
 }
 
 fn update_heat_bar_threshold(
-    mut heat_bar_query: Query<(&mut Visibility, &mut Transform, &HeatBarThreshold)>,
-    heat_query: Query<&Heat, (With<Player>, Without<HeatBarThreshold>)>,
+    mut heat_bar_query: Query<(&mut Visibility, &mut Style, &HeatBarThreshold)>,
+    heat_query: Query<&Heat, LeadPlayer>,
 ) {
     let Ok(heat) = heat_query.get_single() else { return; };
 
-    for (mut visibility, mut transform, HeatBarThreshold) in &mut heat_bar_query {
+    for (mut visibility, mut style, HeatBarThreshold) in &mut heat_bar_query {
         if heat.threshold_visible() {
             *visibility = Visibility::Inherited;
-            transform.translation.x = BAR_LENGTH * heat.reaction_threshold();
+            style.position.left = Val::Percent(100.0 * heat.reaction_threshold());
         } else {
             *visibility = Visibility::Hidden;
         }
@@ -217,113 +922,388 @@ fn update_heat_bar_threshold(
 }
 
 fn update_reagent_bar_threshold(
-    mut reagent_bar_query: Query<(&mut Visibility, &mut Transform, &ReagentBarThreshold)>,
-    inventory_query: Query<&Inventory, (With<Player>, Without<ReagentBarThreshold>)>,
+    mut reagent_bar_query: Query<(&mut Visibility, &mut Style, &ReagentBarThreshold)>,
+    inventory_query: Query<&Inventory, LeadPlayer>,
 ) {
     let Ok(inventory) = inventory_query.get_single() else { return; };
 
-    for (mut visibility, mut transform, ReagentBarThreshold { reagent }) in &mut reagent_bar_query {
+    for (mut visibility, mut style, ReagentBarThreshold { reagent }) in &mut reagent_bar_query {
         if let Some(threshold) = inventory.reagent(*reagent).threshold() {
             *visibility = Visibility::Inherited;
-            transform.translation.x = BAR_LENGTH * threshold;
+            style.position.left = Val::Percent(100.0 * threshold);
         } else {
             *visibility = Visibility::Hidden;
         }
     }
 }
 
-#[derive(Component, Debug, Default)]
-struct HintMarker {
-    stage: ProgressStages,
+/// Sits beside the reagent bars, joining up the ones a reaction uses
+#[derive(Component, Debug)]
+struct ReactionIndicator {
+    /// Where it is in `Reactions`
+    reaction: usize,
+    reagents: Vec<Reagent>,
 }
 
-fn setup_all_hints(mut commands: Commands, asset_server: Res<AssetServer>) {
-    const FONT_SIZE: f32 = 25.0;
+/// Runs down the indicator's line from the reagent being used up to the result
+#[derive(Component, Debug)]
+struct ReactionIndicatorPulse {
+    from: f32,
+    to: f32,
+}
 
-    let text = include_str!("hints/0.txt");
-    let font = asset_server.load("font/BebasNeueRegular.otf");
-    let sections = crate::util::markup_to_text_sections(
-        text,
-        font,
-        FONT_SIZE,
-        Color::ORANGE_RED,
-        Color::WHITE,
-    );
-    setup_hint(
-        &mut commands,
-        sections,
-        HintMarker {
-            stage: ProgressStages::Exploration,
-        },
-    );
+const INDICATOR_SPACING: f32 = 12.0;
+const INDICATOR_PULSE_SIZE: f32 = 6.0;
+/// Pulses per second
+const INDICATOR_PULSE_SPEED: f32 = 1.5;
 
-    let text = include_str!("hints/1.txt");
-    let font = asset_server.load("font/BebasNeueRegular.otf");
-    let sections = crate::util::markup_to_text_sections(
-        text,
-        font,
-        FONT_SIZE,
-        Color::ORANGE_RED,
-        Color::WHITE,
-    );
-    setup_hint(
-        &mut commands,
-        sections,
-        HintMarker {
-            stage: ProgressStages::GunAndHeat,
-        },
-    );
+/// The middle of a row of reagent bars, down from the top of the first one
+fn bar_row_middle(row: usize) -> f32 {
+    row as f32 * (FONT_HEIGHT + BAR_PADDING) + FONT_HEIGHT / 2.0
+}
 
-    let text = include_str!("hints/2.txt");
-    let font = asset_server.load("font/BebasNeueRegular.otf");
-    let sections = crate::util::markup_to_text_sections(
-        text,
-        font,
-        FONT_SIZE,
-        Color::ORANGE_RED,
-        Color::WHITE,
-    );
-    setup_hint(
-        &mut commands,
-        sections,
-        HintMarker {
-            stage: ProgressStages::CollectExotic,
+/// A plain rectangle, placed from the top left of its parent
+fn indicator_rect(left: f32, top: f32, width: f32, height: f32, color: Color) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(left),
+                top: Val::Px(top),
+                ..default()
+            },
+            size: Size::new(Val::Px(width), Val::Px(height)),
+            ..default()
         },
-    );
+        background_color: color.into(),
+        ..default()
+    }
+}
 
-    let text = include_str!("hints/3.txt");
-    let font = asset_server.load("font/BebasNeueRegular.otf");
-    let sections = crate::util::markup_to_text_sections(
-        text,
-        font,
-        FONT_SIZE,
-        Color::ORANGE_RED,
+/// The campaign can add reactions part way through a run, so these are set up again whenever
+/// the reactions or the order of the bars change
+fn setup_reaction_indicators(
+    mut commands: Commands,
+    reactions: Option<Res<Reactions>>,
+    registry: Res<ReagentRegistry>,
+    bar_rows: Res<ReagentBarRows>,
+    indicator_query: Query<Entity, With<ReactionIndicator>>,
+    slot_query: Query<(Entity, &HudSlot)>,
+) {
+    let Some(reactions) = reactions else { return; };
+    if !reactions.is_changed() && !bar_rows.is_changed() {
+        return;
+    }
+    let Some(slot) = hud_slot(&slot_query, HudSlot::Reagents) else { return; };
+    for entity in &indicator_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let with_results = reactions
+        .reactions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, reaction)| Some((i, reaction, reaction.result?)));
+    for (lane, (i, reaction, result)) in with_results.enumerate() {
+        let reagents: Vec<_> = [Some(reaction.reagent1), reaction.reagent2, Some(result)]
+            .into_iter()
+            .flatten()
+            .collect();
+        let rows: Vec<_> = reagents
+            .iter()
+            .map(|&reagent| bar_row_middle(bar_rows.row(reagent)))
+            .collect();
+        let top = rows.iter().copied().fold(f32::INFINITY, f32::min);
+        let bottom = rows.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let x = (lane + 1) as f32 * INDICATOR_SPACING;
+        let color = registry.get(result).color;
+        let from = rows[0] - INDICATOR_PULSE_SIZE / 2.0;
+        let to = bar_row_middle(bar_rows.row(result)) - INDICATOR_PULSE_SIZE / 2.0;
+
+        let indicator = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        // Just off the right hand end of the bars
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            left: Val::Px(BAR_LENGTH),
+                            top: Val::Px(0.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                ReactionIndicator {
+                    reaction: i,
+                    reagents,
+                },
+                RenderLayers::layer(1),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    indicator_rect(x - 1.0, top, 2.0, bottom - top, Color::WHITE),
+                    RenderLayers::layer(1),
+                ));
+                // A stub out to each bar the reaction uses
+                for &row in &rows {
+                    parent.spawn((
+                        indicator_rect(0.0, row - 1.0, x, 2.0, Color::WHITE),
+                        RenderLayers::layer(1),
+                    ));
+                }
+                parent.spawn((
+                    indicator_rect(
+                        x - INDICATOR_PULSE_SIZE / 2.0,
+                        from,
+                        INDICATOR_PULSE_SIZE,
+                        INDICATOR_PULSE_SIZE,
+                        color,
+                    ),
+                    RenderLayers::layer(1),
+                    ReactionIndicatorPulse { from, to },
+                ));
+            })
+            .id();
+        commands.entity(slot).add_child(indicator);
+    }
+}
+
+fn update_reaction_indicators(
+    mut indicator_query: Query<(&mut Visibility, &ReactionIndicator)>,
+    mut pulse_query: Query<(&mut Style, &ReactionIndicatorPulse)>,
+    player_query: Query<&ReactionActivity, LeadPlayer>,
+    bar_rows: Res<ReagentBarRows>,
+    time: Res<Time>,
+) {
+    let Ok(activity) = player_query.get_single() else { return; };
+    for (mut visibility, indicator) in &mut indicator_query {
+        let shown = activity.is_active(indicator.reaction)
+            && indicator
+                .reagents
+                .iter()
+                .all(|&reagent| bar_rows.shown(reagent));
+        *visibility = if shown {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    let t = (time.elapsed_seconds() * INDICATOR_PULSE_SPEED).fract();
+    for (mut style, pulse) in &mut pulse_query {
+        style.position.top = Val::Px(pulse.from + (pulse.to - pulse.from) * t);
+    }
+}
+
+/// What one of the second player's bars shows
+#[derive(Debug, Clone, Copy)]
+enum SecondPlayerStat {
+    Heat,
+    Hull,
+    Reagent(Reagent),
+}
+
+impl SecondPlayerStat {
+    fn fraction(&self, heat: &Heat, hull: &Hull, inventory: &Inventory) -> f32 {
+        match self {
+            SecondPlayerStat::Heat => heat.fraction(),
+            SecondPlayerStat::Hull => hull.fraction(),
+            SecondPlayerStat::Reagent(reagent) => inventory.reagent(*reagent).fraction(),
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct SecondPlayerBarAnchor {
+    stat: SecondPlayerStat,
+    /// Counted up from the bottom of the screen
+    row: usize,
+}
+
+#[derive(Component, Debug)]
+struct CurrentSecondPlayerBar {
+    stat: SecondPlayerStat,
+}
+
+#[derive(Component, Debug)]
+struct SecondPlayerBarThreshold {
+    stat: SecondPlayerStat,
+}
+
+/// The second player's bars go in the bottom left, stacked up the screen in the same order as
+/// the first player's. Their reagents are only shown while the cargo is split
+fn setup_second_player_bars(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    player_query: Query<(&Heat, &Inventory), Added<SecondPlayer>>,
+    reagent_appearances: Res<ReagentAppearances>,
+) {
+    let Ok((heat, inventory)) = player_query.get_single() else { return; };
+    let stat = SecondPlayerStat::Hull;
+    setup_ui_bar(
+        &mut commands,
+        &assets_server,
+        SecondPlayerBarAnchor { stat, row: 0 },
+        CurrentSecondPlayerBar { stat },
+        SecondPlayerBarThreshold { stat },
+        "P2 HULL",
+        HULL_COLOR,
         Color::WHITE,
+        None,
     );
-    setup_hint(
+    let reagent_count = inventory.reagents().count();
+    for (reagent, entry) in inventory.reagents() {
+        let stat = SecondPlayerStat::Reagent(reagent);
+        setup_ui_bar(
+            &mut commands,
+            &assets_server,
+            SecondPlayerBarAnchor {
+                stat,
+                row: reagent_count - reagent.index(),
+            },
+            CurrentSecondPlayerBar { stat },
+            SecondPlayerBarThreshold { stat },
+            &format!("P2 {}", entry.name()),
+            reagent_appearances.get(reagent).color,
+            Color::WHITE,
+            None,
+        );
+    }
+    let stat = SecondPlayerStat::Heat;
+    setup_ui_bar(
         &mut commands,
-        sections,
-        HintMarker {
-            stage: ProgressStages::ShieldAndStrange,
+        &assets_server,
+        SecondPlayerBarAnchor {
+            stat,
+            row: reagent_count + 1,
         },
+        CurrentSecondPlayerBar { stat },
+        SecondPlayerBarThreshold { stat },
+        "P2 HEAT",
+        Color::RED,
+        Color::WHITE,
+        Some(heat.reaction_threshold()),
     );
+    debug!("Finished setting up ui bars for the second player");
+}
+
+fn update_second_player_bars(
+    mut bar_query: Query<(&mut Transform, &CurrentSecondPlayerBar)>,
+    player_query: Query<(&Heat, &Hull, &Inventory), With<SecondPlayer>>,
+) {
+    let Ok((heat, hull, inventory)) = player_query.get_single() else { return; };
+    for (mut transform, CurrentSecondPlayerBar { stat }) in &mut bar_query {
+        transform.scale.x = stat.fraction(heat, hull, inventory);
+    }
+}
 
-    let text = include_str!("hints/4.txt");
+fn reposition_second_player_bars(
+    mut bar_query: Query<(&mut Transform, &SecondPlayerBarAnchor), Without<CustomUICamera>>,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+) {
+    let Ok(ui_camera) = ui_camera.get_single() else {return;};
+    let Some(size) = ui_camera.logical_viewport_size() else {return;};
+    let bottom_left = Vec2::new(-size.x / 2.0, -size.y / 2.0);
+    for (mut transform, SecondPlayerBarAnchor { row, .. }) in &mut bar_query {
+        // The bars hang down from their anchor, so each one is placed by its top edge
+        transform.translation.x = bottom_left.x;
+        transform.translation.y =
+            bottom_left.y + BAR_PADDING + FONT_HEIGHT + *row as f32 * (FONT_HEIGHT + BAR_PADDING);
+    }
+}
+
+fn update_second_player_bar_visibility(
+    mut bar_query: Query<(&mut Visibility, &SecondPlayerBarAnchor)>,
+    player_query: Query<(&Heat, &Hull, &Inventory), With<SecondPlayer>>,
+    coop_settings: Res<CoopSettings>,
+    progress: Res<State<ProgressStages>>,
+) {
+    let Ok((heat, hull, inventory)) = player_query.get_single() else { return; };
+
+    for (mut visibility, SecondPlayerBarAnchor { stat, .. }) in &mut bar_query {
+        let visible = match stat {
+            SecondPlayerStat::Heat => heat.enabled(),
+            // Same as the first player's hull bar
+            SecondPlayerStat::Hull => {
+                progress.0 >= ProgressStages::ShieldAndStrange || hull.fraction() < 1.0
+            }
+            // Shared cargo is already on the first player's bars
+            SecondPlayerStat::Reagent(reagent) => {
+                !coop_settings.shared_inventory && inventory.reagent(*reagent).visibile()
+            }
+        };
+        *visibility = if visible {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn update_second_player_bar_thresholds(
+    mut threshold_query: Query<(&mut Visibility, &mut Transform, &SecondPlayerBarThreshold)>,
+    player_query: Query<(&Heat, &Inventory), With<SecondPlayer>>,
+) {
+    let Ok((heat, inventory)) = player_query.get_single() else { return; };
+
+    for (mut visibility, mut transform, SecondPlayerBarThreshold { stat }) in &mut threshold_query
+    {
+        let threshold = match stat {
+            SecondPlayerStat::Heat => heat
+                .threshold_visible()
+                .then_some(heat.reaction_threshold()),
+            SecondPlayerStat::Hull => None,
+            SecondPlayerStat::Reagent(reagent) => inventory.reagent(*reagent).threshold(),
+        };
+        if let Some(threshold) = threshold {
+            *visibility = Visibility::Inherited;
+            transform.translation.x = BAR_LENGTH * threshold;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+#[derive(Component, Debug, Default)]
+struct HintMarker {
+    stage: ProgressStages,
+}
+
+/// The stages whose hints have been shown so far this run
+#[derive(Resource, Debug, Default)]
+pub struct HintLog {
+    pub seen: Vec<ProgressStages>,
+}
+
+fn setup_all_hints(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut hint_log: ResMut<HintLog>,
+    campaign: Res<Campaign>,
+    text_pack: Res<TextPack>,
+    texts: Res<Assets<TextAsset>>,
+) {
+    const FONT_SIZE: f32 = 25.0;
+
+    hint_log.seen.clear();
     let font = asset_server.load("font/BebasNeueRegular.otf");
-    let sections = crate::util::markup_to_text_sections(
-        text,
-        font,
-        FONT_SIZE,
-        Color::ORANGE_RED,
-        Color::WHITE,
-    );
-    setup_hint(
-        &mut commands,
-        sections,
-        HintMarker {
-            stage: ProgressStages::Continuum,
-        },
-    );
+    for campaign_stage in campaign.stages() {
+        let Some(hint) = &campaign_stage.hint else { continue; };
+        let pack_text = PackText {
+            file: PackFile::Hint(hint.clone()),
+            font_size: FONT_SIZE,
+            highlight_color: Color::ORANGE_RED,
+            color: Color::WHITE,
+        };
+        let sections = pack_text.sections(font.clone(), &text_pack, &texts);
+        let marker = HintMarker {
+            stage: campaign_stage.stage,
+        };
+        setup_hint(&mut commands, sections, pack_text, marker);
+    }
 }
 
 fn cleanup_ui(mut commands: Commands, query: Query<Entity, With<UIMarker>>) {
@@ -335,6 +1315,7 @@ fn cleanup_ui(mut commands: Commands, query: Query<Entity, With<UIMarker>>) {
 fn display_correct_hint(
     mut query: Query<(&mut Visibility, &HintMarker)>,
     progress: Res<State<ProgressStages>>,
+    mut hint_log: ResMut<HintLog>,
 ) {
     if !progress.is_changed() {
         return;
@@ -345,6 +1326,9 @@ fn display_correct_hint(
 
         if hint_marker.stage == progress.0 {
             *visibility = Visibility::Visible;
+            if !hint_log.seen.contains(&progress.0) {
+                hint_log.seen.push(progress.0);
+            }
         }
     }
 }
@@ -365,7 +1349,7 @@ fn reposition_hints(
 
 use bitflags::bitflags;
 bitflags! {
-    #[derive(Resource, PartialEq, Eq, Hash)]
+    #[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct EnabledControls: u8 {
         const Move   = 0b00000001;
         const Look   = 0b00000010;
@@ -431,6 +1415,74 @@ fn update_control_display_visibility(
     }
 }
 
+/// Flashes up above the controls when something is pressed before it's been unlocked
+#[derive(Component, Debug)]
+struct LockedControlBlip {
+    timer: Timer,
+}
+
+const BLIP_SECONDS: f32 = 1.2;
+
+fn control_name(control: EnabledControls) -> &'static str {
+    [
+        (EnabledControls::Move, "MOVING"),
+        (EnabledControls::Look, "LOOKING AROUND"),
+        (EnabledControls::Shoot, "THE MAIN CANNON"),
+        (EnabledControls::Shield, "THE SHIELD"),
+        (EnabledControls::Dump, "DROPPING CARGO"),
+    ]
+    .into_iter()
+    .find(|(flag, _)| *flag == control)
+    .map_or("THAT", |(_, name)| name)
+}
+
+fn setup_locked_control_blip(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("font/BebasNeueRegular.otf");
+    let mut timer = Timer::from_seconds(BLIP_SECONDS, TimerMode::Once);
+    // Starts out finished so there's nothing on screen
+    timer.tick(timer.duration());
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: CONTROL_FONT_SIZE,
+                    color: Color::ORANGE_RED.with_a(0.0),
+                },
+            ),
+            text_anchor: Anchor::BottomCenter,
+            ..Default::default()
+        },
+        LockedControlBlip { timer },
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+fn update_locked_control_blip(
+    mut reader: EventReader<LockedActionPressed>,
+    mut blip_query: Query<(&mut Text, &mut Transform, &mut LockedControlBlip)>,
+    ui_camera: Query<&Camera, With<CustomUICamera>>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    time: Res<Time>,
+) {
+    let Ok((mut text, mut transform, mut blip)) = blip_query.get_single_mut() else { return; };
+    if let Some(ev) = reader.iter().last() {
+        text.sections[0].value = format!("{} ISN'T AVAILABLE YET", control_name(ev.control));
+        blip.timer.reset();
+        sound_event_writer.send(SoundEvent::LockedControl);
+    }
+    blip.timer.tick(time.delta());
+    let alpha = blip.timer.percent_left();
+    text.sections[0].style.color.set_a(alpha);
+
+    let Ok(ui_camera) = ui_camera.get_single() else { return; };
+    let Some(size) = ui_camera.logical_viewport_size() else { return; };
+    transform.translation.x = 0.0;
+    transform.translation.y = -size.y / 2.0 + BAR_PADDING * 20.0;
+}
+
 fn reposition_control_displays(
     mut display_query: Query<
         (&mut Transform, &ControlIndex),
@@ -460,7 +1512,7 @@ pub struct ControlIndex {
     index: u32,
 }
 
-const CONTROL_FONT_SIZE: f32 = 25.0;
+pub const CONTROL_FONT_SIZE: f32 = 25.0;
 const CONTROL_WIDTH: f32 = 200.0;
 fn setup_control_display<C: Component>(
     commands: &mut Commands,
@@ -546,14 +1598,19 @@ fn setup_control_display<C: Component>(
 pub struct HintAnchor;
 
 const HINT_WIDTH: f32 = 350.0;
-const HINT_HEIGHT: f32 = 200.0;
+pub const HINT_HEIGHT: f32 = 200.0;
 
 // These are used to place the text properly
 // probably need to be tuned differently for different fonts
 const NUDGE_RIGHT: f32 = 5.0;
 const NUDGE_DOWN: f32 = 4.0;
 
-fn setup_hint<C: Component>(commands: &mut Commands, sections: Vec<TextSection>, marker: C) {
+fn setup_hint<C: Component>(
+    commands: &mut Commands,
+    sections: Vec<TextSection>,
+    pack_text: PackText,
+    marker: C,
+) {
     commands
         .spawn((
             SpatialBundle {
@@ -576,6 +1633,7 @@ fn setup_hint<C: Component>(commands: &mut Commands, sections: Vec<TextSection>,
                     transform: Transform::from_xyz(-NUDGE_RIGHT, -NUDGE_DOWN, 2.0),
                     ..Default::default()
                 },
+                pack_text,
                 RenderLayers::layer(1),
             ));
             // background
@@ -713,26 +1771,137 @@ fn setup_ui_bar<T: Component, U: Component, V: Component>(
         .id()
 }
 
+/// Same as `setup_ui_bar`, but made of bevy_ui nodes and added to the end of `parent`
+#[allow(clippy::too_many_arguments)]
+fn setup_hud_bar<T: Component, U: Component, V: Component>(
+    commands: &mut Commands,
+    assets_server: &AssetServer,
+    parent: Entity,
+    anchor_component: T,
+    current_component: U,
+    threshold_component: V,
+    label: &str,
+    bar_color: Color,
+    text_color: Color,
+    threshold: Option<f32>,
+) -> Entity {
+    let font = assets_server.load("font/BebasNeueRegular.otf");
+    let placed = |left: Val, top: Val, size: Size| Style {
+        position_type: PositionType::Absolute,
+        position: UiRect {
+            left,
+            top,
+            ..default()
+        },
+        size,
+        ..default()
+    };
+    let label_style = |color: Color| TextStyle {
+        font: font.clone(),
+        font_size: FONT_HEIGHT,
+        color,
+    };
+
+    // Everything gets the HUD's render layer as well, so the HUD settings know what to fade
+    let bar = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(BAR_LENGTH), Val::Px(FONT_HEIGHT)),
+                    ..default()
+                },
+                ..default()
+            },
+            anchor_component,
+            RenderLayers::layer(1),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: placed(
+                        Val::Px(0.0),
+                        Val::Px(0.0),
+                        Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    ),
+                    background_color: bar_color.into(),
+                    ..default()
+                },
+                RenderLayers::layer(1),
+                current_component,
+            ));
+            parent.spawn((
+                NodeBundle {
+                    style: placed(
+                        Val::Percent(100.0 * threshold.unwrap_or_default()),
+                        Val::Px(0.0),
+                        Size::new(Val::Px(2.0), Val::Percent(100.0)),
+                    ),
+                    background_color: Color::YELLOW.into(),
+                    visibility: if threshold.is_some() {
+                        Visibility::Inherited
+                    } else {
+                        Visibility::Hidden
+                    },
+                    ..default()
+                },
+                RenderLayers::layer(1),
+                threshold_component,
+            ));
+            // Drop shadow
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(label, label_style(Color::BLACK)),
+                    style: placed(Val::Px(NUDGE_RIGHT + 2.0), Val::Px(2.0), Size::AUTO),
+                    ..default()
+                },
+                RenderLayers::layer(1),
+            ));
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(label, label_style(text_color)),
+                    style: placed(Val::Px(NUDGE_RIGHT), Val::Px(0.0), Size::AUTO),
+                    ..default()
+                },
+                RenderLayers::layer(1),
+            ));
+        })
+        .id();
+    commands.entity(parent).add_child(bar);
+    bar
+}
+
+fn hud_on_screen(state: Res<State<GameState>>) -> bool {
+    matches!(state.0, GameState::InGame | GameState::Paused)
+}
+
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<HintLog>();
+        app.init_resource::<ReagentBarRows>();
+        app.init_resource::<HudSettings>();
+        app.init_resource::<HudAlphas>();
+        app.add_systems((toggle_hud_on_hotkey, show_or_hide_hud).chain());
+        // Faded once everything else is done with the colours, and put back before the next frame
+        app.add_system(fade_hud.in_base_set(CoreSet::Last));
+        app.add_system(unfade_hud.in_base_set(CoreSet::First));
         app.add_system(setup_all_hints.in_schedule(OnExit(GameState::Intro)));
         app.add_system(setup_control_flags.in_schedule(OnExit(GameState::Intro)));
         app.add_system(setup_all_control_displays.in_schedule(OnExit(GameState::Intro)));
+        app.add_system(setup_locked_control_blip.in_schedule(OnExit(GameState::Intro)));
+        app.add_system(setup_hud.in_schedule(OnExit(GameState::Intro)));
         app.add_system(cleanup_ui.in_schedule(OnEnter(GameState::Outro)));
         app.add_systems(
             (
                 setup_heat_display,
-                reposition_heat_bar,
-                reposition_reagent_bar,
                 update_heat_bar,
                 update_heat_bar_visibility,
                 update_heat_bar_threshold,
                 setup_reagent_bars,
                 update_reagent_bar,
-                update_reagent_bar_visibility,
                 update_reagent_bar_threshold,
+                update_reaction_indicators,
                 display_correct_hint,
                 reposition_hints,
                 reposition_control_displays,
@@ -740,5 +1909,46 @@ impl Plugin for UIPlugin {
             )
                 .in_set(OnUpdate(GameState::InGame)),
         );
+        app.add_system(update_locked_control_blip.in_set(OnUpdate(GameState::InGame)));
+        app.add_system(update_status_icons.in_set(OnUpdate(GameState::InGame)));
+        app.add_system(update_consumable_slots.in_set(OnUpdate(GameState::InGame)));
+        app.add_system(update_navigation_readout.in_set(OnUpdate(GameState::InGame)));
+        app.add_systems(
+            (start_reagent_bar_flashes, update_reagent_bar_flashes)
+                .chain()
+                .in_set(OnUpdate(GameState::InGame)),
+        );
+        // Also kept up to date while paused, so changes to the layout show up straight away
+        app.add_systems(
+            (
+                update_reagent_bar_rows,
+                reorder_reagent_bars,
+                setup_reaction_indicators,
+            )
+                .chain()
+                .distributive_run_if(hud_on_screen),
+        );
+        app.add_systems(
+            (
+                setup_hull_display,
+                update_hull_bar,
+                update_hull_bar_visibility,
+            )
+                .in_set(OnUpdate(GameState::InGame)),
+        );
+        app.add_systems(
+            (update_ultimate_bar, update_ultimate_bar_visibility)
+                .in_set(OnUpdate(GameState::InGame)),
+        );
+        app.add_systems(
+            (
+                setup_second_player_bars,
+                update_second_player_bars,
+                reposition_second_player_bars,
+                update_second_player_bar_visibility,
+                update_second_player_bar_thresholds,
+            )
+                .in_set(OnUpdate(GameState::InGame)),
+        );
     }
 }
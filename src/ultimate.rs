@@ -0,0 +1,175 @@
+use bevy::{prelude::*, render::view::RenderLayers};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    camera::MainCamera,
+    collectible::CollectionEvent,
+    game_time::GameTime,
+    input::Action,
+    inventory::Reagent,
+    player::LeadPlayer,
+    rock::{Rock, RockDestroyed},
+    sound::SoundEvent,
+    state::GameState,
+    status_effect::{ApplyStatus, StatusKind},
+    ui::UIMarker,
+};
+
+/// How much Strange matter it takes to fully charge the pulse
+const CHARGE_NEEDED: f32 = 5.0;
+const COOLDOWN_SECONDS: f32 = 30.0;
+const FLASH_SECONDS: f32 = 0.6;
+/// How long the ship keeps pulling in the minerals the pulse breaks loose
+const MAGNETIZE_SECONDS: f32 = 4.0;
+
+/// The Continuum Pulse, which wipes out every rock on screen at once. It charges up as the
+/// player collects Strange matter
+#[derive(Resource, Debug)]
+pub struct UltimateCharge {
+    pub charge: f32,
+    pub cooldown_timer: Timer,
+}
+
+impl Default for UltimateCharge {
+    fn default() -> Self {
+        let mut cooldown_timer = Timer::from_seconds(COOLDOWN_SECONDS, TimerMode::Once);
+        cooldown_timer.tick(cooldown_timer.duration());
+        Self {
+            charge: 0.0,
+            cooldown_timer,
+        }
+    }
+}
+
+impl UltimateCharge {
+    pub fn fraction(&self) -> f32 {
+        (self.charge / CHARGE_NEEDED).clamp(0.0, 1.0)
+    }
+
+    pub fn ready(&self) -> bool {
+        self.charge >= CHARGE_NEEDED && self.cooldown_timer.finished()
+    }
+
+    /// Fully charges the pulse. It still has to wait for the cooldown
+    pub fn fill(&mut self) {
+        self.charge = CHARGE_NEEDED;
+    }
+}
+
+#[derive(Component, Debug)]
+struct PulseFlash {
+    timer: Timer,
+}
+
+fn reset_ultimate_charge(mut ultimate_charge: ResMut<UltimateCharge>) {
+    *ultimate_charge = UltimateCharge::default();
+}
+
+fn charge_ultimate(
+    mut reader: EventReader<CollectionEvent>,
+    mut ultimate_charge: ResMut<UltimateCharge>,
+    game_time: Res<GameTime>,
+) {
+    ultimate_charge.cooldown_timer.tick(game_time.delta());
+    for ev in reader.iter() {
+        if let Reagent::STRANGE = ev.reagent {
+            ultimate_charge.charge = (ultimate_charge.charge + ev.amount).min(CHARGE_NEEDED);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn trigger_ultimate(
+    mut commands: Commands,
+    player_query: Query<(Entity, &ActionState<Action>), LeadPlayer>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    rock_query: Query<(Entity, &Transform), With<Rock>>,
+    mut ultimate_charge: ResMut<UltimateCharge>,
+    mut rock_destroyed_writer: EventWriter<RockDestroyed>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+    mut status_writer: EventWriter<ApplyStatus>,
+) {
+    let Ok((player, action_state)) = player_query.get_single() else { return; };
+    if !action_state.just_pressed(Action::Ultimate) {
+        return;
+    }
+    if !ultimate_charge.ready() {
+        debug!("Continuum Pulse isn't ready yet");
+        return;
+    }
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return; };
+    let Some(size) = camera.logical_viewport_size() else { return; };
+
+    ultimate_charge.charge = 0.0;
+    ultimate_charge.cooldown_timer.reset();
+
+    // Destroyed rocks already break up into minerals, so there's nothing more to do for those
+    let mut count = 0;
+    for (entity, transform) in &rock_query {
+        let Some(pos) =
+            camera.world_to_viewport(camera_transform, transform.translation) else { continue; };
+        if pos.x < 0.0 || pos.y < 0.0 || pos.x > size.x || pos.y > size.y {
+            continue;
+        }
+        rock_destroyed_writer.send(RockDestroyed {
+            entity,
+            position: transform.translation,
+        });
+        count += 1;
+    }
+    debug!("Continuum Pulse destroyed {count} rocks");
+    status_writer.send(ApplyStatus {
+        entity: player,
+        kind: StatusKind::Magnetized,
+        seconds: MAGNETIZE_SECONDS,
+        strength: 1.0,
+    });
+
+    sound_event_writer.send(SoundEvent::ContinuumPulse);
+    commands.spawn((
+        PulseFlash {
+            timer: Timer::from_seconds(FLASH_SECONDS, TimerMode::Once),
+        },
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.8, 0.9, 1.0, 1.0),
+                custom_size: Some(size * 2.0),
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, 10.0),
+            ..Default::default()
+        },
+        // Cleaned up along with the rest of the UI
+        UIMarker,
+        RenderLayers::layer(1),
+    ));
+}
+
+fn fade_pulse_flash(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut PulseFlash, &mut Sprite)>,
+    game_time: Res<GameTime>,
+) {
+    for (entity, mut flash, mut sprite) in &mut query {
+        flash.timer.tick(game_time.delta());
+        if flash.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite.color.set_a(1.0 - flash.timer.percent());
+    }
+}
+
+pub struct UltimatePlugin;
+
+impl Plugin for UltimatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UltimateCharge>()
+            .add_system(reset_ultimate_charge.in_schedule(OnExit(GameState::Intro)))
+            .add_systems(
+                (charge_ultimate, trigger_ultimate, fade_pulse_flash)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
@@ -1,11 +1,52 @@
 use bevy::{
-    prelude::{Color, Handle, Vec2},
+    log::warn,
+    prelude::{Color, Handle, Resource, Vec2},
     text::{Font, TextSection, TextStyle},
+    utils::HashMap,
 };
-use rand::{distributions::uniform::SampleUniform, Rng};
+use rand::{distributions::uniform::SampleUniform, rngs::StdRng, Rng, SeedableRng};
 
-pub fn random_direction() -> Vec2 {
-    let mut rng = rand::thread_rng();
+/// The run's seeded randomness, for anything that changes how a run plays out. Each gameplay
+/// system draws from its own stream split off the seed, so neither the order systems happen to
+/// run in nor how often one of them draws can change the numbers another one gets.
+///
+/// Systems that draw on a timer or every tick run in `FixedUpdate` after the `ReplaySystem`, so
+/// they draw on the same ticks whatever the frame rate. The rest only draw once per collision
+/// or event, which the physics steps send out at the same fixed rate
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    streams: HashMap<&'static str, StdRng>,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::default(),
+        }
+    }
+
+    /// The stream for the system called `name`
+    pub fn stream(&mut self, name: &'static str) -> &mut StdRng {
+        let seed = self.seed;
+        self.streams.entry(name).or_insert_with(|| {
+            // FNV-1a, which unlike the std hasher gives the same value on every run
+            let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+            StdRng::seed_from_u64(seed ^ hash)
+        })
+    }
+}
+
+pub fn random_direction(rng: &mut impl Rng) -> Vec2 {
     let mut dir = Vec2::ZERO;
     while dir.length_squared() == 0.0 {
         dir = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0));
@@ -13,15 +54,14 @@ pub fn random_direction() -> Vec2 {
     dir.normalize()
 }
 
-pub fn random_range<T: SampleUniform + PartialOrd>(min: T, max: T) -> T {
-    let mut rng = rand::thread_rng();
+pub fn random_range<T: SampleUniform + PartialOrd>(rng: &mut impl Rng, min: T, max: T) -> T {
     rng.gen_range(min..=max)
 }
 
-pub fn random_in_circle(radius: f32) -> Vec2 {
+pub fn random_in_circle(rng: &mut impl Rng, radius: f32) -> Vec2 {
     loop {
-        let x = random_range(-1.0, 1.0);
-        let y = random_range(-1.0, 1.0);
+        let x: f32 = rng.gen_range(-1.0..=1.0);
+        let y: f32 = rng.gen_range(-1.0..=1.0);
 
         if x * x + y * y <= 1.0 {
             return Vec2::new(x, y) * radius;
@@ -29,6 +69,25 @@ pub fn random_in_circle(radius: f32) -> Vec2 {
     }
 }
 
+/// Randomness for particles, camera shake, spinning models and the like, which can't change how
+/// a run plays out. They draw from their own generator and leave [`GameRng`] to the gameplay
+pub mod cosmetic {
+    use bevy::prelude::Vec2;
+    use rand::distributions::uniform::SampleUniform;
+
+    pub fn random_direction() -> Vec2 {
+        super::random_direction(&mut rand::thread_rng())
+    }
+
+    pub fn random_range<T: SampleUniform + PartialOrd>(min: T, max: T) -> T {
+        super::random_range(&mut rand::thread_rng(), min, max)
+    }
+
+    pub fn random_in_circle(radius: f32) -> Vec2 {
+        super::random_in_circle(&mut rand::thread_rng(), radius)
+    }
+}
+
 pub fn markup_to_text_sections(
     input: &str,
     font: Handle<Font>,
@@ -67,3 +126,48 @@ pub fn markup_to_text_sections(
 
     return result;
 }
+
+/// Reads the first `N` numbers out of a line from one of the data files
+pub fn parse_floats<const N: usize>(value: &str) -> Option<[f32; N]> {
+    let mut values = [0.0; N];
+    let mut parts = value.split_whitespace();
+    for v in &mut values {
+        *v = parts.next()?.parse().ok()?;
+    }
+    Some(values)
+}
+
+/// The keyword and the rest of each line of one of the data files, leaving out blank lines and
+/// `#` comments
+pub fn parse_config_lines(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return None;
+        }
+        line.split_once(' ')
+    })
+}
+
+/// A keyword nothing reads is skipped with a warning rather than failing the whole file, so a
+/// typo doesn't lose every other setting
+pub fn warn_unknown_config(kind: &str, key: &str) {
+    warn!("Unknown {kind} {key:?}");
+}
+
+/// The data file next to the game, falling back to the bundled copy if it's missing or can't be
+/// used. The web build only has the copy it was built with
+pub fn load_config_file<T>(
+    path: &str,
+    bundled: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> T {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(text) = std::fs::read_to_string(path) {
+        match parse(&text) {
+            Ok(value) => return value,
+            Err(err) => bevy::log::error!("Couldn't use {path}: {err}"),
+        }
+    }
+    parse(bundled).unwrap_or_else(|err| panic!("The bundled copy of {path} should be valid: {err}"))
+}
@@ -1,44 +1,85 @@
-use std::{f32::consts::PI, time::Duration};
+use std::{collections::VecDeque, f32::consts::PI, time::Duration};
 
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use leafwing_input_manager::prelude::ActionState;
 
 use crate::{
-    collectible::{Collectible, CollectibleBundle, ExoticMatter, ExoticMatterAppearance},
+    collectible::{
+        CargoPod, CargoPodAppearance, Collectible, CollectibleBundle, SpawnCollectibleEvent,
+    },
+    game_time::GameTime,
     heat::Heat,
+    hull::Faction,
     input::Action,
     inventory::{Inventory, Reagent},
-    player::Player,
-    rock::RotatingRock,
+    physics::{
+        field_acceleration, AffectedByFields, GravityField, SLUG_COLLISION_GROUPS,
+        SLUG_SOLVER_GROUPS,
+    },
+    player::{LeadPlayer, Player},
+    rock::{Cull, Rock, RotatingRock},
     sound::SoundEvent,
     state::GameState,
-    util::{random_direction, random_range},
+    swarm::SwarmFireEvent,
+    ui::HudSettings,
+    util::{cosmetic, random_direction, GameRng},
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FireMode {
+    #[default]
+    Slug,
+    /// A spread of self guided darts, see `swarm.rs`
+    Swarm,
+}
+
 #[derive(Component, Debug)]
 pub struct MainGun {
     pub enabled: bool,
+    pub fire_mode: FireMode,
+    /// Swarm mode is only available late in the game
+    pub swarm_unlocked: bool,
+    pub swarm_fire_delay: f32,
+    pub swarm_heat_generated: f32,
     pub fire_delay: f32,
     pub delay_timer: Timer,
     pub recoil: f32,
+    /// Fire the thrusters against the recoil so the ship stays where it is, for a bit of extra
+    /// heat
+    pub counter_thrust: bool,
+    pub counter_thrust_heat: f32,
     pub projectile_speed: f32,
     pub max_projectile_distance: f32,
     pub origin_distance: f32,
     pub heat_generated: f32,
+    pub damage: f32,
+    /// How many times each slug bounces off a rock before it's used up
+    pub ricochets: u32,
+    /// How many rocks each slug goes straight through before it has to stop or bounce
+    pub pierces: u32,
 }
 
 impl Default for MainGun {
     fn default() -> Self {
         Self {
             enabled: false,
+            fire_mode: FireMode::Slug,
+            swarm_unlocked: false,
+            swarm_fire_delay: 1.0,
+            swarm_heat_generated: 30.0,
             fire_delay: 0.33,
             delay_timer: Timer::from_seconds(0.0, TimerMode::Once),
             recoil: 5.0,
+            counter_thrust: false,
+            counter_thrust_heat: 2.0,
             projectile_speed: 45.0,
             max_projectile_distance: 15.0,
             origin_distance: 1.5,
             heat_generated: 8.0,
+            damage: 10.0,
+            ricochets: 0,
+            pierces: 0,
         }
     }
 }
@@ -46,10 +87,129 @@ impl Default for MainGun {
 #[derive(Component, Debug)]
 pub struct Slug {
     pub timer: Timer,
+    /// How much damage this slug does to a `Hull` belonging to another faction
+    pub damage: f32,
+    pub ricochets_left: u32,
+    pub pierces_left: u32,
+}
+
+/// Every pooled slug entity keeps this marker, whether it's currently in flight or not
+#[derive(Component, Debug, Default)]
+pub struct PooledSlug;
+
+/// Marks slugs fired by a deployed turret rather than the player's own gun
+#[derive(Component, Debug, Default)]
+pub struct TurretSlug;
+
+/// The parts of a slug that change every time it's fired
+#[derive(Bundle)]
+pub struct SlugBundle {
+    pub slug: Slug,
+    pub faction: Faction,
+    pub velocity: Velocity,
+    pub transform: Transform,
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+/// Recycles slug entities instead of spawning a new one for every shot, and keeps
+/// the number of slugs with a `PointLight` under `max_lights`
+#[derive(Resource, Debug)]
+pub struct SlugPool {
+    free: Vec<Entity>,
+    /// Slugs with a light, oldest first
+    lit: VecDeque<Entity>,
+    pub max_lights: usize,
+}
+
+impl Default for SlugPool {
+    fn default() -> Self {
+        Self {
+            free: Vec::new(),
+            lit: VecDeque::new(),
+            max_lights: 12,
+        }
+    }
+}
+
+impl SlugPool {
+    /// Puts a slug in flight and returns its entity
+    pub fn fire(
+        &mut self,
+        commands: &mut Commands,
+        bundle: SlugBundle,
+        light_color: Color,
+    ) -> Entity {
+        let entity = match self.free.pop() {
+            Some(entity) => {
+                commands
+                    .entity(entity)
+                    .remove::<(ColliderDisabled, RigidBodyDisabled)>()
+                    .insert(Visibility::Visible);
+                entity
+            }
+            None => commands
+                .spawn((
+                    PooledSlug,
+                    RigidBody::Dynamic,
+                    AdditionalMassProperties::Mass(10.0),
+                    Collider::capsule_y(0.25, 0.1),
+                    SLUG_COLLISION_GROUPS,
+                    SLUG_SOLVER_GROUPS,
+                    Ccd::enabled(),
+                    ActiveEvents::COLLISION_EVENTS,
+                    PbrBundle::default(),
+                ))
+                .id(),
+        };
+
+        if self.lit.len() >= self.max_lights {
+            // Too many lights at once, steal the oldest one
+            if let Some(oldest) = self.lit.pop_front() {
+                commands.entity(oldest).remove::<PointLight>();
+            }
+        }
+        self.lit.push_back(entity);
+
+        commands.entity(entity).insert((
+            bundle,
+            PointLight {
+                color: light_color,
+                intensity: 4000.0,
+                radius: 5.0,
+                ..Default::default()
+            },
+        ));
+        entity
+    }
+
+    /// Takes the slug out of play and puts it back in the pool. Use this instead of despawning slugs.
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        if self.free.contains(&entity) {
+            return;
+        }
+        self.lit.retain(|e| *e != entity);
+        commands
+            .entity(entity)
+            .remove::<(Slug, Faction, TurretSlug, AffectedByFields, PointLight)>()
+            .insert((
+                ColliderDisabled,
+                RigidBodyDisabled,
+                Velocity::zero(),
+                Visibility::Hidden,
+            ));
+        self.free.push(entity);
+    }
+
+    /// Forget about every pooled slug, for when they've all been despawned
+    pub fn clear(&mut self) {
+        self.free.clear();
+        self.lit.clear();
+    }
 }
 
 #[derive(Resource, Debug, Default)]
-struct SlugVisuals {
+pub struct SlugVisuals {
     pub mesh: Handle<Mesh>,
     pub material: Handle<StandardMaterial>,
 }
@@ -75,10 +235,10 @@ fn setup_slug_visuals(
     commands.insert_resource(SlugVisuals { material, mesh });
 }
 
-fn tick_slug(mut query: Query<&mut Slug>, time: Res<Time>) {
+fn tick_slug(mut query: Query<&mut Slug>, game_time: Res<GameTime>) {
     for mut slug in &mut query {
         slug.timer
-            .tick(Duration::from_secs_f32(time.delta_seconds()));
+            .tick(game_time.delta());
     }
 }
 
@@ -90,11 +250,12 @@ pub struct SlugDecayedEvent {
 fn kill_slug(
     mut commands: Commands,
     query: Query<(Entity, &Transform, &Velocity, &Slug)>,
+    mut slug_pool: ResMut<SlugPool>,
     mut writer: EventWriter<SlugDecayedEvent>,
 ) {
     for (e, transform, velocity, slug) in &query {
         if slug.timer.finished() {
-            commands.entity(e).despawn_recursive();
+            slug_pool.release(&mut commands, e);
             writer.send(SlugDecayedEvent {
                 position: transform.translation,
                 velocity: velocity.linvel,
@@ -103,10 +264,45 @@ fn kill_slug(
     }
 }
 
-fn tick_gun_timer(mut query: Query<&mut MainGun>, time: Res<Time>) {
+/// Slugs go through rocks while they have pierces left, then bounce off while they have ricochets
+/// left, and are used up after that
+fn handle_slug_rock_hits(
+    mut commands: Commands,
+    mut reader: EventReader<CollisionEvent>,
+    mut slug_query: Query<(&mut Slug, &mut Velocity, &mut Transform), Without<Rock>>,
+    rock_query: Query<&Transform, With<Rock>>,
+    mut slug_pool: ResMut<SlugPool>,
+) {
+    for ev in reader.iter() {
+        let CollisionEvent::Started(e1, e2, _flags) = ev else { continue; };
+        for (slug_entity, rock_entity) in [(*e1, *e2), (*e2, *e1)] {
+            let Ok((mut slug, mut velocity, mut transform)) =
+                slug_query.get_mut(slug_entity) else { continue; };
+            let Ok(rock_transform) = rock_query.get(rock_entity) else { continue; };
+
+            if slug.pierces_left > 0 {
+                slug.pierces_left -= 1;
+            } else if slug.ricochets_left > 0 {
+                slug.ricochets_left -= 1;
+                // Rocks are round, so the surface normal points straight out from the middle
+                let normal = (transform.translation - rock_transform.translation)
+                    .truncate()
+                    .normalize_or_zero();
+                let linvel = velocity.linvel;
+                velocity.linvel = linvel - 2.0 * linvel.dot(normal) * normal;
+                let facing = velocity.linvel.y.atan2(velocity.linvel.x);
+                transform.rotation = Quat::from_rotation_z(PI / 2.0 + facing);
+            } else {
+                slug_pool.release(&mut commands, slug_entity);
+            }
+        }
+    }
+}
+
+fn tick_gun_timer(mut query: Query<&mut MainGun>, game_time: Res<GameTime>) {
     for mut gun in &mut query {
         gun.delay_timer
-            .tick(Duration::from_secs_f32(time.delta_seconds()));
+            .tick(game_time.delta());
     }
 }
 
@@ -127,7 +323,9 @@ fn fire_main_gun(
         &ActionState<crate::input::Action>,
     )>,
     slug_visuals: Res<SlugVisuals>,
+    mut slug_pool: ResMut<SlugPool>,
     mut gun_event_writer: EventWriter<FireMainGunEvent>,
+    mut swarm_event_writer: EventWriter<SwarmFireEvent>,
     mut sound_event_writer: EventWriter<SoundEvent>,
 ) {
     for (
@@ -142,53 +340,75 @@ fn fire_main_gun(
     {
         if !main_gun.enabled {
             // main gun not enabled
-            return;
+            continue;
         }
         if action_state.value(crate::input::Action::FireMainGun) <= 0.0 {
             // Not pressing the fire input
-            return;
+            continue;
         }
         if !main_gun.delay_timer.finished() {
             // not ready to fire the next shot yet
-            return;
+            continue;
         }
-        if heat.limit() - heat.current() < main_gun.heat_generated {
+        let heat_generated = match main_gun.fire_mode {
+            FireMode::Slug if main_gun.counter_thrust => {
+                main_gun.heat_generated + main_gun.counter_thrust_heat
+            }
+            FireMode::Slug => main_gun.heat_generated,
+            FireMode::Swarm => main_gun.swarm_heat_generated,
+        };
+        if heat.limit() - heat.current() < heat_generated {
             // prevent firing if we're overheated
-            return;
+            continue;
         }
 
         let facing_dir = Vec2::from_angle(player.facing);
-        let pos = transform.translation().truncate() + facing_dir * main_gun.origin_distance;
+        let (pos, velocity) = slug_launch(
+            &main_gun,
+            player.facing,
+            transform.translation().truncate(),
+            player_velocity.linvel,
+        );
+
+        if main_gun.fire_mode == FireMode::Swarm {
+            swarm_event_writer.send(SwarmFireEvent {
+                position: Vec3::new(pos.x, pos.y, transform.translation().z),
+                facing: player.facing,
+                velocity: player_velocity.linvel,
+            });
+            sound_event_writer.send(SoundEvent::CannonFire {
+                direction: player.facing,
+            });
+            heat.add(heat_generated);
+            let delay = Duration::from_secs_f32(main_gun.swarm_fire_delay);
+            main_gun.delay_timer.reset();
+            main_gun.delay_timer.set_duration(delay);
+            continue;
+        }
         let rot = Quat::from_rotation_z(PI / 2.0 + player.facing);
 
         let time_to_live = main_gun.max_projectile_distance / main_gun.projectile_speed;
 
-        let velocity = facing_dir * main_gun.projectile_speed + player_velocity.linvel;
-
-        commands.spawn((
-            Slug {
-                timer: Timer::from_seconds(time_to_live, TimerMode::Once),
-            },
-            Velocity::linear(velocity),
-            RigidBody::Dynamic,
-            AdditionalMassProperties::Mass(10.0),
-            Collider::capsule_y(0.25, 0.1),
-            Ccd::enabled(),
-            PointLight {
-                color: Color::ORANGE_RED,
-                intensity: 4000.0,
-                radius: 5.0,
-                ..Default::default()
-            },
-            ActiveEvents::COLLISION_EVENTS,
-            PbrBundle {
+        let slug = slug_pool.fire(
+            &mut commands,
+            SlugBundle {
+                slug: Slug {
+                    timer: Timer::from_seconds(time_to_live, TimerMode::Once),
+                    damage: main_gun.damage,
+                    ricochets_left: main_gun.ricochets,
+                    pierces_left: main_gun.pierces,
+                },
+                faction: Faction::Player,
+                velocity: Velocity::linear(velocity),
                 transform: Transform::from_xyz(pos.x, pos.y, transform.translation().z)
                     .with_rotation(rot),
                 mesh: slug_visuals.mesh.clone(),
                 material: slug_visuals.material.clone(),
-                ..Default::default()
             },
-        ));
+            Color::ORANGE_RED,
+        );
+        // The player's slugs curve around wormholes, for trick shots
+        commands.entity(slug).insert(AffectedByFields);
 
         gun_event_writer.send(FireMainGunEvent {
             position: Vec3::new(pos.x, pos.y, transform.translation().z),
@@ -199,9 +419,11 @@ fn fire_main_gun(
             direction: player.facing,
         });
 
-        ext_impulse.impulse += -facing_dir * main_gun.recoil;
+        if !main_gun.counter_thrust {
+            ext_impulse.impulse += -facing_dir * main_gun.recoil;
+        }
 
-        heat.add(main_gun.heat_generated);
+        heat.add(heat_generated);
 
         let delay = Duration::from_secs_f32(main_gun.fire_delay);
         main_gun.delay_timer.reset();
@@ -209,13 +431,172 @@ fn fire_main_gun(
     }
 }
 
+/// Set from the settings menu, and handed on to every ship's `MainGun`
+#[derive(Resource, Debug, Default)]
+pub struct WeaponSettings {
+    /// Cancel out the gun's recoil with the thrusters
+    pub counter_thrust: bool,
+}
+
+fn apply_weapon_settings(mut query: Query<&mut MainGun>, weapon_settings: Res<WeaponSettings>) {
+    for mut main_gun in &mut query {
+        if weapon_settings.is_changed() || main_gun.is_added() {
+            main_gun.counter_thrust = weapon_settings.counter_thrust;
+        }
+    }
+}
+
+/// Where a slug fired now would leave the muzzle, and how fast it would go. Slugs keep the
+/// ship's own velocity on top of their own
+fn slug_launch(
+    main_gun: &MainGun,
+    facing: f32,
+    ship_position: Vec2,
+    ship_velocity: Vec2,
+) -> (Vec2, Vec2) {
+    let facing_dir = Vec2::from_angle(facing);
+    let position = ship_position + facing_dir * main_gun.origin_distance;
+    let velocity = facing_dir * main_gun.projectile_speed + ship_velocity;
+    (position, velocity)
+}
+
+/// Keeps slugs pointing along their path as the fields bend it
+#[allow(clippy::type_complexity)]
+fn turn_curving_slugs(
+    mut query: Query<(&mut Transform, &Velocity), (With<Slug>, With<AffectedByFields>)>,
+) {
+    for (mut transform, velocity) in &mut query {
+        if velocity.linvel != Vec2::ZERO {
+            let facing = velocity.linvel.y.atan2(velocity.linvel.x);
+            transform.rotation = Quat::from_rotation_z(PI / 2.0 + facing);
+        }
+    }
+}
+
+/// One of the dots marking out where a slug fired now would go
+#[derive(Component, Debug)]
+struct AimLineDot {
+    index: usize,
+}
+
+const AIM_LINE_DOTS: usize = 16;
+/// The path is stepped through the fields this many times between each dot
+const AIM_LINE_STEPS_PER_DOT: usize = 4;
+
+#[derive(Resource, Debug, Default)]
+struct AimLineVisuals {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+fn setup_aim_line_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1.0, 1.0, 1.0, 0.3),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..Default::default()
+    });
+    let mesh = meshes.add(
+        shape::Icosphere {
+            subdivisions: 0,
+            radius: 0.06,
+        }
+        .try_into()
+        .unwrap(),
+    );
+    commands.insert_resource(AimLineVisuals { mesh, material });
+}
+
+/// Lays the dots out along the path a slug would take, out to where it would decay, bending
+/// through any fields on the way. Only for slugs, the swarm's darts steer themselves
+fn update_aim_line(
+    mut commands: Commands,
+    player_query: Query<(&Player, &MainGun, &GlobalTransform, &Velocity), LeadPlayer>,
+    mut dot_query: Query<(&AimLineDot, &mut Transform, &mut Visibility)>,
+    field_query: Query<(&GravityField, &GlobalTransform)>,
+    hud_settings: Res<HudSettings>,
+    aim_line_visuals: Res<AimLineVisuals>,
+) {
+    if dot_query.is_empty() {
+        for index in 0..AIM_LINE_DOTS {
+            commands.spawn((
+                PbrBundle {
+                    mesh: aim_line_visuals.mesh.clone(),
+                    material: aim_line_visuals.material.clone(),
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+                AimLineDot { index },
+            ));
+        }
+        return;
+    }
+
+    let launch = player_query
+        .get_single()
+        .ok()
+        .filter(|(_, main_gun, _, _)| {
+            hud_settings.aim_line
+                && hud_settings.visible
+                && main_gun.enabled
+                && main_gun.fire_mode == FireMode::Slug
+        })
+        .map(|(player, main_gun, transform, velocity)| {
+            let (position, velocity) = slug_launch(
+                main_gun,
+                player.facing,
+                transform.translation().truncate(),
+                velocity.linvel,
+            );
+            let time_to_live = main_gun.max_projectile_distance / main_gun.projectile_speed;
+            (position, velocity, time_to_live, transform.translation().z)
+        });
+    let path = launch.map(|(mut position, mut velocity, time_to_live, z)| {
+        // Stepped the same way the slug's velocity is, so it bends where the slug will
+        let dt = time_to_live / (AIM_LINE_DOTS * AIM_LINE_STEPS_PER_DOT) as f32;
+        let mut path = [Vec3::ZERO; AIM_LINE_DOTS];
+        for point in &mut path {
+            for _ in 0..AIM_LINE_STEPS_PER_DOT {
+                velocity += field_acceleration(&field_query, position) * dt;
+                position += velocity * dt;
+            }
+            *point = position.extend(z);
+        }
+        path
+    });
+    for (dot, mut transform, mut visibility) in &mut dot_query {
+        match &path {
+            Some(path) => {
+                transform.translation = path[dot.index];
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn hide_aim_line(mut query: Query<&mut Visibility, With<AimLineDot>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn cleanup_aim_line(mut commands: Commands, query: Query<Entity, With<AimLineDot>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
 #[derive(Component, Debug, Default)]
 pub struct CargoDumper {
     pub enabled: bool,
 }
 
 fn dump_cargo(
-    mut commands: Commands,
     mut query: Query<(
         &Player,
         &CargoDumper,
@@ -224,8 +605,10 @@ fn dump_cargo(
         &mut Inventory,
         &ActionState<Action>,
     )>,
-    exotic_matter_appearance: Res<ExoticMatterAppearance>,
+    mut collectible_writer: EventWriter<SpawnCollectibleEvent>,
+    mut game_rng: ResMut<GameRng>,
 ) {
+    let rng = game_rng.stream("dump_cargo");
     for (player, cargo_dumper, transform, velocity, mut inventory, action_state) in &mut query {
         if !cargo_dumper.enabled {
             continue;
@@ -234,67 +617,134 @@ fn dump_cargo(
             continue;
         }
 
-        let amount = inventory.reagent(Reagent::Exotic).current();
+        let amount = inventory.reagent(Reagent::EXOTIC).current();
         let num_chunks = amount as u32;
         let facing_dir = Vec2::from_angle(player.facing);
         let pos = transform.translation.truncate() + facing_dir * 3.0;
         if num_chunks > 0 {
             let amount_per_chunk = amount / num_chunks as f32;
-            inventory.reagent_mut(Reagent::Exotic).add(-amount);
+            inventory.reagent_mut(Reagent::EXOTIC).add(-amount);
 
             for _ in 0..num_chunks {
-                let linvel = facing_dir * 3.0 + velocity.linvel + random_direction() * 1.5;
-                let angvel = Vec3::new(
-                    random_range(-PI, PI),
-                    random_range(-PI, PI),
-                    random_range(-PI, PI),
-                );
-                commands
-                    .spawn((
-                        CollectibleBundle {
-                            transform: Transform::from_xyz(pos.x, pos.y, transform.translation.z),
-                            velocity: Velocity::linear(linvel),
-                            collectible: Collectible::CollectibleReagent {
-                                reagent: Reagent::Exotic,
-                                amount: amount_per_chunk,
-                            },
-                            ..Default::default()
-                        },
-                        ExoticMatter::default(),
-                    ))
-                    .with_children(|parent| {
-                        parent.spawn((
-                            RotatingRock { angvel },
-                            PbrBundle {
-                                mesh: exotic_matter_appearance.mesh.clone(),
-                                material: exotic_matter_appearance.material.clone(),
-                                visibility: Visibility::Visible,
-                                ..Default::default()
-                            },
-                        ));
-                    });
+                let linvel = facing_dir * 3.0 + velocity.linvel + random_direction(rng) * 1.5;
+                let position = pos.extend(transform.translation.z);
+                collectible_writer.send(SpawnCollectibleEvent::new(
+                    Reagent::EXOTIC,
+                    amount_per_chunk,
+                    position,
+                    linvel,
+                ));
             }
         }
     }
 }
 
+/// Seals everything in the hold into a single pod that stays put until it's picked up again,
+/// instead of scattering it like `dump_cargo`
+#[allow(clippy::type_complexity)]
+fn eject_cargo_pod(
+    mut commands: Commands,
+    mut query: Query<(
+        &Player,
+        &CargoDumper,
+        &Transform,
+        &Velocity,
+        &mut Inventory,
+        &ActionState<Action>,
+    )>,
+    cargo_pod_appearance: Res<CargoPodAppearance>,
+) {
+    for (player, cargo_dumper, transform, velocity, mut inventory, action_state) in &mut query {
+        if !cargo_dumper.enabled {
+            continue;
+        }
+        if !action_state.just_pressed(Action::EjectCargoPod) {
+            continue;
+        }
+
+        let reagents: Vec<_> = inventory
+            .reagents()
+            // Continuum is what the whole run is building towards, so it stays on board
+            .filter(|(reagent, entry)| *reagent != Reagent::CONTINUUM && entry.current() > 0.0)
+            .map(|(reagent, _)| reagent)
+            .collect();
+        let mut contents = Vec::with_capacity(reagents.len());
+        for reagent in reagents {
+            let entry = inventory.reagent_mut(reagent);
+            contents.push((reagent, entry.current()));
+            entry.add(-entry.current());
+        }
+        if contents.is_empty() {
+            continue;
+        }
+        debug!("Ejecting a cargo pod holding {contents:?}");
+
+        let facing_dir = Vec2::from_angle(player.facing);
+        let pos = transform.translation.truncate() + facing_dir * 3.0;
+        let angvel = Vec3::new(
+            cosmetic::random_range(-0.5, 0.5),
+            cosmetic::random_range(-0.5, 0.5),
+            cosmetic::random_range(-0.5, 0.5),
+        );
+        commands
+            .spawn((
+                CollectibleBundle {
+                    transform: Transform::from_xyz(pos.x, pos.y, transform.translation.z),
+                    velocity: Velocity::linear(facing_dir * 3.0 + velocity.linvel),
+                    collectible: Collectible::CargoPod { contents },
+                    collider: Collider::ball(0.8),
+                    ..Default::default()
+                },
+                CargoPod,
+            ))
+            .remove::<Cull>()
+            .with_children(|parent| {
+                parent.spawn((
+                    RotatingRock { angvel },
+                    PbrBundle {
+                        mesh: cargo_pod_appearance.mesh.clone(),
+                        material: cargo_pod_appearance.material.clone(),
+                        visibility: Visibility::Visible,
+                        ..Default::default()
+                    },
+                ));
+            });
+    }
+}
+
 pub struct WeaponPlugin;
 
 impl Plugin for WeaponPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<FireMainGunEvent>()
             .add_event::<SlugDecayedEvent>()
+            .init_resource::<SlugPool>()
+            .init_resource::<WeaponSettings>()
             .add_startup_system(setup_slug_visuals)
+            .add_startup_system(setup_aim_line_visuals)
             .add_systems(
-                (tick_slug, kill_slug)
+                (
+                    tick_slug,
+                    kill_slug,
+                    handle_slug_rock_hits,
+                    turn_curving_slugs,
+                )
                     .chain()
                     .in_set(OnUpdate(GameState::InGame)),
             )
             .add_systems(
-                (tick_gun_timer, fire_main_gun)
+                (apply_weapon_settings, tick_gun_timer, fire_main_gun)
                     .chain()
                     .in_set(OnUpdate(GameState::InGame)),
             )
-            .add_system(dump_cargo.in_set(OnUpdate(GameState::InGame)));
+            .add_systems((dump_cargo, eject_cargo_pod).in_set(OnUpdate(GameState::InGame)))
+            .add_system(
+                update_aim_line
+                    .after(fire_main_gun)
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            // Nothing to aim at behind the pause menu
+            .add_system(hide_aim_line.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(cleanup_aim_line.in_schedule(OnEnter(GameState::Outro)));
     }
 }
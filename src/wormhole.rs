@@ -0,0 +1,289 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    camera::MainCamera,
+    game_time::GameTime,
+    particles::{Particle, ParticleBundle},
+    physics::GravityField,
+    player::{LeadPlayer, Player},
+    replay::ReplaySystem,
+    rock::RotatingRock,
+    sound::SoundEvent,
+    state::GameState,
+    util::{cosmetic, random_direction, GameRng},
+};
+
+/// One end of a wormhole. Flying into it puts the ship out of its `twin`, keeping its velocity
+#[derive(Component, Debug)]
+pub struct Wormhole {
+    pub twin: Entity,
+    pub radius: f32,
+}
+
+/// Added to anything that's just come out of a wormhole, so it doesn't get sent straight back
+#[derive(Component, Debug)]
+pub struct Warped {
+    pub timer: Timer,
+}
+
+impl Default for Warped {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(2.0, TimerMode::Once),
+        }
+    }
+}
+
+/// Wormholes bend the path of slugs flying past them, enough to curve a shot around one
+const WORMHOLE_FIELD: GravityField = GravityField {
+    strength: 600.0,
+    radius: 12.0,
+};
+
+/// Keeps a pair of wormholes somewhere near the player
+#[derive(Resource, Debug)]
+pub struct WormholeSettings {
+    /// New entrances are placed this far from the player
+    pub spawn_distance: f32,
+    /// How far apart the two ends of a pair are
+    pub travel_distance: f32,
+    /// Pairs with both ends further than this from the player are removed
+    pub max_distance: f32,
+}
+
+impl Default for WormholeSettings {
+    fn default() -> Self {
+        Self {
+            spawn_distance: 40.0,
+            travel_distance: 400.0,
+            max_distance: 800.0,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+struct WormholeAppearance {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    warp_mesh: Handle<Mesh>,
+    warp_material: Handle<StandardMaterial>,
+}
+
+fn setup_wormhole_appearance(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mesh = meshes.add(
+        shape::Torus {
+            radius: 2.0,
+            ring_radius: 0.3,
+            ..Default::default()
+        }
+        .into(),
+    );
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.6, 0.2, 1.0),
+        emissive: Color::rgb(0.5, 0.1, 1.0) * 4.0,
+        ..Default::default()
+    });
+    let warp_mesh = meshes.add(
+        shape::Quad {
+            size: Vec2::splat(0.3),
+            ..Default::default()
+        }
+        .into(),
+    );
+    let warp_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.8, 0.6, 1.0),
+        emissive: Color::rgb(0.6, 0.2, 1.0) * 5.0,
+        ..Default::default()
+    });
+
+    commands.insert_resource(WormholeAppearance {
+        mesh,
+        material,
+        warp_mesh,
+        warp_material,
+    });
+}
+
+fn spawn_wormhole_end(
+    commands: &mut Commands,
+    wormhole_appearance: &WormholeAppearance,
+    position: Vec2,
+) -> Entity {
+    let angvel = Vec3::new(
+        cosmetic::random_range(-1.0, 1.0),
+        cosmetic::random_range(-1.0, 1.0),
+        PI,
+    );
+    commands
+        .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+            position.x, position.y, 0.0,
+        )))
+        .with_children(|parent| {
+            parent.spawn((
+                RotatingRock { angvel },
+                PbrBundle {
+                    mesh: wormhole_appearance.mesh.clone(),
+                    material: wormhole_appearance.material.clone(),
+                    // Stood up to face the camera
+                    transform: Transform::from_rotation(Quat::from_rotation_x(PI / 2.0)),
+                    ..Default::default()
+                },
+            ));
+        })
+        .id()
+}
+
+fn spawn_wormhole_pairs(
+    mut commands: Commands,
+    wormhole_query: Query<(Entity, &Wormhole, &Transform)>,
+    player_query: Query<&Transform, LeadPlayer>,
+    wormhole_appearance: Res<WormholeAppearance>,
+    settings: Res<WormholeSettings>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = game_rng.stream("spawn_wormhole_pairs");
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let player_pos = player_transform.translation.truncate();
+
+    let distance = |transform: &Transform| transform.translation.truncate().distance(player_pos);
+    for (entity, wormhole, transform) in &wormhole_query {
+        let Ok((_, _, twin_transform)) = wormhole_query.get(wormhole.twin) else { continue; };
+        if distance(transform) > settings.max_distance
+            && distance(twin_transform) > settings.max_distance
+        {
+            debug!("Despawning far away wormhole {entity:?}");
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    let nearby = wormhole_query
+        .iter()
+        .any(|(_, _, transform)| distance(transform) < settings.spawn_distance * 2.0);
+    if nearby {
+        return;
+    }
+
+    let entrance_pos = player_pos + random_direction(rng) * settings.spawn_distance;
+    let exit_pos = entrance_pos + random_direction(rng) * settings.travel_distance;
+    debug!("Spawning a wormhole pair between {entrance_pos:?} and {exit_pos:?}");
+    let entrance = spawn_wormhole_end(&mut commands, &wormhole_appearance, entrance_pos);
+    let exit = spawn_wormhole_end(&mut commands, &wormhole_appearance, exit_pos);
+    commands.entity(entrance).insert((
+        Wormhole {
+            twin: exit,
+            radius: 2.0,
+        },
+        WORMHOLE_FIELD,
+    ));
+    commands.entity(exit).insert((
+        Wormhole {
+            twin: entrance,
+            radius: 2.0,
+        },
+        WORMHOLE_FIELD,
+    ));
+}
+
+fn tick_warped(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Warped)>,
+    game_time: Res<GameTime>,
+) {
+    for (entity, mut warped) in &mut query {
+        warped.timer.tick(game_time.delta());
+        if warped.timer.finished() {
+            commands.entity(entity).remove::<Warped>();
+        }
+    }
+}
+
+fn spawn_warp_particles(
+    commands: &mut Commands,
+    wormhole_appearance: &WormholeAppearance,
+    position: Vec3,
+) {
+    const NUM_PARTICLES: u32 = 24;
+    for _ in 0..NUM_PARTICLES {
+        commands.spawn(ParticleBundle {
+            mesh: wormhole_appearance.warp_mesh.clone(),
+            material: wormhole_appearance.warp_material.clone(),
+            particle: Particle {
+                lifetime_timer: Timer::from_seconds(0.6, TimerMode::Once),
+            },
+            velocity: Velocity {
+                linvel: cosmetic::random_direction() * cosmetic::random_range(4.0, 10.0),
+                angvel: cosmetic::random_range(-PI, PI),
+            },
+            transform: Transform::from_translation(position),
+            ..Default::default()
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn teleport_through_wormholes(
+    mut commands: Commands,
+    wormhole_query: Query<(&Wormhole, &Transform), Without<Player>>,
+    mut player_query: Query<
+        (Entity, &mut Transform, &mut TransformInterpolation),
+        (LeadPlayer, Without<Warped>),
+    >,
+    mut camera_query: Query<&mut Transform, (With<MainCamera>, Without<Player>, Without<Wormhole>)>,
+    wormhole_appearance: Res<WormholeAppearance>,
+    mut sound_event_writer: EventWriter<SoundEvent>,
+) {
+    let Ok((player_entity, mut player_transform, mut interpolation)) = player_query.get_single_mut() else { return; };
+    let player_pos = player_transform.translation.truncate();
+
+    for (wormhole, transform) in &wormhole_query {
+        let entrance = transform.translation;
+        if entrance.truncate().distance(player_pos) > wormhole.radius {
+            continue;
+        }
+        let Ok((_, twin_transform)) = wormhole_query.get(wormhole.twin) else { continue; };
+        let offset = (twin_transform.translation - entrance).truncate().extend(0.0);
+        debug!("Player warped by {offset:?}");
+
+        spawn_warp_particles(&mut commands, &wormhole_appearance, player_transform.translation);
+        player_transform.translation += offset;
+        // Don't let the physics smooth the ship across the whole trip
+        interpolation.start = None;
+        interpolation.end = None;
+        // Move the camera by the same amount, so `SmoothFollow` doesn't swoop after the ship
+        for mut camera_transform in &mut camera_query {
+            camera_transform.translation += offset;
+        }
+        spawn_warp_particles(&mut commands, &wormhole_appearance, player_transform.translation);
+
+        commands.entity(player_entity).insert(Warped::default());
+        sound_event_writer.send(SoundEvent::Warp);
+        break;
+    }
+}
+
+pub struct WormholePlugin;
+
+impl Plugin for WormholePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WormholeSettings>()
+            .add_startup_system(setup_wormhole_appearance)
+            .add_system(
+                spawn_wormhole_pairs
+                    .after(ReplaySystem)
+                    .run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (tick_warped, teleport_through_wormholes)
+                    .chain()
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
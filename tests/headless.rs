@@ -0,0 +1,83 @@
+//! Drives the whole game without a window, the way the `headless` feature is meant to be used
+
+use bevy::prelude::*;
+use warlord::{
+    build_game_app,
+    heat::Heat,
+    inventory::{Inventory, Reagent, ReagentRegistry},
+    player::{LeadPlayer, Player},
+    state::GameState,
+    CliPlugin, LaunchOptions,
+};
+
+/// Loading, the main menu and the intro are each only a frame or two once the menus are
+/// skipped, this is plenty
+const MAX_FRAMES_TO_GAME: usize = 600;
+
+fn game_app() -> App {
+    let mut app = build_game_app(true);
+    app.add_plugin(CliPlugin {
+        options: LaunchOptions {
+            skip_menu: true,
+            seed: Some(1),
+            mute: true,
+            ..Default::default()
+        },
+    });
+    app
+}
+
+fn run_until_in_game(app: &mut App) {
+    for _ in 0..MAX_FRAMES_TO_GAME {
+        app.update();
+        if app.world.resource::<State<GameState>>().0 == GameState::InGame {
+            return;
+        }
+    }
+    panic!(
+        "Never got into the game, stuck in {:?}",
+        app.world.resource::<State<GameState>>().0
+    );
+}
+
+#[test]
+fn starts_a_run_with_an_empty_hold_and_a_cool_ship() {
+    let mut app = game_app();
+    run_until_in_game(&mut app);
+    app.update();
+
+    let registry_len = app.world.resource::<ReagentRegistry>().len();
+    let mut query = app
+        .world
+        .query_filtered::<(&Inventory, &Heat), LeadPlayer>();
+    let (inventory, heat) = query.single(&app.world);
+    assert_eq!(inventory.reagents().count(), registry_len);
+    for (_, entry) in inventory.reagents() {
+        assert_eq!(entry.current(), 0.0);
+    }
+    assert_eq!(heat.current(), 0.0);
+}
+
+#[test]
+fn collected_matter_and_heat_stay_on_the_ship() {
+    let mut app = game_app();
+    run_until_in_game(&mut app);
+    app.update();
+
+    let mut query = app
+        .world
+        .query_filtered::<(&mut Inventory, &mut Heat), With<Player>>();
+    for (mut inventory, mut heat) in query.iter_mut(&mut app.world) {
+        inventory.reagent_mut(Reagent::MINERALS).add(10.0);
+        heat.add(50.0);
+    }
+    app.update();
+
+    let mut query = app
+        .world
+        .query_filtered::<(&Inventory, &Heat), LeadPlayer>();
+    let (inventory, heat) = query.single(&app.world);
+    assert_eq!(inventory.reagent(Reagent::MINERALS).current(), 10.0);
+    // It might have started cooling down, but can't have gone anywhere near cold in a frame
+    assert!(heat.current() > 40.0 && heat.current() <= 50.0);
+}
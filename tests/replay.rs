@@ -0,0 +1,131 @@
+//! Records a run without a window and plays it back in a fresh app. It's a file of its own so no
+//! other test draws from the seeded random numbers while it runs
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy, utils::Instant};
+use warlord::{
+    build_game_app,
+    drone::Drone,
+    inventory::Inventory,
+    physics::FIXED_TIMESTEP,
+    player::{LeadPlayer, Player},
+    replay::ReplayMode,
+    state::{GameState, ProgressStages},
+    wormhole::Wormhole,
+    CliPlugin, LaunchOptions,
+};
+
+const MAX_FRAMES_TO_GAME: usize = 600;
+const TICKS: usize = 600;
+/// Far enough in for the drone to be out fetching, and with the gun to break rocks open
+const STAGE: ProgressStages = ProgressStages::GunAndHeat;
+
+fn game_app(options: LaunchOptions) -> App {
+    let mut app = build_game_app(true);
+    app.insert_resource(TimeUpdateStrategy::ManualInstant(Instant::now()));
+    app.add_plugin(CliPlugin {
+        options: LaunchOptions {
+            skip_menu: true,
+            mute: true,
+            ..options
+        },
+    });
+    app
+}
+
+/// Moves the clock on by exactly one fixed tick, however long the update really takes, so both
+/// runs step the same way
+fn step(app: &mut App) {
+    let mut strategy = app.world.resource_mut::<TimeUpdateStrategy>();
+    if let TimeUpdateStrategy::ManualInstant(instant) = strategy.as_mut() {
+        *instant += Duration::from_secs_f32(FIXED_TIMESTEP);
+    }
+    app.update();
+}
+
+/// Into the game and through the stages before the one it starts at, so everything the stage
+/// unlocks is ready before any keys go down
+fn run_until_in_stage(app: &mut App) {
+    for _ in 0..MAX_FRAMES_TO_GAME {
+        step(app);
+        if app.world.resource::<State<GameState>>().0 == GameState::InGame
+            && app.world.resource::<State<ProgressStages>>().0 == STAGE
+        {
+            return;
+        }
+    }
+    panic!(
+        "Never got into the game, stuck in {:?}",
+        app.world.resource::<State<GameState>>().0
+    );
+}
+
+/// Where the lead player ended up and what they're carrying
+fn outcome(app: &mut App) -> (Transform, Vec<f32>) {
+    let mut query = app
+        .world
+        .query_filtered::<(&Transform, &Inventory), LeadPlayer>();
+    let (transform, inventory) = query.single(&app.world);
+    let reagents = inventory
+        .reagents()
+        .map(|(_, entry)| entry.current())
+        .collect();
+    (*transform, reagents)
+}
+
+fn count<T: Component>(app: &mut App) -> usize {
+    app.world.query_filtered::<(), With<T>>().iter(&app.world).count()
+}
+
+#[test]
+fn playing_a_replay_back_ends_up_where_the_recording_did() {
+    let path = std::env::temp_dir().join(format!("warlord-{}.replay", std::process::id()));
+
+    let mut app = game_app(LaunchOptions {
+        stage: Some(STAGE),
+        seed: Some(7),
+        record: Some(path.clone()),
+        ..Default::default()
+    });
+    run_until_in_stage(&mut app);
+    let start = outcome(&mut app).0;
+    // Hold the keys to fly up and to the right, firing a little off to one side
+    let mut query = app.world.query_filtered::<&mut Player, LeadPlayer>();
+    query.single_mut(&mut app.world).facing = 0.5;
+    let mut keyboard = app.world.resource_mut::<Input<KeyCode>>();
+    keyboard.press(KeyCode::W);
+    keyboard.press(KeyCode::D);
+    app.world
+        .resource_mut::<Input<MouseButton>>()
+        .press(MouseButton::Left);
+    for _ in 0..TICKS {
+        step(&mut app);
+    }
+    let recorded = outcome(&mut app);
+    assert_ne!(recorded.0.translation, start.translation);
+    // The wormholes are placed at random and the drone fetches whatever the gun knocks loose, so
+    // any draw that isn't repeatable would throw the playback off
+    assert_eq!(count::<Drone>(&mut app), 1);
+    assert!(count::<Wormhole>(&mut app) > 0);
+
+    let ReplayMode::Record { replay, .. } = app.world.resource::<ReplayMode>() else {
+        panic!("Wasn't recording");
+    };
+    replay.save(&path).unwrap();
+    drop(app);
+
+    let mut app = game_app(LaunchOptions {
+        stage: Some(STAGE),
+        replay: Some(path.clone()),
+        ..Default::default()
+    });
+    run_until_in_stage(&mut app);
+    for _ in 0..TICKS {
+        step(&mut app);
+    }
+    let played_back = outcome(&mut app);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(played_back, recorded);
+}